@@ -0,0 +1,170 @@
+use std::str::FromStr;
+
+use crate::{operators::Opcode, runtime_error::RuntimeErrorKind};
+
+/// What happens when the result of an arithmetic operation does not fit in an i32, a
+/// division by zero is an error whatever the behaviour is
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Overflow {
+    // Stops the program with an error
+    Trap,
+    // Wraps around at the bounds of an i32
+    Wrap,
+    // Clamps the result to the bounds of an i32
+    Saturate,
+}
+
+impl FromStr for Overflow {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Overflow, ()> {
+        match s {
+            "trap" => Ok(Overflow::Trap),
+            "wrap" => Ok(Overflow::Wrap),
+            "saturate" => Ok(Overflow::Saturate),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Overflow {
+    pub fn to_string(&self) -> String {
+        match self {
+            Overflow::Trap => String::from("trap"),
+            Overflow::Wrap => String::from("wrap"),
+            Overflow::Saturate => String::from("saturate"),
+        }
+    }
+}
+
+/// Gets the operator and overflow behaviour of a method of numbers which does arithmetic,
+/// i.e. "wrapping_add" or "saturating_div"
+pub fn arith_method(name: &str) -> Option<(Opcode, Overflow)> {
+    let (overflow, op) = match name.find('_') {
+        Some(pos) => (&name[..pos], &name[pos + 1..]),
+        None => return None,
+    };
+    let overflow = match overflow {
+        "wrapping" => Overflow::Wrap,
+        "saturating" => Overflow::Saturate,
+        _ => return None,
+    };
+    let op = match op {
+        "add" => Opcode::Add,
+        "sub" => Opcode::Sub,
+        "mul" => Opcode::Mul,
+        "div" => Opcode::Div,
+        _ => return None,
+    };
+    Some((op, overflow))
+}
+
+/// Applies an arithmetic operator to two numbers
+pub fn arith(
+    left: i32,
+    op: Opcode,
+    right: i32,
+    overflow: Overflow,
+) -> Result<i32, RuntimeErrorKind> {
+    if op == Opcode::Div && right == 0 {
+        return Err(RuntimeErrorKind::DivByZero { left: left });
+    }
+    let checked = match op {
+        Opcode::Add => left.checked_add(right),
+        Opcode::Sub => left.checked_sub(right),
+        Opcode::Mul => left.checked_mul(right),
+        Opcode::Div => left.checked_div(right),
+        _ => unreachable!(),
+    };
+    match (checked, overflow) {
+        (Some(res), _) => Ok(res),
+        (None, Overflow::Trap) => Err(RuntimeErrorKind::Overflow {
+            op: op,
+            left: left,
+            right: right,
+        }),
+        (None, Overflow::Wrap) => Ok(match op {
+            Opcode::Add => left.wrapping_add(right),
+            Opcode::Sub => left.wrapping_sub(right),
+            Opcode::Mul => left.wrapping_mul(right),
+            _ => left.wrapping_div(right),
+        }),
+        // A division only overflows for i32::MIN / -1
+        (None, Overflow::Saturate) => Ok(match op {
+            Opcode::Add => left.saturating_add(right),
+            Opcode::Sub => left.saturating_sub(right),
+            Opcode::Mul => left.saturating_mul(right),
+            _ => i32::MAX,
+        }),
+    }
+}
+
+/// Negates a number, which only overflows for i32::MIN
+pub fn negate(num: i32, overflow: Overflow) -> Result<i32, RuntimeErrorKind> {
+    match (num.checked_neg(), overflow) {
+        (Some(res), _) => Ok(res),
+        (None, Overflow::Trap) => Err(RuntimeErrorKind::NegOverflow { num: num }),
+        (None, Overflow::Wrap) => Ok(num.wrapping_neg()),
+        (None, Overflow::Saturate) => Ok(i32::MAX),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow_modes() {
+        let max = i32::MAX;
+        let min = i32::MIN;
+        assert_eq!(arith(max, Opcode::Add, 1, Overflow::Wrap), Ok(min));
+        assert_eq!(arith(max, Opcode::Add, 1, Overflow::Saturate), Ok(max));
+        assert_eq!(
+            arith(max, Opcode::Add, 1, Overflow::Trap),
+            Err(RuntimeErrorKind::Overflow {
+                op: Opcode::Add,
+                left: max,
+                right: 1,
+            })
+        );
+        assert_eq!(arith(min, Opcode::Sub, 1, Overflow::Saturate), Ok(min));
+        assert_eq!(arith(min, Opcode::Mul, -1, Overflow::Wrap), Ok(min));
+        assert_eq!(arith(min, Opcode::Mul, 2, Overflow::Saturate), Ok(min));
+        assert_eq!(arith(min, Opcode::Div, -1, Overflow::Wrap), Ok(min));
+        assert_eq!(arith(min, Opcode::Div, -1, Overflow::Saturate), Ok(max));
+        assert!(arith(min, Opcode::Div, -1, Overflow::Trap).is_err());
+        assert_eq!(arith(-7, Opcode::Div, 2, Overflow::Trap), Ok(-3));
+
+        assert_eq!(negate(min, Overflow::Wrap), Ok(min));
+        assert_eq!(negate(min, Overflow::Saturate), Ok(max));
+        assert_eq!(
+            negate(min, Overflow::Trap),
+            Err(RuntimeErrorKind::NegOverflow { num: min })
+        );
+    }
+
+    #[test]
+    fn div_by_zero() {
+        for overflow in [Overflow::Trap, Overflow::Wrap, Overflow::Saturate].iter() {
+            assert_eq!(
+                arith(5, Opcode::Div, 0, *overflow),
+                Err(RuntimeErrorKind::DivByZero { left: 5 })
+            );
+        }
+    }
+
+    #[test]
+    fn methods() {
+        assert_eq!(
+            arith_method("wrapping_add"),
+            Some((Opcode::Add, Overflow::Wrap))
+        );
+        assert_eq!(
+            arith_method("saturating_div"),
+            Some((Opcode::Div, Overflow::Saturate))
+        );
+        assert_eq!(arith_method("checked_add"), None);
+        assert_eq!(arith_method("wrapping_rem"), None);
+        assert_eq!(arith_method("len"), None);
+    }
+}
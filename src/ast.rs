@@ -1,19 +1,86 @@
-use crate::{operators::Opcode, types::LiteralType};
+use crate::{operators::Opcode, pattern::Pattern, types::LiteralType};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Node {
     Number(i32),
     Bool(bool),
     _String(String),
+    // The value of a "return;" without an expression
+    Unit,
 
     Var(String),
     VarBinding(Box<Node>, LiteralType, bool),
+    // Destructuring a tuple i.e. "let (a, mut b): (i32, bool) = f();"
+    TupleBinding(Vec<(Box<Node>, bool)>, LiteralType),
 
     Expr(Box<Node>, Opcode, Box<Node>),
     UnaryOp(Opcode, Box<Node>),
 
+    // Arrays i.e. "[1, 2, 3]", "[0; 10]" and "a[i]"
+    Array(Vec<Box<Node>>),
+    ArrayRepeat(Box<Node>, usize),
+    Index(Box<Node>, Box<Node>),
+
+    // Vectors i.e. "Vec::new()"
+    VecNew,
+
+    // Structs i.e. "struct Point { x: i32, y: i32 }", "Point { x: 1, y: 2 }" and "p.x"
+    Struct {
+        name: String,
+        fields: Vec<(String, LiteralType)>,
+    },
+    StructLiteral {
+        name: String,
+        fields: Vec<(String, Box<Node>)>,
+    },
+    Field(Box<Node>, String),
+
+    // Tuples i.e. "(1, true)", the elements are accessed as fields i.e. "t.0"
+    Tuple(Vec<Box<Node>>),
+
+    // References i.e. "&x", "&mut a[i]" and "*r"
+    Ref(Box<Node>, bool),
+    Deref(Box<Node>),
+
+    // Enums i.e. "enum Shape { Circle(i32), Empty }" and "Shape::Circle(1)"
+    Enum {
+        name: String,
+        variants: Vec<(String, Vec<LiteralType>)>,
+    },
+    Variant {
+        enm: String,
+        name: String,
+        args: Vec<Box<Node>>,
+    },
+
+    // Match expressions i.e. "match (s) { Shape::Circle(r) => r, _ => 0 }"
+    Match {
+        expr: Box<Node>,
+        arms: Vec<(Pattern, Box<Node>)>,
+    },
+
+    // Closures i.e. "|x: i32| x + n", which capture the variables they use by value
+    Closure {
+        params: Vec<Box<Node>>,
+        body: Box<Node>,
+    },
+
+    // Calls on a value i.e. "a.len()" or "v.push(1);"
+    MethodCall {
+        recv: Box<Node>,
+        name: String,
+        args: Vec<Box<Node>>,
+        next: Option<Box<Node>>,
+    },
+
     FuncParam(Box<Node>, LiteralType, bool),
 
+    // Attributes of the file i.e. "#![allow(unused_variables, dead_code)]"
+    Attribute {
+        level: String,
+        lints: Vec<String>,
+    },
+
     // Keywords
     // Keywords contain an optional field for the "next" node in the ast which contains other keywords
     VarValue {
@@ -46,9 +113,16 @@ pub enum Node {
         statement: Box<Node>,
         next: Option<Box<Node>>,
     },
+    // Match with blocks as arms i.e. "match (code) { 1 => { ... } _ => { ... } }"
+    MatchStatement {
+        expr: Box<Node>,
+        arms: Vec<(Pattern, Box<Node>)>,
+        next: Option<Box<Node>>,
+    },
 
     Func {
         name: String,
+        generics: Vec<String>,
         params: Vec<Box<Node>>,
         r_type: Option<LiteralType>,
         body: Box<Node>,
@@ -59,7 +133,7 @@ pub enum Node {
         next: Option<Box<Node>>,
     },
 
-    // NOT IMPLEMENTED Loop modifiers
+    // Loop modifiers
     Break,
     Continue,
 
@@ -102,11 +176,22 @@ impl Node {
                 statement: _,
                 ref mut next,
             } => *next = Some(node),
+            Node::MatchStatement {
+                expr: _,
+                arms: _,
+                ref mut next,
+            } => *next = Some(node),
             Node::FuncCall {
                 name: _,
                 args: _,
                 ref mut next,
             } => *next = Some(node),
+            Node::MethodCall {
+                recv: _,
+                name: _,
+                args: _,
+                ref mut next,
+            } => *next = Some(node),
             Node::Return {
                 expr: _,
                 ref mut next,
@@ -116,4 +201,246 @@ impl Node {
             }
         }
     }
+
+    /// Replaces the types of the parameters, the return type and the variables of a function
+    /// or of a statement and the statements after it, see LiteralType::map
+    pub fn map_types(&mut self, f: &dyn Fn(&LiteralType) -> Option<LiteralType>) {
+        match self {
+            Node::Func {
+                r_type: Some(typ), ..
+            }
+            | Node::FuncParam(_, typ, _)
+            | Node::VarBinding(_, typ, _)
+            | Node::TupleBinding(_, typ) => *typ = typ.map(f),
+            _ => (),
+        }
+
+        for child in self.children_mut() {
+            child.map_types(f);
+        }
+    }
+
+    /// Gets the nodes contained in a node, including the next statement
+    pub fn children_mut(&mut self) -> Vec<&mut Box<Node>> {
+        let mut nodes: Vec<&mut Box<Node>> = vec![];
+        match self {
+            Node::VarBinding(var, _, _) | Node::FuncParam(var, _, _) => nodes.push(var),
+            Node::TupleBinding(vars, _) => nodes.extend(vars.iter_mut().map(|(var, _)| var)),
+            Node::Expr(left, _, right) | Node::Index(left, right) => {
+                nodes.push(left);
+                nodes.push(right);
+            }
+            Node::UnaryOp(_, expr)
+            | Node::ArrayRepeat(expr, _)
+            | Node::Field(expr, _)
+            | Node::Ref(expr, _)
+            | Node::Deref(expr) => nodes.push(expr),
+            Node::Array(elems) | Node::Tuple(elems) | Node::Variant { args: elems, .. } => {
+                nodes.extend(elems.iter_mut())
+            }
+            Node::StructLiteral { fields, .. } => {
+                nodes.extend(fields.iter_mut().map(|(_, expr)| expr))
+            }
+            Node::Match { expr, arms } => {
+                nodes.push(expr);
+                nodes.extend(arms.iter_mut().map(|(_, arm)| arm));
+            }
+            Node::Closure { params, body } | Node::Func { params, body, .. } => {
+                nodes.extend(params.iter_mut());
+                nodes.push(body);
+            }
+            Node::MethodCall {
+                recv, args, next, ..
+            } => {
+                nodes.push(recv);
+                nodes.extend(args.iter_mut());
+                nodes.extend(next.iter_mut());
+            }
+            Node::FuncCall { args, next, .. } => {
+                nodes.extend(args.iter_mut());
+                nodes.extend(next.iter_mut());
+            }
+            Node::VarValue { var, expr, next } | Node::Let { var, expr, next } => {
+                nodes.push(var);
+                nodes.push(expr);
+                nodes.extend(next.iter_mut());
+            }
+            Node::Print { expr, next } | Node::Return { expr, next } => {
+                nodes.push(expr);
+                nodes.extend(next.iter_mut());
+            }
+            Node::If {
+                cond,
+                statement,
+                next,
+            }
+            | Node::While {
+                cond,
+                statement,
+                next,
+            } => {
+                nodes.push(cond);
+                nodes.push(statement);
+                nodes.extend(next.iter_mut());
+            }
+            Node::IfElse {
+                cond,
+                if_statement,
+                else_statement,
+                next,
+            } => {
+                nodes.push(cond);
+                nodes.push(if_statement);
+                nodes.push(else_statement);
+                nodes.extend(next.iter_mut());
+            }
+            Node::MatchStatement { expr, arms, next } => {
+                nodes.push(expr);
+                nodes.extend(arms.iter_mut().map(|(_, arm)| arm));
+                nodes.extend(next.iter_mut());
+            }
+            _ => (),
+        }
+        nodes
+    }
+
+    /// Gets the nodes contained in a node, including the next statement if with_next
+    pub fn children(&self, with_next: bool) -> Vec<&Box<Node>> {
+        let mut nodes: Vec<&Box<Node>> = match self {
+            Node::VarBinding(var, _, _) | Node::FuncParam(var, _, _) => vec![var],
+            Node::TupleBinding(vars, _) => vars.iter().map(|(var, _)| var).collect(),
+            Node::Expr(left, _, right) => vec![left, right],
+            Node::UnaryOp(_, expr)
+            | Node::ArrayRepeat(expr, _)
+            | Node::Field(expr, _)
+            | Node::Ref(expr, _)
+            | Node::Deref(expr) => vec![expr],
+            Node::Array(elems) | Node::Tuple(elems) => elems.iter().collect(),
+            Node::Index(arr, idx) => vec![arr, idx],
+            Node::StructLiteral { fields, .. } => fields.iter().map(|(_, expr)| expr).collect(),
+            Node::Variant { args, .. } | Node::FuncCall { args, .. } => args.iter().collect(),
+            Node::Match { expr, arms } | Node::MatchStatement { expr, arms, .. } => {
+                let mut nodes = vec![expr];
+                nodes.extend(arms.iter().map(|(_, arm)| arm));
+                nodes
+            }
+            Node::Closure { body, .. } => vec![body],
+            Node::MethodCall { recv, args, .. } => {
+                let mut nodes = vec![recv];
+                nodes.extend(args.iter());
+                nodes
+            }
+            Node::VarValue { var, expr, .. } | Node::Let { var, expr, .. } => vec![var, expr],
+            Node::Print { expr, .. } | Node::Return { expr, .. } => vec![expr],
+            Node::If {
+                cond, statement, ..
+            }
+            | Node::While {
+                cond, statement, ..
+            } => vec![cond, statement],
+            Node::IfElse {
+                cond,
+                if_statement,
+                else_statement,
+                ..
+            } => vec![cond, if_statement, else_statement],
+            _ => vec![],
+        };
+
+        if with_next {
+            if let Some(next) = self.next_stmnt() {
+                nodes.push(next);
+            }
+        }
+        nodes
+    }
+
+    pub fn next_stmnt(&self) -> Option<&Box<Node>> {
+        match self {
+            Node::VarValue { next, .. }
+            | Node::Let { next, .. }
+            | Node::Print { next, .. }
+            | Node::If { next, .. }
+            | Node::IfElse { next, .. }
+            | Node::While { next, .. }
+            | Node::MatchStatement { next, .. }
+            | Node::FuncCall { next, .. }
+            | Node::MethodCall { next, .. }
+            | Node::Return { next, .. } => next.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Removes the statement after a statement, so that it can be evaluated on its own
+    pub fn take_next(&mut self) -> Option<Box<Node>> {
+        match self {
+            Node::VarValue { next, .. }
+            | Node::Let { next, .. }
+            | Node::Print { next, .. }
+            | Node::If { next, .. }
+            | Node::IfElse { next, .. }
+            | Node::While { next, .. }
+            | Node::MatchStatement { next, .. }
+            | Node::FuncCall { next, .. }
+            | Node::MethodCall { next, .. }
+            | Node::Return { next, .. } => next.take(),
+            _ => None,
+        }
+    }
+
+    /// Gets the names used in the body of a closure which are not bound by it, in the order
+    /// they are first used. These are the variables it captures, unless they are functions
+    pub fn captures(&self) -> Vec<String> {
+        let mut names = vec![];
+        if let Node::Closure { params, body } = self {
+            let mut bound = params.iter().map(|param| param_name(param)).collect();
+            body.free_names(&mut bound, &mut names);
+        }
+        names
+    }
+
+    fn free_names(&self, bound: &mut Vec<String>, names: &mut Vec<String>) {
+        match self {
+            Node::Var(name) | Node::FuncCall { name, .. }
+                if !bound.contains(name) && !names.contains(name) =>
+            {
+                names.push(name.to_string());
+            }
+            Node::Var(_) | Node::FuncCall { .. } => (),
+            // The variables bound by the pattern of an arm are only bound in the arm
+            Node::Match { expr, arms } => {
+                expr.free_names(bound, names);
+                for (pat, arm) in arms.iter() {
+                    let len = bound.len();
+                    bound.extend(pat.bindings());
+                    arm.free_names(bound, names);
+                    bound.truncate(len);
+                }
+                return;
+            }
+            Node::Closure { .. } => {
+                for name in self.captures().into_iter() {
+                    if !bound.contains(&name) && !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                return;
+            }
+            _ => (),
+        }
+
+        for child in self.children(false).into_iter() {
+            child.free_names(bound, names);
+        }
+    }
+}
+
+pub fn param_name(param: &Node) -> String {
+    match param {
+        Node::FuncParam(var, _, _) => match &**var {
+            Node::Var(name) => name.to_string(),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
 }
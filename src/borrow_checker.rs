@@ -0,0 +1,633 @@
+use crate::{
+    ast::Node,
+    type_errors::{ErrorKind, TypeErrors},
+    types::LiteralType,
+};
+
+/// A borrow of a variable, the borrows of reference parameters are external since they
+/// refer to variables of the caller
+#[derive(Clone, Debug, PartialEq)]
+struct Loan {
+    var: String,
+    mutable: bool,
+    external: bool,
+}
+
+/// A variable of a reference type and the borrows of the reference it holds
+#[derive(Clone, Debug, PartialEq)]
+struct Holder {
+    name: String,
+    depth: usize,
+    loans: Vec<Loan>,
+}
+
+/// The place that is borrowed or assigned to, either a variable or the value behind a
+/// reference i.e. "*r"
+enum Root {
+    Var(String),
+    Deref(String),
+}
+
+/// Checks the borrows of the functions of a type checked program, a variable can either be
+/// borrowed as mutable once or as shared any number of times, it can not be assigned to
+/// while it is borrowed and references can not outlive the variables they refer to.
+///
+/// A borrow lasts until the last use of the reference holding it, where a reference which
+/// is used in a loop is used until the loop ends
+pub fn borrow_check(program: &Vec<Box<Node>>) -> Result<(), TypeErrors> {
+    let mut err = TypeErrors::new();
+    for item in program.iter() {
+        if let Node::Func {
+            params,
+            r_type,
+            body,
+            ..
+        } = &**item
+        {
+            let returns_ref = matches!(r_type, Some(LiteralType::Ref(_, _)));
+            BorrowChecker::new(returns_ref).check_fn(params, body, &mut err);
+        }
+    }
+
+    if err.len() > 0 {
+        return Err(err);
+    }
+    Ok(())
+}
+
+struct BorrowChecker<'a> {
+    // The declared variables and the depth of the scope they are declared in
+    vars: Vec<(String, usize)>,
+    holders: Vec<Holder>,
+    depth: usize,
+    // The statements that are executed after the current statement and the depth of their
+    // scope, loops are executed again after their body
+    rest: Vec<(&'a Node, usize)>,
+    curr: Option<&'a Node>,
+    // The borrows made by the current statement which are not held by a variable
+    temps: Vec<Loan>,
+    returns_ref: bool,
+}
+
+impl<'a> BorrowChecker<'a> {
+    fn new(returns_ref: bool) -> BorrowChecker<'a> {
+        BorrowChecker {
+            vars: vec![],
+            holders: vec![],
+            depth: 0,
+            rest: vec![],
+            curr: None,
+            temps: vec![],
+            returns_ref: returns_ref,
+        }
+    }
+
+    fn check_fn(
+        &mut self,
+        params: &'a Vec<Box<Node>>,
+        body: &'a Box<Node>,
+        err: &mut TypeErrors,
+    ) {
+        for param in params.iter() {
+            if let Node::FuncParam(var, typ, _) = &**param {
+                let name = var_name(var);
+                self.declare(&name);
+                if let LiteralType::Ref(_, mutable) = typ {
+                    let loan = Loan {
+                        var: name.clone(),
+                        mutable: *mutable,
+                        external: true,
+                    };
+                    self.hold(&name, vec![loan]);
+                }
+            }
+        }
+        self.check_block(body, vec![], err);
+    }
+
+    fn declare(&mut self, name: &str) {
+        self.vars.push((name.to_string(), self.depth));
+    }
+
+    fn var_depth(&self, name: &str) -> Option<usize> {
+        self.vars
+            .iter()
+            .rev()
+            .find(|(var, _)| var == name)
+            .map(|(_, depth)| *depth)
+    }
+
+    /// Sets the borrows held by a variable
+    fn hold(&mut self, name: &str, loans: Vec<Loan>) {
+        let depth = self.var_depth(name).unwrap_or(self.depth);
+        self.holders.retain(|holder| holder.name != name || holder.depth != depth);
+        self.holders.push(Holder {
+            name: name.to_string(),
+            depth: depth,
+            loans: loans,
+        });
+    }
+
+    fn get_holder(&self, name: &str) -> Option<&Holder> {
+        let depth = self.var_depth(name)?;
+        self.holders
+            .iter()
+            .find(|holder| holder.name == name && holder.depth == depth)
+    }
+
+    /// Checks if a reference is used by the current statement or any statement after it
+    fn is_live(&self, holder: &Holder) -> bool {
+        let used_now = match self.curr {
+            Some(curr) => mentions(curr, &holder.name, false),
+            None => false,
+        };
+        used_now
+            || self
+                .rest
+                .iter()
+                .any(|(node, depth)| holder.depth <= *depth && mentions(node, &holder.name, true))
+    }
+
+    /// Gets the borrows of a variable which are still in use
+    fn live_loans(&self, var: &str) -> Vec<Loan> {
+        let mut loans: Vec<Loan> = self
+            .holders
+            .iter()
+            .filter(|holder| self.is_live(holder))
+            .flat_map(|holder| holder.loans.iter().cloned())
+            .filter(|loan| loan.var == var && !loan.external)
+            .collect();
+        loans.extend(self.temps.iter().filter(|loan| loan.var == var).cloned());
+        loans
+    }
+
+    /// Checks if a variable of the current scope is borrowed by a reference which is used
+    /// after the current statement, shadowing it would hide the variable the reference
+    /// refers to
+    fn borrowed_later(&self, var: &str) -> bool {
+        self.var_depth(var) == Some(self.depth)
+            && self.holders.iter().any(|holder| {
+                holder.loans.iter().any(|loan| loan.var == var && !loan.external)
+                    && self.rest.iter().any(|(node, depth)| {
+                        holder.depth <= *depth && mentions(node, &holder.name, true)
+                    })
+            })
+    }
+
+    /// Checks the statements of a block in a new scope, which contains the variables bound
+    /// by the pattern of a match arm
+    fn check_block(&mut self, stmnt: &'a Box<Node>, bindings: Vec<String>, err: &mut TypeErrors) {
+        self.depth += 1;
+        for name in bindings.iter() {
+            self.declare(name);
+        }
+
+        let mut curr = Some(stmnt);
+        while let Some(stmnt) = curr {
+            let next = stmnt.next_stmnt();
+            if let Some(next) = next {
+                self.rest.push((next, self.depth));
+            }
+            self.check_stmnt(stmnt, err);
+            if next.is_some() {
+                self.rest.pop();
+            }
+            curr = next;
+        }
+
+        // The variables of the scope and the borrows they hold are dropped
+        let depth = self.depth;
+        self.vars.retain(|(_, var_depth)| *var_depth < depth);
+        self.holders.retain(|holder| holder.depth < depth);
+        self.depth -= 1;
+    }
+
+    fn check_stmnt(&mut self, stmnt: &'a Box<Node>, err: &mut TypeErrors) {
+        self.curr = Some(stmnt);
+        self.temps.clear();
+
+        match &**stmnt {
+            Node::Let { var, expr, .. } => {
+                self.check_expr(expr, err);
+                let loans = self.loans(expr);
+                let names = match &**var {
+                    Node::VarBinding(var, _, _) => vec![var_name(var)],
+                    Node::TupleBinding(vars, _) => {
+                        vars.iter().map(|(var, _)| var_name(var)).collect()
+                    }
+                    _ => unreachable!(),
+                };
+                for name in names.into_iter().filter(|name| self.borrowed_later(name)) {
+                    err.insert_err(ErrorKind::ShadowBorrowed { var: name });
+                }
+
+                match &**var {
+                    Node::VarBinding(var, typ, _) => {
+                        let name = var_name(var);
+                        self.declare(&name);
+                        if let LiteralType::Ref(_, _) = typ {
+                            self.hold(&name, loans);
+                        }
+                    }
+                    Node::TupleBinding(vars, _) => {
+                        for (var, _) in vars.iter() {
+                            self.declare(&var_name(var));
+                        }
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Node::VarValue { var, expr, .. } => {
+                // The borrows held by a reference that is assigned to are released
+                let holder = match &**var {
+                    Node::Var(name) if self.get_holder(name).is_some() => {
+                        self.hold(name, vec![]);
+                        Some(name)
+                    }
+                    _ => None,
+                };
+
+                self.check_place_exprs(var, err);
+                self.check_expr(expr, err);
+                self.check_assign(var, err);
+
+                if let Some(name) = holder {
+                    let loans = self.loans(expr);
+                    self.check_outlives(name, &loans, err);
+                    self.hold(name, loans);
+                }
+            }
+            Node::Return { expr, .. } => {
+                self.check_expr(expr, err);
+                if !self.returns_ref {
+                    return;
+                }
+                let mut reported: Vec<String> = vec![];
+                for loan in self.loans(expr).into_iter() {
+                    if !loan.external && !reported.contains(&loan.var) {
+                        reported.push(loan.var.clone());
+                        err.insert_err(ErrorKind::ReturnLocalRef { var: loan.var });
+                    }
+                }
+            }
+            Node::If {
+                cond, statement, ..
+            } => {
+                self.check_expr(cond, err);
+                self.check_block(statement, vec![], err);
+            }
+            Node::IfElse {
+                cond,
+                if_statement,
+                else_statement,
+                ..
+            } => {
+                self.check_expr(cond, err);
+                self.check_block(if_statement, vec![], err);
+                self.check_block(else_statement, vec![], err);
+            }
+            Node::While {
+                cond, statement, ..
+            } => {
+                // Everything in the loop is executed again after the body
+                self.rest.push((stmnt, self.depth));
+                self.check_expr(cond, err);
+                self.check_block(statement, vec![], err);
+                self.rest.pop();
+            }
+            Node::MatchStatement { expr, arms, .. } => {
+                self.check_expr(expr, err);
+                for (pat, arm) in arms.iter() {
+                    self.check_block(arm, pat.bindings(), err);
+                }
+            }
+            Node::Print { expr, .. } => self.check_expr(expr, err),
+            Node::FuncCall { .. } | Node::MethodCall { .. } => self.check_expr(stmnt, err),
+            _ => (),
+        }
+    }
+
+    /// Checks the uses of variables in an expression and the borrows it makes
+    fn check_expr(&mut self, expr: &'a Box<Node>, err: &mut TypeErrors) {
+        match &**expr {
+            Node::Var(name) => {
+                if self.live_loans(name).iter().any(|loan| loan.mutable) {
+                    err.insert_err(ErrorKind::UseMutBorrowed {
+                        var: name.to_string(),
+                    });
+                }
+            }
+            Node::Ref(place, mutable) => {
+                self.check_place_exprs(place, err);
+                // Reborrows i.e. "&mut *r" are part of the borrow held by the reference
+                if let Some(Root::Var(name)) = root(place) {
+                    let conflict = self
+                        .live_loans(&name)
+                        .iter()
+                        .any(|loan| *mutable || loan.mutable);
+                    if conflict {
+                        err.insert_err(ErrorKind::BorrowConflict {
+                            var: name.clone(),
+                            mutable: *mutable,
+                        });
+                    }
+                    self.temps.push(Loan {
+                        var: name,
+                        mutable: *mutable,
+                        external: false,
+                    });
+                }
+            }
+            Node::MethodCall {
+                recv, name, args, ..
+            } => {
+                match name.as_str() {
+                    "push" | "pop" => {
+                        self.check_place_exprs(recv, err);
+                        self.check_assign(recv, err);
+                    }
+                    _ => self.check_expr(recv, err),
+                }
+                for arg in args.iter() {
+                    self.check_expr(arg, err);
+                }
+            }
+            Node::Match { expr, arms } => {
+                self.check_expr(expr, err);
+                for (_, arm) in arms.iter() {
+                    self.check_expr(arm, err);
+                }
+            }
+            // The captured variables are copied into the closure when it is created
+            Node::Closure { .. } => {
+                for name in expr.captures().into_iter() {
+                    if self.live_loans(&name).iter().any(|loan| loan.mutable) {
+                        err.insert_err(ErrorKind::UseMutBorrowed { var: name });
+                    }
+                }
+            }
+            _ => {
+                for child in expr.children(false).into_iter() {
+                    self.check_expr(child, err);
+                }
+            }
+        }
+    }
+
+    /// Checks the expressions used to index into a place that is borrowed or assigned to
+    fn check_place_exprs(&mut self, place: &'a Box<Node>, err: &mut TypeErrors) {
+        match &**place {
+            Node::Index(arr, idx) => {
+                self.check_place_exprs(arr, err);
+                self.check_expr(idx, err);
+            }
+            Node::Field(strct, _) => self.check_place_exprs(strct, err),
+            Node::Deref(expr) => self.check_place_exprs(expr, err),
+            _ => (),
+        }
+    }
+
+    /// Checks that a variable is not borrowed when it is assigned to
+    fn check_assign(&mut self, place: &Box<Node>, err: &mut TypeErrors) {
+        if let Some(Root::Var(name)) = root(place) {
+            if !self.live_loans(&name).is_empty() {
+                err.insert_err(ErrorKind::AssignBorrowed { var: name });
+            }
+        }
+    }
+
+    /// Checks that the variables borrowed by the reference assigned to a variable are
+    /// declared in the same or in an outer scope, otherwise they are dropped first
+    fn check_outlives(&self, name: &str, loans: &Vec<Loan>, err: &mut TypeErrors) {
+        let depth = match self.var_depth(name) {
+            Some(depth) => depth,
+            None => return,
+        };
+        for loan in loans.iter() {
+            match self.var_depth(&loan.var) {
+                Some(var_depth) if !loan.external && var_depth > depth => {
+                    err.insert_err(ErrorKind::BorrowOutlives {
+                        var: loan.var.clone(),
+                    })
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Gets the borrows held by the value of an expression, a returned reference is
+    /// assumed to hold the borrows of all of the arguments of the call
+    fn loans(&self, expr: &Box<Node>) -> Vec<Loan> {
+        match &**expr {
+            Node::Ref(place, mutable) => match root(place) {
+                Some(Root::Var(name)) => vec![Loan {
+                    var: name,
+                    mutable: *mutable,
+                    external: false,
+                }],
+                Some(Root::Deref(name)) => match self.get_holder(&name) {
+                    Some(holder) => holder.loans.clone(),
+                    None => vec![],
+                },
+                None => vec![],
+            },
+            Node::Var(name) => match self.get_holder(name) {
+                Some(holder) => holder.loans.clone(),
+                None => vec![],
+            },
+            Node::FuncCall { args, .. } => args.iter().flat_map(|arg| self.loans(arg)).collect(),
+            Node::Match { arms, .. } => arms.iter().flat_map(|(_, arm)| self.loans(arm)).collect(),
+            _ => vec![],
+        }
+    }
+}
+
+fn var_name(var: &Node) -> String {
+    match var {
+        Node::Var(name) => name.to_string(),
+        _ => unreachable!(),
+    }
+}
+
+/// Gets the variable or reference that a place is part of
+fn root(place: &Node) -> Option<Root> {
+    match place {
+        Node::Var(name) => Some(Root::Var(name.to_string())),
+        Node::Index(place, _) | Node::Field(place, _) => root(place),
+        Node::Deref(expr) => match &**expr {
+            Node::Var(name) => Some(Root::Deref(name.to_string())),
+            _ => root(expr),
+        },
+        _ => None,
+    }
+}
+
+/// Checks if a variable is used in a node, including the statements after it if with_next
+fn mentions(node: &Node, name: &str, with_next: bool) -> bool {
+    match node {
+        Node::Var(var) => var == name,
+        _ => node.children(with_next)
+            .into_iter()
+            .any(|child| mentions(child, name, true)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::program_parser::parse;
+
+    #[test]
+    fn borrow_conflicts() {
+        let input = parse(
+            "fn main() {
+				let mut x: i32 = 1;
+				let a: &mut i32 = &mut x;
+				let b: &i32 = &x;
+				let y: i32 = x + *b;
+				x = 2;
+				*a = 3;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::BorrowConflict {
+            var: String::from("x"),
+            mutable: false,
+        });
+        errors.insert_err(ErrorKind::UseMutBorrowed {
+            var: String::from("x"),
+        });
+        errors.insert_err(ErrorKind::AssignBorrowed {
+            var: String::from("x"),
+        });
+        assert_eq!(borrow_check(&input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn borrow_last_use() {
+        let input = parse(
+            "fn main() {
+				let mut x: i32 = 1;
+				let a: &mut i32 = &mut x;
+				*a = 2;
+				let b: &i32 = &x;
+				let c: i32 = *b;
+				x = c;
+				let mut i: i32 = 0;
+				while (i < 2) {
+					let r: &mut i32 = &mut x;
+					*r = *r + 1;
+					i = i + 1;
+				}
+				print(x);
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(borrow_check(&input), Ok(()));
+    }
+
+    #[test]
+    fn borrow_loop() {
+        let input = parse(
+            "fn main() {
+				let mut x: i32 = 0;
+				let r: &i32 = &x;
+				let mut i: i32 = 0;
+				while (i < 3) {
+					x = x + 1;
+					i = i + *r;
+				}
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::AssignBorrowed {
+            var: String::from("x"),
+        });
+        assert_eq!(borrow_check(&input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn borrow_lifetimes() {
+        let input = parse(
+            "fn max(a: &i32, b: &i32) -> &i32 {
+				if (*a > *b) {
+					return a;
+				}
+				return b;
+			}
+
+			fn local() -> &i32 {
+				let x: i32 = 1;
+				return &x;
+			}
+
+			fn main() {
+				let x: i32 = 1;
+				let mut r: &i32 = &x;
+				if (x > 0) {
+					let y: i32 = 2;
+					r = &y;
+				}
+				print(*r);
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::ReturnLocalRef {
+            var: String::from("x"),
+        });
+        errors.insert_err(ErrorKind::BorrowOutlives {
+            var: String::from("y"),
+        });
+        assert_eq!(borrow_check(&input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn borrow_shadowing() {
+        let input = parse(
+            "fn main() {
+				let x: i32 = 4;
+				let r: &i32 = &x;
+				let x: i32 = 1;
+				let y: i32 = *r + x;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::ShadowBorrowed {
+            var: String::from("x"),
+        });
+        assert_eq!(borrow_check(&input).unwrap_err(), errors);
+
+        // The reference is not used after the shadowing or the variable is shadowed in a
+        // nested scope
+        let input = parse(
+            "fn main() {
+				let x: i32 = 4;
+				let r: &i32 = &x;
+				let x: i32 = *r + 1;
+				let a: i32 = 4;
+				let b: &i32 = &a;
+				if (a > 0) {
+					let a: i32 = 1;
+					print(*b + a);
+				}
+			}"
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(borrow_check(&input), Ok(()));
+    }
+}
@@ -0,0 +1,651 @@
+use std::collections::HashMap;
+
+use super::{Bytecode, Function, Instr, MAX_REPEAT};
+use crate::{
+    arithmetic::arith_method, ast::Node, control_flow::stmnt_kind, operators::Opcode,
+    runtime_error::RuntimeErrorKind, value::Value,
+};
+
+/// Compiles a type checked program to bytecode. The variables are resolved to the slots of
+/// the call frames and the functions to their index, so that nothing is looked up by name
+/// when the program is run. Structs and their fields, enums, references, closures, matches,
+/// tuple destructuring and assignments to nested elements such as `a[1][0] = 5` are not
+/// supported, a program which uses them has to be run by the interpreter
+pub fn compile(program: &Vec<Box<Node>>) -> Result<Bytecode, RuntimeErrorKind> {
+    let mut fn_ids = HashMap::new();
+    for item in program.iter() {
+        if let Node::Func { name, params, .. } = &**item {
+            let id = fn_ids.len() as u32;
+            if fn_ids.insert(name.to_string(), (id, params.len() as u32)).is_some() {
+                return Err(RuntimeErrorKind::FnDefinedMultiple {
+                    name: name.to_string(),
+                });
+            }
+        }
+    }
+    let main = match fn_ids.get("main") {
+        Some((id, _)) => *id,
+        None => return Err(RuntimeErrorKind::MainMissing),
+    };
+
+    let mut functions = vec![];
+    for item in program.iter() {
+        if let Node::Func {
+            name, params, body, ..
+        } = &**item
+        {
+            functions.push(FnCompiler::new(name, &fn_ids).compile(params, body)?);
+        }
+    }
+    Ok(Bytecode {
+        functions: functions,
+        main: main,
+    })
+}
+
+// The index and the number of parameters of every function
+type FnIds = HashMap<String, (u32, u32)>;
+
+/// The position of a loop in the code of a function and the breaks out of it, which jump
+/// to its end once it is known
+struct Loop {
+    start: usize,
+    breaks: Vec<usize>,
+}
+
+struct FnCompiler<'a> {
+    func: Function,
+    fn_ids: &'a FnIds,
+    // The variables which are in scope, their slots and if they are mutable
+    scopes: Vec<Vec<(String, u32, bool)>>,
+    loops: Vec<Loop>,
+    // The number of statements which have been compiled
+    stmnts: u32,
+}
+
+impl<'a> FnCompiler<'a> {
+    fn new(name: &str, fn_ids: &'a FnIds) -> FnCompiler<'a> {
+        FnCompiler {
+            func: Function {
+                name: name.to_string(),
+                arity: 0,
+                locals: vec![],
+                consts: vec![],
+                code: vec![],
+                lines: vec![],
+                source_lines: vec![],
+            },
+            fn_ids: fn_ids,
+            scopes: vec![vec![]],
+            loops: vec![],
+            stmnts: 0,
+        }
+    }
+
+    fn compile(
+        mut self,
+        params: &Vec<Box<Node>>,
+        body: &Node,
+    ) -> Result<Function, RuntimeErrorKind> {
+        for param in params.iter() {
+            match &**param {
+                Node::FuncParam(var, _, mutable) => self.declare(var, *mutable)?,
+                param => return Err(not_supported(param)),
+            };
+        }
+        self.func.arity = params.len() as u32;
+
+        // A function which reaches the end of its body does not return a value
+        self.compile_block(body)?;
+        self.emit(Instr::Unit);
+        self.emit(Instr::Return);
+        Ok(self.func)
+    }
+
+    fn emit(&mut self, instr: Instr) -> usize {
+        self.func.code.push(instr);
+        self.func.code.len() - 1
+    }
+
+    /// Emits a jump to the start of a loop
+    fn emit_loop(&mut self, start: usize) {
+        let offset = start as i32 - self.func.code.len() as i32 - 1;
+        self.emit(Instr::Jump(offset));
+    }
+
+    /// Sets the target of a forward jump to the next instruction that is emitted
+    fn patch(&mut self, jump: usize) {
+        let offset = (self.func.code.len() - jump - 1) as i32;
+        self.func.code[jump] = match self.func.code[jump] {
+            Instr::Jump(_) => Instr::Jump(offset),
+            Instr::JumpIfFalse(_) => Instr::JumpIfFalse(offset),
+            Instr::And(_) => Instr::And(offset),
+            Instr::Or(_) => Instr::Or(offset),
+            _ => unreachable!(),
+        };
+    }
+
+    /// Marks the start of the code of a statement with its index, or the continuation of it
+    /// after a block nested in it
+    fn mark(&mut self, stmnt: &Node, idx: u32) {
+        let kind = match stmnt_kind(stmnt) {
+            Some(kind) => kind,
+            None => return,
+        };
+        let pc = self.func.code.len() as u32;
+        match self.func.lines.last_mut() {
+            Some(line) if line.0 == pc => *line = (pc, idx, kind),
+            _ => self.func.lines.push((pc, idx, kind)),
+        }
+    }
+
+    fn add_const(&mut self, val: Value) -> u32 {
+        self.func.consts.push(val);
+        self.func.consts.len() as u32 - 1
+    }
+
+    /// Declares a variable in the innermost scope and returns its slot
+    fn declare(&mut self, var: &Node, mutable: bool) -> Result<u32, RuntimeErrorKind> {
+        let name = match var {
+            Node::Var(name) => name,
+            var => return Err(not_supported(var)),
+        };
+        let slot = self.func.locals.len() as u32;
+        self.func.locals.push(name.to_string());
+        let scope = self.scopes.last_mut().unwrap();
+        scope.push((name.to_string(), slot, mutable));
+        Ok(slot)
+    }
+
+    /// Gets the slot of the variable with a name which was declared last and if it is mutable
+    fn resolve(&self, name: &str) -> Option<(u32, bool)> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(var, _, _)| var == name)
+            .map(|(_, slot, mutable)| (*slot, *mutable))
+    }
+
+    fn resolve_var(&self, var: &Node) -> Result<(u32, bool), RuntimeErrorKind> {
+        match var {
+            Node::Var(name) => self
+                .resolve(name)
+                .ok_or_else(|| RuntimeErrorKind::VarNotDefined {
+                    var: name.to_string(),
+                }),
+            var => Err(not_supported(var)),
+        }
+    }
+
+    /// Gets the slot of a variable which is assigned to, which has to be mutable
+    fn resolve_mut(&self, var: &Node) -> Result<u32, RuntimeErrorKind> {
+        match self.resolve_var(var)? {
+            (slot, true) => Ok(slot),
+            (_, false) => Err(RuntimeErrorKind::VarImmut {
+                var: match var {
+                    Node::Var(name) => name.to_string(),
+                    _ => unreachable!(),
+                },
+            }),
+        }
+    }
+
+    fn compile_block(&mut self, block: &Node) -> Result<(), RuntimeErrorKind> {
+        self.scopes.push(vec![]);
+        let mut stmnt = Some(block);
+        while let Some(curr) = stmnt {
+            self.compile_stmnt(curr)?;
+            stmnt = curr.next_stmnt().map(|next| &**next);
+        }
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn compile_stmnt(&mut self, stmnt: &Node) -> Result<(), RuntimeErrorKind> {
+        // The statements are visited in the order they appear in
+        let idx = self.stmnts;
+        self.stmnts += 1;
+        self.mark(stmnt, idx);
+        match stmnt {
+            Node::Let { var, expr, .. } => {
+                // The expression is compiled first since it can use a variable it shadows
+                self.compile_expr(expr)?;
+                let slot = match &**var {
+                    Node::VarBinding(var, _, mutable) => self.declare(var, *mutable)?,
+                    var => return Err(not_supported(var)),
+                };
+                self.emit(Instr::Store(slot));
+            }
+            Node::VarValue { var, expr, .. } => {
+                self.compile_expr(expr)?;
+                match &**var {
+                    Node::Index(arr, idx) => {
+                        let slot = self.resolve_mut(arr)?;
+                        self.compile_expr(idx)?;
+                        self.emit(Instr::SetIndex(slot));
+                    }
+                    var => {
+                        let slot = self.resolve_mut(var)?;
+                        self.emit(Instr::Store(slot));
+                    }
+                }
+            }
+            Node::Print { expr, .. } => {
+                self.compile_expr(expr)?;
+                match &**expr {
+                    Node::Var(name) => {
+                        let name = self.add_const(Value::String(name.to_string()));
+                        self.emit(Instr::PrintVar(name));
+                    }
+                    _ => {
+                        self.emit(Instr::Print);
+                    }
+                }
+            }
+            Node::If {
+                cond, statement, ..
+            } => {
+                self.compile_expr(cond)?;
+                let jump = self.emit(Instr::JumpIfFalse(0));
+                self.compile_block(statement)?;
+                self.mark(stmnt, idx);
+                self.patch(jump);
+            }
+            Node::IfElse {
+                cond,
+                if_statement,
+                else_statement,
+                ..
+            } => {
+                self.compile_expr(cond)?;
+                let else_jump = self.emit(Instr::JumpIfFalse(0));
+                self.compile_block(if_statement)?;
+                self.mark(stmnt, idx);
+                let end_jump = self.emit(Instr::Jump(0));
+                self.patch(else_jump);
+                self.compile_block(else_statement)?;
+                self.mark(stmnt, idx);
+                self.patch(end_jump);
+            }
+            Node::While {
+                cond, statement, ..
+            } => {
+                let start = self.func.code.len();
+                self.compile_expr(cond)?;
+                let end_jump = self.emit(Instr::JumpIfFalse(0));
+                self.loops.push(Loop {
+                    start: start,
+                    breaks: vec![],
+                });
+                self.compile_block(statement)?;
+                self.mark(stmnt, idx);
+                self.emit_loop(start);
+                self.patch(end_jump);
+                for jump in self.loops.pop().unwrap().breaks {
+                    self.patch(jump);
+                }
+            }
+            Node::Break => {
+                let jump = self.emit(Instr::Jump(0));
+                match self.loops.last_mut() {
+                    Some(innermost) => innermost.breaks.push(jump),
+                    None => return Err(RuntimeErrorKind::LoopModifierOutsideLoop),
+                }
+            }
+            Node::Continue => match self.loops.last() {
+                Some(innermost) => {
+                    let start = innermost.start;
+                    self.emit_loop(start);
+                }
+                None => return Err(RuntimeErrorKind::LoopModifierOutsideLoop),
+            },
+            Node::Return { expr, .. } => {
+                self.compile_expr(expr)?;
+                self.emit(Instr::Return);
+            }
+            // The values of calls which are statements are discarded
+            Node::FuncCall { .. } | Node::MethodCall { .. } => {
+                self.compile_expr(stmnt)?;
+                self.emit(Instr::Pop);
+            }
+            stmnt => return Err(not_supported(stmnt)),
+        }
+        Ok(())
+    }
+
+    fn compile_expr(&mut self, expr: &Node) -> Result<(), RuntimeErrorKind> {
+        let instr = match expr {
+            Node::Number(num) => Instr::Int(*num),
+            Node::Bool(b) => Instr::Bool(*b),
+            Node::_String(text) => Instr::Const(self.add_const(Value::String(text.to_string()))),
+            Node::Unit => Instr::Unit,
+            Node::Var(_) => Instr::Load(self.resolve_var(expr)?.0),
+            Node::UnaryOp(_, value) => {
+                self.compile_expr(value)?;
+                Instr::Neg
+            }
+            // The right operand of a logical operation is only evaluated if the left one does
+            // not decide its result
+            Node::Expr(left, op, right) if *op == Opcode::AND || *op == Opcode::OR => {
+                self.compile_expr(left)?;
+                let skip = match op {
+                    Opcode::AND => self.emit(Instr::And(0)),
+                    _ => self.emit(Instr::Or(0)),
+                };
+                self.compile_expr(right)?;
+                self.emit(Instr::BinOp(*op));
+                self.patch(skip);
+                return Ok(());
+            }
+            Node::Expr(left, op, right) => {
+                self.compile_expr(left)?;
+                self.compile_expr(right)?;
+                Instr::BinOp(*op)
+            }
+            Node::FuncCall { name, args, .. } => self.compile_call(name, args)?,
+            Node::Array(elems) => Instr::MakeArray(self.compile_all(elems)?),
+            Node::Tuple(elems) => Instr::MakeTuple(self.compile_all(elems)?),
+            Node::VecNew => Instr::MakeVec(0),
+            Node::ArrayRepeat(_, len) if *len > MAX_REPEAT as usize => {
+                return Err(not_supported(expr))
+            }
+            Node::ArrayRepeat(expr, len) => {
+                self.compile_expr(expr)?;
+                Instr::Repeat(*len as u32)
+            }
+            // The element of a variable is read without copying the variable, unless the
+            // index can change it after the variable would have been read
+            Node::Index(arr, idx) if matches!(**arr, Node::Var(_)) && is_pure(idx) => {
+                let (slot, _) = self.resolve_var(arr)?;
+                self.compile_expr(idx)?;
+                Instr::LoadIndex(slot)
+            }
+            Node::Index(arr, idx) => {
+                self.compile_expr(arr)?;
+                self.compile_expr(idx)?;
+                Instr::Index
+            }
+            // Only the fields of tuples are supported
+            Node::Field(tuple, field) => match field.parse() {
+                Ok(idx) => {
+                    self.compile_expr(tuple)?;
+                    Instr::TupleField(idx)
+                }
+                Err(_) => return Err(not_supported(expr)),
+            },
+            Node::MethodCall {
+                recv, name, args, ..
+            } => self.compile_method_call(recv, name, args)?,
+            expr => return Err(not_supported(expr)),
+        };
+        self.emit(instr);
+        Ok(())
+    }
+
+    /// Compiles a list of expressions in order and returns how many there are
+    fn compile_all(&mut self, exprs: &Vec<Box<Node>>) -> Result<u32, RuntimeErrorKind> {
+        for expr in exprs.iter() {
+            self.compile_expr(expr)?;
+        }
+        Ok(exprs.len() as u32)
+    }
+
+    fn compile_call(
+        &mut self,
+        name: &str,
+        args: &Vec<Box<Node>>,
+    ) -> Result<Instr, RuntimeErrorKind> {
+        // A variable which shadows a function holds a closure
+        if self.resolve(name).is_some() {
+            return Err(RuntimeErrorKind::BytecodeNotSupported {
+                node: String::from("Closure"),
+            });
+        }
+        let (id, arity) = match self.fn_ids.get(name) {
+            Some(func) => *func,
+            None => {
+                return Err(RuntimeErrorKind::FnNotDefined {
+                    name: name.to_string(),
+                })
+            }
+        };
+        if args.len() as u32 != arity {
+            return Err(RuntimeErrorKind::FnArgCount {
+                name: name.to_string(),
+                takes: arity as usize,
+                supplied: args.len(),
+            });
+        }
+        Ok(Instr::Call(id, self.compile_all(args)?))
+    }
+
+    /// Compiles the methods of arrays and vectors, a vector which is changed by a method has
+    /// to be a variable
+    fn compile_method_call(
+        &mut self,
+        recv: &Node,
+        name: &str,
+        args: &Vec<Box<Node>>,
+    ) -> Result<Instr, RuntimeErrorKind> {
+        match (name, args.len()) {
+            ("len", 0) => {
+                self.compile_expr(recv)?;
+                Ok(Instr::Len)
+            }
+            ("push", 1) => {
+                let slot = self.resolve_mut(recv)?;
+                self.compile_expr(&args[0])?;
+                Ok(Instr::Push(slot))
+            }
+            ("pop", 0) => Ok(Instr::PopElem(self.resolve_mut(recv)?)),
+            (name, 1) if arith_method(name).is_some() => {
+                let (op, overflow) = arith_method(name).unwrap();
+                self.compile_expr(recv)?;
+                self.compile_expr(&args[0])?;
+                Ok(Instr::Arith(op, overflow))
+            }
+            _ => Err(RuntimeErrorKind::BytecodeNotSupported {
+                node: format!("MethodCall '{}'", name),
+            }),
+        }
+    }
+}
+
+/// Checks if an expression is evaluated without calls, which could change the variables
+fn is_pure(expr: &Node) -> bool {
+    match expr {
+        Node::Number(_) | Node::Bool(_) | Node::Var(_) => true,
+        Node::UnaryOp(_, value) => is_pure(value),
+        Node::Expr(left, _, right) | Node::Index(left, right) => is_pure(left) && is_pure(right),
+        _ => false,
+    }
+}
+
+/// The error of a node which the bytecode compiler does not support, which is described by
+/// the name of its variant since it can be a large tree
+fn not_supported(node: &Node) -> RuntimeErrorKind {
+    let node = format!("{:?}", node);
+    let variant = node.split(|c: char| !c.is_alphanumeric() && c != '_').next();
+    RuntimeErrorKind::BytecodeNotSupported {
+        node: variant.unwrap_or_default().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{operators::Opcode, parse::program_parser::parse};
+
+    fn compile_main(input: &str) -> Result<Function, RuntimeErrorKind> {
+        let bytecode = compile(&parse(input.to_string()).unwrap())?;
+        Ok(bytecode.functions[bytecode.main as usize].clone())
+    }
+
+    #[test]
+    fn loops() {
+        let main = compile_main(
+            "fn main() {
+                let mut i: i32 = 0;
+                while (true) {
+                    i = i + 1;
+                    if (i > 10) {
+                        break;
+                    }
+                    continue;
+                }
+            }",
+        )
+        .unwrap();
+        assert_eq!(
+            main.code,
+            vec![
+                Instr::Int(0),
+                Instr::Store(0),
+                Instr::Bool(true),
+                Instr::JumpIfFalse(11),
+                Instr::Load(0),
+                Instr::Int(1),
+                Instr::BinOp(Opcode::Add),
+                Instr::Store(0),
+                Instr::Load(0),
+                Instr::Int(10),
+                Instr::BinOp(Opcode::GT),
+                Instr::JumpIfFalse(1),
+                Instr::Jump(2),
+                Instr::Jump(-12),
+                Instr::Jump(-13),
+                Instr::Unit,
+                Instr::Return,
+            ]
+        );
+    }
+
+    #[test]
+    fn slots() {
+        let main = compile_main(
+            "fn main() -> i32 {
+                let x: i32 = 1;
+                if (true) {
+                    let x: i32 = x + 1;
+                    print(x);
+                }
+                let x: i32 = x * 2;
+                return x;
+            }",
+        )
+        .unwrap();
+
+        // Every binding gets a slot of its own, a shadowed variable is used until the
+        // binding which shadows it is assigned
+        assert_eq!(main.locals, vec!["x", "x", "x"]);
+        assert_eq!(
+            main.code[..12].to_vec(),
+            vec![
+                Instr::Int(1),
+                Instr::Store(0),
+                Instr::Bool(true),
+                Instr::JumpIfFalse(6),
+                Instr::Load(0),
+                Instr::Int(1),
+                Instr::BinOp(Opcode::Add),
+                Instr::Store(1),
+                Instr::Load(1),
+                Instr::PrintVar(0),
+                Instr::Load(0),
+                Instr::Int(2),
+            ]
+        );
+        assert_eq!(main.code[13..15].to_vec(), vec![Instr::Store(2), Instr::Load(2)]);
+    }
+
+    #[test]
+    fn load_index() {
+        let main = compile_main(
+            "fn main() -> i32 {
+                let mut v: Vec<i32> = Vec::new();
+                v.push(1);
+                let i: i32 = v[0];
+                return v[v.pop()];
+            }",
+        )
+        .unwrap();
+
+        // An element is read from the slot, unless the index can change the vector
+        let i = main.locals.iter().position(|local| local == "i").unwrap() as u32;
+        let load = main.code.iter().position(|instr| *instr == Instr::Store(i)).unwrap();
+        assert_eq!(main.code[load - 2..load], [Instr::Int(0), Instr::LoadIndex(0)]);
+        assert_eq!(main.code[load + 1], Instr::Load(0));
+        assert!(main.code.contains(&Instr::Index));
+    }
+
+    #[test]
+    fn logical_ops() {
+        let main = compile_main(
+            "fn main() -> bool {
+                return true || false && true;
+            }",
+        )
+        .unwrap();
+        // The operators have the same precedence and are applied from left to right, the
+        // right operand of each is skipped if the left one is its result
+        assert_eq!(
+            main.code,
+            vec![
+                Instr::Bool(true),
+                Instr::Or(2),
+                Instr::Bool(false),
+                Instr::BinOp(Opcode::OR),
+                Instr::And(2),
+                Instr::Bool(true),
+                Instr::BinOp(Opcode::AND),
+                Instr::Return,
+                Instr::Unit,
+                Instr::Return,
+            ]
+        );
+    }
+
+    #[test]
+    fn compile_errors() {
+        assert_eq!(
+            compile_main("fn f() { print(1); }").unwrap_err(),
+            RuntimeErrorKind::MainMissing
+        );
+        assert_eq!(
+            compile_main("fn main() { g(1); }").unwrap_err(),
+            RuntimeErrorKind::FnNotDefined {
+                name: String::from("g")
+            }
+        );
+        assert_eq!(
+            compile_main("fn main() { break; }").unwrap_err(),
+            RuntimeErrorKind::LoopModifierOutsideLoop
+        );
+        assert_eq!(
+            compile_main(
+                "fn main() -> i32 {
+                    let f = |x: i32| x + 1;
+                    return f(1);
+                }"
+            )
+            .unwrap_err(),
+            RuntimeErrorKind::BytecodeNotSupported {
+                node: String::from("Closure")
+            }
+        );
+        assert_eq!(
+            compile_main(
+                "fn main() -> i32 {
+                    let x: i32 = 1;
+                    let r: &i32 = &x;
+                    return *r;
+                }"
+            )
+            .unwrap_err(),
+            RuntimeErrorKind::BytecodeNotSupported {
+                node: String::from("Ref")
+            }
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use super::{Bytecode, Function, Instr};
+
+/// Formats the bytecode of a program as text with one instruction per line, the slots,
+/// constants, functions and jump targets that the instructions refer to are shown after them.
+/// The start of the code of a statement is marked by a comment
+pub fn disassemble(bytecode: &Bytecode) -> String {
+    let mut text = String::new();
+    for func in bytecode.functions.iter() {
+        let params = &func.locals[..func.arity as usize];
+        text += &format!("fn {}({}):\n", func.name, params.join(", "));
+        let mut lines = func.lines.iter().peekable();
+        for (pc, instr) in func.code.iter().enumerate() {
+            while let Some((_, _, kind)) = lines.next_if(|(start, ..)| *start as usize <= pc) {
+                text += &format!("        ; {}\n", kind);
+            }
+            text += &format!("{:>6}  {}\n", pc, disassemble_instr(bytecode, func, pc, instr));
+        }
+    }
+    text
+}
+
+fn disassemble_instr(bytecode: &Bytecode, func: &Function, pc: usize, instr: &Instr) -> String {
+    let local = |slot: &u32| func.locals[*slot as usize].to_string();
+    let target = |offset: &i32| pc as i32 + 1 + *offset;
+    match instr {
+        Instr::Int(num) => format!("{:<14}{}", "INT", num),
+        Instr::Bool(b) => format!("{:<14}{}", "BOOL", b),
+        Instr::Const(idx) => format!(
+            "{:<14}{:<6}({:?})",
+            "CONST",
+            idx,
+            func.consts[*idx as usize]
+        ),
+        Instr::Unit => String::from("UNIT"),
+        Instr::Load(slot) => format!("{:<14}{:<6}({})", "LOAD", slot, local(slot)),
+        Instr::Store(slot) => format!("{:<14}{:<6}({})", "STORE", slot, local(slot)),
+        Instr::Pop => String::from("POP"),
+        Instr::Neg => String::from("NEG"),
+        Instr::BinOp(op) => format!("{:<14}{}", "BINOP", op.to_string()),
+        Instr::Arith(op, overflow) => format!(
+            "{:<14}{:<6}({})",
+            "ARITH",
+            op.to_string(),
+            overflow.to_string()
+        ),
+        Instr::Jump(offset) => format!("{:<14}{:<6}(to {})", "JUMP", offset, target(offset)),
+        Instr::JumpIfFalse(offset) => format!(
+            "{:<14}{:<6}(to {})",
+            "JUMP_IF_FALSE",
+            offset,
+            target(offset)
+        ),
+        Instr::And(offset) => format!("{:<14}{:<6}(to {})", "AND", offset, target(offset)),
+        Instr::Or(offset) => format!("{:<14}{:<6}(to {})", "OR", offset, target(offset)),
+        Instr::Call(id, argc) => format!(
+            "{:<14}{:<6}({})",
+            "CALL",
+            format!("{} {}", id, argc),
+            bytecode.functions[*id as usize].name
+        ),
+        Instr::Return => String::from("RETURN"),
+        Instr::Print => String::from("PRINT"),
+        Instr::PrintVar(idx) => format!(
+            "{:<14}{:<6}({:?})",
+            "PRINT_VAR",
+            idx,
+            func.consts[*idx as usize]
+        ),
+        Instr::MakeArray(len) => format!("{:<14}{}", "MAKE_ARRAY", len),
+        Instr::MakeTuple(len) => format!("{:<14}{}", "MAKE_TUPLE", len),
+        Instr::MakeVec(len) => format!("{:<14}{}", "MAKE_VEC", len),
+        Instr::Repeat(len) => format!("{:<14}{}", "REPEAT", len),
+        Instr::Index => String::from("INDEX"),
+        Instr::TupleField(idx) => format!("{:<14}{}", "TUPLE_FIELD", idx),
+        Instr::SetIndex(slot) => format!("{:<14}{:<6}({})", "SET_INDEX", slot, local(slot)),
+        Instr::LoadIndex(slot) => format!("{:<14}{:<6}({})", "LOAD_INDEX", slot, local(slot)),
+        Instr::Len => String::from("LEN"),
+        Instr::Push(slot) => format!("{:<14}{:<6}({})", "PUSH", slot, local(slot)),
+        Instr::PopElem(slot) => format!("{:<14}{:<6}({})", "POP_ELEM", slot, local(slot)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bytecode::compiler::compile, parse::program_parser::parse};
+
+    #[test]
+    fn disassemble_fn() {
+        let program = parse(
+            "fn f(mut n: i32) -> i32 {
+                let s: String = \"n\";
+                while (n > 0) {
+                    n = n - 1;
+                }
+                print(s);
+                return f(n);
+            }
+
+            fn main() {
+                f(-1);
+            }"
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            disassemble(&compile(&program).unwrap()),
+            "fn f(n):
+        ; let
+     0  CONST         0     (String(\"n\"))
+     1  STORE         1     (s)
+        ; while
+     2  LOAD          0     (n)
+     3  INT           0
+     4  BINOP         >
+     5  JUMP_IF_FALSE 5     (to 11)
+        ; assignment
+     6  LOAD          0     (n)
+     7  INT           1
+     8  BINOP         -
+     9  STORE         0     (n)
+        ; while
+    10  JUMP          -9    (to 2)
+        ; print
+    11  LOAD          1     (s)
+    12  PRINT_VAR     1     (String(\"s\"))
+        ; return
+    13  LOAD          0     (n)
+    14  CALL          0 1   (f)
+    15  RETURN
+    16  UNIT
+    17  RETURN
+fn main():
+        ; call to 'f'
+     0  INT           1
+     1  NEG
+     2  CALL          0 1   (f)
+     3  POP
+     4  UNIT
+     5  RETURN
+"
+        );
+    }
+}
@@ -0,0 +1,775 @@
+use std::fmt;
+
+use super::{Bytecode, Function, Instr, MAX_REPEAT};
+use crate::{arithmetic::Overflow, operators::Opcode, value::Value};
+
+// A bytecode file starts with the magic bytes and the version of its format, followed by
+//
+//     the constant pool      the number of constants and the constants, the strings and
+//                            literals that the rest of the file refers to by their index
+//     the function table     the number of functions and for each of them its name, arity,
+//                            local variables, constants, code, line table and the lines
+//                            of its statements in the source
+//     main                   the index of the main function
+//     the checksum           the FNV-1a hash of everything before it
+//
+// All numbers are stored in little endian, and the lengths as u32
+const MAGIC: &[u8; 4] = b"D7BC";
+
+/// The version of the format of bytecode files, it has to be increased when the encoding of
+/// an instruction or the layout of a file changes
+pub const VERSION: u16 = 6;
+
+// The encoding of an operator is its index
+const OPCODES: [Opcode; 12] = [
+    Opcode::Mul,
+    Opcode::Div,
+    Opcode::Add,
+    Opcode::Sub,
+    Opcode::AND,
+    Opcode::OR,
+    Opcode::EQ,
+    Opcode::NEQ,
+    Opcode::GT,
+    Opcode::LT,
+    Opcode::LEQ,
+    Opcode::GEQ,
+];
+
+const OVERFLOWS: [Overflow; 3] = [Overflow::Trap, Overflow::Wrap, Overflow::Saturate];
+
+/// The reasons a bytecode file can not be loaded
+#[derive(Debug, PartialEq)]
+pub enum LoadError {
+    // The file does not start with the magic bytes
+    NotBytecode,
+    Version { found: u16 },
+    Checksum,
+    Truncated,
+    Invalid { reason: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::NotBytecode => write!(f, "The file is not a bytecode file"),
+            LoadError::Version { found } => write!(
+                f,
+                "The bytecode file has version {}, but only version {} is supported",
+                found, VERSION
+            ),
+            LoadError::Checksum => write!(
+                f,
+                "The bytecode file is corrupt, its checksum does not match its contents"
+            ),
+            LoadError::Truncated => write!(f, "The bytecode file ends unexpectedly"),
+            LoadError::Invalid { reason } => write!(f, "The bytecode file is invalid, {}", reason),
+        }
+    }
+}
+
+fn invalid<T>(reason: String) -> Result<T, LoadError> {
+    Err(LoadError::Invalid { reason: reason })
+}
+
+/// Encodes a program compiled to bytecode in the format of a bytecode file
+pub fn serialize(bytecode: &Bytecode) -> Vec<u8> {
+    let mut pool = vec![];
+    let mut funcs = vec![];
+    write_u32(&mut funcs, bytecode.functions.len() as u32);
+    for func in bytecode.functions.iter() {
+        write_function(&mut funcs, &mut pool, func);
+    }
+    write_u32(&mut funcs, bytecode.main);
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    write_u32(&mut bytes, pool.len() as u32);
+    for val in pool.iter() {
+        write_const(&mut bytes, val);
+    }
+    bytes.extend(funcs);
+    let checksum = checksum(&bytes);
+    write_u32(&mut bytes, checksum);
+    bytes
+}
+
+/// Decodes a bytecode file, the file is validated so that the program can not access a
+/// slot, constant, function or instruction which does not exist or pop more values than
+/// it pushed
+pub fn deserialize(bytes: &[u8]) -> Result<Bytecode, LoadError> {
+    if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(LoadError::NotBytecode);
+    }
+    let mut header = Reader {
+        bytes: &bytes[..bytes.len().min(MAGIC.len() + 2)],
+        pos: MAGIC.len(),
+    };
+    let version = header.u16()?;
+    if version != VERSION {
+        return Err(LoadError::Version { found: version });
+    }
+    let body_end = match bytes.len().checked_sub(4) {
+        Some(end) if end >= header.pos => end,
+        _ => return Err(LoadError::Truncated),
+    };
+    let mut trailer = Reader {
+        bytes: bytes,
+        pos: body_end,
+    };
+    if trailer.u32()? != checksum(&bytes[..body_end]) {
+        return Err(LoadError::Checksum);
+    }
+
+    let mut reader = Reader {
+        bytes: &bytes[..body_end],
+        pos: header.pos,
+    };
+    let mut pool = vec![];
+    for _ in 0..reader.u32()? {
+        pool.push(reader.constant()?);
+    }
+    let mut functions = vec![];
+    for _ in 0..reader.u32()? {
+        functions.push(reader.function(&pool)?);
+    }
+    let bytecode = Bytecode {
+        functions: functions,
+        main: reader.u32()?,
+    };
+    if reader.pos != reader.bytes.len() {
+        return invalid(String::from("it has data after the main function"));
+    }
+
+    match bytecode.functions.get(bytecode.main as usize) {
+        Some(main) if main.arity == 0 => (),
+        Some(main) => return invalid(format!("main function '{}' takes arguments", main.name)),
+        None => return invalid(format!("main function {} does not exist", bytecode.main)),
+    }
+    for func in bytecode.functions.iter() {
+        validate(&bytecode, func)?;
+    }
+    Ok(bytecode)
+}
+
+fn write_u32(bytes: &mut Vec<u8>, num: u32) {
+    bytes.extend_from_slice(&num.to_le_bytes());
+}
+
+fn write_i32(bytes: &mut Vec<u8>, num: i32) {
+    bytes.extend_from_slice(&num.to_le_bytes());
+}
+
+fn write_const(bytes: &mut Vec<u8>, val: &Value) {
+    match val {
+        Value::Number(num) => {
+            bytes.push(0);
+            write_i32(bytes, *num);
+        }
+        Value::Bool(b) => bytes.extend_from_slice(&[1, *b as u8]),
+        Value::String(text) => {
+            bytes.push(2);
+            write_u32(bytes, text.len() as u32);
+            bytes.extend_from_slice(text.as_bytes());
+        }
+        Value::Unit => bytes.push(3),
+        _ => unreachable!("only literals are constants"),
+    }
+}
+
+/// Gets the index of a constant in the pool, it is added to it if it is not already there
+fn pool_index(pool: &mut Vec<Value>, val: Value) -> u32 {
+    match pool.iter().position(|c| *c == val) {
+        Some(idx) => idx as u32,
+        None => {
+            pool.push(val);
+            pool.len() as u32 - 1
+        }
+    }
+}
+
+fn write_function(bytes: &mut Vec<u8>, pool: &mut Vec<Value>, func: &Function) {
+    let mut write_string = |bytes: &mut Vec<u8>, text: &str| {
+        write_u32(bytes, pool_index(pool, Value::String(text.to_string())))
+    };
+    write_string(bytes, &func.name);
+    write_u32(bytes, func.arity);
+    write_u32(bytes, func.locals.len() as u32);
+    for local in func.locals.iter() {
+        write_string(bytes, local);
+    }
+    write_u32(bytes, func.consts.len() as u32);
+    for val in func.consts.iter() {
+        write_u32(bytes, pool_index(pool, val.clone()));
+    }
+    write_u32(bytes, func.code.len() as u32);
+    for instr in func.code.iter() {
+        write_instr(bytes, instr);
+    }
+    write_u32(bytes, func.lines.len() as u32);
+    for (pc, idx, kind) in func.lines.iter() {
+        write_u32(bytes, *pc);
+        write_u32(bytes, *idx);
+        write_u32(bytes, pool_index(pool, Value::String(kind.to_string())));
+    }
+    write_u32(bytes, func.source_lines.len() as u32);
+    for line in func.source_lines.iter() {
+        write_u32(bytes, *line);
+    }
+}
+
+// The tag of an instruction is the index of its variant, followed by its operands
+fn write_instr(bytes: &mut Vec<u8>, instr: &Instr) {
+    let (tag, operands): (u8, Vec<u32>) = match *instr {
+        Instr::Int(num) => (0, vec![num as u32]),
+        Instr::Bool(b) => (1, vec![b as u32]),
+        Instr::Const(idx) => (2, vec![idx]),
+        Instr::Unit => (3, vec![]),
+        Instr::Load(slot) => (4, vec![slot]),
+        Instr::Store(slot) => (5, vec![slot]),
+        Instr::Pop => (6, vec![]),
+        Instr::Neg => (7, vec![]),
+        Instr::BinOp(op) => (8, vec![OPCODES.iter().position(|o| *o == op).unwrap() as u32]),
+        Instr::Jump(offset) => (9, vec![offset as u32]),
+        Instr::JumpIfFalse(offset) => (10, vec![offset as u32]),
+        Instr::Call(func, argc) => (11, vec![func, argc]),
+        Instr::Return => (12, vec![]),
+        Instr::Print => (13, vec![]),
+        Instr::PrintVar(idx) => (14, vec![idx]),
+        Instr::MakeArray(len) => (15, vec![len]),
+        Instr::MakeTuple(len) => (16, vec![len]),
+        Instr::MakeVec(len) => (17, vec![len]),
+        Instr::Repeat(len) => (18, vec![len]),
+        Instr::Index => (19, vec![]),
+        Instr::TupleField(idx) => (20, vec![idx]),
+        Instr::SetIndex(slot) => (21, vec![slot]),
+        Instr::Len => (22, vec![]),
+        Instr::Push(slot) => (23, vec![slot]),
+        Instr::PopElem(slot) => (24, vec![slot]),
+        Instr::And(offset) => (25, vec![offset as u32]),
+        Instr::Or(offset) => (26, vec![offset as u32]),
+        Instr::Arith(op, overflow) => (
+            27,
+            vec![
+                OPCODES.iter().position(|o| *o == op).unwrap() as u32,
+                OVERFLOWS.iter().position(|o| *o == overflow).unwrap() as u32,
+            ],
+        ),
+        Instr::LoadIndex(slot) => (28, vec![slot]),
+    };
+    bytes.push(tag);
+    for operand in operands {
+        write_u32(bytes, operand);
+    }
+}
+
+/// The 32 bit FNV-1a hash of the bytes
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LoadError> {
+        if self.bytes.len() - self.pos < len {
+            return Err(LoadError::Truncated);
+        }
+        self.pos += len;
+        Ok(&self.bytes[self.pos - len..self.pos])
+    }
+
+    fn u8(&mut self) -> Result<u8, LoadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, LoadError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, LoadError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn i32(&mut self) -> Result<i32, LoadError> {
+        Ok(self.u32()? as i32)
+    }
+
+    fn constant(&mut self) -> Result<Value, LoadError> {
+        match self.u8()? {
+            0 => Ok(Value::Number(self.i32()?)),
+            1 => match self.u8()? {
+                0 => Ok(Value::Bool(false)),
+                1 => Ok(Value::Bool(true)),
+                b => invalid(format!("{} is not a bool", b)),
+            },
+            2 => {
+                let len = self.u32()? as usize;
+                match String::from_utf8(self.take(len)?.to_vec()) {
+                    Ok(text) => Ok(Value::String(text)),
+                    Err(_) => invalid(String::from("a string constant is not valid UTF-8")),
+                }
+            }
+            3 => Ok(Value::Unit),
+            tag => invalid(format!("{} is not the tag of a constant", tag)),
+        }
+    }
+
+    /// Reads the index of a string in the constant pool
+    fn string(&mut self, pool: &[Value]) -> Result<String, LoadError> {
+        let idx = self.u32()?;
+        match pool.get(idx as usize) {
+            Some(Value::String(text)) => Ok(text.to_string()),
+            Some(_) => invalid(format!("constant {} is not a string", idx)),
+            None => invalid(format!("constant {} does not exist", idx)),
+        }
+    }
+
+    fn function(&mut self, pool: &[Value]) -> Result<Function, LoadError> {
+        let name = self.string(pool)?;
+        let arity = self.u32()?;
+        let mut locals = vec![];
+        for _ in 0..self.u32()? {
+            locals.push(self.string(pool)?);
+        }
+        let mut consts = vec![];
+        for _ in 0..self.u32()? {
+            let idx = self.u32()?;
+            match pool.get(idx as usize) {
+                Some(val) => consts.push(val.clone()),
+                None => return invalid(format!("constant {} does not exist", idx)),
+            }
+        }
+        let mut code = vec![];
+        for _ in 0..self.u32()? {
+            code.push(self.instr()?);
+        }
+        let mut lines = vec![];
+        for _ in 0..self.u32()? {
+            lines.push((self.u32()?, self.u32()?, self.string(pool)?));
+        }
+        let mut source_lines = vec![];
+        for _ in 0..self.u32()? {
+            source_lines.push(self.u32()?);
+        }
+        Ok(Function {
+            name: name,
+            arity: arity,
+            locals: locals,
+            consts: consts,
+            code: code,
+            lines: lines,
+            source_lines: source_lines,
+        })
+    }
+
+    fn opcode(&mut self) -> Result<Opcode, LoadError> {
+        let op = self.u32()?;
+        match OPCODES.get(op as usize) {
+            Some(op) => Ok(*op),
+            None => invalid(format!("{} is not an operator", op)),
+        }
+    }
+
+    fn instr(&mut self) -> Result<Instr, LoadError> {
+        let instr = match self.u8()? {
+            0 => Instr::Int(self.i32()?),
+            1 => match self.u32()? {
+                0 => Instr::Bool(false),
+                1 => Instr::Bool(true),
+                b => return invalid(format!("{} is not a bool", b)),
+            },
+            2 => Instr::Const(self.u32()?),
+            3 => Instr::Unit,
+            4 => Instr::Load(self.u32()?),
+            5 => Instr::Store(self.u32()?),
+            6 => Instr::Pop,
+            7 => Instr::Neg,
+            8 => Instr::BinOp(self.opcode()?),
+            9 => Instr::Jump(self.i32()?),
+            10 => Instr::JumpIfFalse(self.i32()?),
+            11 => Instr::Call(self.u32()?, self.u32()?),
+            12 => Instr::Return,
+            13 => Instr::Print,
+            14 => Instr::PrintVar(self.u32()?),
+            15 => Instr::MakeArray(self.u32()?),
+            16 => Instr::MakeTuple(self.u32()?),
+            17 => Instr::MakeVec(self.u32()?),
+            18 => Instr::Repeat(self.u32()?),
+            19 => Instr::Index,
+            20 => Instr::TupleField(self.u32()?),
+            21 => Instr::SetIndex(self.u32()?),
+            22 => Instr::Len,
+            23 => Instr::Push(self.u32()?),
+            24 => Instr::PopElem(self.u32()?),
+            25 => Instr::And(self.i32()?),
+            26 => Instr::Or(self.i32()?),
+            27 => {
+                let op = self.opcode()?;
+                let overflow = self.u32()?;
+                let arith_ops = [Opcode::Add, Opcode::Sub, Opcode::Mul, Opcode::Div];
+                match OVERFLOWS.get(overflow as usize) {
+                    Some(_) if !arith_ops.contains(&op) => {
+                        return invalid(format!("'{}' is not arithmetic", op.to_string()))
+                    }
+                    Some(overflow) => Instr::Arith(op, *overflow),
+                    None => return invalid(format!("{} is not an overflow behaviour", overflow)),
+                }
+            }
+            28 => Instr::LoadIndex(self.u32()?),
+            tag => return invalid(format!("{} is not the tag of an instruction", tag)),
+        };
+        Ok(instr)
+    }
+}
+
+/// Checks that the instructions of a function only refer to things that exist, and that
+/// every instruction is reached with the same number of values on the stack whichever way
+/// it is reached, so that the stack never runs out of values
+fn validate(bytecode: &Bytecode, func: &Function) -> Result<(), LoadError> {
+    let in_fn = |reason: String| invalid(format!("{} in function '{}'", reason, func.name));
+    if func.arity as usize > func.locals.len() {
+        return in_fn(String::from("there are fewer slots than parameters"));
+    }
+    if func.lines.iter().any(|(pc, ..)| *pc as usize >= func.code.len())
+        || func.lines.windows(2).any(|pair| pair[0].0 >= pair[1].0)
+    {
+        return in_fn(String::from("the line table does not match the code"));
+    }
+
+    // The number of values on the stack before each instruction, which is None until the
+    // instruction is reached
+    let mut heights: Vec<Option<usize>> = vec![None; func.code.len()];
+    if func.code.is_empty() {
+        return in_fn(String::from("there is no code"));
+    }
+    let mut reached = vec![(0, 0)];
+    while let Some((pc, height)) = reached.pop() {
+        let instr = &func.code[pc];
+        match heights[pc] {
+            Some(h) if h == height => continue,
+            Some(_) => return in_fn(format!("the stack height differs at {}", pc)),
+            None => heights[pc] = Some(height),
+        }
+
+        let slot = |slot: &u32| match (*slot as usize) < func.locals.len() {
+            true => Ok(()),
+            false => in_fn(format!("slot {} does not exist at {}", slot, pc)),
+        };
+        let constant = |idx: &u32| match (*idx as usize) < func.consts.len() {
+            true => Ok(()),
+            false => in_fn(format!("constant {} does not exist at {}", idx, pc)),
+        };
+        let (pops, pushes) = match instr {
+            Instr::Int(_) | Instr::Bool(_) | Instr::Unit => (0, 1),
+            Instr::Const(idx) => {
+                constant(idx)?;
+                (0, 1)
+            }
+            Instr::Load(s) => {
+                slot(s)?;
+                (0, 1)
+            }
+            Instr::Store(s) => {
+                slot(s)?;
+                (1, 0)
+            }
+            Instr::Pop | Instr::Print | Instr::JumpIfFalse(_) => (1, 0),
+            Instr::Repeat(len) if *len > MAX_REPEAT => {
+                return in_fn(format!("the array of {} elements is too long at {}", len, pc))
+            }
+            Instr::Neg | Instr::Repeat(_) | Instr::TupleField(_) | Instr::Len => (1, 1),
+            // The left operand is kept on the stack
+            Instr::And(_) | Instr::Or(_) => (1, 1),
+            Instr::BinOp(_) | Instr::Arith(_, _) | Instr::Index => (2, 1),
+            Instr::Jump(_) => (0, 0),
+            Instr::Call(id, argc) => match bytecode.functions.get(*id as usize) {
+                Some(callee) if callee.arity == *argc => (*argc as usize, 1),
+                Some(callee) => {
+                    return in_fn(format!(
+                        "'{}' is called with {} arguments at {}",
+                        callee.name, argc, pc
+                    ))
+                }
+                None => return in_fn(format!("function {} does not exist at {}", id, pc)),
+            },
+            Instr::Return => (1, 0),
+            Instr::PrintVar(idx) => match func.consts.get(*idx as usize) {
+                Some(Value::String(_)) => (1, 0),
+                _ => return in_fn(format!("constant {} is not a name at {}", idx, pc)),
+            },
+            Instr::MakeArray(len) | Instr::MakeTuple(len) | Instr::MakeVec(len) => {
+                (*len as usize, 1)
+            }
+            Instr::SetIndex(s) => {
+                slot(s)?;
+                (2, 0)
+            }
+            Instr::LoadIndex(s) => {
+                slot(s)?;
+                (1, 1)
+            }
+            Instr::Push(s) => {
+                slot(s)?;
+                (1, 1)
+            }
+            Instr::PopElem(s) => {
+                slot(s)?;
+                (0, 1)
+            }
+        };
+        if height < pops {
+            return in_fn(format!("the stack runs out of values at {}", pc));
+        }
+        let height = height - pops + pushes;
+
+        let next = pc as isize + 1;
+        let successors = match instr {
+            Instr::Return => vec![],
+            Instr::Jump(offset) => vec![next + *offset as isize],
+            Instr::JumpIfFalse(offset) | Instr::And(offset) | Instr::Or(offset) => {
+                vec![next + *offset as isize, next]
+            }
+            _ => vec![next],
+        };
+        for succ in successors {
+            if succ < 0 || succ as usize >= func.code.len() {
+                return in_fn(format!("the code continues outside of it after {}", pc));
+            }
+            reached.push((succ as usize, height));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        bytecode::{compiler::compile, vm::run},
+        limits::Limits,
+        parse::program_parser::{parse, parse_with_lines},
+    };
+
+    fn compile_str(input: &str) -> Bytecode {
+        compile(&parse(input.to_string()).unwrap()).unwrap()
+    }
+
+    // Changes a byte of a file and updates its checksum, so that the change is found by the
+    // validation and not by the checksum
+    fn tamper(bytes: &mut [u8], pos: usize, byte: u8) {
+        bytes[pos] = byte;
+        let end = bytes.len() - 4;
+        let checksum = checksum(&bytes[..end]);
+        bytes[end..].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    // Every test program which can be compiled to bytecode is the same after it is loaded
+    #[test]
+    fn round_trip() {
+        let mut compiled = 0;
+        for dir in fs::read_dir("tests").unwrap() {
+            for file in fs::read_dir(dir.unwrap().path()).unwrap() {
+                let input = fs::read_to_string(file.unwrap().path()).unwrap();
+                let bytecode = match compile(&parse(input).unwrap()) {
+                    Ok(bytecode) => bytecode,
+                    Err(_) => continue,
+                };
+                compiled += 1;
+                assert_eq!(deserialize(&serialize(&bytecode)), Ok(bytecode));
+            }
+        }
+        assert!(compiled > 20);
+    }
+
+    #[test]
+    fn run_loaded() {
+        let bytecode = compile_str(
+            "fn greet(name: String, times: i32) -> i32 {
+                let mut i: i32 = 0;
+                while (i < times) {
+                    print(name);
+                    i = i + 1;
+                }
+                return 0 - i * 3;
+            }
+
+            fn main() -> i32 {
+                return greet(\"world\", 2);
+            }",
+        );
+        let loaded = deserialize(&serialize(&bytecode)).unwrap();
+        assert_eq!(run(&loaded, Limits::new()), Ok(Some(Value::Number(-6))));
+    }
+
+    // The line of a runtime error is found in a program which was loaded from a file
+    #[test]
+    fn lines_kept() {
+        let (program, lines) = parse_with_lines(
+            "fn main() -> i32 {
+                let a: [i32; 2] = [1, 2];
+                let i: i32 = 2;
+                return a[i];
+            }"
+            .to_string(),
+        )
+        .unwrap();
+        let bytecode = compile(&program).unwrap().with_lines(&lines);
+        let loaded = deserialize(&serialize(&bytecode)).unwrap();
+        assert_eq!(loaded.lines(), lines);
+        let err = run(&loaded, Limits::new()).unwrap_err();
+        assert_eq!(err.locate(&loaded.lines()).line, Some(4));
+    }
+
+    #[test]
+    fn reject_incompatible() {
+        let bytes = serialize(&compile_str("fn main() { print(1); }"));
+        assert_eq!(deserialize(b"\x7fELF\x02\x01"), Err(LoadError::NotBytecode));
+        assert_eq!(deserialize(b"D7"), Err(LoadError::NotBytecode));
+
+        let mut newer = bytes.clone();
+        newer[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert_eq!(
+            deserialize(&newer),
+            Err(LoadError::Version { found: VERSION + 1 })
+        );
+        assert_eq!(
+            LoadError::Version { found: 1 }.to_string(),
+            "The bytecode file has version 1, but only version 6 is supported"
+        );
+    }
+
+    #[test]
+    fn reject_corrupt() {
+        let bytecode = compile_str(
+            "fn f(n: i32) -> i32 {
+                return n;
+            }
+
+            fn main() -> i32 {
+                return f(1);
+            }",
+        );
+        let bytes = serialize(&bytecode);
+        assert_eq!(deserialize(&bytes[..5]), Err(LoadError::Truncated));
+        assert_eq!(deserialize(&bytes[..8]), Err(LoadError::Truncated));
+
+        // Every truncation and every change of a single bit is rejected
+        for len in 0..bytes.len() {
+            assert!(deserialize(&bytes[..len]).is_err());
+        }
+        for pos in 0..bytes.len() {
+            for bit in 0..8 {
+                let mut corrupt = bytes.clone();
+                corrupt[pos] ^= 1 << bit;
+                assert!(deserialize(&corrupt).is_err());
+            }
+        }
+        let mut corrupt = bytes.clone();
+        corrupt[20] ^= 1;
+        assert_eq!(deserialize(&corrupt), Err(LoadError::Checksum));
+
+        let mut longer = bytes[..bytes.len() - 4].to_vec();
+        longer.push(0);
+        let checksum = checksum(&longer);
+        write_u32(&mut longer, checksum);
+        assert_eq!(
+            deserialize(&longer),
+            invalid(String::from("it has data after the main function"))
+        );
+    }
+
+    #[test]
+    fn reject_invalid() {
+        let bytecode = Bytecode {
+            functions: vec![Function {
+                name: String::from("main"),
+                arity: 0,
+                locals: vec![String::from("x")],
+                consts: vec![],
+                code: vec![Instr::Int(1), Instr::Store(0), Instr::Unit, Instr::Return],
+                lines: vec![(0, 0, String::from("let"))],
+                source_lines: vec![],
+            }],
+            main: 0,
+        };
+        let bytes = serialize(&bytecode);
+        assert_eq!(deserialize(&bytes), Ok(bytecode.clone()));
+        // The code of 12 bytes is followed by the line table, the source lines, main and the
+        // checksum
+        let code = bytes.len() - 12 - 16 - 4 - 4 - 4;
+        assert_eq!(bytes[code], 0);
+
+        let mut tag = bytes.clone();
+        tamper(&mut tag, code, 99);
+        assert_eq!(
+            deserialize(&tag),
+            invalid(String::from("99 is not the tag of an instruction"))
+        );
+
+        let mut slot = bytes.clone();
+        tamper(&mut slot, code + 6, 1);
+        assert_eq!(
+            deserialize(&slot),
+            invalid(String::from("slot 1 does not exist at 1 in function 'main'"))
+        );
+
+        let mut pop = bytes.clone();
+        tamper(&mut pop, code, 6);
+        assert!(deserialize(&pop).is_err());
+
+        let invalid_code = |code: Vec<Instr>| {
+            let mut func = bytecode.functions[0].clone();
+            func.code = code;
+            deserialize(&serialize(&Bytecode {
+                functions: vec![func],
+                main: 0,
+            }))
+        };
+        assert_eq!(
+            invalid_code(vec![Instr::Pop, Instr::Unit, Instr::Return]),
+            invalid(String::from(
+                "the stack runs out of values at 0 in function 'main'"
+            ))
+        );
+        assert_eq!(
+            invalid_code(vec![Instr::Unit, Instr::Jump(-3)]),
+            invalid(String::from(
+                "the code continues outside of it after 1 in function 'main'"
+            ))
+        );
+        assert_eq!(
+            invalid_code(vec![Instr::Unit, Instr::Jump(-2)]),
+            invalid(String::from(
+                "the stack height differs at 0 in function 'main'"
+            ))
+        );
+        assert_eq!(
+            invalid_code(vec![Instr::Call(0, 1), Instr::Return]),
+            invalid(String::from(
+                "'main' is called with 1 arguments at 0 in function 'main'"
+            ))
+        );
+        assert_eq!(
+            invalid_code(vec![Instr::Unit, Instr::Unit]),
+            invalid(String::from(
+                "the code continues outside of it after 1 in function 'main'"
+            ))
+        );
+        assert_eq!(
+            invalid_code(vec![Instr::Unit, Instr::Repeat(u32::MAX), Instr::Return]),
+            invalid(String::from(
+                "the array of 4294967295 elements is too long at 1 in function 'main'"
+            ))
+        );
+    }
+}
@@ -0,0 +1,135 @@
+pub mod compiler;
+pub mod disassembler;
+pub mod file;
+pub mod vm;
+
+use crate::{
+    arithmetic::Overflow, operators::Opcode, parse::program_parser::FuncLines, value::Value,
+};
+
+/// The length of the longest array that can be created by repeating a value, so that a
+/// bytecode file cannot make the virtual machine allocate an unbounded amount of memory
+pub const MAX_REPEAT: u32 = 1 << 24;
+
+/// An instruction of the virtual machine, which evaluates expressions on a stack of values.
+/// The offset of a jump is relative to the instruction after it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instr {
+    // Pushes a literal or a constant of the function, i.e. a string
+    Int(i32),
+    Bool(bool),
+    Const(u32),
+    Unit,
+
+    // The local variables of a call are stored in the slots of its frame, starting with the
+    // parameters
+    Load(u32),
+    Store(u32),
+    Pop,
+
+    Neg,
+    BinOp(Opcode),
+    // An arithmetic operator with another overflow behaviour than the one of the program,
+    // i.e. "wrapping_add"
+    Arith(Opcode, Overflow),
+
+    Jump(i32),
+    // Pops a condition and jumps if it is false
+    JumpIfFalse(i32),
+    // Jumps over the right operand of a logical operation if the value of the left one on
+    // top of the stack is false or true respectively, it is then the result of the operation
+    And(i32),
+    Or(i32),
+
+    // Calls the function at an index with the number of arguments on top of the stack, the
+    // value it returns replaces them
+    Call(u32, u32),
+    Return,
+
+    // Prints the value on top of the stack, along with the name of the variable in a slot
+    // if it is the value of a variable
+    Print,
+    PrintVar(u32),
+
+    // Creates an array, a tuple or a vector of the number of values on top of the stack
+    MakeArray(u32),
+    MakeTuple(u32),
+    MakeVec(u32),
+    // Creates an array which repeats the value on top of the stack
+    Repeat(u32),
+    Index,
+    TupleField(u32),
+    // Pops an index and a value and assigns the value to the element of the array or vector
+    // in a slot
+    SetIndex(u32),
+    // Pops an index and pushes the element of the array or vector in a slot, without
+    // copying the rest of it
+    LoadIndex(u32),
+    Len,
+    // Pushes a value to or pops a value from the vector in a slot
+    Push(u32),
+    PopElem(u32),
+}
+
+/// A function compiled to bytecode
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub arity: u32,
+    // The names of the local variables in the order of their slots, a variable which is
+    // shadowed gets a slot of its own
+    pub locals: Vec<String>,
+    pub consts: Vec<Value>,
+    pub code: Vec<Instr>,
+    // The statement which the code belongs to from each position on, by its index in the
+    // function and its kind. The statements are numbered in the order they appear in, like
+    // the interpreter does, so that the line of a runtime error can be found from them
+    pub lines: Vec<(u32, u32, String)>,
+    // The line in the source of each statement by its index, which is empty if the source is
+    // not known
+    pub source_lines: Vec<u32>,
+}
+
+impl Function {
+    /// Gets the index and the kind of the statement that the instruction at pc belongs to
+    pub fn stmnt_at(&self, pc: usize) -> Option<(usize, String)> {
+        let line = self.lines.partition_point(|(start, ..)| *start as usize <= pc);
+        match line {
+            0 => None,
+            line => {
+                let (_, idx, kind) = &self.lines[line - 1];
+                Some((*idx as usize, kind.to_string()))
+            }
+        }
+    }
+}
+
+/// A program compiled to bytecode, the functions are called by their index
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bytecode {
+    pub functions: Vec<Function>,
+    pub main: u32,
+}
+
+impl Bytecode {
+    /// Sets the lines in the source of the statements of the functions, so that they are
+    /// kept when the program is written to a bytecode file
+    pub fn with_lines(mut self, lines: &FuncLines) -> Self {
+        for func in self.functions.iter_mut() {
+            if let Some(lines) = lines.get(&func.name) {
+                func.source_lines = lines.iter().map(|line| *line as u32).collect();
+            }
+        }
+        self
+    }
+
+    /// Gets the lines in the source of the statements of the functions, which are used to
+    /// find the line of a runtime error
+    pub fn lines(&self) -> FuncLines {
+        let lines = self.functions.iter().map(|func| {
+            let lines = func.source_lines.iter().map(|line| *line as usize).collect();
+            (func.name.to_string(), lines)
+        });
+        lines.collect()
+    }
+}
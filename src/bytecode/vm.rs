@@ -0,0 +1,549 @@
+use super::{Bytecode, Instr};
+use crate::{
+    arithmetic::negate,
+    interpreter::{check_index, eval_bin_op, eval_cond, value_type},
+    limits::{Budget, Limits},
+    runtime_error::{RuntimeError, RuntimeErrorKind},
+    value::Value,
+};
+use std::mem;
+
+/// The call of a function which has not yet returned, the slots of its local variables
+/// start at the base of the stack
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Frame {
+    func: usize,
+    pc: usize,
+    base: usize,
+}
+
+/// Runs the main function of a program compiled to bytecode within the limits given, every
+/// instruction that is executed is one step. Returns None if main does not return a value
+pub fn run(bytecode: &Bytecode, limits: Limits) -> Result<Option<Value>, RuntimeError> {
+    let mut vm = Vm {
+        bytecode: bytecode,
+        stack: vec![],
+        frames: vec![],
+        budget: Budget::new(limits),
+    };
+    let mut frame = match vm.call(bytecode.main as usize, 0) {
+        Ok(frame) => frame,
+        Err(kind) => return Err(vm.trace(kind)),
+    };
+    match vm.execute(&mut frame) {
+        Ok(res) => Ok(res),
+        Err(kind) => {
+            // The pc has moved past the instruction which failed
+            let stmnt = bytecode.functions[frame.func].stmnt_at(frame.pc - 1);
+            vm.frames.push(frame);
+            let err = vm.trace(kind);
+            match stmnt {
+                Some((idx, stmnt)) => Err(err.at(Some(stmnt), idx)),
+                None => Err(err),
+            }
+        }
+    }
+}
+
+struct Vm<'a> {
+    bytecode: &'a Bytecode,
+    stack: Vec<Value>,
+    // The callers of the function which is executed
+    frames: Vec<Frame>,
+    budget: Budget,
+}
+
+impl<'a> Vm<'a> {
+    /// Executes the instructions of the functions that are called until main returns, the
+    /// frame is the one of the function that is executed
+    fn execute(&mut self, frame: &mut Frame) -> Result<Option<Value>, RuntimeErrorKind> {
+        let bytecode = self.bytecode;
+        let mut code = &bytecode.functions[frame.func].code;
+        loop {
+            let instr = code[frame.pc];
+            frame.pc += 1;
+            self.budget.step()?;
+
+            match instr {
+                Instr::Int(num) => self.stack.push(Value::Number(num)),
+                Instr::Bool(b) => self.stack.push(Value::Bool(b)),
+                Instr::Const(idx) => {
+                    let val = bytecode.functions[frame.func].consts[idx as usize].clone();
+                    self.stack.push(val);
+                }
+                Instr::Unit => self.stack.push(Value::Unit),
+                Instr::Load(slot) => {
+                    let val = self.stack[frame.base + slot as usize].clone();
+                    self.stack.push(val);
+                }
+                Instr::Store(slot) => {
+                    let val = self.pop();
+                    self.stack[frame.base + slot as usize] = val;
+                }
+                Instr::Pop => {
+                    self.pop();
+                }
+                Instr::Neg => match self.pop() {
+                    Value::Number(n) => {
+                        let overflow = self.budget.limits.overflow;
+                        self.stack.push(Value::Number(negate(n, overflow)?))
+                    }
+                    val => {
+                        return Err(RuntimeErrorKind::UnaryOpWrongType {
+                            found: value_type(&val),
+                        })
+                    }
+                },
+                Instr::BinOp(op) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let overflow = self.budget.limits.overflow;
+                    self.stack.push(eval_bin_op(left, op, right, overflow)?);
+                }
+                Instr::Arith(op, overflow) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    self.stack.push(eval_bin_op(left, op, right, overflow)?);
+                }
+                Instr::Jump(offset) => frame.pc = jump(frame.pc, offset),
+                Instr::JumpIfFalse(offset) => {
+                    if !eval_cond(self.pop())? {
+                        frame.pc = jump(frame.pc, offset);
+                    }
+                }
+                Instr::And(offset) => {
+                    if self.stack.last() == Some(&Value::Bool(false)) {
+                        frame.pc = jump(frame.pc, offset);
+                    }
+                }
+                Instr::Or(offset) => {
+                    if self.stack.last() == Some(&Value::Bool(true)) {
+                        frame.pc = jump(frame.pc, offset);
+                    }
+                }
+                Instr::Call(func, argc) => {
+                    let callee = self.call(func as usize, argc as usize)?;
+                    self.frames.push(*frame);
+                    *frame = callee;
+                    code = &bytecode.functions[frame.func].code;
+                }
+                Instr::Return => {
+                    let val = self.pop();
+                    self.stack.truncate(frame.base);
+                    self.budget.exit_call();
+                    match self.frames.pop() {
+                        Some(caller) => {
+                            *frame = caller;
+                            code = &bytecode.functions[frame.func].code;
+                            self.stack.push(val);
+                        }
+                        None => {
+                            return match val {
+                                Value::Unit => Ok(None),
+                                val => Ok(Some(val)),
+                            }
+                        }
+                    }
+                }
+                Instr::Print => println!("{:#?}", self.pop()),
+                Instr::PrintVar(name) => {
+                    let val = self.pop();
+                    match &bytecode.functions[frame.func].consts[name as usize] {
+                        Value::String(name) => println!("{:#?} = {:#?}", name, val),
+                        _ => unreachable!(),
+                    }
+                }
+                Instr::MakeArray(len) => {
+                    let elems = self.pop_n(len);
+                    self.stack.push(Value::Array(elems));
+                }
+                Instr::MakeTuple(len) => {
+                    let elems = self.pop_n(len);
+                    self.stack.push(Value::Tuple(elems));
+                }
+                Instr::MakeVec(len) => {
+                    let vec = Value::Vec(self.pop_n(len));
+                    if self.budget.counts_memory() {
+                        self.budget.reserve(vec.size())?;
+                    }
+                    self.stack.push(vec);
+                }
+                Instr::Repeat(len) => {
+                    let val = self.pop();
+                    if self.budget.counts_memory() {
+                        self.budget.reserve(val.size().saturating_mul(len as usize))?;
+                    }
+                    self.stack.push(Value::Array(vec![val; len as usize]));
+                }
+                Instr::Index => {
+                    let idx = self.pop();
+                    let elem = match self.pop() {
+                        Value::Array(mut elems) | Value::Vec(mut elems) => {
+                            let i = check_index(elems.len(), idx)?;
+                            elems.swap_remove(i)
+                        }
+                        val => {
+                            return Err(RuntimeErrorKind::IndexNotArray {
+                                found: value_type(&val),
+                            })
+                        }
+                    };
+                    self.stack.push(elem);
+                }
+                Instr::TupleField(idx) => {
+                    let elem = match self.pop() {
+                        Value::Tuple(mut elems) if (idx as usize) < elems.len() => {
+                            elems.swap_remove(idx as usize)
+                        }
+                        val => {
+                            return Err(RuntimeErrorKind::FieldNotFound {
+                                field: idx.to_string(),
+                                found: value_type(&val),
+                            })
+                        }
+                    };
+                    self.stack.push(elem);
+                }
+                Instr::LoadIndex(slot) => {
+                    let idx = self.pop();
+                    let elem = match &self.stack[frame.base + slot as usize] {
+                        Value::Array(elems) | Value::Vec(elems) => {
+                            elems[check_index(elems.len(), idx)?].clone()
+                        }
+                        arr => {
+                            return Err(RuntimeErrorKind::IndexNotArray {
+                                found: value_type(arr),
+                            })
+                        }
+                    };
+                    self.stack.push(elem);
+                }
+                Instr::SetIndex(slot) => {
+                    let idx = self.pop();
+                    let val = self.pop();
+                    match &mut self.stack[frame.base + slot as usize] {
+                        Value::Array(elems) | Value::Vec(elems) => {
+                            let i = check_index(elems.len(), idx)?;
+                            elems[i] = val;
+                        }
+                        arr => {
+                            return Err(RuntimeErrorKind::IndexNotArray {
+                                found: value_type(arr),
+                            })
+                        }
+                    }
+                }
+                Instr::Len => match self.pop() {
+                    Value::Array(elems) | Value::Vec(elems) => {
+                        self.stack.push(Value::Number(elems.len() as i32))
+                    }
+                    val => return Err(method_not_found("len", &val)),
+                },
+                Instr::Push(slot) => {
+                    let val = self.pop();
+                    let counts_memory = self.budget.counts_memory();
+                    match &mut self.stack[frame.base + slot as usize] {
+                        // The memory owned by the other elements is not counted, so that a
+                        // push does not take time linear in the length of the vector
+                        Value::Vec(elems) if counts_memory => {
+                            let size = (elems.len() + 1) * mem::size_of::<Value>() + val.size();
+                            self.budget.reserve(size)?;
+                            elems.push(val)
+                        }
+                        Value::Vec(elems) => elems.push(val),
+                        vec => return Err(method_not_found("push", vec)),
+                    }
+                    self.stack.push(Value::Unit);
+                }
+                Instr::PopElem(slot) => {
+                    let elem = match &mut self.stack[frame.base + slot as usize] {
+                        Value::Vec(elems) => elems.pop().ok_or(RuntimeErrorKind::PopEmpty)?,
+                        vec => return Err(method_not_found("pop", vec)),
+                    };
+                    self.stack.push(elem);
+                }
+            }
+        }
+    }
+
+    /// Starts a call to a function with its arguments on top of the stack and returns its
+    /// frame, the slots of its other local variables are Value::Unit until they are assigned
+    fn call(&mut self, func: usize, argc: usize) -> Result<Frame, RuntimeErrorKind> {
+        let callee = &self.bytecode.functions[func];
+        self.budget.enter_call(&callee.name)?;
+        let base = self.stack.len() - argc;
+        self.stack.resize(base + callee.locals.len(), Value::Unit);
+        Ok(Frame {
+            func: func,
+            pc: 0,
+            base: base,
+        })
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap()
+    }
+
+    fn pop_n(&mut self, n: u32) -> Vec<Value> {
+        self.stack.split_off(self.stack.len() - n as usize)
+    }
+
+    /// Adds the calls which had not returned when an error occurred to its trace, the frame
+    /// of the function it occurred in has to be the last one
+    fn trace(&self, kind: RuntimeErrorKind) -> RuntimeError {
+        let mut err = RuntimeError::from(kind);
+        for frame in self.frames.iter().rev() {
+            err = err.called_from(&self.bytecode.functions[frame.func].name);
+        }
+        err
+    }
+}
+
+fn jump(pc: usize, offset: i32) -> usize {
+    (pc as isize + offset as isize) as usize
+}
+
+fn method_not_found(name: &str, recv: &Value) -> RuntimeErrorKind {
+    RuntimeErrorKind::MethodNotFound {
+        name: name.to_string(),
+        found: value_type(recv),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arithmetic::Overflow, bytecode::compiler::compile, interpreter::interp,
+        operators::Opcode,
+        parse::program_parser::{parse, parse_with_lines},
+    };
+    use std::{fs, time::Instant};
+
+    fn compile_run(input: &str) -> Result<Option<Value>, RuntimeError> {
+        let bytecode = compile(&parse(input.to_string()).unwrap()).unwrap();
+        run(&bytecode, Limits::new())
+    }
+
+    // Every test program which can be compiled to bytecode gives the same result as when it
+    // is interpreted, or stops with the same error in the same statement
+    #[test]
+    fn same_as_interpreter() {
+        let mut compiled = 0;
+        for dir in fs::read_dir("tests").unwrap() {
+            for file in fs::read_dir(dir.unwrap().path()).unwrap() {
+                let input = fs::read_to_string(file.unwrap().path()).unwrap();
+                let program = parse(input).unwrap();
+                let bytecode = match compile(&program) {
+                    Ok(bytecode) => bytecode,
+                    Err(_) => continue,
+                };
+                compiled += 1;
+                match (run(&bytecode, Limits::new()), interp(program)) {
+                    (Err(vm_err), Err(err)) => {
+                        assert_eq!(vm_err.kind, err.kind);
+                        assert_eq!(vm_err.stmnt, err.stmnt);
+                        assert_eq!(vm_err.stmnt_idx, err.stmnt_idx);
+                    }
+                    (res, interp_res) => assert_eq!(res, interp_res),
+                }
+            }
+        }
+        assert!(compiled > 20);
+    }
+
+    #[test]
+    fn vec_methods() {
+        let res = compile_run(
+            "fn main() -> i32 {
+                let mut v: Vec<i32> = Vec::new();
+                let mut i: i32 = 0;
+                while (i < 5) {
+                    v.push(i * i);
+                    i = i + 1;
+                }
+                v[0] = v.pop() + v.len();
+                return v[0] + v[3];
+            }",
+        );
+        assert_eq!(res, Ok(Some(Value::Number(29))));
+    }
+
+    #[test]
+    fn vm_errors() {
+        let err = compile_run(
+            "fn get(a: [i32; 3], i: i32) -> i32 {
+                return a[i];
+            }
+
+            fn main() -> i32 {
+                let a: [i32; 3] = [0; 3];
+                return get(a, 3);
+            }",
+        )
+        .unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::IndexOutOfBounds { len: 3, index: 3 });
+        assert_eq!(err.trace, vec![String::from("get"), String::from("main")]);
+
+        let err = compile_run(
+            "fn down(n: i32) -> i32 {
+                return down(n - 1) + 1;
+            }
+
+            fn main() -> i32 {
+                return down(0);
+            }",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Stack overflow in function 'down', calls cannot be nested more than 1000 deep
+    at the return statement in 'down'
+    called from 'down' 998 times
+    called from 'main'"
+        );
+    }
+
+    #[test]
+    fn vm_max_memory() {
+        let bytecode = compile(
+            &parse(
+                "fn main() -> i32 {
+                    let a: [i32; 10000000] = [0; 10000000];
+                    return a[0];
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let mut limits = Limits::new();
+        limits.max_memory = Some(4096);
+        assert_eq!(
+            run(&bytecode, limits).unwrap_err().kind,
+            RuntimeErrorKind::OutOfMemory { max_memory: 4096 }
+        );
+
+        // A vector which is pushed to without end
+        let bytecode = compile(
+            &parse(
+                "fn main() -> i32 {
+                    let mut v: Vec<i32> = Vec::new();
+                    while (true) {
+                        v.push(1);
+                    }
+                    return 0;
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let mut limits = Limits::new();
+        limits.max_memory = Some(4096);
+        assert_eq!(
+            run(&bytecode, limits).unwrap_err().kind,
+            RuntimeErrorKind::OutOfMemory { max_memory: 4096 }
+        );
+    }
+
+    #[test]
+    fn vm_fuel() {
+        let bytecode = compile(
+            &parse(
+                "fn main() {
+                    while (true) {
+                        print(1);
+                    }
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let mut limits = Limits::new();
+        limits.fuel = Some(100);
+        assert_eq!(
+            run(&bytecode, limits).unwrap_err().kind,
+            RuntimeErrorKind::OutOfFuel { fuel: 100 }
+        );
+    }
+
+    #[test]
+    fn vm_overflow() {
+        let input = fs::read_to_string("tests/arithmetic/factorial.txt").unwrap();
+        let (program, lines) = parse_with_lines(input).unwrap();
+        let bytecode = compile(&program).unwrap();
+        let err = run(&bytecode, Limits::new()).unwrap_err().locate(&lines);
+        assert_eq!(
+            err.to_string(),
+            "Arithmetic overflow in '13 * 479001600'
+    at the return statement on line 5 in 'fact'
+    called from 'main'"
+        );
+
+        let mut limits = Limits::new();
+        limits.overflow = Overflow::Wrap;
+        assert_eq!(
+            run(&bytecode, limits.clone()),
+            Ok(Some(Value::Number(1932053504)))
+        );
+        limits.overflow = Overflow::Saturate;
+        assert_eq!(run(&bytecode, limits), Ok(Some(Value::Number(i32::MAX))));
+
+        // The methods are not affected by the overflow behaviour of the program
+        let res = compile_run(
+            "fn main() -> i32 {
+                let min: i32 = -2147483647 - 1;
+                return min.saturating_sub(1) + min.wrapping_div(-1);
+            }",
+        );
+        assert_eq!(
+            res.unwrap_err().kind,
+            RuntimeErrorKind::Overflow {
+                op: Opcode::Add,
+                left: i32::MIN,
+                right: i32::MIN,
+            }
+        );
+    }
+
+    /// Compares the time it takes to run fibonacci with the interpreter and the virtual
+    /// machine, run with "cargo test --release bench_fibonacci -- --ignored --nocapture"
+    #[test]
+    #[ignore]
+    fn bench_fibonacci() {
+        let program = parse(
+            "fn fibonacci(n: i32) -> i32 {
+                if (n < 2) {
+                    return n;
+                }
+                return fibonacci(n - 2) + fibonacci(n - 1);
+            }
+
+            fn main() -> i32 {
+                return fibonacci(25);
+            }"
+            .to_string(),
+        )
+        .unwrap();
+
+        let start = Instant::now();
+        let res = interp(program.clone());
+        let interp_time = start.elapsed();
+
+        let start = Instant::now();
+        let bytecode = compile(&program).unwrap();
+        let vm_res = run(&bytecode, Limits::new());
+        let vm_time = start.elapsed();
+
+        assert_eq!(vm_res, res);
+        println!(
+            "fibonacci(25): interpreter {:?}, bytecode {:?}, {:.1} times faster",
+            interp_time,
+            vm_time,
+            interp_time.as_secs_f64() / vm_time.as_secs_f64()
+        );
+        assert!(vm_time < interp_time);
+    }
+}
@@ -1,41 +1,140 @@
-use crate::{scope::Scope, types::LiteralType, value::Value, variable::Variable};
+use std::collections::HashMap;
+
+use crate::{
+    debugger::Debugger,
+    enumeration::Enum,
+    limits::{Budget, Limits},
+    operators::Opcode,
+    reference::Reference,
+    runtime_error::RuntimeErrorKind,
+    scope::Scope,
+    structure::Struct,
+    tracer::Tracer,
+    types::LiteralType,
+    value::Value,
+    variable::Variable,
+};
 
-#[derive(Clone, Debug, PartialEq)]
 pub struct Context {
     pub scopes: Vec<Scope>,
+    // The index of the first scope of the function which is currently called, the scopes
+    // of its callers are not visible to it
+    frame: usize,
+    // The limits of the program that is interpreted and how much of them it has used
+    pub budget: Budget,
+    pub structs: HashMap<String, Struct>,
+    pub enums: HashMap<String, Enum>,
+    // The types inferred by the type checker for the let bindings without a type annotation
+    pub inferred: Vec<LiteralType>,
+    // The operators used on type parameters and the type arguments of the calls to generic
+    // functions, in the function that is type checked, with the statements they are in
+    pub param_ops: Vec<(String, Opcode, usize)>,
+    pub type_args: Vec<(String, HashMap<String, LiteralType>, usize)>,
+    // The number of errors found before each statement of the function that is type
+    // checked, and the number of loops the statement which is type checked is nested in
+    pub stmnt_errors: Vec<usize>,
+    pub loops: usize,
+    // Stops the interpreted program at breakpoints, if it is debugged
+    pub debugger: Option<Debugger>,
+    // Logs the statements and updates of the variables and profiles the calls of the
+    // interpreted program, if it is traced
+    pub tracer: Option<Tracer>,
 }
 
 impl Context {
     pub fn new() -> Context {
-        Context { scopes: vec![] }
+        Context {
+            scopes: vec![],
+            frame: 0,
+            budget: Budget::new(Limits::new()),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            inferred: vec![],
+            param_ops: vec![],
+            type_args: vec![],
+            stmnt_errors: vec![],
+            loops: 0,
+            debugger: None,
+            tracer: None,
+        }
+    }
+
+    pub fn insert_struct(&mut self, strct: Struct) {
+        self.structs.insert(strct.name.clone(), strct);
+    }
+
+    pub fn get_struct(&self, name: &str) -> Option<&Struct> {
+        self.structs.get(name)
+    }
+
+    pub fn insert_enum(&mut self, enm: Enum) {
+        self.enums.insert(enm.name.clone(), enm);
+    }
+
+    pub fn get_enum(&self, name: &str) -> Option<&Enum> {
+        self.enums.get(name)
     }
 
-    pub fn insert_var(&mut self, name: String, mutable: bool, typ: LiteralType, value: Value) {
+    /// Declares a variable in the innermost scope, returns false if there is no scope
+    pub fn insert_var(
+        &mut self,
+        name: String,
+        mutable: bool,
+        typ: LiteralType,
+        value: Value,
+    ) -> bool {
+        if let Some(tracer) = &mut self.tracer {
+            tracer.var(&name, &value);
+        }
         let new_var = Variable::new(value, mutable, typ);
-        match self.scopes.iter_mut().last() {
-            Some(scope) => scope.vars.insert(name, new_var),
-            None => panic!("insert_var: No scope in context"),
+        let scope = match self.scopes.last_mut() {
+            Some(scope) => scope,
+            None => return false,
+        };
+        if !self.budget.counts_memory() {
+            scope.vars.insert(name, new_var);
+            return true;
+        }
+
+        // A variable which is shadowed in the same scope is replaced by the new one
+        let name_size = name.len();
+        let size = name_size + new_var.size();
+        let freed = match scope.vars.insert(name, new_var) {
+            Some(old) => name_size + old.size(),
+            None => 0,
         };
+        self.budget.realloc(freed, size);
+        true
     }
 
-    pub fn update_var(&mut self, name: String, value: Value) -> Option<Value> {
-        for scope in self.scopes.iter_mut().rev() {
+    pub fn update_var(
+        &mut self,
+        name: String,
+        value: Value,
+    ) -> Result<Option<Value>, RuntimeErrorKind> {
+        for scope in self.scopes[self.frame..].iter_mut().rev() {
             match scope.vars.get_mut(&name.clone()) {
                 Some(var) => {
                     if var.is_mut() {
-                        return Some(var.update_value(value.clone()));
+                        if let Some(tracer) = &mut self.tracer {
+                            tracer.var(&name, &value);
+                        }
+                        if self.budget.counts_memory() {
+                            self.budget.realloc(var.size(), value.size());
+                        }
+                        return Ok(Some(var.update_value(value.clone())));
                     } else {
-                        panic!("Cannot assign twice to immutable variable \"{}\"", name)
+                        return Err(RuntimeErrorKind::VarImmut { var: name });
                     }
                 }
                 None => (),
             };
         }
-        None // Variable was not found in any scope
+        Ok(None) // Variable was not found in any scope
     }
 
     pub fn get_var(&mut self, name: &str) -> Option<&Variable> {
-        for scope in self.scopes.iter().rev() {
+        for scope in self.scopes[self.frame..].iter().rev() {
             match scope.vars.get(name) {
                 Some(var) => return Some(var),
                 None => (),
@@ -44,6 +143,67 @@ impl Context {
         None
     }
 
+    /// Gets the index of the innermost scope of the current call frame that a variable is
+    /// declared in
+    pub fn get_var_scope(&self, name: &str) -> Option<usize> {
+        self.scopes[self.frame..]
+            .iter()
+            .rposition(|scope| scope.vars.contains_key(name))
+            .map(|idx| self.frame + idx)
+    }
+
+    /// Gets the value that a reference refers to
+    pub fn deref(&self, reference: &Reference) -> Result<Value, RuntimeErrorKind> {
+        match self.get_ref_var(reference) {
+            Some(var) => reference.get(var.get_value()),
+            None => Err(RuntimeErrorKind::RefDangling {
+                var: reference.var.to_string(),
+            }),
+        }
+    }
+
+    /// Assigns to the value that a reference refers to, the mutability of the variable is
+    /// not checked since a mutable reference can only be taken of a mutable variable
+    pub fn update_ref(
+        &mut self,
+        reference: &Reference,
+        value: Value,
+    ) -> Result<(), RuntimeErrorKind> {
+        if !reference.mutable {
+            return Err(RuntimeErrorKind::RefImmut {
+                var: reference.var.to_string(),
+            });
+        }
+        match self
+            .scopes
+            .get_mut(reference.scope)
+            .and_then(|scope| scope.vars.get_mut(&reference.var))
+        {
+            Some(var) => {
+                let updated = reference.set(var.get_value(), value)?;
+                if let Some(tracer) = &mut self.tracer {
+                    tracer.var(&reference.var, &updated);
+                }
+                if self.budget.counts_memory() {
+                    self.budget.realloc(var.size(), updated.size());
+                }
+                var.update_value(updated);
+                Ok(())
+            }
+            None => Err(RuntimeErrorKind::RefDangling {
+                var: reference.var.to_string(),
+            }),
+        }
+    }
+
+    /// Gets the variable that a reference refers to, the scope it was declared in has been
+    /// popped if the reference outlived it
+    fn get_ref_var(&self, reference: &Reference) -> Option<&Variable> {
+        self.scopes
+            .get(reference.scope)
+            .and_then(|scope| scope.vars.get(&reference.var))
+    }
+
     pub fn get_var_value(&mut self, name: &str) -> Option<Value> {
         match self.get_var(name) {
             Some(var) => Some(var.get_value()),
@@ -52,13 +212,55 @@ impl Context {
     }
 
     pub fn push(&mut self, scope: Scope) {
+        if self.budget.counts_memory() {
+            self.budget.realloc(0, scope.size());
+        }
         self.scopes.push(scope);
     }
 
-    pub fn pop(&mut self) -> Scope {
-        match self.scopes.pop() {
-            Some(scope) => scope,
-            None => panic!("Trying to pop from empty context scope"),
+    pub fn pop(&mut self) -> Option<Scope> {
+        let scope = self.scopes.pop();
+        if let Some(scope) = &scope {
+            if self.budget.counts_memory() {
+                self.budget.realloc(scope.size(), 0);
+            }
+        }
+        scope
+    }
+
+    /// Starts the call frame of a called function with the scope of its parameters, only
+    /// the variables of the new frame are visible until it is popped. The scopes of the
+    /// callers are kept since references can refer to their variables. Returns the start of
+    /// the caller's frame, or an error if the call to the function would exceed the maximum
+    /// call depth
+    pub fn push_frame(&mut self, name: &str, scope: Scope) -> Result<usize, RuntimeErrorKind> {
+        self.budget.enter_call(name)?;
+        let caller = self.frame;
+        self.frame = self.scopes.len();
+        if let Some(debugger) = &mut self.debugger {
+            debugger.enter_call(name, self.frame);
+        }
+        if let Some(tracer) = &mut self.tracer {
+            tracer.enter_call(name);
+        }
+        self.push(scope);
+        Ok(caller)
+    }
+
+    /// Pops all of the scopes of the current call frame and returns to the caller's frame
+    pub fn pop_frame(&mut self, caller: usize) {
+        if self.budget.counts_memory() {
+            let freed = self.scopes[self.frame..].iter().map(Scope::size).sum();
+            self.budget.realloc(freed, 0);
+        }
+        self.scopes.truncate(self.frame);
+        self.frame = caller;
+        self.budget.exit_call();
+        if let Some(debugger) = &mut self.debugger {
+            debugger.exit_call();
+        }
+        if let Some(tracer) = &mut self.tracer {
+            tracer.exit_call();
         }
     }
 }
@@ -0,0 +1,320 @@
+use crate::{ast::Node, type_errors::Warning};
+
+/// Finds a path through the body of a function that reaches its end without returning.
+///
+/// Returns the index and the kind of the last statement on the path that falls through, or
+/// None if every path ends in a return or in a loop which never exits i.e.
+/// "while (true) { ... }" without a break
+pub fn falls_through(body: &Node) -> Option<(usize, String)> {
+    block_falls_through(body, 0)
+}
+
+/// Finds the statements of a program which can never be executed since they follow a
+/// statement that never completes, as it returns or loops forever on every path
+pub fn unreachable_code(program: &Vec<Box<Node>>) -> Vec<Warning> {
+    let mut warnings = vec![];
+    for item in program.iter() {
+        if let Node::Func { name, body, .. } = &**item {
+            check_block(name, body, &mut warnings);
+        }
+    }
+    warnings
+}
+
+/// Follows the statements of a block whose first statement has the index given, the path
+/// of the last statement is the one that falls through as the earlier ones always continue
+/// to the next statement
+fn block_falls_through(block: &Node, first: usize) -> Option<(usize, String)> {
+    let mut path = None;
+    let mut idx = first;
+    let mut stmnt = Some(block);
+    while let Some(curr) = stmnt {
+        path = Some(stmnt_falls_through(curr, idx)?);
+        idx += stmnt_len(curr);
+        stmnt = curr.next_stmnt().map(|next| &**next);
+    }
+    path
+}
+
+fn stmnt_falls_through(stmnt: &Node, idx: usize) -> Option<(usize, String)> {
+    match stmnt {
+        Node::Return { .. } => None,
+        Node::IfElse {
+            if_statement,
+            else_statement,
+            ..
+        } => block_falls_through(if_statement, idx + 1).or_else(|| {
+            block_falls_through(else_statement, idx + 1 + block_len(if_statement))
+        }),
+        Node::MatchStatement { arms, .. } if !arms.is_empty() => {
+            let mut first = idx + 1;
+            arms.iter().find_map(|(_, arm)| {
+                let path = block_falls_through(arm, first);
+                first += block_len(arm);
+                path
+            })
+        }
+        Node::While {
+            cond, statement, ..
+        } if matches!(**cond, Node::Bool(true)) && !breaks(statement) => None,
+        _ => Some((idx, stmnt_kind(stmnt).unwrap_or_else(|| String::from("statement")))),
+    }
+}
+
+/// Checks if a loop body contains a break which exits it, breaks in nested loops exit the
+/// nested loop instead
+fn breaks(block: &Node) -> bool {
+    let mut stmnt = Some(block);
+    while let Some(curr) = stmnt {
+        let found = match curr {
+            Node::Break => true,
+            Node::If { statement, .. } => breaks(statement),
+            Node::IfElse {
+                if_statement,
+                else_statement,
+                ..
+            } => breaks(if_statement) || breaks(else_statement),
+            Node::MatchStatement { arms, .. } => arms.iter().any(|(_, arm)| breaks(arm)),
+            _ => false,
+        };
+        if found {
+            return true;
+        }
+        stmnt = curr.next_stmnt().map(|next| &**next);
+    }
+    false
+}
+
+fn check_block(name: &str, block: &Node, warnings: &mut Vec<Warning>) {
+    let mut stmnt = Some(block);
+    while let Some(curr) = stmnt {
+        match curr {
+            Node::If { statement, .. } | Node::While { statement, .. } => {
+                check_block(name, statement, warnings)
+            }
+            Node::IfElse {
+                if_statement,
+                else_statement,
+                ..
+            } => {
+                check_block(name, if_statement, warnings);
+                check_block(name, else_statement, warnings);
+            }
+            Node::MatchStatement { arms, .. } => {
+                for (_, arm) in arms.iter() {
+                    check_block(name, arm, warnings);
+                }
+            }
+            _ => (),
+        }
+
+        let next = curr.next_stmnt();
+        if let Some(next) = next {
+            if stmnt_falls_through(curr, 0).is_none() {
+                warnings.push(Warning::UnreachableCode {
+                    name: name.to_string(),
+                    stmnt: stmnt_kind(next).unwrap_or_else(|| String::from("statement")),
+                    after: stmnt_kind(curr).unwrap_or_else(|| String::from("statement")),
+                });
+                return;
+            }
+        }
+        stmnt = next.map(|next| &**next);
+    }
+}
+
+/// Describes the kind of a statement, or None if the node is not a statement
+pub fn stmnt_kind(stmnt: &Node) -> Option<String> {
+    let kind = match stmnt {
+        Node::Let { .. } => String::from("let"),
+        Node::VarValue { .. } => String::from("assignment"),
+        Node::Print { .. } => String::from("print"),
+        Node::If { .. } | Node::IfElse { .. } => String::from("if"),
+        Node::While { .. } => String::from("while"),
+        Node::MatchStatement { .. } => String::from("match"),
+        Node::FuncCall { name, .. } => format!("call to '{}'", name),
+        Node::MethodCall { name, .. } => format!("call to '{}'", name),
+        Node::Return { .. } => String::from("return"),
+        Node::Break => String::from("break"),
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Counts the statements of a block, including the statements nested in them
+pub fn block_len(block: &Node) -> usize {
+    let mut len = 0;
+    let mut stmnt = Some(block);
+    while let Some(curr) = stmnt {
+        len += stmnt_len(curr);
+        stmnt = curr.next_stmnt().map(|next| &**next);
+    }
+    len
+}
+
+/// Counts a statement and the statements nested in it, which come right after it when the
+/// statements of a function are numbered in the order they appear in
+pub fn stmnt_len(stmnt: &Node) -> usize {
+    let nested = match stmnt {
+        Node::If { statement, .. } | Node::While { statement, .. } => block_len(statement),
+        Node::IfElse {
+            if_statement,
+            else_statement,
+            ..
+        } => block_len(if_statement) + block_len(else_statement),
+        Node::MatchStatement { arms, .. } => arms.iter().map(|(_, arm)| block_len(arm)).sum(),
+        _ => 0,
+    };
+    1 + nested
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::program_parser::parse;
+
+    fn body_of(input: &str) -> Box<Node> {
+        match *parse(input.to_string()).unwrap().remove(0) {
+            Node::Func { body, .. } => body,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_falls_through() {
+        let body = body_of(
+            "fn f(x: i32) -> i32 {
+                if (x > 0) {
+                    print(x);
+                } else {
+                    return 1;
+                }
+            }",
+        );
+        assert_eq!(falls_through(&body), Some((1, String::from("print"))));
+
+        let body = body_of(
+            "fn f(x: i32) -> i32 {
+                if (x > 0) {
+                    return 0;
+                }
+            }",
+        );
+        assert_eq!(falls_through(&body), Some((0, String::from("if"))));
+
+        let body = body_of(
+            "fn f(x: i32) -> i32 {
+                while (x > 0) {
+                    return 0;
+                }
+            }",
+        );
+        assert_eq!(falls_through(&body), Some((0, String::from("while"))));
+
+        let body = body_of(
+            "fn f(x: i32) -> i32 {
+                match (x) {
+                    0 => {
+                        return 0;
+                    }
+                    _ => {
+                        print(x);
+                    }
+                }
+            }",
+        );
+        assert_eq!(falls_through(&body), Some((2, String::from("print"))));
+
+        let body = body_of(
+            "fn f(x: i32) -> i32 {
+                if (x > 0) {
+                    return 0;
+                }
+                print(x);
+            }",
+        );
+        assert_eq!(falls_through(&body), Some((2, String::from("print"))));
+    }
+
+    #[test]
+    fn test_returns() {
+        let body = body_of(
+            "fn f(x: i32) -> i32 {
+                if (x > 0) {
+                    return 0;
+                } else {
+                    return 1;
+                }
+            }",
+        );
+        assert_eq!(falls_through(&body), None);
+
+        let body = body_of(
+            "fn f(x: i32) -> i32 {
+                while (true) {
+                    if (x > 0) {
+                        return x;
+                    }
+                    x = x + 1;
+                }
+            }",
+        );
+        assert_eq!(falls_through(&body), None);
+
+        let body = body_of(
+            "fn f(x: i32) -> i32 {
+                while (true) {
+                    if (x > 0) {
+                        break;
+                    }
+                }
+            }",
+        );
+        assert_eq!(falls_through(&body), Some((0, String::from("while"))));
+    }
+
+    #[test]
+    fn test_unreachable_code() {
+        let program = parse(
+            "fn f(x: i32) -> i32 {
+                if (x > 0) {
+                    return 0;
+                    print(x);
+                }
+                return 1;
+                let y = 2;
+                print(y);
+            }
+
+            fn g() {
+                while (true) {
+                    print(1);
+                }
+                print(2);
+            }"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            unreachable_code(&program),
+            vec![
+                Warning::UnreachableCode {
+                    name: String::from("f"),
+                    stmnt: String::from("print"),
+                    after: String::from("return"),
+                },
+                Warning::UnreachableCode {
+                    name: String::from("f"),
+                    stmnt: String::from("let"),
+                    after: String::from("return"),
+                },
+                Warning::UnreachableCode {
+                    name: String::from("g"),
+                    stmnt: String::from("print"),
+                    after: String::from("while"),
+                },
+            ]
+        );
+    }
+}
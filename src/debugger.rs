@@ -0,0 +1,638 @@
+use std::{
+    io::{BufRead, Write},
+    str::FromStr,
+};
+
+use crate::{
+    context::Context, parse::program_parser::FuncLines, runtime_error::RuntimeErrorKind,
+    types::LiteralType, value::Value,
+};
+
+/// A place where the debugger stops the program, before a statement on a line or before
+/// the first statement of a function
+#[derive(Clone, Debug, PartialEq)]
+pub enum Breakpoint {
+    Line(usize),
+    Func(String),
+}
+
+impl FromStr for Breakpoint {
+    type Err = ();
+
+    /// Parses a line number i.e. "12" or the name of a function i.e. "fib"
+    fn from_str(s: &str) -> Result<Breakpoint, ()> {
+        match s.parse() {
+            Ok(line) => Ok(Breakpoint::Line(line)),
+            Err(_) if !s.is_empty() && !s.contains(char::is_whitespace) => {
+                Ok(Breakpoint::Func(s.to_string()))
+            }
+            Err(_) => Err(()),
+        }
+    }
+}
+
+impl Breakpoint {
+    pub fn to_string(&self) -> String {
+        match self {
+            Breakpoint::Line(line) => format!("line {}", line),
+            Breakpoint::Func(name) => format!("function '{}'", name),
+        }
+    }
+}
+
+/// How a stopped program is resumed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    // Runs until a breakpoint is reached
+    Continue,
+    // Stops at the next statement, which is in the function called if it is a call
+    StepInto,
+    // Stops at the next statement of the current call or of one of its callers
+    StepOver,
+    // Stops at the next statement of a caller
+    StepOut,
+    // Stops the program with an error
+    Quit,
+}
+
+/// Why the program was stopped
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reason {
+    Breakpoint(Breakpoint),
+    Step,
+}
+
+/// A variable of a scope as it is shown by the debugger
+#[derive(Clone, Debug, PartialEq)]
+pub struct VarInfo {
+    pub name: String,
+    pub typ: LiteralType,
+    pub mutable: bool,
+    pub value: Value,
+}
+
+/// A scope along with the function it belongs to, the variables are sorted by their names
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScopeInfo {
+    pub func: String,
+    pub vars: Vec<VarInfo>,
+}
+
+/// A call which has not returned, the line is None if none of its statements has been
+/// reached yet i.e. for a closure
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallInfo {
+    pub func: String,
+    pub line: Option<usize>,
+}
+
+/// The state of a program which is stopped before a statement
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stop {
+    pub reason: Reason,
+    pub func: String,
+    pub line: Option<usize>,
+    // The kind of the statement, i.e. "let"
+    pub stmnt: String,
+    // The call stack with the innermost call first
+    pub calls: Vec<CallInfo>,
+    // All of the scopes of the context with the innermost one last, including the scopes of
+    // the callers
+    pub scopes: Vec<ScopeInfo>,
+}
+
+/// Drives the debugger when the program is stopped, i.e. a command prompt or a test
+pub trait Frontend {
+    /// Decides how the program is resumed, the breakpoints can be changed before it is
+    fn stop(&mut self, stop: &Stop, breakpoints: &mut Vec<Breakpoint>) -> Command;
+}
+
+/// When the program is stopped by the next statement, other than at a breakpoint. The depth
+/// is the number of calls when the step was taken
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    Run,
+    StepInto,
+    StepOver(usize),
+    StepOut(usize),
+}
+
+struct Call {
+    func: String,
+    // The index of the first scope of the call in the context
+    scope: usize,
+    line: Option<usize>,
+    // Whether a statement of the call has been reached, a breakpoint on the function stops
+    // at the first one
+    started: bool,
+}
+
+/// Stops an interpreted program at breakpoints or after steps and lets a frontend inspect it
+pub struct Debugger {
+    lines: FuncLines,
+    breakpoints: Vec<Breakpoint>,
+    mode: Mode,
+    calls: Vec<Call>,
+    frontend: Box<dyn Frontend + Send>,
+}
+
+impl Debugger {
+    /// Creates a debugger for a program with the lines of its functions, as they are returned
+    /// by parse_with_lines
+    pub fn new(lines: FuncLines, frontend: Box<dyn Frontend + Send>) -> Debugger {
+        Debugger {
+            lines: lines,
+            breakpoints: vec![],
+            mode: Mode::Run,
+            calls: vec![],
+            frontend: frontend,
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// Stops the program before its first statement
+    pub fn stop_at_start(&mut self) {
+        self.mode = Mode::StepInto;
+    }
+
+    /// Is called when a function is called, with the index of its first scope
+    pub fn enter_call(&mut self, name: &str, scope: usize) {
+        self.calls.push(Call {
+            func: name.to_string(),
+            scope: scope,
+            line: None,
+            started: false,
+        });
+    }
+
+    pub fn exit_call(&mut self) {
+        self.calls.pop();
+    }
+
+    /// Is called before a statement is executed, with the index of the statement in its
+    /// function. Stops the program if it has reached a breakpoint or the end of a step,
+    /// returns an error if it is quit
+    pub fn stmnt(
+        &mut self,
+        idx: usize,
+        kind: &Option<String>,
+        context: &Context,
+    ) -> Result<(), RuntimeErrorKind> {
+        let depth = self.calls.len();
+        let (func, line, first) = match self.calls.last_mut() {
+            Some(call) => {
+                call.line = self
+                    .lines
+                    .get(&call.func)
+                    .and_then(|lines| lines.get(idx).copied());
+                let first = !call.started;
+                call.started = true;
+                (call.func.to_string(), call.line, first)
+            }
+            None => return Ok(()),
+        };
+
+        let breakpoint = self.breakpoints.iter().find(|breakpoint| match breakpoint {
+            Breakpoint::Line(l) => line == Some(*l),
+            Breakpoint::Func(name) => first && *name == func,
+        });
+        let reason = match (breakpoint, self.mode) {
+            (Some(breakpoint), _) => Reason::Breakpoint(breakpoint.clone()),
+            (None, Mode::StepInto) => Reason::Step,
+            (None, Mode::StepOver(d)) if depth <= d => Reason::Step,
+            (None, Mode::StepOut(d)) if depth < d => Reason::Step,
+            _ => return Ok(()),
+        };
+
+        let stop = Stop {
+            reason: reason,
+            func: func,
+            line: line,
+            // Only a continue statement has no kind
+            stmnt: kind.clone().unwrap_or_else(|| String::from("continue")),
+            calls: self.call_stack(),
+            scopes: self.scopes(context),
+        };
+        self.mode = match self.frontend.stop(&stop, &mut self.breakpoints) {
+            Command::Continue => Mode::Run,
+            Command::StepInto => Mode::StepInto,
+            Command::StepOver => Mode::StepOver(depth),
+            Command::StepOut => Mode::StepOut(depth),
+            Command::Quit => return Err(RuntimeErrorKind::Quit),
+        };
+        Ok(())
+    }
+
+    fn call_stack(&self) -> Vec<CallInfo> {
+        self.calls
+            .iter()
+            .rev()
+            .map(|call| CallInfo {
+                func: call.func.to_string(),
+                line: call.line,
+            })
+            .collect()
+    }
+
+    fn scopes(&self, context: &Context) -> Vec<ScopeInfo> {
+        let mut scopes = vec![];
+        for (i, scope) in context.scopes.iter().enumerate() {
+            let func = match self.calls.iter().rev().find(|call| call.scope <= i) {
+                Some(call) => call.func.to_string(),
+                None => String::new(),
+            };
+            let mut vars: Vec<VarInfo> = scope
+                .vars
+                .iter()
+                .map(|(name, var)| VarInfo {
+                    name: name.to_string(),
+                    typ: var.get_type(),
+                    mutable: var.is_mut(),
+                    value: var.get_value(),
+                })
+                .collect();
+            vars.sort_by(|a, b| a.name.cmp(&b.name));
+            scopes.push(ScopeInfo {
+                func: func,
+                vars: vars,
+            });
+        }
+        scopes
+    }
+}
+
+const HELP: &str = "Commands:
+    c, continue        run until a breakpoint is reached
+    s, step            stop at the next statement, in the function called if it is a call
+    n, next            stop at the next statement which is not in a call
+    f, finish          stop at the next statement after the current call has returned
+    b, break LOC       add a breakpoint at a line or a function, i.e. 'b 12' or 'b fib'
+    d, delete LOC      remove a breakpoint
+    l, list            show the breakpoints
+    bt, backtrace      show the call stack
+    v, vars            show every scope with the name, type, mutability and value of
+                       its variables
+    q, quit            stop the program
+";
+
+/// A command prompt which reads commands from an input, i.e. stdin, and writes to an output
+pub struct Prompt<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Prompt<R, W> {
+    pub fn new(input: R, output: W) -> Prompt<R, W> {
+        Prompt {
+            input: input,
+            output: output,
+        }
+    }
+
+    /// Handles a line of input, returns the command that resumes the program if it is one
+    fn exec(
+        &mut self,
+        line: &str,
+        stop: &Stop,
+        breakpoints: &mut Vec<Breakpoint>,
+    ) -> Option<Command> {
+        let mut words = line.split_whitespace();
+        let cmd = words.next().unwrap_or("");
+        let arg = words.next().unwrap_or("");
+        let out = &mut self.output;
+        let res = match cmd {
+            "c" | "continue" => return Some(Command::Continue),
+            "s" | "step" => return Some(Command::StepInto),
+            "n" | "next" => return Some(Command::StepOver),
+            "f" | "finish" => return Some(Command::StepOut),
+            "q" | "quit" => return Some(Command::Quit),
+            "" => Ok(()),
+            "b" | "break" | "d" | "delete" => match arg.parse::<Breakpoint>() {
+                Ok(breakpoint) if cmd.starts_with('b') => {
+                    let res = writeln!(out, "Breakpoint at {}", breakpoint.to_string());
+                    if !breakpoints.contains(&breakpoint) {
+                        breakpoints.push(breakpoint);
+                    }
+                    res
+                }
+                Ok(breakpoint) => match breakpoints.iter().position(|b| *b == breakpoint) {
+                    Some(pos) => {
+                        breakpoints.remove(pos);
+                        writeln!(out, "Deleted the breakpoint at {}", breakpoint.to_string())
+                    }
+                    None => writeln!(out, "There is no breakpoint at {}", breakpoint.to_string()),
+                },
+                Err(_) => writeln!(out, "Expected a line or a function, i.e. '{} 12'", cmd),
+            },
+            "l" | "list" => breakpoints
+                .iter()
+                .try_for_each(|breakpoint| writeln!(out, "{}", breakpoint.to_string())),
+            "bt" | "backtrace" => {
+                stop.calls
+                    .iter()
+                    .enumerate()
+                    .try_for_each(|(i, call)| match call.line {
+                        Some(line) => writeln!(out, "#{} '{}' at line {}", i, call.func, line),
+                        None => writeln!(out, "#{} '{}'", i, call.func),
+                    })
+            }
+            "v" | "vars" => stop.scopes.iter().enumerate().try_for_each(|(i, scope)| {
+                writeln!(out, "scope {} in '{}':", i, scope.func)?;
+                scope.vars.iter().try_for_each(|var| {
+                    let mutable = if var.mutable { "mut " } else { "" };
+                    writeln!(
+                        out,
+                        "    {}{}: {} = {:?}",
+                        mutable,
+                        var.name,
+                        var.typ.to_string(),
+                        var.value
+                    )
+                })
+            }),
+            _ => write!(out, "{}", HELP),
+        };
+        // The program is quit if the output is closed
+        match res {
+            Ok(()) => None,
+            Err(_) => Some(Command::Quit),
+        }
+    }
+}
+
+impl<R: BufRead, W: Write> Frontend for Prompt<R, W> {
+    fn stop(&mut self, stop: &Stop, breakpoints: &mut Vec<Breakpoint>) -> Command {
+        let reason = match &stop.reason {
+            Reason::Breakpoint(breakpoint) => {
+                format!("at the breakpoint at {}", breakpoint.to_string())
+            }
+            Reason::Step => String::from("after a step"),
+        };
+        let line = match stop.line {
+            Some(line) => format!(" on line {}", line),
+            None => String::new(),
+        };
+        let _ = writeln!(
+            self.output,
+            "Stopped {} before the {} statement{} in '{}'",
+            reason, stop.stmnt, line, stop.func
+        );
+
+        loop {
+            let _ = write!(self.output, "(debug) ");
+            let _ = self.output.flush();
+            let mut input = String::new();
+            match self.input.read_line(&mut input) {
+                // The program is quit at the end of the input
+                Ok(0) | Err(_) => return Command::Quit,
+                Ok(_) => (),
+            }
+            if let Some(cmd) = self.exec(&input, stop, breakpoints) {
+                return cmd;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::Node, interpreter::debug, limits::Limits, parse::program_parser::parse_with_lines,
+        runtime_error::RuntimeError,
+    };
+    use std::{
+        fs,
+        io::Cursor,
+        sync::{Arc, Mutex},
+    };
+
+    // Records every stop and resumes the program with the commands in order, it continues
+    // when they run out
+    struct Script {
+        commands: Vec<Command>,
+        stops: Arc<Mutex<Vec<Stop>>>,
+    }
+
+    impl Frontend for Script {
+        fn stop(&mut self, stop: &Stop, _: &mut Vec<Breakpoint>) -> Command {
+            self.stops.lock().unwrap().push(stop.clone());
+            match self.commands.len() {
+                0 => Command::Continue,
+                _ => self.commands.remove(0),
+            }
+        }
+    }
+
+    fn debugger(
+        commands: Vec<Command>,
+        stops: &Arc<Mutex<Vec<Stop>>>,
+    ) -> (Vec<Box<Node>>, Debugger) {
+        let input = fs::read_to_string("tests/debugger/calls.txt").unwrap();
+        let (program, lines) = parse_with_lines(input).unwrap();
+        let script = Script {
+            commands: commands,
+            stops: stops.clone(),
+        };
+        let debugger = Debugger::new(lines, Box::new(script));
+        (program, debugger)
+    }
+
+    fn run(
+        breakpoints: Vec<Breakpoint>,
+        commands: Vec<Command>,
+        at_start: bool,
+    ) -> (Result<Option<Value>, RuntimeError>, Vec<Stop>) {
+        let stops = Arc::new(Mutex::new(vec![]));
+        let (program, mut debugger) = debugger(commands, &stops);
+        for breakpoint in breakpoints.into_iter() {
+            debugger.add_breakpoint(breakpoint);
+        }
+        if at_start {
+            debugger.stop_at_start();
+        }
+        let res = debug(program, Limits::new(), debugger);
+        let stops = stops.lock().unwrap().clone();
+        (res, stops)
+    }
+
+    fn lines(stops: &Vec<Stop>) -> Vec<Option<usize>> {
+        stops.iter().map(|stop| stop.line).collect()
+    }
+
+    fn var(name: &str, mutable: bool, value: i32) -> VarInfo {
+        VarInfo {
+            name: name.to_string(),
+            typ: LiteralType::I32,
+            mutable: mutable,
+            value: Value::Number(value),
+        }
+    }
+
+    #[test]
+    fn lines_of_funcs() {
+        let stops = Arc::new(Mutex::new(vec![]));
+        let (_, debugger) = debugger(vec![], &stops);
+        assert_eq!(debugger.lines["square"], vec![2, 3]);
+        assert_eq!(
+            debugger.lines["main"],
+            vec![7, 8, 9, 10, 11, 12, 13, 15, 16, 18]
+        );
+    }
+
+    #[test]
+    fn func_lines_after_break() {
+        // The statements after a break are dropped, so they have no lines
+        let input = "fn main() -> i32 {
+            let mut i: i32 = 0;
+            while (true) {
+                i = i + 1;
+                break;
+                print(i);
+                i = 5;
+            }
+            return i;
+        }";
+        let (_, lines) = parse_with_lines(input.to_string()).unwrap();
+        assert_eq!(lines["main"], vec![2, 3, 4, 5, 9]);
+    }
+
+    #[test]
+    fn breakpoints() {
+        let (res, stops) = run(vec![Breakpoint::Line(2)], vec![], false);
+        assert_eq!(res, Ok(Some(Value::Number(5))));
+        assert_eq!(lines(&stops), vec![Some(2), Some(2)]);
+
+        let stop = &stops[0];
+        assert_eq!(stop.reason, Reason::Breakpoint(Breakpoint::Line(2)));
+        assert_eq!(stop.func, "square");
+        assert_eq!(stop.stmnt, "let");
+        assert_eq!(
+            stop.calls,
+            vec![
+                CallInfo {
+                    func: String::from("square"),
+                    line: Some(2),
+                },
+                CallInfo {
+                    func: String::from("main"),
+                    line: Some(15),
+                },
+            ]
+        );
+        // The scopes of main and its loop, then the parameters of square
+        assert_eq!(
+            stop.scopes,
+            vec![
+                ScopeInfo {
+                    func: String::from("main"),
+                    vars: vec![var("i", true, 1), var("sum", true, 0)],
+                },
+                ScopeInfo {
+                    func: String::from("main"),
+                    vars: vec![],
+                },
+                ScopeInfo {
+                    func: String::from("square"),
+                    vars: vec![var("x", false, 1)],
+                },
+            ]
+        );
+
+        let (_, stops) = run(
+            vec![Breakpoint::Func(String::from("square"))],
+            vec![],
+            false,
+        );
+        assert_eq!(lines(&stops), vec![Some(2), Some(2)]);
+        assert_eq!(stops[1].scopes[2].vars, vec![var("x", false, 2)]);
+    }
+
+    #[test]
+    fn steps() {
+        let commands = vec![
+            Command::StepOver,
+            Command::StepOver,
+            Command::StepInto,
+            Command::StepInto,
+            Command::StepInto,
+            Command::StepInto,
+            Command::StepOut,
+        ];
+        let (res, stops) = run(vec![], commands, true);
+        assert_eq!(res, Ok(Some(Value::Number(5))));
+        // The first iteration of the loop skips both branches of the if statement, the
+        // else-if is a statement of its own
+        assert_eq!(
+            lines(&stops),
+            vec![
+                Some(7),
+                Some(8),
+                Some(9),
+                Some(10),
+                Some(12),
+                Some(15),
+                Some(2),
+                Some(16)
+            ]
+        );
+        assert!(stops.iter().all(|stop| stop.reason == Reason::Step));
+        assert_eq!(stops[6].func, "square");
+        assert_eq!(stops[7].func, "main");
+    }
+
+    #[test]
+    fn quit() {
+        let (res, stops) = run(vec![], vec![Command::Quit], true);
+        assert_eq!(stops.len(), 1);
+        let err = res.unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::Quit);
+        assert_eq!(err.stmnt, Some(String::from("let")));
+        assert_eq!(err.trace, vec![String::from("main")]);
+    }
+
+    #[test]
+    fn prompt() {
+        let stop = Stop {
+            reason: Reason::Step,
+            func: String::from("f"),
+            line: Some(3),
+            stmnt: String::from("let"),
+            calls: vec![
+                CallInfo {
+                    func: String::from("f"),
+                    line: Some(3),
+                },
+                CallInfo {
+                    func: String::from("main"),
+                    line: None,
+                },
+            ],
+            scopes: vec![ScopeInfo {
+                func: String::from("f"),
+                vars: vec![var("x", true, 1)],
+            }],
+        };
+        let input = Cursor::new("b square\nb 7\nd 7\nbt\nvars\nhelp\nn\n");
+        let mut prompt = Prompt::new(input, vec![]);
+        let mut breakpoints = vec![];
+        assert_eq!(prompt.stop(&stop, &mut breakpoints), Command::StepOver);
+        assert_eq!(breakpoints, vec![Breakpoint::Func(String::from("square"))]);
+
+        let output = String::from_utf8(prompt.output.clone()).unwrap();
+        assert!(output.starts_with("Stopped after a step before the let statement on line 3"));
+        assert!(output.contains("Breakpoint at function 'square'"));
+        assert!(output.contains("Deleted the breakpoint at line 7"));
+        assert!(output.contains("#0 'f' at line 3\n#1 'main'\n"));
+        assert!(output.contains("scope 0 in 'f':\n    mut x: i32 = Number(1)\n"));
+        assert!(output.contains("Commands:"));
+
+        // The program is quit at the end of the input
+        assert_eq!(prompt.stop(&stop, &mut breakpoints), Command::Quit);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::types::LiteralType;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Enum {
+    pub name: String,
+    pub variants: Vec<(String, Vec<LiteralType>)>,
+}
+
+impl Enum {
+    pub fn new(name: String, variants: Vec<(String, Vec<LiteralType>)>) -> Enum {
+        Enum {
+            name: name,
+            variants: variants,
+        }
+    }
+
+    /// Gets the types of the fields of a variant
+    pub fn get_variant(&self, variant: &str) -> Option<&Vec<LiteralType>> {
+        match self.variants.iter().find(|(name, _)| name == variant) {
+            Some((_, fields)) => Some(fields),
+            None => None,
+        }
+    }
+
+    /// Gets the position of a variant in the declaration of the enum which is used as its tag
+    pub fn get_variant_index(&self, variant: &str) -> Option<u32> {
+        self.variants
+            .iter()
+            .position(|(name, _)| name == variant)
+            .map(|idx| idx as u32)
+    }
+}
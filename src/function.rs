@@ -1,12 +1,19 @@
 use std::collections::HashMap;
 
 use crate::{
-    ast::Node, context::Context, interpreter::visit, scope::Scope, types::LiteralType, value::Value,
+    ast::Node,
+    context::Context,
+    interpreter::{exec, visit, ControlFlow},
+    runtime_error::{RuntimeError, RuntimeErrorKind},
+    scope::Scope,
+    types::LiteralType,
+    value::Value,
 };
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Func {
     pub name: String,
+    pub generics: Vec<String>,
     pub params: Vec<Box<Node>>,
     pub r_type: Option<LiteralType>,
     pub body: Box<Node>,
@@ -17,12 +24,14 @@ type Funcs = HashMap<String, Func>; // Stores all the function names and their b
 impl Func {
     pub fn new(
         name: String,
+        generics: Vec<String>,
         params: Vec<Box<Node>>,
         r_type: Option<LiteralType>,
         body: Box<Node>,
     ) -> Func {
         Func {
             name: name,
+            generics: generics,
             params: params,
             r_type: r_type,
             body: body,
@@ -30,8 +39,8 @@ impl Func {
     }
 
     pub fn get_r_type(&self) -> LiteralType {
-        match self.r_type {
-            Some(typ) => typ,
+        match &self.r_type {
+            Some(typ) => typ.clone(),
             None => LiteralType::Void,
         }
     }
@@ -50,36 +59,45 @@ impl Func {
         param_types
     }
 
+    /// Calls the function, an error which occurs in its body gets the function added to its
+    /// trace. A function which does not return a value returns Value::Unit
     pub fn call(
         &mut self,
         args: Vec<Box<Node>>,
         context: &mut Context,
         funcs: &mut Funcs,
-    ) -> Option<Value> {
-        self.check_args(&args);
+    ) -> Result<Value, RuntimeError> {
+        self.check_args(&args)?;
         let mut param_arg = vec![];
 
         // Create a scope containing the parameters with value of the arguments
         for pair in self.params.iter().zip(args.iter()) {
             let (param, arg) = pair;
-            param_arg.push((&**param, visit(arg.clone(), context, funcs)));
+            param_arg.push((&**param, visit(arg.clone(), context, funcs)?));
         }
-        context.push(Scope::init(param_arg));
+        let caller = context.push_frame(&self.name, Scope::init(param_arg))?;
 
-        // Execute the function body and return if the function returns
-        match visit(self.body.clone(), context, funcs) {
-            Value::Bool(b) => Some(Value::Bool(b)),
-            Value::Number(num) => Some(Value::Number(num)),
-            Value::None => None,
-            _ => panic!("Unkown return type in function \"{:?}\"", self.name),
+        // Execute the function body until it returns or reaches its end
+        let res = exec(self.body.clone(), 0, context, funcs);
+        context.pop_frame(caller);
+        match res.map_err(|err| err.called_from(&self.name))? {
+            ControlFlow::Return(val) => Ok(val),
+            ControlFlow::Normal => Ok(Value::Unit),
+            ControlFlow::Break | ControlFlow::Continue => {
+                let err = RuntimeError::from(RuntimeErrorKind::LoopModifierOutsideLoop);
+                Err(err.called_from(&self.name))
+            }
         }
-
-        // visit(self.body.clone(), context, funcs)
     }
 
-    fn check_args(&self, args: &Vec<Box<Node>>) {
+    fn check_args(&self, args: &Vec<Box<Node>>) -> Result<(), RuntimeErrorKind> {
         if args.len() != self.params.len() {
-            panic!("Invlaid arguments")
+            return Err(RuntimeErrorKind::FnArgCount {
+                name: self.name.to_string(),
+                takes: self.params.len(),
+                supplied: args.len(),
+            });
         }
+        Ok(())
     }
 }
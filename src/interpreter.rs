@@ -1,109 +1,359 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, panic, thread};
 
 use crate::{
-    ast::Node, context::Context, function::Func, operators::Opcode, scope::Scope,
-    types::LiteralType, value::Value,
+    arithmetic::{arith, arith_method, negate, Overflow},
+    ast::Node,
+    context::Context,
+    control_flow::{block_len, stmnt_kind, stmnt_len},
+    debugger::Debugger,
+    enumeration::Enum,
+    function::Func,
+    limits::{Budget, Limits},
+    operators::Opcode,
+    pattern::Pattern,
+    reference::{Access, Reference},
+    runtime_error::{RuntimeError, RuntimeErrorKind},
+    scope::Scope,
+    structure::Struct,
+    tracer::Tracer,
+    types::LiteralType,
+    value::Value,
+    variable::Variable,
 };
 
 type Funcs = HashMap<String, Func>; // Stores all the function names and the their structs
 
-pub fn interp(mut funcs_ast: Vec<Box<Node>>) -> Option<Value> {
-    let mut context = Context::new();
+// The stack of the interpreter that is reserved for every nested call, a call evaluates
+// a number of nested nodes which each take a frame on the stack
+const STACK_PER_CALL: usize = 64 * 1024;
+// The largest stack the interpreter thread is started with, which bounds the maximum depth
+// of calls so that the thread can always be started
+const MAX_STACK_SIZE: usize = 1024 * 1024 * 1024;
+// The largest maximum depth of calls, a larger limit is clamped to it
+pub const MAX_DEPTH: usize = MAX_STACK_SIZE / STACK_PER_CALL - 1;
+
+/// The result of executing a statement, the statements after it in a block are only
+/// executed if it completes normally. A return is passed on to the function that is called
+/// and a break or continue to the innermost loop
+#[derive(Clone, Debug, PartialEq)]
+pub enum ControlFlow {
+    Normal,
+    Return(Value),
+    Break,
+    Continue,
+}
+
+/// Runs the main function of a program, an error stops the execution and is returned with
+/// the statement and the calls it occurred in. Returns None if main does not return a value
+#[allow(dead_code)]
+pub fn interp(funcs_ast: Vec<Box<Node>>) -> Result<Option<Value>, RuntimeError> {
+    interp_with_limits(funcs_ast, Limits::new())
+}
+
+/// Runs a program within the limits given, a program which exceeds one of them is stopped
+/// with an error. The program is run on a thread with a stack that is large enough for the
+/// maximum depth of calls, so that a stack overflow is reported instead of crashing
+pub fn interp_with_limits(
+    funcs_ast: Vec<Box<Node>>,
+    limits: Limits,
+) -> Result<Option<Value>, RuntimeError> {
+    run_on_thread(funcs_ast, limits, None, None).0
+}
+
+/// Runs a program with a debugger, which stops it at its breakpoints
+pub fn debug(
+    funcs_ast: Vec<Box<Node>>,
+    limits: Limits,
+    debugger: Debugger,
+) -> Result<Option<Value>, RuntimeError> {
+    run_on_thread(funcs_ast, limits, Some(debugger), None).0
+}
+
+/// Runs a program with a tracer, which is returned along with the result so that the
+/// profile and trace of the program can be reported
+pub fn trace(
+    funcs_ast: Vec<Box<Node>>,
+    limits: Limits,
+    tracer: Tracer,
+) -> (Result<Option<Value>, RuntimeError>, Tracer) {
+    match run_on_thread(funcs_ast, limits, None, Some(tracer)) {
+        (res, Some(tracer)) => (res, tracer),
+        (_, None) => unreachable!(),
+    }
+}
+
+fn run_on_thread(
+    funcs_ast: Vec<Box<Node>>,
+    limits: Limits,
+    debugger: Option<Debugger>,
+    tracer: Option<Tracer>,
+) -> (Result<Option<Value>, RuntimeError>, Option<Tracer>) {
+    let mut limits = limits;
+    limits.max_depth = limits.max_depth.min(MAX_DEPTH);
+    let stack_size = (limits.max_depth + 1) * STACK_PER_CALL;
+    let interpreter = thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || {
+            let mut context = Context::new();
+            context.budget = Budget::new(limits);
+            context.debugger = debugger;
+            context.tracer = tracer;
+            let res = run_main(funcs_ast, &mut context);
+            (res, context.tracer)
+        })
+        .expect("Could not start the interpreter thread");
+    match interpreter.join() {
+        Ok(res) => res,
+        Err(e) => panic::resume_unwind(e),
+    }
+}
+
+fn run_main(
+    mut funcs_ast: Vec<Box<Node>>,
+    context: &mut Context,
+) -> Result<Option<Value>, RuntimeError> {
     let mut funcs: Funcs = HashMap::new();
 
-    // Declare all of the functions of the parsed program
-    for func in funcs_ast.drain(..) {
-        visit(func, &mut context, &mut funcs);
+    // Declare all of the functions and structs of the parsed program
+    for item in funcs_ast.drain(..) {
+        declare(item, context, &mut funcs)?;
     }
 
-    match funcs.get("main") {
-        Some(main) => main.clone().call(vec![], &mut context, &mut funcs),
-        None => panic!("No main function is defined in the program"),
+    let res = match funcs.get("main") {
+        Some(main) => main.clone().call(vec![], context, &mut funcs)?,
+        None => return Err(RuntimeErrorKind::MainMissing.into()),
+    };
+    match res {
+        Value::Unit => Ok(None),
+        res => Ok(Some(res)),
     }
 }
 
-pub fn visit(node: Box<Node>, context: &mut Context, funcs: &mut Funcs) -> Value {
-    match *node {
-        Node::Number(num) => Value::Number(num),
-        Node::UnaryOp(op, value) => eval_unary(op, visit(value, context, funcs)),
-        Node::Bool(b) => Value::Bool(b),
-        Node::_String(text) => Value::String(text),
-        Node::Var(name) => eval_var(&name, context),
-        Node::VarValue { var, expr, next } => {
-            update_var(var, visit(expr, context, funcs), context, funcs, next)
-        }
-        Node::Expr(left, op, right) => eval_expr(
-            visit(left, context, funcs),
-            op,
-            visit(right, context, funcs),
-        ),
-        Node::Let { var, expr, next } => {
-            assign_var(var, visit(expr, context, funcs), context, funcs, next)
+/// Declares a function, struct or enum of a program
+fn declare(item: Box<Node>, context: &mut Context, funcs: &mut Funcs) -> Result<(), RuntimeError> {
+    match *item {
+        Node::Func {
+            name,
+            generics,
+            params,
+            r_type,
+            body,
+        } => eval_func_dec(&name, generics, &params, r_type, &body, funcs)?,
+        Node::Struct { name, fields } => context.insert_struct(Struct::new(name, fields)),
+        Node::Enum { name, variants } => context.insert_enum(Enum::new(name, variants)),
+        Node::Attribute { .. } => (),
+        item => {
+            return Err(RuntimeErrorKind::NodeNotSupported {
+                node: format!("{:?}", item),
+            }
+            .into())
         }
+    }
+    Ok(())
+}
+
+/// Executes the statements of a block in order until one of them does not complete normally.
+/// The statements of a function are numbered in the order they appear in for the debugger,
+/// first is the number of the first statement of the block
+pub fn exec(
+    block: Box<Node>,
+    first: usize,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<ControlFlow, RuntimeError> {
+    let mut idx = first;
+    let mut stmnt = Some(block);
+    while let Some(mut curr) = stmnt {
+        stmnt = curr.take_next();
+        let len = stmnt_len(&curr);
+        match exec_stmnt(curr, idx, context, funcs)? {
+            ControlFlow::Normal => (),
+            flow => return Ok(flow),
+        }
+        idx += len;
+    }
+    Ok(ControlFlow::Normal)
+}
+
+/// Executes a block in a new scope, i.e. the body of a loop or a branch of an if statement
+fn exec_scoped(
+    block: Box<Node>,
+    first: usize,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<ControlFlow, RuntimeError> {
+    context.push(Scope::new());
+    let flow = exec(block, first, context, funcs);
+    pop_scope(context)?;
+    flow
+}
+
+/// Executes a single statement, an error is returned with the innermost statement it
+/// occurred in
+fn exec_stmnt(
+    stmnt: Box<Node>,
+    idx: usize,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<ControlFlow, RuntimeError> {
+    let kind = stmnt_kind(&stmnt);
+
+    // The debugger is taken out of the context while the program is stopped, so that it can
+    // inspect the scopes of the context
+    if let Some(mut debugger) = context.debugger.take() {
+        let res = debugger.stmnt(idx, &kind, context);
+        context.debugger = Some(debugger);
+        res.map_err(|err| RuntimeError::from(err).at(kind.clone(), idx))?;
+    }
+    if let Some(tracer) = &mut context.tracer {
+        tracer.stmnt(idx, &kind);
+    }
+    exec_node(stmnt, idx, context, funcs).map_err(|err| err.at(kind, idx))
+}
+
+/// Executes a statement, the statements nested in it are numbered from the one after it
+fn exec_node(
+    stmnt: Box<Node>,
+    idx: usize,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<ControlFlow, RuntimeError> {
+    context.budget.step()?;
+
+    // Like visit the execution of each statement is done in a separate function
+    match *stmnt {
+        Node::Let { var, expr, .. } => assign_var(var, expr, context, funcs),
+        Node::VarValue { var, expr, .. } => update_var(var, expr, context, funcs),
+        Node::Print { expr, .. } => eval_print(expr, context, funcs),
         Node::If {
-            cond,
-            statement,
-            next,
-        } => eval_if_statement(visit(cond, context, funcs), statement, context, funcs, next),
+            cond, statement, ..
+        } => eval_if_statement(cond, statement, idx + 1, context, funcs),
         Node::IfElse {
             cond,
             if_statement,
             else_statement,
-            next,
-        } => eval_if_else_statement(
-            visit(cond, context, funcs),
-            if_statement,
-            else_statement,
-            context,
-            funcs,
-            next,
-        ),
+            ..
+        } => eval_if_else_statement(cond, if_statement, else_statement, idx + 1, context, funcs),
         Node::While {
-            cond,
-            statement,
-            next,
-        } => eval_while_statement(cond, statement, context, funcs, next),
-        Node::Func {
-            name,
-            params,
-            r_type,
-            body,
-        } => eval_func_dec(&name, &params, r_type, &body, funcs),
-        Node::FuncCall { name, args, next } => eval_func_call(&name, args, context, funcs, next),
-        Node::Return { expr, .. } => visit(expr, context, funcs),
-        // Print node used for easier debugging
-        Node::Print { expr, next } => {
-            let var_name = match *expr.clone() {
-                Node::Var(name) => Some(name),
-                _ => None,
-            };
+            cond, statement, ..
+        } => eval_while_statement(cond, statement, idx + 1, context, funcs),
+        Node::MatchStatement { expr, arms, .. } => exec_match(expr, arms, idx + 1, context, funcs),
+        // The values of calls which are statements are discarded
+        Node::FuncCall { name, args, .. } => {
+            eval_func_call(&name, args, context, funcs).map(|_| ControlFlow::Normal)
+        }
+        Node::MethodCall {
+            recv, name, args, ..
+        } => eval_method_call(recv, &name, args, context, funcs).map(|_| ControlFlow::Normal),
+        Node::Return { expr, .. } => visit(expr, context, funcs).map(ControlFlow::Return),
+        Node::Break => Ok(ControlFlow::Break),
+        Node::Continue => Ok(ControlFlow::Continue),
+        stmnt => Err(RuntimeErrorKind::NodeNotSupported {
+            node: format!("{:?}", stmnt),
+        }
+        .into()),
+    }
+}
 
-            match var_name {
-                Some(name) => println!("{:#?} = {:#?}", name, visit(expr, context, funcs)),
-                None => println!("{:#?}", visit(expr, context, funcs)),
-            }
+/// Evaluates an expression
+pub fn visit(
+    node: Box<Node>,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<Value, RuntimeError> {
+    context.budget.step()?;
 
-            match next {
-                Some(statement) => visit(statement, context, funcs),
-                None => Value::None,
+    // The evaluation of each node is done in a separate function, which keeps the stack frame
+    // of this one small since it is on the stack once for every node that is evaluated
+    match *node {
+        Node::Number(num) => Ok(Value::Number(num)),
+        Node::Unit => Ok(Value::Unit),
+        Node::UnaryOp(op, value) => eval_unary(op, value, context, funcs),
+        Node::Bool(b) => Ok(Value::Bool(b)),
+        Node::_String(text) => Ok(Value::String(text)),
+        Node::Var(name) => eval_var(&name, context, funcs).map_err(RuntimeError::from),
+        Node::Array(elems) => visit_all(elems, context, funcs).map(Value::Array),
+        Node::Tuple(elems) => visit_all(elems, context, funcs).map(Value::Tuple),
+        Node::Ref(place, mutable) => eval_ref(place, mutable, context, funcs).map(Value::Ref),
+        Node::Deref(expr) => eval_deref(expr, context, funcs),
+        Node::ArrayRepeat(expr, len) => {
+            let val = visit(expr, context, funcs)?;
+            // The array is only allocated if it fits in the memory the program has left
+            if context.budget.counts_memory() {
+                context.budget.reserve(val.size().saturating_mul(len))?;
             }
+            Ok(Value::Array(vec![val; len]))
+        }
+        Node::Index(arr, idx) => eval_index(arr, idx, context, funcs),
+        Node::VecNew => Ok(Value::Vec(vec![])),
+        Node::StructLiteral { name, fields } => eval_struct_literal(name, fields, context, funcs),
+        Node::Field(strct, name) => eval_field(strct, &name, context, funcs),
+        Node::Variant { enm, name, args } => {
+            visit_all(args, context, funcs).map(|args| Value::Enum(enm, name, args))
+        }
+        Node::Match { expr, arms } => eval_match(expr, arms, context, funcs),
+        Node::Closure { .. } => Ok(eval_closure(node, context)),
+        Node::MethodCall {
+            recv, name, args, ..
+        } => eval_method_call(recv, &name, args, context, funcs),
+        Node::Expr(left, op, right) => eval_expr(left, op, right, context, funcs),
+        Node::FuncCall { name, args, .. } => eval_func_call(&name, args, context, funcs),
+        _ => Err(RuntimeErrorKind::NodeNotSupported {
+            node: format!("{:?}", *node),
         }
-        _ => panic!("Node not supported: {:?}", *node),
+        .into()),
+    }
+}
+
+// Print node used for easier debugging
+fn eval_print(
+    expr: Box<Node>,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<ControlFlow, RuntimeError> {
+    let var_name = match *expr.clone() {
+        Node::Var(name) => Some(name),
+        _ => None,
+    };
+
+    let val = visit(expr, context, funcs)?;
+    match var_name {
+        Some(name) => println!("{:#?} = {:#?}", name, val),
+        None => println!("{:#?}", val),
     }
+    Ok(ControlFlow::Normal)
+}
+
+/// Evaluates a list of expressions in order, i.e. the elements of an array
+fn visit_all(
+    exprs: Vec<Box<Node>>,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<Vec<Value>, RuntimeError> {
+    exprs
+        .into_iter()
+        .map(|expr| visit(expr, context, funcs))
+        .collect()
 }
 
+/// Declares a function, generic functions are run with the types of the values they are
+/// called with
 fn eval_func_dec(
     name: &str,
+    generics: Vec<String>,
     params: &Vec<Box<Node>>,
     r_type: Option<LiteralType>,
     body: &Box<Node>,
     funcs: &mut Funcs,
-) -> Value {
-    let func = Func::new(name.to_string(), params.clone(), r_type, body.clone());
+) -> Result<(), RuntimeError> {
+    let func = Func::new(name.to_string(), generics, params.clone(), r_type, body.clone());
 
     match funcs.insert(name.to_string(), func) {
-        Some(_) => panic!("Function: {} is already defined", name.clone()),
-        None => Value::None,
+        Some(_) => Err(RuntimeErrorKind::FnDefinedMultiple {
+            name: name.to_string(),
+        }
+        .into()),
+        None => Ok(()),
     }
 }
 
@@ -112,288 +362,797 @@ fn eval_func_call(
     args: Vec<Box<Node>>,
     context: &mut Context,
     funcs: &mut Funcs,
-    next: Option<Box<Node>>,
-) -> Value {
-    let func_res = match funcs.get(name) {
-        Some(func) => match func.clone().call(args, context, funcs) {
-            Some(ret) => ret,
-            None => Value::None,
-        },
-        None => panic!("eval_func_call: Function \"{}\" is not defined", name),
-    };
-    context.pop();
-    match next {
-        Some(statement) => visit(statement, context, funcs),
-        None => func_res,
+) -> Result<Value, RuntimeError> {
+    // Variables shadow the functions with the same name
+    if let Some(closure) = context.get_var_value(name) {
+        return call_closure(name, closure, args, context, funcs);
+    }
+
+    match funcs.get(name) {
+        Some(func) => func.clone().call(args, context, funcs),
+        None => Err(RuntimeErrorKind::FnNotDefined {
+            name: name.to_string(),
+        }
+        .into()),
     }
 }
 
 // TODO: Allow for only def. a variable and not having to assign value
 fn assign_var(
     var: Box<Node>,
-    expr: Value,
+    expr: Box<Node>,
     context: &mut Context,
     funcs: &mut Funcs,
-    next: Option<Box<Node>>,
-) -> Value {
+) -> Result<ControlFlow, RuntimeError> {
+    let expr = visit(expr, context, funcs)?;
     match *var {
-        Node::VarBinding(var, typ, mutable) => def_var(var, mutable, typ, expr, context),
-        _ => panic!("assign_var: No VarBinding node"),
+        Node::VarBinding(var, typ, mutable) => def_var(var, mutable, typ, expr, context)?,
+        Node::TupleBinding(vars, typ) => match (known_type(typ, &expr), expr) {
+            (LiteralType::Tuple(types), Value::Tuple(elems)) => {
+                for (((var, mutable), typ), elem) in vars.into_iter().zip(types).zip(elems) {
+                    def_var(var, mutable, typ, elem, context)?;
+                }
+            }
+            (_, expr) => {
+                return Err(RuntimeErrorKind::DestructureNotTuple {
+                    found: value_type(&expr),
+                }
+                .into())
+            }
+        },
+        var => {
+            return Err(RuntimeErrorKind::NodeNotSupported {
+                node: format!("{:?}", var),
+            }
+            .into())
+        }
     }
+    Ok(ControlFlow::Normal)
+}
 
-    match next {
-        Some(statement) => visit(statement, context, funcs),
-        None => Value::None,
+fn def_var(
+    var: Box<Node>,
+    mutable: bool,
+    typ: LiteralType,
+    expr: Value,
+    context: &mut Context,
+) -> Result<(), RuntimeErrorKind> {
+    match *var {
+        Node::Var(name) => {
+            if context.insert_var(name, mutable, known_type(typ, &expr), expr) {
+                Ok(())
+            } else {
+                Err(RuntimeErrorKind::NoScope)
+            }
+        }
+        var => Err(RuntimeErrorKind::NodeNotSupported {
+            node: format!("{:?}", var),
+        }),
     }
 }
 
-fn def_var(var: Box<Node>, mutable: bool, typ: LiteralType, expr: Value, context: &mut Context) {
-    match *var {
-        Node::Var(name) => context.insert_var(name, mutable, typ, expr),
-        _ => panic!("def_var: No var node"),
+/// Gets the type of a variable, which is the type of its value if it has not been inferred
+/// by the type checker
+fn known_type(typ: LiteralType, val: &Value) -> LiteralType {
+    match typ {
+        LiteralType::Infer => value_type(val),
+        typ => typ,
     }
 }
 
 fn update_var(
     var: Box<Node>,
+    expr: Box<Node>,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<ControlFlow, RuntimeError> {
+    let expr = visit(expr, context, funcs)?;
+    update_place(var, expr, context, funcs)?;
+    Ok(ControlFlow::Normal)
+}
+
+/// Assigns a value to a place, i.e. a variable, an element of an array or vector or a field of a struct
+fn update_place(
+    place: Box<Node>,
     expr: Value,
     context: &mut Context,
     funcs: &mut Funcs,
-    next: Option<Box<Node>>,
-) -> Value {
-    match *var {
+) -> Result<(), RuntimeError> {
+    match *place {
         Node::Var(name) => {
-            match context.update_var(name.clone(), expr) {
-                None => panic!(
-                    "Variable \"{}\" is not defined, try: Let {}: <type> = <expr>;",
-                    name, name
-                ),
-                Some(_) => (),
+            if context.update_var(name.clone(), expr)?.is_none() {
+                return Err(RuntimeErrorKind::VarNotDefined { var: name }.into());
+            }
+        }
+        Node::Index(arr, idx) => {
+            let idx = visit(idx, context, funcs)?;
+            let updated = match visit(arr.clone(), context, funcs)? {
+                Value::Array(mut elems) => {
+                    let i = check_index(elems.len(), idx)?;
+                    elems[i] = expr;
+                    Value::Array(elems)
+                }
+                Value::Vec(mut elems) => {
+                    let i = check_index(elems.len(), idx)?;
+                    elems[i] = expr;
+                    Value::Vec(elems)
+                }
+                val => {
+                    return Err(RuntimeErrorKind::IndexNotArray {
+                        found: value_type(&val),
+                    }
+                    .into())
+                }
             };
+            update_place(arr, updated, context, funcs)?;
+        }
+        Node::Field(strct, name) => {
+            let updated = match visit(strct.clone(), context, funcs)? {
+                Value::Struct(strct_name, mut fields) => {
+                    match fields.iter_mut().find(|(field, _)| *field == name) {
+                        Some((_, value)) => *value = expr,
+                        None => {
+                            return Err(RuntimeErrorKind::FieldNotFound {
+                                field: name,
+                                found: LiteralType::Named(strct_name),
+                            }
+                            .into())
+                        }
+                    }
+                    Value::Struct(strct_name, fields)
+                }
+                Value::Tuple(mut elems) => {
+                    let i = tuple_index(&name, &elems)?;
+                    elems[i] = expr;
+                    Value::Tuple(elems)
+                }
+                val => {
+                    return Err(RuntimeErrorKind::FieldNotFound {
+                        field: name,
+                        found: value_type(&val),
+                    }
+                    .into())
+                }
+            };
+            update_place(strct, updated, context, funcs)?;
+        }
+        Node::Deref(reference) => match visit(reference, context, funcs)? {
+            Value::Ref(reference) => context.update_ref(&reference, expr)?,
+            val => {
+                return Err(RuntimeErrorKind::NotRef {
+                    found: value_type(&val),
+                }
+                .into())
+            }
+        },
+        place => {
+            return Err(RuntimeErrorKind::NodeNotSupported {
+                node: format!("{:?}", place),
+            }
+            .into())
         }
-        _ => panic!("update_var: Node not Var type"),
-    }
-
-    match next {
-        Some(statement) => visit(statement, context, funcs),
-        None => Value::None,
     }
+    Ok(())
 }
 
-fn eval_var(name: &str, context: &mut Context) -> Value {
+fn eval_var(
+    name: &str,
+    context: &mut Context,
+    funcs: &Funcs,
+) -> Result<Value, RuntimeErrorKind> {
     match context.get_var_value(name) {
-        Some(value) => value,
-        None => panic!("Variable \"{}\" is not defined", name),
+        Some(value) => Ok(value),
+        // A function used as a value is a closure which calls it with its parameters
+        None => match funcs.get(name) {
+            Some(func) => {
+                let mut args = vec![];
+                for param in func.params.iter() {
+                    match &**param {
+                        Node::FuncParam(var, _, _) => args.push(var.clone()),
+                        _ => unreachable!(),
+                    }
+                }
+                let call = Node::FuncCall {
+                    name: name.to_string(),
+                    args: args,
+                    next: None,
+                };
+                Ok(Value::Closure(func.params.clone(), Box::new(call), vec![]))
+            }
+            None => Err(RuntimeErrorKind::VarNotDefined {
+                var: name.to_string(),
+            }),
+        },
     }
 }
 
-fn eval_unary(op: Opcode, val: Value) -> Value {
-    let n = match val {
-        Value::Number(n) => n,
-        _ => panic!("UnaryOp on value not number!"),
-    };
-
-    match op {
-        Opcode::Sub => Value::Number(-n),
+/// Creates a closure, the values of the variables it uses are copied into it
+fn eval_closure(node: Box<Node>, context: &mut Context) -> Value {
+    let mut env = vec![];
+    for name in node.captures().into_iter() {
+        if let Some(value) = context.get_var_value(&name) {
+            env.push((name, value));
+        }
+    }
+    match *node {
+        Node::Closure { params, body } => Value::Closure(params, body, env),
         _ => unreachable!(),
     }
 }
 
-fn eval_expr(left: Value, op: Opcode, right: Value) -> Value {
-    match op {
-        Opcode::Add | Opcode::Sub | Opcode::Div | Opcode::Mul => eval_num_expr(left, op, right),
-        Opcode::AND | Opcode::OR => eval_log_op(left, op, right),
-        Opcode::EQ | Opcode::NEQ | Opcode::GT | Opcode::LT | Opcode::LEQ | Opcode::GEQ => {
-            eval_rel_op(left, op, right)
+/// Calls a closure, its body is evaluated in a new call frame containing the parameters and
+/// the variables it captured
+fn call_closure(
+    name: &str,
+    closure: Value,
+    args: Vec<Box<Node>>,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<Value, RuntimeError> {
+    let (params, body, env) = match closure {
+        Value::Closure(params, body, env) => (params, body, env),
+        val => {
+            return Err(RuntimeErrorKind::NotCallable {
+                name: name.to_string(),
+                found: value_type(&val),
+            }
+            .into())
         }
-    }
-}
-
-/// Evaluates an expression which will result in a number (Value::Number enum)
-fn eval_num_expr(left: Value, op: Opcode, right: Value) -> Value {
-    let (l, r) = match (left.clone(), right.clone()) {
-        (Value::Number(l_num), Value::Number(r_num)) => (l_num, r_num),
-        _ => panic!(
-            "Left or right part of number expression not a number, 
-			found: left = {:#?} and right = {:#?}",
-            left, right
-        ),
     };
+    if args.len() != params.len() {
+        return Err(RuntimeErrorKind::FnArgCount {
+            name: name.to_string(),
+            takes: params.len(),
+            supplied: args.len(),
+        }
+        .into());
+    }
 
-    match op {
-        Opcode::Add => Value::Number(l + r),
-        Opcode::Sub => Value::Number(l - r),
-        Opcode::Div => Value::Number(l / r),
-        Opcode::Mul => Value::Number(l * r),
-        _ => panic!(
-            "Wrong operation for evaluating a expression resulting in a number, found: {}",
-            op.to_string()
-        ),
+    let mut param_arg = vec![];
+    for (param, arg) in params.iter().zip(args) {
+        param_arg.push((&**param, visit(arg, context, funcs)?));
+    }
+    let mut scope = Scope::init(param_arg);
+    for (name, value) in env.into_iter() {
+        let typ = value_type(&value);
+        scope.vars.insert(name, Variable::new(value, false, typ));
     }
+
+    let caller = context.push_frame(name, scope)?;
+    let res = visit(body, context, funcs);
+    context.pop_frame(caller);
+    res.map_err(|err| err.called_from(name))
 }
 
-fn eval_rel_op(left: Value, op: Opcode, right: Value) -> Value {
-    match (left, right) {
-        (Value::Number(l_num), Value::Number(r_num)) => eval_num_rel_op(l_num, op, r_num),
-        (Value::Bool(l_bool), Value::Bool(r_bool)) => eval_bool_rel_op(l_bool, op, r_bool),
-        _ => panic!("eval_rel_op left and right not same type"),
+/// Creates a reference to a place, the indices are evaluated and bounds checked when the
+/// reference is created
+fn eval_ref(
+    place: Box<Node>,
+    mutable: bool,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<Reference, RuntimeError> {
+    match *place {
+        Node::Var(name) => match context.get_var_scope(&name) {
+            Some(scope) => Ok(Reference::new(scope, name, mutable)),
+            None => Err(RuntimeErrorKind::VarNotDefined { var: name }.into()),
+        },
+        Node::Index(arr, idx) => {
+            let mut reference = eval_ref(arr, mutable, context, funcs)?;
+            let idx = visit(idx, context, funcs)?;
+            let i = match context.deref(&reference)? {
+                Value::Array(elems) | Value::Vec(elems) => check_index(elems.len(), idx)?,
+                val => {
+                    return Err(RuntimeErrorKind::IndexNotArray {
+                        found: value_type(&val),
+                    }
+                    .into())
+                }
+            };
+            reference.path.push(Access::Index(i));
+            Ok(reference)
+        }
+        Node::Field(strct, name) => {
+            let mut reference = eval_ref(strct, mutable, context, funcs)?;
+            match context.deref(&reference)? {
+                Value::Tuple(elems) => {
+                    let i = tuple_index(&name, &elems)?;
+                    reference.path.push(Access::Index(i))
+                }
+                _ => reference.path.push(Access::Field(name)),
+            }
+            Ok(reference)
+        }
+        // Reborrowing i.e. "&mut *r"
+        Node::Deref(expr) => match visit(expr, context, funcs)? {
+            Value::Ref(mut reference) => {
+                reference.mutable &= mutable;
+                Ok(reference)
+            }
+            val => Err(RuntimeErrorKind::NotRef {
+                found: value_type(&val),
+            }
+            .into()),
+        },
+        place => Err(RuntimeErrorKind::NodeNotSupported {
+            node: format!("{:?}", place),
+        }
+        .into()),
     }
 }
 
-fn eval_num_rel_op(left: i32, op: Opcode, right: i32) -> Value {
-    match op {
-        Opcode::EQ => Value::Bool(left == right),
-        Opcode::NEQ => Value::Bool(left != right),
-        Opcode::GT => Value::Bool(left > right),
-        Opcode::LT => Value::Bool(left < right),
-        Opcode::GEQ => Value::Bool(left >= right),
-        Opcode::LEQ => Value::Bool(left <= right),
-        _ => panic!(
-            "Wrong operation for evaluating a relational expression, found: {}",
-            op.to_string()
-        ),
+fn eval_index(
+    arr: Box<Node>,
+    idx: Box<Node>,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<Value, RuntimeError> {
+    let arr = visit(arr, context, funcs)?;
+    let idx = visit(idx, context, funcs)?;
+    match arr {
+        Value::Array(elems) | Value::Vec(elems) => {
+            let i = check_index(elems.len(), idx)?;
+            Ok(elems[i].clone())
+        }
+        val => Err(RuntimeErrorKind::IndexNotArray {
+            found: value_type(&val),
+        }
+        .into()),
     }
 }
 
-fn eval_bool_rel_op(left: bool, op: Opcode, right: bool) -> Value {
-    match op {
-        Opcode::EQ => Value::Bool(left == right),
-        Opcode::NEQ => Value::Bool(left != right),
-        _ => panic!(
-            "Wrong operation for evaluating a relational expression, found: {}",
-            op.to_string()
-        ),
+fn eval_deref(
+    expr: Box<Node>,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<Value, RuntimeError> {
+    match visit(expr, context, funcs)? {
+        Value::Ref(reference) => Ok(context.deref(&reference)?),
+        val => Err(RuntimeErrorKind::NotRef {
+            found: value_type(&val),
+        }
+        .into()),
     }
 }
 
-fn eval_log_op(left: Value, op: Opcode, right: Value) -> Value {
-    let (l, r) = match (left, right) {
-        (Value::Bool(l_bool), Value::Bool(r_bool)) => (l_bool, r_bool),
-        _ => panic!("eval_log_op LEFT and RIGHT not both booleans"),
-    };
+/// Evaluates the fields of a struct literal, the fields are stored in the order of the struct declaration
+fn eval_struct_literal(
+    name: String,
+    fields: Vec<(String, Box<Node>)>,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<Value, RuntimeError> {
+    let mut values = vec![];
+    for (field, expr) in fields.into_iter() {
+        values.push((field, visit(expr, context, funcs)?));
+    }
 
-    match op {
-        Opcode::AND => Value::Bool(l && r),
-        Opcode::OR => Value::Bool(l || r),
-        _ => panic!(
-            "Wrong operation for evaluating a logical expression, found: {}",
-            op.to_string()
-        ),
+    if let Some(strct) = context.get_struct(&name) {
+        values.sort_by_key(|(field, _)| strct.get_field_index(field));
     }
+    Ok(Value::Struct(name, values))
 }
 
-fn eval_if_statement(
-    cond: Value,
-    statement: Box<Node>,
+fn eval_field(
+    strct: Box<Node>,
+    name: &str,
     context: &mut Context,
     funcs: &mut Funcs,
-    next: Option<Box<Node>>,
-) -> Value {
-    context.push(Scope::new());
-    let stmnt_res = match cond.clone() {
-        Value::Bool(b) => {
-            if b {
-                visit(statement, context, funcs)
-            } else {
-                Value::None
+) -> Result<Value, RuntimeError> {
+    match visit(strct, context, funcs)? {
+        Value::Struct(strct_name, fields) => {
+            match fields.into_iter().find(|(field, _)| field == name) {
+                Some((_, value)) => Ok(value),
+                None => Err(RuntimeErrorKind::FieldNotFound {
+                    field: name.to_string(),
+                    found: LiteralType::Named(strct_name),
+                }
+                .into()),
             }
         }
-        _ => panic!("eval_if_statement CONDITION did not evaluate to a boolean"),
-    };
-    context.pop();
+        Value::Tuple(mut elems) => {
+            let i = tuple_index(name, &elems)?;
+            Ok(elems.swap_remove(i))
+        }
+        val => Err(RuntimeErrorKind::FieldNotFound {
+            field: name.to_string(),
+            found: value_type(&val),
+        }
+        .into()),
+    }
+}
 
-    // If the if-statement returns anything, return that. Otherwise do the next statement
-    match stmnt_res {
-        Value::None => match next {
-            Some(stmnt) => visit(stmnt, context, funcs),
-            None => Value::None,
-        },
-        _ => stmnt_res,
+/// Gets the index of the element of a tuple that a field i.e. "0" refers to
+fn tuple_index(field: &str, elems: &Vec<Value>) -> Result<usize, RuntimeErrorKind> {
+    match field.parse::<usize>() {
+        Ok(idx) if idx < elems.len() => Ok(idx),
+        _ => Err(RuntimeErrorKind::FieldNotFound {
+            field: field.to_string(),
+            found: LiteralType::Tuple(elems.iter().map(value_type).collect()),
+        }),
     }
 }
 
-fn eval_if_else_statement(
-    cond: Value,
-    if_s: Box<Node>,
-    else_s: Box<Node>,
+/// Evaluates the arm of a match expression whose pattern matches the value of the expression
+fn eval_match(
+    expr: Box<Node>,
+    arms: Vec<(Pattern, Box<Node>)>,
     context: &mut Context,
     funcs: &mut Funcs,
-    next: Option<Box<Node>>,
-) -> Value {
-    context.push(Scope::new());
-    let stmnt_res = match cond.clone() {
-        Value::Bool(b) => {
-            if b {
-                visit(if_s, context, funcs)
-            } else {
-                visit(else_s, context, funcs)
+) -> Result<Value, RuntimeError> {
+    let val = visit(expr, context, funcs)?;
+    let (_, arm) = enter_arm(&val, arms, context)?;
+    let res = visit(arm, context, funcs);
+    pop_scope(context)?;
+    res
+}
+
+/// Executes the block of the arm of a match statement whose pattern matches the value of the
+/// expression
+fn exec_match(
+    expr: Box<Node>,
+    arms: Vec<(Pattern, Box<Node>)>,
+    first: usize,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<ControlFlow, RuntimeError> {
+    let val = visit(expr, context, funcs)?;
+    // The statements of an arm are numbered after the ones of the arms before it
+    let lens: Vec<usize> = arms.iter().map(|(_, arm)| block_len(arm)).collect();
+    let (pos, arm) = enter_arm(&val, arms, context)?;
+    let first = first + lens[..pos].iter().sum::<usize>();
+    let flow = exec(arm, first, context, funcs);
+    pop_scope(context)?;
+    flow
+}
+
+/// Finds the first arm whose pattern matches the value and pushes a new scope containing the
+/// variables bound by the pattern, which is popped after the arm is evaluated. Returns the
+/// position of the arm along with it
+fn enter_arm(
+    val: &Value,
+    arms: Vec<(Pattern, Box<Node>)>,
+    context: &mut Context,
+) -> Result<(usize, Box<Node>), RuntimeError> {
+    for (pos, (pat, arm)) in arms.into_iter().enumerate() {
+        let mut bindings = vec![];
+        if match_pattern(&pat, val, &mut bindings)? {
+            context.push(Scope::new());
+            for (name, value) in bindings.into_iter() {
+                let typ = value_type(&value);
+                context.insert_var(name, false, typ, value);
             }
+            return Ok((pos, arm));
         }
-        _ => panic!("eval_if_else_statement CONDITION does not evaluate to a boolean"),
-    };
-    context.pop();
-
-    match stmnt_res {
-        Value::None => match next {
-            Some(stmnt) => visit(stmnt, context, funcs),
-            None => Value::None,
-        },
-        _ => stmnt_res,
     }
+    Err(RuntimeErrorKind::NoMatchingArm {
+        found: value_type(val),
+    }
+    .into())
 }
 
-fn eval_while_statement(
-    cond: Box<Node>,
-    statement: Box<Node>,
-    context: &mut Context,
-    funcs: &mut Funcs,
-    next: Option<Box<Node>>,
-) -> Value {
-    context.push(Scope::new());
-    match visit(cond.clone(), context, funcs) {
-        Value::Bool(b) => {
-            if b {
-                let do_stmnt = visit(statement.clone(), context, funcs);
-                // Return the result of the while statement if it returns anything,
-                // otherwise continue the loop
-                match do_stmnt {
-                    Value::None => {
-                        context.pop();
-                        eval_while_statement(cond, statement.clone(), context, funcs, next.clone())
-                    }
-                    _ => {
-                        context.pop();
-                        do_stmnt
-                    }
+/// Checks if a value matches a pattern and collects the values of the variables it binds
+fn match_pattern(
+    pat: &Pattern,
+    val: &Value,
+    bindings: &mut Vec<(String, Value)>,
+) -> Result<bool, RuntimeErrorKind> {
+    match (pat, val) {
+        (Pattern::Wildcard, _) => Ok(true),
+        (Pattern::Binding(name), _) => {
+            bindings.push((name.to_string(), val.clone()));
+            Ok(true)
+        }
+        (Pattern::Number(pat_num), Value::Number(num)) => Ok(pat_num == num),
+        (Pattern::Bool(pat_b), Value::Bool(b)) => Ok(pat_b == b),
+        (Pattern::Variant { name, fields, .. }, Value::Enum(_, variant, values)) => {
+            if name != variant {
+                return Ok(false);
+            }
+            for (field, value) in fields.iter().zip(values.iter()) {
+                if !match_pattern(field, value, bindings)? {
+                    return Ok(false);
                 }
-            } else {
-                match next {
-                    Some(stmnt) => {
-                        context.pop();
-                        visit(stmnt, context, funcs)
-                    }
-                    None => {
-                        context.pop();
-                        Value::None
-                    }
+            }
+            Ok(true)
+        }
+        _ => Err(RuntimeErrorKind::PatternMismatch {
+            pattern: pat.to_string(),
+            found: value_type(val),
+        }),
+    }
+}
+
+/// Gets the type of a value, the element type of an empty vector and the type that a
+/// reference refers to are unknown
+pub fn value_type(val: &Value) -> LiteralType {
+    match val {
+        Value::Number(_) => LiteralType::I32,
+        Value::Bool(_) => LiteralType::Bool,
+        Value::String(_) => LiteralType::_String,
+        Value::Array(elems) => match elems.first() {
+            Some(elem) => LiteralType::Array(Box::new(value_type(elem)), elems.len()),
+            None => LiteralType::Array(Box::new(LiteralType::Infer), 0),
+        },
+        Value::Vec(elems) => match elems.first() {
+            Some(elem) => LiteralType::Vec(Box::new(value_type(elem))),
+            None => LiteralType::Vec(Box::new(LiteralType::Infer)),
+        },
+        Value::Struct(name, _) | Value::Enum(name, _, _) => LiteralType::Named(name.to_string()),
+        Value::Tuple(elems) => LiteralType::Tuple(elems.iter().map(value_type).collect()),
+        Value::Ref(_) => LiteralType::Ref(Box::new(LiteralType::Infer), false),
+        Value::Closure(params, _, _) => {
+            let mut param_types = vec![];
+            for param in params.iter() {
+                if let Node::FuncParam(_, typ, _) = &**param {
+                    param_types.push(typ.clone());
                 }
             }
+            LiteralType::Fn(param_types, Box::new(LiteralType::Infer))
         }
-        _ => panic!("eval_while_statement CONDITION does not evalute to a boolean"),
+        Value::Unit | Value::None => LiteralType::Void,
     }
 }
 
-// --------------------------------- TESTS ---------------------------------
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::parse::program_parser::parse;
-    use std::{error::Error, fs::File, io::prelude::*, path::Path};
+/// Checks that an index is within the bounds of an array of length len
+pub fn check_index(len: usize, idx: Value) -> Result<usize, RuntimeErrorKind> {
+    match idx {
+        Value::Number(i) => {
+            if i < 0 || i as usize >= len {
+                return Err(RuntimeErrorKind::IndexOutOfBounds { len: len, index: i });
+            }
+            Ok(i as usize)
+        }
+        val => Err(RuntimeErrorKind::IndexWrongType {
+            found: value_type(&val),
+        }),
+    }
+}
 
-    fn parse_interp(path: &Path) -> Option<Value> {
-        let display = path.display();
-        let mut file = match File::open(&path) {
-            Ok(file) => file,
+/// Pops the scope of a block, the context has no scope to pop if a block popped more scopes
+/// than it pushed
+fn pop_scope(context: &mut Context) -> Result<Scope, RuntimeErrorKind> {
+    context.pop().ok_or(RuntimeErrorKind::NoScope)
+}
+
+fn eval_method_call(
+    recv: Box<Node>,
+    name: &str,
+    args: Vec<Box<Node>>,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<Value, RuntimeError> {
+    let recv_val = visit(recv.clone(), context, funcs)?;
+    let mut args = visit_all(args, context, funcs)?;
+
+    match (recv_val, name, args.len()) {
+        (Value::Array(elems), "len", 0) | (Value::Vec(elems), "len", 0) => {
+            Ok(Value::Number(elems.len() as i32))
+        }
+        (Value::Vec(mut elems), "push", 1) => {
+            elems.push(args.remove(0));
+            update_place(recv, Value::Vec(elems), context, funcs)?;
+            Ok(Value::Unit)
+        }
+        (Value::Vec(mut elems), "pop", 0) => match elems.pop() {
+            Some(val) => {
+                update_place(recv, Value::Vec(elems), context, funcs)?;
+                Ok(val)
+            }
+            None => Err(RuntimeErrorKind::PopEmpty.into()),
+        },
+        // Arithmetic with another overflow behaviour than the one of the program, i.e.
+        // "a.wrapping_add(b)"
+        (Value::Number(l), name, 1) if arith_method(name).is_some() => {
+            let (op, overflow) = arith_method(name).unwrap();
+            match args.remove(0) {
+                Value::Number(r) => Ok(Value::Number(arith(l, op, r, overflow)?)),
+                r => Err(op_error(&Value::Number(l), op, &r).into()),
+            }
+        }
+        (recv_val, _, _) => Err(RuntimeErrorKind::MethodNotFound {
+            name: name.to_string(),
+            found: value_type(&recv_val),
+        }
+        .into()),
+    }
+}
+
+fn eval_unary(
+    op: Opcode,
+    value: Box<Node>,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<Value, RuntimeError> {
+    let n = match visit(value, context, funcs)? {
+        Value::Number(n) => n,
+        val => {
+            return Err(RuntimeErrorKind::UnaryOpWrongType {
+                found: value_type(&val),
+            }
+            .into())
+        }
+    };
+
+    match op {
+        Opcode::Sub => Ok(Value::Number(negate(n, context.budget.limits.overflow)?)),
+        _ => unreachable!(),
+    }
+}
+
+fn eval_expr(
+    left: Box<Node>,
+    op: Opcode,
+    right: Box<Node>,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<Value, RuntimeError> {
+    let left = visit(left, context, funcs)?;
+    // The right operand of a logical operation is only evaluated if the left one does not
+    // decide its result
+    match (&left, op) {
+        (Value::Bool(false), Opcode::AND) | (Value::Bool(true), Opcode::OR) => return Ok(left),
+        _ => (),
+    }
+    let right = visit(right, context, funcs)?;
+    eval_bin_op(left, op, right, context.budget.limits.overflow).map_err(RuntimeError::from)
+}
+
+/// Applies a binary operation to the values of its operands, an arithmetic operation which
+/// overflows has the behaviour given
+pub fn eval_bin_op(
+    left: Value,
+    op: Opcode,
+    right: Value,
+    overflow: Overflow,
+) -> Result<Value, RuntimeErrorKind> {
+    match op {
+        Opcode::Add | Opcode::Sub | Opcode::Div | Opcode::Mul => {
+            eval_num_expr(left, op, right, overflow)
+        }
+        Opcode::AND | Opcode::OR => eval_log_op(left, op, right),
+        Opcode::EQ | Opcode::NEQ | Opcode::GT | Opcode::LT | Opcode::LEQ | Opcode::GEQ => {
+            eval_rel_op(left, op, right)
+        }
+    }
+}
+
+fn op_error(left: &Value, op: Opcode, right: &Value) -> RuntimeErrorKind {
+    RuntimeErrorKind::OpWrongType {
+        op: op,
+        left: value_type(left),
+        right: value_type(right),
+    }
+}
+
+/// Evaluates an expression which will result in a number (Value::Number enum)
+fn eval_num_expr(
+    left: Value,
+    op: Opcode,
+    right: Value,
+    overflow: Overflow,
+) -> Result<Value, RuntimeErrorKind> {
+    match (&left, &right) {
+        (Value::Number(l), Value::Number(r)) => Ok(Value::Number(arith(*l, op, *r, overflow)?)),
+        _ => Err(op_error(&left, op, &right)),
+    }
+}
+
+fn eval_rel_op(left: Value, op: Opcode, right: Value) -> Result<Value, RuntimeErrorKind> {
+    match (&left, &right) {
+        (Value::Number(l_num), Value::Number(r_num)) => Ok(eval_num_rel_op(*l_num, op, *r_num)),
+        (Value::Bool(l_bool), Value::Bool(r_bool)) => match op {
+            Opcode::EQ => Ok(Value::Bool(l_bool == r_bool)),
+            Opcode::NEQ => Ok(Value::Bool(l_bool != r_bool)),
+            _ => Err(op_error(&left, op, &right)),
+        },
+        _ => Err(op_error(&left, op, &right)),
+    }
+}
+
+fn eval_num_rel_op(left: i32, op: Opcode, right: i32) -> Value {
+    match op {
+        Opcode::EQ => Value::Bool(left == right),
+        Opcode::NEQ => Value::Bool(left != right),
+        Opcode::GT => Value::Bool(left > right),
+        Opcode::LT => Value::Bool(left < right),
+        Opcode::GEQ => Value::Bool(left >= right),
+        Opcode::LEQ => Value::Bool(left <= right),
+        _ => unreachable!(),
+    }
+}
+
+fn eval_log_op(left: Value, op: Opcode, right: Value) -> Result<Value, RuntimeErrorKind> {
+    match (&left, &op, &right) {
+        (Value::Bool(l), Opcode::AND, Value::Bool(r)) => Ok(Value::Bool(*l && *r)),
+        (Value::Bool(l), Opcode::OR, Value::Bool(r)) => Ok(Value::Bool(*l || *r)),
+        _ => Err(op_error(&left, op, &right)),
+    }
+}
+
+/// Gets the value of the condition of an if statement or a while loop
+pub fn eval_cond(cond: Value) -> Result<bool, RuntimeErrorKind> {
+    match cond {
+        Value::Bool(b) => Ok(b),
+        val => Err(RuntimeErrorKind::Cond {
+            found: value_type(&val),
+        }),
+    }
+}
+
+fn eval_if_statement(
+    cond: Box<Node>,
+    statement: Box<Node>,
+    first: usize,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<ControlFlow, RuntimeError> {
+    if eval_cond(visit(cond, context, funcs)?)? {
+        exec_scoped(statement, first, context, funcs)
+    } else {
+        Ok(ControlFlow::Normal)
+    }
+}
+
+fn eval_if_else_statement(
+    cond: Box<Node>,
+    if_s: Box<Node>,
+    else_s: Box<Node>,
+    first: usize,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<ControlFlow, RuntimeError> {
+    if eval_cond(visit(cond, context, funcs)?)? {
+        exec_scoped(if_s, first, context, funcs)
+    } else {
+        let first = first + block_len(&if_s);
+        exec_scoped(else_s, first, context, funcs)
+    }
+}
+
+fn eval_while_statement(
+    cond: Box<Node>,
+    statement: Box<Node>,
+    first: usize,
+    context: &mut Context,
+    funcs: &mut Funcs,
+) -> Result<ControlFlow, RuntimeError> {
+    while eval_cond(visit(cond.clone(), context, funcs)?)? {
+        // The loop is the statement before the first one of its body
+        if let Some(tracer) = &mut context.tracer {
+            tracer.iteration(first - 1);
+        }
+        match exec_scoped(statement.clone(), first, context, funcs)? {
+            ControlFlow::Normal | ControlFlow::Continue => (),
+            ControlFlow::Break => break,
+            flow => return Ok(flow),
+        }
+    }
+    Ok(ControlFlow::Normal)
+}
+
+// --------------------------------- TESTS ---------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        limits::DEFAULT_MAX_DEPTH,
+        parse::program_parser::{parse, parse_with_lines},
+    };
+    use std::{error::Error, fs, fs::File, io::prelude::*, path::Path, time::Duration};
+
+    fn parse_interp(path: &Path) -> Option<Value> {
+        interp_file(path).unwrap()
+    }
+
+    fn parse_interp_err(path: &Path) -> RuntimeError {
+        interp_file(path).unwrap_err()
+    }
+
+    fn interp_file(path: &Path) -> Result<Option<Value>, RuntimeError> {
+        let display = path.display();
+        let mut file = match File::open(&path) {
+            Ok(file) => file,
             Err(e) => panic!("Could not open {}: {}", display, e.description()),
         };
 
@@ -403,7 +1162,9 @@ mod tests {
             Err(e) => panic!("Could not read file: {:?}", e),
         }
 
-        interp(parse(input).unwrap())
+        // The errors are located so that their lines can be checked
+        let (program, lines) = parse_with_lines(input).unwrap();
+        interp(program).map_err(|err| err.locate(&lines))
     }
 
     // Expression precedence
@@ -459,15 +1220,56 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn if_var_outside_scope() {
-        parse_interp(Path::new("tests/scope/if_var_outside_scope.txt"));
+        assert_eq!(
+            parse_interp_err(Path::new("tests/scope/if_var_outside_scope.txt")).kind,
+            RuntimeErrorKind::VarNotDefined {
+                var: String::from("b")
+            }
+        );
     }
 
     #[test]
-    #[should_panic]
     fn fn_scope_not_same() {
-        parse_interp(Path::new("tests/scope/fn_scope_not_same.txt"));
+        assert_eq!(
+            parse_interp_err(Path::new("tests/scope/fn_scope_not_same.txt")).kind,
+            RuntimeErrorKind::VarNotDefined {
+                var: String::from("x")
+            }
+        );
+    }
+
+    #[test]
+    fn fn_read_caller_var() {
+        let err = parse_interp_err(Path::new("tests/scope/fn_read_caller_var.txt"));
+        assert_eq!(
+            err,
+            RuntimeError {
+                kind: RuntimeErrorKind::VarNotDefined {
+                    var: String::from("secret")
+                },
+                stmnt: Some(String::from("return")),
+                stmnt_idx: Some(0),
+                line: Some(2),
+                trace: vec![String::from("peek"), String::from("main")],
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Variable 'secret' is not defined
+    at the return statement on line 2 in 'peek'
+    called from 'main'"
+        );
+    }
+
+    #[test]
+    fn fn_write_caller_var() {
+        assert_eq!(
+            parse_interp_err(Path::new("tests/scope/fn_write_caller_var.txt")).kind,
+            RuntimeErrorKind::VarNotDefined {
+                var: String::from("secret")
+            }
+        );
     }
 
     #[test]
@@ -495,9 +1297,13 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn while_var_outside_scope() {
-        parse_interp(Path::new("tests/scope/while_var_outside_scope.txt"));
+        assert_eq!(
+            parse_interp_err(Path::new("tests/scope/while_var_outside_scope.txt")).kind,
+            RuntimeErrorKind::VarImmut {
+                var: String::from("x")
+            }
+        );
     }
 
     #[test]
@@ -525,9 +1331,13 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn if_else_var_outside_scope() {
-        parse_interp(Path::new("tests/scope/if_else_var_outside_scope.txt"));
+        assert_eq!(
+            parse_interp_err(Path::new("tests/scope/if_else_var_outside_scope.txt")).kind,
+            RuntimeErrorKind::VarImmut {
+                var: String::from("x")
+            }
+        );
     }
 
     // Functions
@@ -545,9 +1355,13 @@ mod tests {
 
     // Mutability
     #[test]
-    #[should_panic]
     fn no_mut_var() {
-        parse_interp(Path::new("tests/mutability/no_mut_var.txt"));
+        assert_eq!(
+            parse_interp_err(Path::new("tests/mutability/no_mut_var.txt")).kind,
+            RuntimeErrorKind::VarImmut {
+                var: String::from("a")
+            }
+        );
     }
 
     #[test]
@@ -559,9 +1373,13 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn fn_no_mut_param() {
-        parse_interp(Path::new("tests/mutability/fn_no_mut_param.txt"));
+        assert_eq!(
+            parse_interp_err(Path::new("tests/mutability/fn_no_mut_param.txt")).kind,
+            RuntimeErrorKind::VarImmut {
+                var: String::from("a")
+            }
+        );
     }
 
     #[test]
@@ -571,4 +1389,723 @@ mod tests {
             Some(Value::Number(51))
         );
     }
+
+    #[test]
+    fn fn_mut_ref() {
+        assert_eq!(
+            parse_interp(Path::new("tests/mutability/fn_mut_ref.txt")),
+            Some(Value::Number(51))
+        );
+    }
+
+    // Arrays
+    #[test]
+    fn array_index() {
+        assert_eq!(
+            parse_interp(Path::new("tests/array/index.txt")),
+            Some(Value::Number(15))
+        );
+    }
+
+    #[test]
+    fn array_update() {
+        assert_eq!(
+            parse_interp(Path::new("tests/array/update.txt")),
+            Some(Value::Number(65))
+        );
+    }
+
+    #[test]
+    fn array_nested() {
+        assert_eq!(
+            parse_interp(Path::new("tests/array/nested.txt")),
+            Some(Value::Number(12))
+        );
+    }
+
+    #[test]
+    fn array_param() {
+        assert_eq!(
+            parse_interp(Path::new("tests/array/param.txt")),
+            Some(Value::Number(10))
+        );
+    }
+
+    #[test]
+    fn array_out_of_bounds() {
+        assert_eq!(
+            parse_interp_err(Path::new("tests/array/out_of_bounds.txt")).kind,
+            RuntimeErrorKind::IndexOutOfBounds { len: 3, index: 3 }
+        );
+    }
+
+    #[test]
+    fn no_mut_array() {
+        assert_eq!(
+            parse_interp_err(Path::new("tests/array/no_mut_array.txt")).kind,
+            RuntimeErrorKind::VarImmut {
+                var: String::from("a")
+            }
+        );
+    }
+
+    // Vectors
+    #[test]
+    fn vec_push_pop() {
+        assert_eq!(
+            parse_interp(Path::new("tests/vec/push_pop.txt")),
+            Some(Value::Number(118))
+        );
+    }
+
+    #[test]
+    fn vec_collect() {
+        assert_eq!(
+            parse_interp(Path::new("tests/vec/collect.txt")),
+            Some(Value::Number(20))
+        );
+    }
+
+    #[test]
+    fn vec_copy() {
+        assert_eq!(
+            parse_interp(Path::new("tests/vec/copy.txt")),
+            Some(Value::Number(121))
+        );
+    }
+
+    #[test]
+    fn vec_pop_empty() {
+        assert_eq!(
+            parse_interp_err(Path::new("tests/vec/pop_empty.txt")).kind,
+            RuntimeErrorKind::PopEmpty
+        );
+    }
+
+    #[test]
+    fn vec_out_of_bounds() {
+        assert_eq!(
+            parse_interp_err(Path::new("tests/vec/out_of_bounds.txt")).kind,
+            RuntimeErrorKind::IndexOutOfBounds { len: 1, index: 1 }
+        );
+    }
+
+    #[test]
+    fn no_mut_vec() {
+        assert_eq!(
+            parse_interp_err(Path::new("tests/vec/no_mut_vec.txt")).kind,
+            RuntimeErrorKind::VarImmut {
+                var: String::from("v")
+            }
+        );
+    }
+
+    // Structs
+    #[test]
+    fn struct_fields() {
+        assert_eq!(
+            parse_interp(Path::new("tests/struct/fields.txt")),
+            Some(Value::Number(12))
+        );
+    }
+
+    #[test]
+    fn struct_update() {
+        assert_eq!(
+            parse_interp(Path::new("tests/struct/update.txt")),
+            Some(Value::Number(14))
+        );
+    }
+
+    #[test]
+    fn struct_param() {
+        assert_eq!(
+            parse_interp(Path::new("tests/struct/param.txt")),
+            Some(Value::Number(36))
+        );
+    }
+
+    #[test]
+    fn struct_vec_field() {
+        assert_eq!(
+            parse_interp(Path::new("tests/struct/vec_field.txt")),
+            Some(Value::Number(236))
+        );
+    }
+
+    #[test]
+    fn no_mut_struct() {
+        assert_eq!(
+            parse_interp_err(Path::new("tests/struct/no_mut_struct.txt")).kind,
+            RuntimeErrorKind::VarImmut {
+                var: String::from("p")
+            }
+        );
+    }
+
+    // Enums
+    #[test]
+    fn enum_area() {
+        assert_eq!(
+            parse_interp(Path::new("tests/enum/area.txt")),
+            Some(Value::Number(19))
+        );
+    }
+
+    #[test]
+    fn enum_nested() {
+        assert_eq!(
+            parse_interp(Path::new("tests/enum/nested.txt")),
+            Some(Value::Number(104))
+        );
+    }
+
+    #[test]
+    fn enum_literal() {
+        assert_eq!(
+            parse_interp(Path::new("tests/enum/literal.txt")),
+            Some(Value::Number(37))
+        );
+    }
+
+    // Type inference
+    #[test]
+    fn infer_let() {
+        assert_eq!(
+            parse_interp(Path::new("tests/inference/let.txt")),
+            Some(Value::Number(12))
+        );
+    }
+
+    #[test]
+    fn infer_shadow() {
+        assert_eq!(
+            parse_interp(Path::new("tests/inference/shadow.txt")),
+            Some(Value::Number(1))
+        );
+    }
+
+    // Generics
+    #[test]
+    fn generic_max() {
+        assert_eq!(
+            parse_interp(Path::new("tests/generics/max.txt")),
+            Some(Value::Number(92))
+        );
+    }
+
+    #[test]
+    fn generic_pair() {
+        assert_eq!(
+            parse_interp(Path::new("tests/generics/pair.txt")),
+            Some(Value::Number(3))
+        );
+    }
+
+    // Closures
+    #[test]
+    fn closure_fold() {
+        assert_eq!(
+            parse_interp(Path::new("tests/closure/fold.txt")),
+            Some(Value::Number(42))
+        );
+    }
+
+    #[test]
+    fn closure_capture() {
+        assert_eq!(
+            parse_interp(Path::new("tests/closure/capture.txt")),
+            Some(Value::Number(108))
+        );
+    }
+
+    // Tuples
+    #[test]
+    fn tuple_divmod() {
+        assert_eq!(
+            parse_interp(Path::new("tests/tuple/divmod.txt")),
+            Some(Value::Number(323))
+        );
+    }
+
+    #[test]
+    fn tuple_update() {
+        assert_eq!(
+            parse_interp(Path::new("tests/tuple/update.txt")),
+            Some(Value::Number(18))
+        );
+    }
+
+    #[test]
+    fn no_mut_tuple() {
+        assert_eq!(
+            parse_interp_err(Path::new("tests/tuple/no_mut_tuple.txt")).kind,
+            RuntimeErrorKind::VarImmut {
+                var: String::from("a")
+            }
+        );
+    }
+
+    // References
+    #[test]
+    fn ref_swap() {
+        assert_eq!(
+            parse_interp(Path::new("tests/reference/swap.txt")),
+            Some(Value::Number(303))
+        );
+    }
+
+    #[test]
+    fn ref_struct() {
+        assert_eq!(
+            parse_interp(Path::new("tests/reference/struct_ref.txt")),
+            Some(Value::Number(72))
+        );
+    }
+
+    #[test]
+    fn no_mut_ref() {
+        assert_eq!(
+            parse_interp_err(Path::new("tests/reference/no_mut_ref.txt")).kind,
+            RuntimeErrorKind::RefImmut {
+                var: String::from("x")
+            }
+        );
+    }
+
+    // Control flow
+    #[test]
+    fn nested_return() {
+        assert_eq!(
+            parse_interp(Path::new("tests/control_flow/nested_return.txt")),
+            Some(Value::Number(105))
+        );
+    }
+
+    #[test]
+    fn void_return() {
+        assert_eq!(
+            parse_interp(Path::new("tests/control_flow/void_return.txt")),
+            Some(Value::Number(12))
+        );
+    }
+
+    #[test]
+    fn call_in_loop() {
+        assert_eq!(
+            parse_interp(Path::new("tests/control_flow/call_in_loop.txt")),
+            Some(Value::Number(45))
+        );
+    }
+
+    #[test]
+    fn break_continue() {
+        assert_eq!(
+            parse_interp(Path::new("tests/control_flow/break_continue.txt")),
+            Some(Value::Number(50))
+        );
+    }
+
+    #[test]
+    fn short_circuit() {
+        assert_eq!(
+            parse_interp(Path::new("tests/short_circuit/div_guard.txt")),
+            Some(Value::Number(12))
+        );
+        assert_eq!(
+            parse_interp(Path::new("tests/short_circuit/index_guard.txt")),
+            Some(Value::Number(311))
+        );
+        // The type of the right operand is still checked when it is evaluated
+        let res = interp(
+            parse(
+                "fn main() -> bool {
+                    return false || 1;
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            res.unwrap_err().kind,
+            RuntimeErrorKind::OpWrongType {
+                op: Opcode::OR,
+                left: LiteralType::Bool,
+                right: LiteralType::I32,
+            }
+        );
+    }
+
+    #[test]
+    fn unit_return() {
+        let res = interp(
+            parse(
+                "fn main() {
+                    let mut i: i32 = 0;
+                    while (i < 3) {
+                        if (i == 1) {
+                            return;
+                        }
+                        i = i + 1;
+                    }
+                    print(i);
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(res, Ok(None));
+
+        let res = interp(
+            parse(
+                "fn f() {
+                    break;
+                }
+
+                fn main() {
+                    f();
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        );
+        let err = res.unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::LoopModifierOutsideLoop);
+        assert_eq!(err.trace, vec![String::from("f"), String::from("main")]);
+    }
+
+    #[test]
+    fn long_loop() {
+        let res = interp(
+            parse(
+                "fn main() -> i32 {
+                    let mut i: i32 = 0;
+                    while (i < 300000) {
+                        i = i + 1;
+                    }
+                    return i;
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(res, Ok(Some(Value::Number(300000))));
+    }
+
+    #[test]
+    fn stack_overflow() {
+        let res = interp(
+            parse(
+                "fn down(n: i32) -> i32 {
+                    return down(n - 1) + 1;
+                }
+
+                fn main() -> i32 {
+                    return down(0);
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        );
+        let err = res.unwrap_err();
+        assert_eq!(
+            err.kind,
+            RuntimeErrorKind::StackOverflow {
+                name: String::from("down"),
+                max_depth: DEFAULT_MAX_DEPTH,
+            }
+        );
+        assert_eq!(err.trace.len(), DEFAULT_MAX_DEPTH);
+        assert_eq!(
+            err.to_string(),
+            "Stack overflow in function 'down', calls cannot be nested more than 1000 deep
+    at the return statement in 'down'
+    called from 'down' 998 times
+    called from 'main'"
+        );
+    }
+
+    #[test]
+    fn max_depth() {
+        let program = parse(
+            "fn fib(n: i32) -> i32 {
+                if (n < 2) {
+                    return n;
+                }
+                return fib(n - 1) + fib(n - 2);
+            }
+
+            fn main() -> i32 {
+                return fib(10);
+            }"
+            .to_string(),
+        )
+        .unwrap();
+        let mut limits = Limits::new();
+        limits.max_depth = 11;
+        assert_eq!(
+            interp_with_limits(program.clone(), limits.clone()),
+            Ok(Some(Value::Number(55)))
+        );
+        limits.max_depth = 10;
+        assert_eq!(
+            interp_with_limits(program, limits).unwrap_err().kind,
+            RuntimeErrorKind::StackOverflow {
+                name: String::from("fib"),
+                max_depth: 10,
+            }
+        );
+
+        // The depth is bounded by the largest stack the interpreter is started with
+        let program = parse(
+            "fn down(n: i32) -> i32 {
+                return down(n + 1);
+            }
+
+            fn main() -> i32 {
+                return down(0);
+            }"
+            .to_string(),
+        )
+        .unwrap();
+        let mut limits = Limits::new();
+        limits.max_depth = usize::MAX;
+        assert_eq!(
+            interp_with_limits(program, limits).unwrap_err().kind,
+            RuntimeErrorKind::StackOverflow {
+                name: String::from("down"),
+                max_depth: MAX_DEPTH,
+            }
+        );
+    }
+
+    // Limits
+    #[test]
+    fn fuel() {
+        let program = parse(
+            "fn main() -> i32 {
+                let mut i: i32 = 0;
+                while (i < 100) {
+                    i = i + 1;
+                }
+                return i;
+            }"
+            .to_string(),
+        )
+        .unwrap();
+        let mut limits = Limits::new();
+        limits.fuel = Some(10000);
+        assert_eq!(
+            interp_with_limits(program.clone(), limits.clone()),
+            Ok(Some(Value::Number(100)))
+        );
+        limits.fuel = Some(500);
+        let err = interp_with_limits(program, limits).unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::OutOfFuel { fuel: 500 });
+        assert_eq!(err.trace, vec![String::from("main")]);
+    }
+
+    #[test]
+    fn timeout() {
+        let program = parse(
+            "fn main() -> i32 {
+                let mut i: i32 = 0;
+                while (true) {
+                    i = i + 1;
+                }
+            }"
+            .to_string(),
+        )
+        .unwrap();
+        let mut limits = Limits::new();
+        limits.timeout = Some(Duration::from_millis(100));
+        assert_eq!(
+            interp_with_limits(program, limits).unwrap_err().kind,
+            RuntimeErrorKind::Timeout {
+                timeout: Duration::from_millis(100)
+            }
+        );
+    }
+
+    #[test]
+    fn max_memory() {
+        let program = parse(
+            "fn fill(n: i32) -> i32 {
+                let mut v: Vec<i32> = Vec::new();
+                while (v.len() < n) {
+                    v.push(v.len());
+                }
+                return v.len();
+            }
+
+            fn main() -> i32 {
+                let mut sum: i32 = 0;
+                let mut i: i32 = 0;
+                while (i < 10) {
+                    sum = sum + fill(10);
+                    i = i + 1;
+                }
+                return sum;
+            }"
+            .to_string(),
+        )
+        .unwrap();
+
+        // The memory of the vectors is freed when fill returns
+        let mut limits = Limits::new();
+        limits.max_memory = Some(4096);
+        assert_eq!(
+            interp_with_limits(program, limits.clone()),
+            Ok(Some(Value::Number(100)))
+        );
+
+        let program = parse(
+            "fn main() {
+                let mut v: Vec<i32> = Vec::new();
+                while (true) {
+                    v.push(1);
+                }
+            }"
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            interp_with_limits(program, limits.clone()).unwrap_err().kind,
+            RuntimeErrorKind::OutOfMemory { max_memory: 4096 }
+        );
+
+        let program = parse(
+            "fn main() -> i32 {
+                let a: [i32; 2000000000] = [0; 2000000000];
+                return a[0];
+            }"
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            interp_with_limits(program, limits).unwrap_err().kind,
+            RuntimeErrorKind::OutOfMemory { max_memory: 4096 }
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let err = parse_interp_err(Path::new("tests/arithmetic/factorial.txt"));
+        assert_eq!(
+            err.to_string(),
+            "Arithmetic overflow in '13 * 479001600'
+    at the return statement on line 5 in 'fact'
+    called from 'main'"
+        );
+
+        let input = fs::read_to_string("tests/arithmetic/factorial.txt").unwrap();
+        let mut limits = Limits::new();
+        limits.overflow = Overflow::Wrap;
+        let res = interp_with_limits(parse(input.clone()).unwrap(), limits.clone());
+        assert_eq!(res, Ok(Some(Value::Number(1932053504))));
+        limits.overflow = Overflow::Saturate;
+        let res = interp_with_limits(parse(input).unwrap(), limits);
+        assert_eq!(res, Ok(Some(Value::Number(i32::MAX))));
+
+        let res = interp(
+            parse(
+                "fn main() -> i32 {
+                    let min: i32 = -2147483647 - 1;
+                    return -min;
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            res.unwrap_err().kind,
+            RuntimeErrorKind::NegOverflow { num: i32::MIN }
+        );
+    }
+
+    #[test]
+    fn div_by_zero() {
+        // A division by zero is an error whatever the overflow behaviour is
+        let input = fs::read_to_string("tests/arithmetic/div_by_zero.txt").unwrap();
+        let mut limits = Limits::new();
+        limits.overflow = Overflow::Wrap;
+        let (program, lines) = parse_with_lines(input).unwrap();
+        let err = interp_with_limits(program, limits).unwrap_err().locate(&lines);
+        assert_eq!(err.kind, RuntimeErrorKind::DivByZero { left: 7 });
+        assert_eq!(err.trace, vec!["div", "main"]);
+        assert_eq!(err.line, Some(2));
+    }
+
+    #[test]
+    fn arith_methods() {
+        // The methods have their own overflow behaviour
+        assert_eq!(
+            parse_interp(Path::new("tests/arithmetic/wrapping.txt")),
+            Some(Value::Number(i32::MAX))
+        );
+        let res = interp(
+            parse(
+                "fn main() -> i32 {
+                    let max: i32 = 2147483647;
+                    return max.wrapping_add(1).wrapping_div(0);
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            res.unwrap_err().kind,
+            RuntimeErrorKind::DivByZero { left: i32::MIN }
+        );
+    }
+
+    // Runtime errors
+    #[test]
+    fn runtime_errors() {
+        let res = interp(parse("fn f() { print(1); }".to_string()).unwrap());
+        assert_eq!(res.unwrap_err().kind, RuntimeErrorKind::MainMissing);
+
+        let res = interp(
+            parse(
+                "fn main() {
+                    if (1) {
+                        print(1);
+                    }
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            res.unwrap_err().kind,
+            RuntimeErrorKind::Cond {
+                found: LiteralType::I32
+            }
+        );
+
+        let res = interp(
+            parse(
+                "fn apply(f: fn(i32) -> i32) -> i32 {
+                    return f(1, 2);
+                }
+
+                fn main() -> i32 {
+                    let g = |x: i32| x;
+                    return apply(g);
+                }"
+                .to_string(),
+            )
+            .unwrap(),
+        );
+        let err = res.unwrap_err();
+        assert_eq!(
+            err.kind,
+            RuntimeErrorKind::FnArgCount {
+                name: String::from("f"),
+                takes: 1,
+                supplied: 2,
+            }
+        );
+        assert_eq!(err.trace, vec![String::from("apply"), String::from("main")]);
+    }
 }
@@ -0,0 +1,128 @@
+use std::time::{Duration, Instant};
+
+use crate::{arithmetic::Overflow, runtime_error::RuntimeErrorKind};
+
+/// The maximum depth of nested function calls, unless another one is given
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+// The number of steps between the checks of the time limit, since getting the time is slow
+// compared to a step
+const STEPS_PER_TIME_CHECK: u64 = 1024;
+
+/// The limits that a program is executed with, a limit which is None is not checked
+#[derive(Clone, Debug, PartialEq)]
+pub struct Limits {
+    // The maximum depth of nested function calls
+    pub max_depth: usize,
+    // The maximum number of steps, every node that is evaluated is one step
+    pub fuel: Option<u64>,
+    // The maximum time the program is executed for
+    pub timeout: Option<Duration>,
+    // The maximum number of bytes used by the variables in scope, including the strings,
+    // arrays and other values they own
+    pub max_memory: Option<usize>,
+    // What happens when the result of an arithmetic operation exceeds the limits of an i32
+    pub overflow: Overflow,
+}
+
+impl Limits {
+    pub fn new() -> Limits {
+        Limits {
+            max_depth: DEFAULT_MAX_DEPTH,
+            fuel: None,
+            timeout: None,
+            max_memory: None,
+            overflow: Overflow::Trap,
+        }
+    }
+}
+
+/// Keeps track of how much of its limits a program has used while it is executed
+#[derive(Clone, Debug, PartialEq)]
+pub struct Budget {
+    pub limits: Limits,
+    // The number of function calls which have not yet returned
+    depth: usize,
+    steps: u64,
+    memory: usize,
+    start: Instant,
+}
+
+impl Budget {
+    /// Starts the budget of a program which is executed from now on
+    pub fn new(limits: Limits) -> Budget {
+        Budget {
+            limits: limits,
+            depth: 0,
+            steps: 0,
+            memory: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// Takes a step, returns an error if the program has run out of fuel or time or if it
+    /// used more memory than it is allowed to
+    pub fn step(&mut self) -> Result<(), RuntimeErrorKind> {
+        self.steps += 1;
+        if let Some(fuel) = self.limits.fuel {
+            if self.steps > fuel {
+                return Err(RuntimeErrorKind::OutOfFuel { fuel: fuel });
+            }
+        }
+        if let Some(max_memory) = self.limits.max_memory {
+            if self.memory > max_memory {
+                return Err(RuntimeErrorKind::OutOfMemory {
+                    max_memory: max_memory,
+                });
+            }
+        }
+        if let Some(timeout) = self.limits.timeout {
+            if self.steps % STEPS_PER_TIME_CHECK == 0 && self.start.elapsed() > timeout {
+                return Err(RuntimeErrorKind::Timeout { timeout: timeout });
+            }
+        }
+        Ok(())
+    }
+
+    /// Enters a call to a function, returns an error if the call would exceed the maximum
+    /// call depth
+    pub fn enter_call(&mut self, name: &str) -> Result<(), RuntimeErrorKind> {
+        if self.depth >= self.limits.max_depth {
+            return Err(RuntimeErrorKind::StackOverflow {
+                name: name.to_string(),
+                max_depth: self.limits.max_depth,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub fn exit_call(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Checks if the memory used by the program has to be counted, the size of a value is
+    /// only computed if it is limited
+    pub fn counts_memory(&self) -> bool {
+        self.limits.max_memory.is_some()
+    }
+
+    /// Checks that a value of the size given can be allocated without using more memory than
+    /// the program is allowed to, before it is allocated
+    pub fn reserve(&self, size: usize) -> Result<(), RuntimeErrorKind> {
+        match self.limits.max_memory {
+            Some(max_memory) if self.memory.saturating_add(size) > max_memory => {
+                Err(RuntimeErrorKind::OutOfMemory {
+                    max_memory: max_memory,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts the memory which was allocated and freed by a change to the variables, the
+    /// limit is checked by the next step
+    pub fn realloc(&mut self, freed: usize, allocated: usize) {
+        self.memory = (self.memory + allocated).saturating_sub(freed);
+    }
+}
@@ -0,0 +1,677 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{param_name, Node},
+    control_flow::unreachable_code,
+    pattern::Pattern,
+    type_errors::Warning,
+};
+
+/// The lints of a program, their levels are set with attributes i.e. "#![allow(dead_code)]"
+/// or with the -A, -W and -D flags
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Lint {
+    UnusedVariables,
+    UnusedMut,
+    UnusedAssignments,
+    DeadCode,
+    UnreachableCode,
+    Shadowing,
+    UnknownLints,
+}
+
+const LINTS: [Lint; 7] = [
+    Lint::UnusedVariables,
+    Lint::UnusedMut,
+    Lint::UnusedAssignments,
+    Lint::DeadCode,
+    Lint::UnreachableCode,
+    Lint::Shadowing,
+    Lint::UnknownLints,
+];
+
+impl Lint {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::UnusedVariables => "unused_variables",
+            Lint::UnusedMut => "unused_mut",
+            Lint::UnusedAssignments => "unused_assignments",
+            Lint::DeadCode => "dead_code",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::Shadowing => "shadowing",
+            Lint::UnknownLints => "unknown_lints",
+        }
+    }
+
+    fn default_level(&self) -> Level {
+        match self {
+            Lint::Shadowing => Level::Allow,
+            _ => Level::Warn,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl Level {
+    fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// The levels of the lints, a lint which has not been set has its default level
+#[derive(Clone, Debug, PartialEq)]
+pub struct LintLevels {
+    levels: HashMap<Lint, Level>,
+}
+
+impl LintLevels {
+    pub fn new() -> LintLevels {
+        LintLevels {
+            levels: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, lint: Lint) -> Level {
+        match self.levels.get(&lint) {
+            Some(level) => *level,
+            None => lint.default_level(),
+        }
+    }
+
+    /// Sets the level of a lint and returns false if there is no lint with the name. The
+    /// name "warnings" sets the level of all the lints which are not allowed, or of all
+    /// lints when they are allowed
+    pub fn set(&mut self, name: &str, level: Level) -> bool {
+        if name == "warnings" {
+            for lint in LINTS.iter() {
+                if level == Level::Allow || self.get(*lint) != Level::Allow {
+                    self.levels.insert(*lint, level);
+                }
+            }
+            return true;
+        }
+
+        match LINTS.iter().find(|lint| lint.name() == name) {
+            Some(lint) => {
+                self.levels.insert(*lint, level);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Runs the lints on a type checked program where the attributes of the program override
+/// the levels given. Returns the warnings of the lints that are not allowed with their level
+pub fn lint(program: &Vec<Box<Node>>, levels: &LintLevels) -> Vec<(Level, Warning)> {
+    let mut levels = levels.clone();
+    let mut warnings = vec![];
+    for item in program.iter() {
+        if let Node::Attribute { level, lints } = &**item {
+            let level = match Level::from_name(level) {
+                Some(level) => level,
+                None => {
+                    warnings.push(Warning::UnknownLint {
+                        lint: level.to_string(),
+                    });
+                    continue;
+                }
+            };
+            for name in lints.iter() {
+                if !levels.set(name, level) {
+                    warnings.push(Warning::UnknownLint {
+                        lint: name.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    warnings.extend(unreachable_code(program));
+    warnings.extend(dead_code(program));
+    for item in program.iter() {
+        if let Node::Func {
+            name, params, body, ..
+        } = &**item
+        {
+            let mut linter = Linter {
+                func: name,
+                scopes: vec![],
+                loops: vec![],
+                warnings: &mut warnings,
+            };
+            linter.func(params, body);
+        }
+    }
+
+    warnings
+        .into_iter()
+        .filter_map(|warning| match levels.get(warning.lint()) {
+            Level::Allow => None,
+            level => Some((level, warning)),
+        })
+        .collect()
+}
+
+/// Finds the functions which can not be reached from main
+fn dead_code(program: &Vec<Box<Node>>) -> Vec<Warning> {
+    let mut refs: HashMap<&str, Vec<String>> = HashMap::new();
+    for item in program.iter() {
+        if let Node::Func { name, body, .. } = &**item {
+            let mut names = vec![];
+            reads(body, &mut names);
+            refs.insert(name, names);
+        }
+    }
+
+    let mut used = vec![String::from("main")];
+    let mut i = 0;
+    while i < used.len() {
+        if let Some(names) = refs.get(used[i].as_str()) {
+            for name in names.iter() {
+                if refs.contains_key(name.as_str()) && !used.contains(name) {
+                    used.push(name.to_string());
+                }
+            }
+        }
+        i += 1;
+    }
+
+    let mut warnings = vec![];
+    for item in program.iter() {
+        if let Node::Func { name, .. } = &**item {
+            if !used.contains(name) && !name.starts_with('_') {
+                warnings.push(Warning::UnusedFn {
+                    name: name.to_string(),
+                });
+            }
+        }
+    }
+    warnings
+}
+
+/// Gets the names read by a node and the statements after it, a variable that is assigned
+/// to is not read
+fn reads(node: &Node, names: &mut Vec<String>) {
+    match node {
+        Node::Var(name) | Node::FuncCall { name, .. } => names.push(name.to_string()),
+        _ => (),
+    }
+
+    for child in node.children(true) {
+        match (node, &**child) {
+            (Node::VarValue { var, .. }, Node::Var(_)) if std::ptr::eq(var, child) => (),
+            _ => reads(child, names),
+        }
+    }
+}
+
+/// A variable bound in the function that is linted, the pending assignments are the depths
+/// of the scopes of the assignments whose values have not been read yet
+struct Binding {
+    name: String,
+    param: bool,
+    mutable: bool,
+    read: bool,
+    mutated: bool,
+    pending: Vec<usize>,
+}
+
+/// Finds the unused variables, parameters, mutable bindings and assignments of a function.
+/// Assigning to a variable in a loop which reads it is assumed to be read by the next
+/// iteration, and an assignment in a nested block is only overwritten in its own block
+struct Linter<'a> {
+    func: &'a str,
+    scopes: Vec<Vec<Binding>>,
+    loops: Vec<Vec<String>>,
+    warnings: &'a mut Vec<Warning>,
+}
+
+impl<'a> Linter<'a> {
+    fn func(&mut self, params: &Vec<Box<Node>>, body: &Node) {
+        self.scopes.push(vec![]);
+        for param in params.iter() {
+            if let Node::FuncParam(_, _, mutable) = &**param {
+                self.bind(&param_name(param), *mutable, true, false);
+            }
+        }
+        self.block(body);
+        self.pop_scope();
+    }
+
+    fn block(&mut self, block: &Node) {
+        self.scopes.push(vec![]);
+        self.stmnts(block);
+        self.pop_scope();
+    }
+
+    fn stmnts(&mut self, block: &Node) {
+        let mut stmnt = Some(block);
+        while let Some(curr) = stmnt {
+            match curr {
+                Node::Let { var, expr, .. } => {
+                    self.expr(expr);
+                    match &**var {
+                        Node::VarBinding(var, _, mutable) => {
+                            if let Node::Var(name) = &**var {
+                                self.bind(name, *mutable, false, true);
+                            }
+                        }
+                        Node::TupleBinding(vars, _) => {
+                            for (var, mutable) in vars.iter() {
+                                if let Node::Var(name) = &**var {
+                                    self.bind(name, *mutable, false, true);
+                                }
+                            }
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+                Node::VarValue { var, expr, .. } => {
+                    self.expr(expr);
+                    match &**var {
+                        Node::Var(name) => self.assign(name),
+                        place => {
+                            self.mutate(place);
+                            self.expr(place);
+                        }
+                    }
+                }
+                Node::Print { expr, .. } | Node::Return { expr, .. } => self.expr(expr),
+                Node::If {
+                    cond, statement, ..
+                } => {
+                    self.expr(cond);
+                    self.block(statement);
+                }
+                Node::IfElse {
+                    cond,
+                    if_statement,
+                    else_statement,
+                    ..
+                } => {
+                    self.expr(cond);
+                    self.block(if_statement);
+                    self.block(else_statement);
+                }
+                Node::While {
+                    cond, statement, ..
+                } => {
+                    let mut names = vec![];
+                    reads(cond, &mut names);
+                    reads(statement, &mut names);
+                    self.loops.push(names);
+                    self.expr(cond);
+                    self.block(statement);
+                    self.loops.pop();
+                }
+                Node::MatchStatement { expr, arms, .. } => {
+                    self.expr(expr);
+                    for (pat, arm) in arms.iter() {
+                        self.scopes.push(vec![]);
+                        self.bind_pattern(pat);
+                        self.stmnts(arm);
+                        self.pop_scope();
+                    }
+                }
+                Node::FuncCall { .. } | Node::MethodCall { .. } => self.expr(curr),
+                _ => (),
+            }
+            stmnt = curr.next_stmnt().map(|next| &**next);
+        }
+    }
+
+    fn expr(&mut self, expr: &Node) {
+        match expr {
+            Node::Var(name) => self.read(name),
+            Node::FuncCall { name, args, .. } => {
+                self.read(name);
+                for arg in args.iter() {
+                    self.expr(arg);
+                }
+            }
+            Node::Ref(place, mutable) => {
+                if *mutable {
+                    self.mutate(place);
+                }
+                self.expr(place);
+            }
+            // Methods such as "push" modify their receiver
+            Node::MethodCall { recv, args, .. } => {
+                self.mutate(recv);
+                self.expr(recv);
+                for arg in args.iter() {
+                    self.expr(arg);
+                }
+            }
+            Node::Match { expr, arms } => {
+                self.expr(expr);
+                for (pat, arm) in arms.iter() {
+                    self.scopes.push(vec![]);
+                    self.bind_pattern(pat);
+                    self.expr(arm);
+                    self.pop_scope();
+                }
+            }
+            Node::Closure { params, body } => {
+                self.scopes.push(vec![]);
+                for param in params.iter() {
+                    if let Node::FuncParam(_, _, mutable) = &**param {
+                        self.bind(&param_name(param), *mutable, false, false);
+                    }
+                }
+                self.expr(body);
+                self.pop_scope();
+            }
+            _ => {
+                for child in expr.children(false) {
+                    self.expr(child);
+                }
+            }
+        }
+    }
+
+    fn bind(&mut self, name: &str, mutable: bool, param: bool, assigned: bool) {
+        if self.scopes.iter().flatten().any(|binding| binding.name == name) {
+            self.warnings.push(Warning::Shadowed {
+                name: self.func.to_string(),
+                var: name.to_string(),
+            });
+        }
+
+        let depth = self.scopes.len();
+        self.scopes.last_mut().unwrap().push(Binding {
+            name: name.to_string(),
+            param,
+            mutable,
+            read: false,
+            mutated: false,
+            pending: if assigned { vec![depth] } else { vec![] },
+        });
+    }
+
+    fn bind_pattern(&mut self, pat: &Pattern) {
+        for name in pat.bindings().iter() {
+            self.bind(name, false, false, false);
+        }
+    }
+
+    fn read(&mut self, name: &str) {
+        if let Some(binding) = get_binding(&mut self.scopes, name) {
+            binding.read = true;
+            binding.pending.clear();
+        }
+    }
+
+    /// Assigns to a variable, a value which is still pending in the same block is
+    /// overwritten before it is read
+    fn assign(&mut self, name: &str) {
+        let depth = self.scopes.len();
+        let in_loop = self.loops.iter().any(|names| names.iter().any(|read| read == name));
+        let binding = match get_binding(&mut self.scopes, name) {
+            Some(binding) => binding,
+            None => return,
+        };
+
+        binding.mutated = true;
+        if in_loop {
+            return;
+        }
+        let overwritten = binding.pending.contains(&depth);
+        binding.pending.retain(|pending| *pending != depth);
+        binding.pending.push(depth);
+        if overwritten && !name.starts_with('_') {
+            self.warnings.push(Warning::UnusedAssign {
+                name: self.func.to_string(),
+                var: name.to_string(),
+            });
+        }
+    }
+
+    /// Marks the variable that owns a place as mutated i.e. "a" in "a[i].x = 1"
+    fn mutate(&mut self, place: &Node) {
+        match place {
+            Node::Var(name) => {
+                if let Some(binding) = get_binding(&mut self.scopes, name) {
+                    binding.mutated = true;
+                }
+            }
+            Node::Index(place, _) | Node::Field(place, _) => self.mutate(place),
+            _ => (),
+        }
+    }
+
+    /// Pops a scope and reports its unused bindings, the values assigned in the scope to
+    /// the variables of the outer scopes are now pending in the outer scope
+    fn pop_scope(&mut self) {
+        let depth = self.scopes.len();
+        for binding in self.scopes.pop().unwrap().into_iter() {
+            self.report(binding);
+        }
+        for binding in self.scopes.iter_mut().flatten() {
+            for pending in binding.pending.iter_mut() {
+                if *pending == depth {
+                    *pending = depth - 1;
+                }
+            }
+        }
+    }
+
+    fn report(&mut self, binding: Binding) {
+        let name = self.func.to_string();
+        let var = binding.name;
+        let ignored = var.starts_with('_');
+        if !binding.read && !ignored {
+            self.warnings.push(match binding.param {
+                true => Warning::UnusedParam {
+                    name: name.clone(),
+                    param: var.clone(),
+                },
+                false => Warning::UnusedVar {
+                    name: name.clone(),
+                    var: var.clone(),
+                },
+            });
+        } else if !binding.pending.is_empty() && !ignored {
+            self.warnings.push(Warning::UnusedAssign {
+                name: name.clone(),
+                var: var.clone(),
+            });
+        }
+        if binding.mutable && !binding.mutated {
+            self.warnings.push(Warning::UnusedMut { name, var });
+        }
+    }
+}
+
+fn get_binding<'b>(scopes: &'b mut Vec<Vec<Binding>>, name: &str) -> Option<&'b mut Binding> {
+    scopes
+        .iter_mut()
+        .rev()
+        .find_map(|scope| scope.iter_mut().rev().find(|binding| binding.name == name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::program_parser::parse;
+
+    fn warnings(input: &str, levels: &LintLevels) -> Vec<(Level, Warning)> {
+        lint(&parse(input.to_string()).unwrap(), levels)
+    }
+
+    #[test]
+    fn unused_vars() {
+        let input = "fn main() {
+                let a: i32 = 1;
+                let mut b: i32 = 2;
+                let mut c: i32 = 3;
+                c = c + b;
+                print(c);
+                let _d: i32 = 4;
+                test(1);
+            }
+
+            fn test(x: i32) {
+                let f = |y: i32| 0;
+                print(f(1));
+            }";
+
+        let name = String::from("main");
+        let expected = vec![
+            (
+                Level::Warn,
+                Warning::UnusedVar {
+                    name: name.clone(),
+                    var: String::from("a"),
+                },
+            ),
+            (
+                Level::Warn,
+                Warning::UnusedMut {
+                    name: name.clone(),
+                    var: String::from("b"),
+                },
+            ),
+            (
+                Level::Warn,
+                Warning::UnusedVar {
+                    name: String::from("test"),
+                    var: String::from("y"),
+                },
+            ),
+            (
+                Level::Warn,
+                Warning::UnusedParam {
+                    name: String::from("test"),
+                    param: String::from("x"),
+                },
+            ),
+        ];
+        assert_eq!(warnings(input, &LintLevels::new()), expected);
+    }
+
+    #[test]
+    fn unused_assignments() {
+        let input = "fn main() {
+                let mut a: i32 = 1;
+                a = 2;
+                if (a > 0) {
+                    a = 3;
+                }
+                print(a);
+
+                let mut b: i32 = 0;
+                let mut i: i32 = 0;
+                while (i < 10) {
+                    b = b + i;
+                    i = i + 1;
+                }
+                b = 5;
+            }";
+
+        let name = String::from("main");
+        let expected = vec![
+            (
+                Level::Warn,
+                Warning::UnusedAssign {
+                    name: name.clone(),
+                    var: String::from("a"),
+                },
+            ),
+            (
+                Level::Warn,
+                Warning::UnusedAssign {
+                    name: name.clone(),
+                    var: String::from("b"),
+                },
+            ),
+        ];
+        assert_eq!(warnings(input, &LintLevels::new()), expected);
+    }
+
+    #[test]
+    fn dead_code() {
+        let input = "fn main() {
+                let f = used;
+                print(f(1));
+            }
+
+            fn used(x: i32) -> i32 {
+                return helper(x);
+            }
+
+            fn helper(x: i32) -> i32 {
+                return x;
+            }
+
+            fn unused(x: i32) -> i32 {
+                return unused(x);
+            }";
+
+        let expected = vec![(
+            Level::Warn,
+            Warning::UnusedFn {
+                name: String::from("unused"),
+            },
+        )];
+        assert_eq!(warnings(input, &LintLevels::new()), expected);
+    }
+
+    #[test]
+    fn lint_levels() {
+        let input = "#![allow(unused_variables, dead_code)]
+            #![warn(shadowing, unknown)]
+
+            fn main() {
+                let a: i32 = 1;
+                let a: i32 = 2;
+                let mut b: i32 = 3;
+                print(b);
+            }
+
+            fn unused() {
+                print(1);
+            }";
+
+        let mut levels = LintLevels::new();
+        levels.set("warnings", Level::Deny);
+        assert_eq!(levels.get(Lint::Shadowing), Level::Allow);
+
+        let name = String::from("main");
+        let expected = vec![
+            (
+                Level::Deny,
+                Warning::UnknownLint {
+                    lint: String::from("unknown"),
+                },
+            ),
+            (
+                Level::Warn,
+                Warning::Shadowed {
+                    name: name.clone(),
+                    var: String::from("a"),
+                },
+            ),
+            (
+                Level::Deny,
+                Warning::UnusedMut {
+                    name: name.clone(),
+                    var: String::from("b"),
+                },
+            ),
+        ];
+        assert_eq!(warnings(input, &levels), expected);
+    }
+}
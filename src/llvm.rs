@@ -1,4 +1,4 @@
-use std::{collections::HashMap, error::Error};
+use std::{collections::HashMap, error::Error, fmt};
 
 use inkwell::{
     basic_block::BasicBlock,
@@ -6,12 +6,23 @@ use inkwell::{
     context::Context,
     execution_engine::JitFunction,
     module::Module,
-    types::BasicTypeEnum,
-    values::{BasicValueEnum, FunctionValue, IntValue, PointerValue, InstructionValue},
-    IntPredicate, OptimizationLevel,
+    types::{BasicType, BasicTypeEnum, FunctionType, StructType},
+    values::{
+        BasicValueEnum, FunctionValue, GlobalValue, InstructionValue, IntValue, PointerValue,
+    },
+    AddressSpace, IntPredicate, OptimizationLevel,
 };
 
-use crate::{ast::Node, operators::Opcode, parse::program_parser, types::LiteralType};
+use crate::{
+    arithmetic::{arith_method, Overflow},
+    ast::Node,
+    enumeration::Enum,
+    operators::Opcode,
+    parse::program_parser::{self, FuncLines},
+    pattern::Pattern,
+    structure::Struct,
+    types::LiteralType,
+};
 
 macro_rules! extract_next {
     ($statement:tt) => {
@@ -47,6 +58,17 @@ macro_rules! extract_next {
                 args: _,
                 next,
             } => next,
+            Node::MethodCall {
+                recv: _,
+                name: _,
+                args: _,
+                next,
+            } => next,
+            Node::MatchStatement {
+                expr: _,
+                arms: _,
+                next,
+            } => next,
             _ => None,
         }
     };
@@ -54,6 +76,53 @@ macro_rules! extract_next {
 
 type MainFn = unsafe extern "C" fn() -> i32;
 
+/// A part of a type checked program which the compiler does not support, the program is not
+/// run if it has any
+#[derive(Debug, PartialEq)]
+pub enum CompileError {
+    // A vector whose elements own heap memory, which would have to be freed with it
+    VecOwnsMemory { typ: LiteralType },
+    // An enum with a variant which holds a value that owns heap memory
+    EnumOwnsMemory { name: String },
+    // A closure which captures a variable that owns heap memory, i.e. a vector or a closure
+    CaptureOwnsMemory { var: String, typ: LiteralType },
+    MethodNotSupported { name: String },
+    // An expression which is indexed, borrowed or whose field is read but is not a place,
+    // described by the name of its node
+    PlaceNotSupported { node: String },
+    MainMissing,
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompileError::VecOwnsMemory { typ } => write!(
+                f,
+                "Vectors of values which own memory such as '{}' are not supported",
+                typ.to_string()
+            ),
+            CompileError::EnumOwnsMemory { name } => write!(
+                f,
+                "The enum '{}' holds values which own memory, which is not supported",
+                name
+            ),
+            CompileError::CaptureOwnsMemory { var, typ } => write!(
+                f,
+                "Closures capturing '{}' of type '{}' which owns memory are not supported",
+                var,
+                typ.to_string()
+            ),
+            CompileError::MethodNotSupported { name } => {
+                write!(f, "The method '{}' is not supported here", name)
+            }
+            CompileError::PlaceNotSupported { node } => {
+                write!(f, "The node {} is not supported as a place", node)
+            }
+            CompileError::MainMissing => write!(f, "The program has no main function"),
+        }
+    }
+}
+
 pub fn main() -> Result<(), Box<dyn Error>> {
     let input = program_parser::parse(
         "
@@ -96,8 +165,28 @@ pub struct Compiler {
     context: Context,
     builder: Builder,
     module: Module,
-    scopes: Vec<HashMap<String, PointerValue>>,
+    scopes: Vec<HashMap<String, (PointerValue, LiteralType)>>,
+    structs: HashMap<String, Struct>,
+    enums: HashMap<String, Enum>,
+    fn_types: HashMap<String, (Vec<LiteralType>, Option<LiteralType>)>,
+    // The generic functions of the program and the instantiations of them which have been
+    // declared but not yet compiled
+    generics: HashMap<String, Box<Node>>,
+    instances: Vec<Box<Node>>,
     curr_fn: Option<FunctionValue>,
+    curr_r_type: Option<LiteralType>,
+    // The counter of the steps taken by a sandboxed program and its fuel, which is the
+    // number of steps it can take before it is aborted
+    fuel: Option<(GlobalValue, u64)>,
+    // What happens when the result of an arithmetic operation does not fit in an i32
+    overflow: Overflow,
+    // The lines of the statements of each function, and the index of the statement which is
+    // compiled along with the number of statements of the function compiled so far
+    lines: FuncLines,
+    stmnt: Option<usize>,
+    stmnts: usize,
+    // The parts of the program which could not be compiled
+    errors: Vec<CompileError>,
 }
 
 /// The compiler assumes that it compiles programs which have been type checked and
@@ -110,24 +199,87 @@ impl Compiler {
             module: context.create_module("program"),
             context: context,
             scopes: vec![],
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            fn_types: HashMap::new(),
+            generics: HashMap::new(),
+            instances: vec![],
             curr_fn: None,
+            curr_r_type: None,
+            fuel: None,
+            overflow: Overflow::Trap,
+            lines: HashMap::new(),
+            stmnt: None,
+            stmnts: 0,
+            errors: vec![],
         }
     }
 
+    /// Creates a compiler for programs which are sandboxed, a step is counted when a function
+    /// is entered and at the end of every iteration of a loop. The program is aborted when it
+    /// takes more steps than its fuel
+    pub fn sandboxed(fuel: u64) -> Self {
+        let mut compiler = Compiler::new();
+        let i64_type = compiler.context.i64_type();
+        let steps = compiler.module.add_global(i64_type, None, "steps");
+        steps.set_initializer(&i64_type.const_int(0, false));
+        compiler.fuel = Some((steps, fuel));
+        compiler
+    }
+
+    /// Sets what happens when the result of an arithmetic operation overflows, the program
+    /// is aborted unless it is set to wrap or saturate
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Sets the lines of the statements of each function, as they are returned by
+    /// parse_with_lines, which are shown when the program is aborted
+    pub fn with_lines(mut self, lines: FuncLines) -> Self {
+        self.lines = lines;
+        self
+    }
+
     /// Compiles a parsed program and returns the resulting JitFunction<MainFn>
-    /// which can den be called to execute the program
-    pub fn compile(&mut self, program: &Vec<Box<Node>>) -> Option<JitFunction<MainFn>> {
+    /// which can den be called to execute the program, the types of its let bindings
+    /// have to be inferred by the type checker first. Returns the parts of the program that
+    /// are not supported if there are any
+    pub fn compile(
+        &mut self,
+        program: &Vec<Box<Node>>,
+    ) -> Result<JitFunction<MainFn>, Vec<CompileError>> {
         let execution_engine = self
             .module
             .create_jit_execution_engine(OptimizationLevel::None)
             .unwrap();
 
         self.compile_program(program);
+        if !self.errors.is_empty() {
+            return Err(std::mem::take(&mut self.errors));
+        }
         self.module.print_to_stderr(); // LLVM IR
 
-        let res: Option<JitFunction<MainFn>> =
-            unsafe { execution_engine.get_function("main").ok() };
-        res
+        unsafe { execution_engine.get_function("main") }
+            .map_err(|_| vec![CompileError::MainMissing])
+    }
+
+    /// Records that a type is not supported if it is a vector whose elements own memory, or
+    /// contains one
+    fn check_type(&mut self, typ: &LiteralType) {
+        match typ {
+            LiteralType::Vec(elem_type) if self.needs_free(elem_type) => {
+                let err = CompileError::VecOwnsMemory { typ: typ.clone() };
+                if !self.errors.contains(&err) {
+                    self.errors.push(err);
+                }
+            }
+            LiteralType::Vec(elem_type) | LiteralType::Array(elem_type, _) => {
+                self.check_type(elem_type)
+            }
+            LiteralType::Tuple(elems) => elems.iter().for_each(|typ| self.check_type(typ)),
+            _ => (),
+        }
     }
 
     /// Gets the function value of the function which is currently being compiled
@@ -138,20 +290,42 @@ impl Compiler {
         }
 	}
 	
-	/// Gets a variable from the vector of scopes by searching in reverse order
+	/// Gets a variable and its type from the vector of scopes by searching in reverse order
 	/// (allows for shadowing)
-	fn get_variable(&self, id: &str) -> PointerValue {
+	fn get_variable(&self, id: &str) -> (PointerValue, LiteralType) {
 		for scope in self.scopes.iter().rev() {
 			match scope.get(id) {
-				Some(ptr) => return *ptr,
+				Some((ptr, typ)) => return (*ptr, typ.clone()),
 				None => ()
 			};
 		}
 		unreachable!()
 	}
 
+    /// Checks if a name refers to a variable rather than to a function
+    fn is_variable(&self, id: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.contains_key(id))
+    }
+
+    /// Inserts a variable into the current scope, a variable it shadows in the same scope
+    /// may still be referenced and is kept until the scope ends
+    fn insert_variable(&mut self, id: String, ptr: PointerValue, typ: LiteralType) {
+        self.check_type(&typ);
+        let scope = self.scopes.last_mut().unwrap();
+        if let Some(old) = scope.insert(id.clone(), (ptr, typ)) {
+            // The name can not be used by a variable
+            let hidden = format!("{}%{}", id, scope.len());
+            scope.insert(hidden, old);
+        }
+    }
+
     /// Creates a new stack allocation instruction in the entry block of the function
-    fn create_entry_block_alloca(&mut self, name: &str, block: &BasicBlock) -> PointerValue {
+    fn create_entry_block_alloca(
+        &mut self,
+        name: &str,
+        block: &BasicBlock,
+        typ: BasicTypeEnum,
+    ) -> PointerValue {
         let builder = self.context.create_builder();
 
         match block.get_first_instruction() {
@@ -159,122 +333,942 @@ impl Compiler {
             None => builder.position_at_end(&block),
         }
 
-        let alloca = builder.build_alloca(self.context.i32_type(), name);
-        self.scopes.last_mut().unwrap().insert(name.to_string(), alloca);
-        alloca
+        builder.build_alloca(typ, name)
+    }
+
+    /// Gets the llvm type of a type, bools are represented as i32s
+    fn llvm_type(&self, typ: &LiteralType) -> BasicTypeEnum {
+        match typ {
+            LiteralType::I32 | LiteralType::Bool => self.context.i32_type().into(),
+            LiteralType::Array(elem_type, len) => {
+                self.llvm_type(elem_type).array_type(*len as u32).into()
+            }
+            // Vectors are a pointer to the elements on the heap, the length and the capacity
+            LiteralType::Vec(elem_type) => {
+                let i32_type = self.context.i32_type();
+                let data_type = self.llvm_type(elem_type).ptr_type(AddressSpace::Generic);
+                self.context
+                    .struct_type(&[data_type.into(), i32_type.into(), i32_type.into()], false)
+                    .into()
+            }
+            LiteralType::Named(name) => match self.structs.get(name) {
+                Some(strct) => {
+                    let field_types: Vec<BasicTypeEnum> = strct
+                        .fields
+                        .iter()
+                        .map(|(_, typ)| self.llvm_type(typ))
+                        .collect();
+                    self.context.struct_type(&field_types, false).into()
+                }
+                // Enums are a tag followed by enough 8 byte words to store the fields of any
+                // of the variants, which are accessed by casting to the type of the variant
+                None => {
+                    let words = (self.payload_size(&self.enums[name]) + 7) / 8;
+                    let payload = self.context.i64_type().array_type(words as u32);
+                    self.context
+                        .struct_type(&[self.context.i32_type().into(), payload.into()], false)
+                        .into()
+                }
+            },
+            // References are pointers to the value they refer to
+            LiteralType::Ref(typ, _) => self.llvm_type(typ).ptr_type(AddressSpace::Generic).into(),
+            // Tuples are structs with the elements as fields
+            LiteralType::Tuple(elems) => {
+                let elem_types: Vec<BasicTypeEnum> =
+                    elems.iter().map(|typ| self.llvm_type(typ)).collect();
+                self.context.struct_type(&elem_types, false).into()
+            }
+            // Functions and closures are a pointer to the function, a pointer to the captured
+            // variables on the heap and their size in bytes
+            LiteralType::Fn(_, _) => {
+                let str_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+                let i64_type = self.context.i64_type();
+                self.context
+                    .struct_type(&[str_type.into(), str_type.into(), i64_type.into()], false)
+                    .into()
+            }
+            LiteralType::Infer => panic!("llvm_type: The type has not been inferred"),
+            _ => unreachable!(),
+        }
     }
 
-    /// Compiles a program by declaring its functions and compiling them
+    /// Gets the llvm type of the function of a closure, which takes a pointer to the
+    /// captured variables before its parameters
+    fn closure_fn_type(&self, params: &Vec<LiteralType>, r_type: &LiteralType) -> FunctionType {
+        let str_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+        let mut param_types: Vec<BasicTypeEnum> = vec![str_type.into()];
+        param_types.extend(params.iter().map(|typ| self.llvm_type(typ)));
+        match r_type {
+            LiteralType::Void => self.context.void_type().fn_type(&param_types, false),
+            typ => self.llvm_type(typ).fn_type(&param_types, false),
+        }
+    }
+
+    /// Gets the types of the fields of a struct or the elements of a tuple
+    fn field_types(&self, typ: &LiteralType) -> Vec<LiteralType> {
+        match typ {
+            LiteralType::Named(name) => self.structs[name]
+                .fields
+                .iter()
+                .map(|(_, typ)| typ.clone())
+                .collect(),
+            LiteralType::Tuple(elems) => elems.clone(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Gets the llvm type of a variant of an enum, a struct of the tag and the fields
+    fn variant_type(&self, enm: &str, variant: &str) -> StructType {
+        let mut field_types: Vec<BasicTypeEnum> = vec![self.context.i32_type().into()];
+        for typ in self.enums[enm].get_variant(variant).unwrap().iter() {
+            field_types.push(self.llvm_type(typ));
+        }
+        self.context.struct_type(&field_types, false)
+    }
+
+    /// Gets an upper bound of the size in bytes of a type, where each field is assumed to
+    /// be aligned to 8 bytes
+    fn type_size(&self, typ: &LiteralType) -> u64 {
+        let align = |size: u64| (size + 7) / 8 * 8;
+        match typ {
+            LiteralType::I32 | LiteralType::Bool => 4,
+            LiteralType::Array(elem_type, len) => align(self.type_size(elem_type)) * *len as u64,
+            LiteralType::Vec(_) | LiteralType::Ref(_, _) => 16,
+            LiteralType::Fn(_, _) => 24,
+            LiteralType::Named(name) => match self.structs.get(name) {
+                Some(strct) => strct
+                    .fields
+                    .iter()
+                    .map(|(_, typ)| align(self.type_size(typ)))
+                    .sum(),
+                None => 8 + align(self.payload_size(&self.enums[name])),
+            },
+            LiteralType::Tuple(elems) => elems.iter().map(|typ| align(self.type_size(typ))).sum(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Gets an upper bound of the size in bytes of the fields of the largest variant of an enum
+    fn payload_size(&self, enm: &Enum) -> u64 {
+        enm.variants
+            .iter()
+            .map(|(_, fields)| {
+                fields
+                    .iter()
+                    .map(|typ| (self.type_size(typ) + 7) / 8 * 8)
+                    .sum()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Checks if a value of a type owns heap memory that has to be freed
+    fn needs_free(&self, typ: &LiteralType) -> bool {
+        match typ {
+            LiteralType::Vec(_) | LiteralType::Fn(_, _) => true,
+            LiteralType::Array(elem_type, _) => self.needs_free(elem_type),
+            LiteralType::Named(name) => match self.structs.get(name) {
+                Some(strct) => strct.fields.iter().any(|(_, typ)| self.needs_free(typ)),
+                // An enum which holds values that own memory is not supported, so the values
+                // of enums never have to be freed
+                None => false,
+            },
+            LiteralType::Tuple(elems) => elems.iter().any(|typ| self.needs_free(typ)),
+            _ => false,
+        }
+    }
+
+    /// Comparisons result in i1s which are extended to the i32s that represents bools
+    fn build_i32(&self, val: IntValue) -> IntValue {
+        let i32_type = self.context.i32_type();
+        match val.get_type().get_bit_width() {
+            32 => val,
+            _ => self.builder.build_int_z_extend(val, i32_type, "ext"),
+        }
+    }
+
+    /// Bools stored as i32s are compared with zero to get the i1s that branches take
+    fn build_i1(&self, val: IntValue) -> IntValue {
+        match val.get_type().get_bit_width() {
+            1 => val,
+            _ => self.builder.build_int_compare(
+                IntPredicate::NE,
+                val,
+                val.get_type().const_int(0, false),
+                "tobool",
+            ),
+        }
+    }
+
+    /// Allocates a temporary value of type typ which is freed when the current scope ends
+    fn build_temp(&mut self, typ: &LiteralType) -> PointerValue {
+        let block = self.fn_value().get_first_basic_block().unwrap();
+        let tmp = self.create_entry_block_alloca("tmp", &block, self.llvm_type(typ));
+        // The name can not be used by a variable
+        self.insert_variable("%tmp".to_string(), tmp, typ.clone());
+        tmp
+    }
+
+    /// Checks if the block currently being built already ends with a terminator, i.e. a return
+    fn block_terminated(&self) -> bool {
+        match self.builder.get_insert_block() {
+            Some(block) => block.get_terminator().is_some(),
+            None => false,
+        }
+    }
+
+    /// Gets a pointer to the element at idx of the array pointed to by arr
+    fn build_elem_ptr(&self, arr: PointerValue, idx: IntValue) -> PointerValue {
+        let zero = self.context.i32_type().const_int(0, false);
+        unsafe { self.builder.build_in_bounds_gep(arr, &[zero, idx], "elemptr") }
+    }
+
+    /// Aborts the program if idx is not within the bounds of an array or vector of length len
+    fn build_bounds_check(&mut self, idx: IntValue, len: IntValue) {
+        let func = self.fn_value();
+
+        // Negative indices are larger than any length when compared as unsigned
+        let in_bounds = self
+            .builder
+            .build_int_compare(IntPredicate::ULT, idx, len, "inbounds");
+
+        let oob_bb = self.context.append_basic_block(&func, "outofbounds");
+        let cont_bb = self.context.append_basic_block(&func, "inbounds");
+        self.builder
+            .build_conditional_branch(in_bounds, &cont_bb, &oob_bb);
+
+        self.builder.position_at_end(&oob_bb);
+        self.build_abort(
+            "Index out of bounds: the len is %d but the index is %d\n",
+            &[len.into(), idx.into()],
+        );
+
+        self.builder.position_at_end(&cont_bb);
+    }
+
+    /// Aborts the program with a message followed by the name of the function if cond is
+    /// true, the message is formatted with the arguments like printf
+    fn build_trap(&mut self, cond: IntValue, msg: &str, args: &[BasicValueEnum]) {
+        let func = self.fn_value();
+        let trap_bb = self.context.append_basic_block(&func, "trap");
+        let cont_bb = self.context.append_basic_block(&func, "notrap");
+        self.builder
+            .build_conditional_branch(cond, &trap_bb, &cont_bb);
+
+        self.builder.position_at_end(&trap_bb);
+        let name = func.get_name().to_str().unwrap().to_string();
+        let line = self
+            .stmnt
+            .and_then(|idx| self.lines.get(&name).and_then(|lines| lines.get(idx)));
+        let line = match line {
+            Some(line) => format!("on line {} ", line),
+            None => String::new(),
+        };
+        self.build_abort(&format!("{}\n    {}in '{}'\n", msg, line, name), args);
+
+        self.builder.position_at_end(&cont_bb);
+    }
+
+    /// Applies an arithmetic operator to two i32s, the program is aborted if it divides by
+    /// zero or if the result overflows and the overflow behaviour is to trap
+    fn build_arith(
+        &mut self,
+        left: IntValue,
+        op: Opcode,
+        right: IntValue,
+        overflow: Overflow,
+    ) -> IntValue {
+        let msg = format!("Arithmetic overflow in '%d {} %d'", op.to_string());
+        match op {
+            Opcode::Div => self.build_div(left, right, overflow),
+            _ => self.build_checked(left, op, right, overflow, &msg, &[left.into(), right.into()]),
+        }
+    }
+
+    /// Negates an i32, which is subtracting it from zero
+    fn build_neg(&mut self, value: IntValue) -> IntValue {
+        let zero = self.context.i32_type().const_int(0, false);
+        let overflow = self.overflow;
+        let msg = "Arithmetic overflow in '-(%d)'";
+        self.build_checked(zero, Opcode::Sub, value, overflow, msg, &[value.into()])
+    }
+
+    /// Adds, subtracts or multiplies two i32s with the intrinsic which also tells if the
+    /// result overflowed
+    fn build_checked(
+        &mut self,
+        left: IntValue,
+        op: Opcode,
+        right: IntValue,
+        overflow: Overflow,
+        msg: &str,
+        args: &[BasicValueEnum],
+    ) -> IntValue {
+        let intrinsic = match op {
+            Opcode::Add => "llvm.sadd.with.overflow.i32",
+            Opcode::Sub => "llvm.ssub.with.overflow.i32",
+            _ => "llvm.smul.with.overflow.i32",
+        };
+        let res = self
+            .builder
+            .build_call(self.get_overflow_intrinsic(intrinsic), &[left.into(), right.into()], "res")
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_struct_value();
+        let value = self.builder.build_extract_value(res, 0, "value").unwrap();
+        let value = value.into_int_value();
+        let overflowed = self.builder.build_extract_value(res, 1, "overflowed").unwrap();
+        let overflowed = overflowed.into_int_value();
+
+        match overflow {
+            Overflow::Trap => {
+                self.build_trap(overflowed, msg, args);
+                value
+            }
+            // The intrinsic gives the wrapped result
+            Overflow::Wrap => value,
+            Overflow::Saturate => {
+                // The exact result of a multiplication is negative if the signs of the
+                // operands differ, an addition or subtraction only overflows if it has the
+                // sign of the left operand
+                let zero = self.context.i32_type().const_int(0, false);
+                let sign = match op {
+                    Opcode::Mul => self.builder.build_xor(left, right, "sign"),
+                    _ => left,
+                };
+                let negative = self
+                    .builder
+                    .build_int_compare(IntPredicate::SLT, sign, zero, "negative");
+                let (min, max) = self.i32_bounds();
+                let bound = self.builder.build_select(negative, min, max, "bound");
+                self.builder
+                    .build_select(overflowed, bound.into_int_value(), value, "sat")
+                    .into_int_value()
+            }
+        }
+    }
+
+    /// Divides two i32s, a division by zero is always aborted. The only division which
+    /// overflows, i32::MIN / -1, is undefined in llvm so it is replaced by i32::MIN / 1
+    /// unless the program is aborted
+    fn build_div(&mut self, left: IntValue, right: IntValue, overflow: Overflow) -> IntValue {
+        let i32_type = self.context.i32_type();
+        let zero = i32_type.const_int(0, false);
+        let is_zero = self
+            .builder
+            .build_int_compare(IntPredicate::EQ, right, zero, "iszero");
+        self.build_trap(is_zero, "Attempt to divide %d by zero", &[left.into()]);
+
+        let (min, max) = self.i32_bounds();
+        let minus_one = i32_type.const_int(-1i64 as u64, true);
+        let is_min = self
+            .builder
+            .build_int_compare(IntPredicate::EQ, left, min, "ismin");
+        let is_minus_one = self
+            .builder
+            .build_int_compare(IntPredicate::EQ, right, minus_one, "isminusone");
+        let overflowed = self.builder.build_and(is_min, is_minus_one, "overflowed");
+
+        if overflow == Overflow::Trap {
+            let msg = "Arithmetic overflow in '%d / %d'";
+            self.build_trap(overflowed, msg, &[left.into(), right.into()]);
+            return self.builder.build_int_signed_div(left, right, "div");
+        }
+        let one = i32_type.const_int(1, false);
+        let divisor = self.builder.build_select(overflowed, one, right, "divisor");
+        let quot = self
+            .builder
+            .build_int_signed_div(left, divisor.into_int_value(), "div");
+        match overflow {
+            Overflow::Saturate => self
+                .builder
+                .build_select(overflowed, max, quot, "sat")
+                .into_int_value(),
+            _ => quot,
+        }
+    }
+
+    fn i32_bounds(&self) -> (IntValue, IntValue) {
+        let i32_type = self.context.i32_type();
+        (
+            i32_type.const_int(i32::MIN as u64, true),
+            i32_type.const_int(i32::MAX as u64, false),
+        )
+    }
+
+    /// Gets an intrinsic which applies an operator to two i32s and returns the result and
+    /// if it overflowed, declares it in the module if it is not already declared
+    fn get_overflow_intrinsic(&self, name: &str) -> FunctionValue {
+        if let Some(func) = self.module.get_function(name) {
+            return func;
+        }
+        let i32_type = self.context.i32_type();
+        let res_type = self
+            .context
+            .struct_type(&[i32_type.into(), self.context.bool_type().into()], false);
+        let fn_type = res_type.fn_type(&[i32_type.into(), i32_type.into()], false);
+        self.module.add_function(name, fn_type, None)
+    }
+
+    /// Counts a step of a sandboxed program and aborts it if it has run out of fuel
+    fn build_step(&mut self) {
+        let (steps, fuel) = match self.fuel {
+            Some(fuel) => fuel,
+            None => return,
+        };
+        let func = self.fn_value();
+        let i64_type = self.context.i64_type();
+
+        let steps = steps.as_pointer_value();
+        let taken = self.builder.build_load(steps, "steps").into_int_value();
+        let taken = self
+            .builder
+            .build_int_add(taken, i64_type.const_int(1, false), "steps");
+        self.builder.build_store(steps, taken);
+
+        let fuel = i64_type.const_int(fuel, false);
+        let out_of_fuel = self
+            .builder
+            .build_int_compare(IntPredicate::UGT, taken, fuel, "outoffuel");
+        let out_bb = self.context.append_basic_block(&func, "outoffuel");
+        let cont_bb = self.context.append_basic_block(&func, "fuelleft");
+        self.builder
+            .build_conditional_branch(out_of_fuel, &out_bb, &cont_bb);
+
+        self.builder.position_at_end(&out_bb);
+        self.build_abort(
+            "The program ran out of fuel after %ld steps\n",
+            &[fuel.into()],
+        );
+
+        self.builder.position_at_end(&cont_bb);
+    }
+
+    /// Prints a formatted message to stderr and aborts the program
+    fn build_abort(&mut self, fmt: &str, args: &[BasicValueEnum]) {
+        let stderr = self.context.i32_type().const_int(2, false);
+        let fmt = self.builder.build_global_string_ptr(fmt, "fmt");
+
+        let mut dprintf_args: Vec<BasicValueEnum> =
+            vec![stderr.into(), fmt.as_pointer_value().into()];
+        dprintf_args.extend_from_slice(args);
+
+        let dprintf = self.get_libc_fn("dprintf");
+        self.builder.build_call(dprintf, &dprintf_args, "dprintf");
+        let abort = self.get_libc_fn("abort");
+        self.builder.build_call(abort, &[], "abort");
+        self.builder.build_unreachable();
+    }
+
+    /// Gets a function from the C standard library, declares it in the module if it is not
+    /// already declared
+    fn get_libc_fn(&self, name: &str) -> FunctionValue {
+        if let Some(func) = self.module.get_function(name) {
+            return func;
+        }
+
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let str_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+        let fn_type = match name {
+            "dprintf" => i32_type.fn_type(&[i32_type.into(), str_type.into()], true),
+            "abort" => self.context.void_type().fn_type(&[], false),
+            "malloc" => str_type.fn_type(&[i64_type.into()], false),
+            "realloc" => str_type.fn_type(&[str_type.into(), i64_type.into()], false),
+            "free" => self.context.void_type().fn_type(&[str_type.into()], false),
+            "memcpy" => {
+                str_type.fn_type(&[str_type.into(), str_type.into(), i64_type.into()], false)
+            }
+            _ => unreachable!(),
+        };
+        self.module.add_function(name, fn_type, None)
+    }
+
+    /// Calls a function from the C standard library which returns a pointer
+    fn build_libc_call(&self, name: &str, args: &[BasicValueEnum]) -> PointerValue {
+        self.builder
+            .build_call(self.get_libc_fn(name), args, name)
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_pointer_value()
+    }
+
+    /// Gets a pointer to the field at idx of the struct pointed to by strct
+    fn build_field_ptr(&self, strct: PointerValue, idx: u32) -> PointerValue {
+        unsafe { self.builder.build_struct_gep(strct, idx, "fieldptr") }
+    }
+
+    /// Gets a pointer to the enum at ptr as the type of one of its variants
+    fn build_variant_ptr(&self, ptr: PointerValue, enm: &str, variant: &str) -> PointerValue {
+        let variant_type = self.variant_type(enm, variant);
+        self.builder
+            .build_pointer_cast(ptr, variant_type.ptr_type(AddressSpace::Generic), "variant")
+    }
+
+    /// Gets a pointer to one of the fields of a vector, 0 = data, 1 = length and 2 = capacity
+    fn build_vec_field(&self, vec: PointerValue, field: u32) -> PointerValue {
+        unsafe { self.builder.build_struct_gep(vec, field, "vecfield") }
+    }
+
+    fn build_vec_data(&self, vec: PointerValue) -> PointerValue {
+        let data_ptr = self.build_vec_field(vec, 0);
+        self.builder.build_load(data_ptr, "data").into_pointer_value()
+    }
+
+    fn build_vec_len(&self, vec: PointerValue) -> IntValue {
+        let len_ptr = self.build_vec_field(vec, 1);
+        self.builder.build_load(len_ptr, "len").into_int_value()
+    }
+
+    /// Computes the size in bytes of n elements of the vector at vec
+    fn build_vec_size(&self, vec: PointerValue, n: IntValue) -> IntValue {
+        // The address of the n:th element of an array starting at address 0 is its size
+        let data = self.build_vec_data(vec);
+        let null = data.get_type().const_null();
+        let end = unsafe { self.builder.build_gep(null, &[n], "end") };
+        self.builder
+            .build_ptr_to_int(end, self.context.i64_type(), "size")
+    }
+
+    /// Stores an empty vector without any allocated memory at vec
+    fn build_vec_new(&self, vec: PointerValue, elem_type: &LiteralType) {
+        let data_type = self.llvm_type(elem_type).ptr_type(AddressSpace::Generic);
+        let zero = self.context.i32_type().const_int(0, false);
+
+        self.builder
+            .build_store(self.build_vec_field(vec, 0), data_type.const_null());
+        self.builder.build_store(self.build_vec_field(vec, 1), zero);
+        self.builder.build_store(self.build_vec_field(vec, 2), zero);
+    }
+
+    /// Copies the elements of the vector at src into newly allocated memory
+    /// and stores the new vector at dst
+    fn build_vec_copy(&self, dst: PointerValue, src: PointerValue) {
+        let len = self.build_vec_len(src);
+        let size = self.build_vec_size(src, len);
+        let src_data = self.build_vec_data(src);
+        let str_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+
+        let raw = self.build_libc_call("malloc", &[size.into()]);
+        let raw_src = self.builder.build_pointer_cast(src_data, str_type, "rawsrc");
+        self.build_libc_call("memcpy", &[raw.into(), raw_src.into(), size.into()]);
+        let data = self
+            .builder
+            .build_pointer_cast(raw, src_data.get_type(), "data");
+
+        self.builder.build_store(self.build_vec_field(dst, 0), data);
+        self.builder.build_store(self.build_vec_field(dst, 1), len);
+        self.builder.build_store(self.build_vec_field(dst, 2), len);
+    }
+
+    /// Copies the captured variables of the closure at src into newly allocated memory
+    /// and stores the new closure at dst
+    fn build_closure_copy(&self, dst: PointerValue, src: PointerValue) {
+        let func = self.builder.build_load(self.build_field_ptr(src, 0), "fn");
+        let env = self.builder.build_load(self.build_field_ptr(src, 1), "env");
+        let size = self.builder.build_load(self.build_field_ptr(src, 2), "size");
+
+        let raw = self.build_libc_call("malloc", &[size]);
+        self.build_libc_call("memcpy", &[raw.into(), env, size]);
+
+        self.builder.build_store(self.build_field_ptr(dst, 0), func);
+        self.builder.build_store(self.build_field_ptr(dst, 1), raw);
+        self.builder.build_store(self.build_field_ptr(dst, 2), size);
+    }
+
+    /// Copies a value of type typ from src to dst, vectors are copied with their elements
+    /// and closures with their captured variables
+    fn build_copy(&self, dst: PointerValue, src: PointerValue, typ: &LiteralType) {
+        match typ {
+            LiteralType::Vec(_) => self.build_vec_copy(dst, src),
+            LiteralType::Fn(_, _) => self.build_closure_copy(dst, src),
+            LiteralType::Array(elem_type, len) if self.needs_free(elem_type) => {
+                for i in 0..*len {
+                    let idx = self.context.i32_type().const_int(i as u64, false);
+                    let dst_elem = self.build_elem_ptr(dst, idx);
+                    let src_elem = self.build_elem_ptr(src, idx);
+                    self.build_copy(dst_elem, src_elem, elem_type);
+                }
+            }
+            LiteralType::Named(_) | LiteralType::Tuple(_) if self.needs_free(typ) => {
+                for (i, field_type) in self.field_types(typ).iter().enumerate() {
+                    let dst_field = self.build_field_ptr(dst, i as u32);
+                    let src_field = self.build_field_ptr(src, i as u32);
+                    self.build_copy(dst_field, src_field, field_type);
+                }
+            }
+            _ => {
+                let val = self.builder.build_load(src, "cpy");
+                self.builder.build_store(dst, val);
+            }
+        }
+    }
+
+    /// Frees the heap memory owned by the value of type typ at ptr
+    fn build_free(&self, ptr: PointerValue, typ: &LiteralType) {
+        match typ {
+            // The elements are not freed, a vector of elements which own memory is not
+            // supported
+            LiteralType::Vec(_) => {
+                let str_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+                let data = self.build_vec_data(ptr);
+                let raw = self.builder.build_pointer_cast(data, str_type, "raw");
+                self.builder
+                    .build_call(self.get_libc_fn("free"), &[raw.into()], "free");
+            }
+            LiteralType::Fn(_, _) => {
+                let env = self.builder.build_load(self.build_field_ptr(ptr, 1), "env");
+                self.builder
+                    .build_call(self.get_libc_fn("free"), &[env], "free");
+            }
+            LiteralType::Array(elem_type, len) if self.needs_free(elem_type) => {
+                for i in 0..*len {
+                    let idx = self.context.i32_type().const_int(i as u64, false);
+                    let elem_ptr = self.build_elem_ptr(ptr, idx);
+                    self.build_free(elem_ptr, elem_type);
+                }
+            }
+            LiteralType::Named(_) | LiteralType::Tuple(_) if self.needs_free(typ) => {
+                for (i, field_type) in self.field_types(typ).iter().enumerate() {
+                    let field_ptr = self.build_field_ptr(ptr, i as u32);
+                    self.build_free(field_ptr, field_type);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Frees the variables of a scope, in the order of their names so that the emitted IR is
+    /// the same every time the program is compiled
+    fn build_free_scope(&self, scope: &HashMap<String, (PointerValue, LiteralType)>) {
+        let mut names: Vec<&String> = scope.keys().collect();
+        names.sort();
+        for name in names.into_iter() {
+            let (ptr, typ) = &scope[name];
+            self.build_free(*ptr, typ);
+        }
+    }
+
+    /// Compiles a program by declaring its structs and functions and compiling the functions,
+    /// the functions are compiled in the order they are defined in
     fn compile_program(&mut self, program: &Vec<Box<Node>>) {
-        let mut funcs: HashMap<&str, (&Option<LiteralType>, &Box<Node>)> = HashMap::new();
+        let mut funcs: Vec<(&str, &Vec<Box<Node>>, &Option<LiteralType>, &Box<Node>)> = vec![];
+
+        // Declare the structs and enums before they are used in function signatures
+        for item in program.iter() {
+            match &**item {
+                Node::Struct { name, fields } => {
+                    let strct = Struct::new(name.to_string(), fields.clone());
+                    self.structs.insert(name.to_string(), strct);
+                }
+                Node::Enum { name, variants } => {
+                    let enm = Enum::new(name.to_string(), variants.clone());
+                    self.enums.insert(name.to_string(), enm);
+                }
+                _ => (),
+            }
+        }
+        for item in program.iter() {
+            match &**item {
+                Node::Struct { fields, .. } => {
+                    fields.iter().for_each(|(_, typ)| self.check_type(typ))
+                }
+                Node::Enum { name, variants }
+                    if variants
+                        .iter()
+                        .flat_map(|(_, fields)| fields.iter())
+                        .any(|typ| self.needs_free(typ)) =>
+                {
+                    let name = name.to_string();
+                    self.errors.push(CompileError::EnumOwnsMemory { name: name });
+                }
+                _ => (),
+            }
+        }
 
-        // Create all of the functions in program
+        // Declare all of the functions in program, generic functions are declared for each of
+        // the type arguments they are called with
         for func in program.iter() {
-            let (name, params, r_type, body) = match &**func {
+            match &**func {
+                Node::Func { name, generics, .. } if !generics.is_empty() => {
+                    self.generics.insert(name.to_string(), func.clone());
+                }
                 Node::Func {
                     name,
                     params,
                     r_type,
                     body,
-                } => (name, params, r_type, body),
+                    ..
+                } => {
+                    self.declare_fn(name, params, r_type);
+                    // Store function bodies, params and return type for compiling them later
+                    funcs.push((name, params, r_type, body));
+                }
+                Node::Struct { .. } | Node::Enum { .. } | Node::Attribute { .. } => continue,
                 _ => unreachable!(),
             };
+        }
 
-            // Get the param types and names
-            let mut param_types: Vec<BasicTypeEnum> = vec![];
-            let mut param_names: Vec<&str> = vec![];
-            for param in params.iter() {
-                match **param {
-                    Node::FuncParam(ref param, param_type, _) => {
-                        match **param {
-                            Node::Var(ref name) => param_names.push(name),
-                            _ => unreachable!(),
-                        }
+        // Compile the functions, the instances of the generic functions are compiled after
+        // the functions that call them
+        for (name, params, r_type, body) in funcs.into_iter() {
+            let func = self.module.get_function(name).unwrap();
+            self.compile_fn(func, params, r_type, body);
+        }
+        while let Some(instance) = self.instances.pop() {
+            if let Node::Func {
+                name,
+                params,
+                r_type,
+                body,
+                ..
+            } = &*instance
+            {
+                let func = self.module.get_function(name).unwrap();
+                self.compile_fn(func, params, r_type, body);
+            }
+        }
+    }
 
-                        match param_type {
-                            LiteralType::I32 => param_types.push(self.context.i32_type().into()),
-                            LiteralType::Bool => param_types.push(self.context.i32_type().into()),
-                            _ => unreachable!(),
-                        }
+    /// Declares a function with an empty entry block
+    fn declare_fn(&mut self, name: &str, params: &Vec<Box<Node>>, r_type: &Option<LiteralType>) {
+        // Get the param types and names, arrays, vectors and structs are passed by value
+        let mut param_types: Vec<LiteralType> = vec![];
+        let mut param_names: Vec<&str> = vec![];
+        for param in params.iter() {
+            match **param {
+                Node::FuncParam(ref param, ref param_type, _) => {
+                    match **param {
+                        Node::Var(ref name) => param_names.push(name),
+                        _ => unreachable!(),
                     }
-                    _ => unreachable!(),
+                    param_types.push(param_type.clone());
                 }
+                _ => unreachable!(),
+            }
+        }
+        if let Some(typ) = r_type {
+            self.check_type(typ);
+        }
+        let llvm_param_types: Vec<BasicTypeEnum> =
+            param_types.iter().map(|typ| self.llvm_type(typ)).collect();
+
+        // Create the function type, a main function without a return type returns 0 since
+        // it is called as a MainFn
+        let fn_type = if let Some(typ) = r_type {
+            self.llvm_type(typ).fn_type(&llvm_param_types, false)
+        } else if name == "main" {
+            self.context.i32_type().fn_type(&llvm_param_types, false)
+        } else {
+            let void_type = self.context.void_type();
+            void_type.fn_type(&llvm_param_types, false)
+        };
+
+        self.fn_types
+            .insert(name.to_string(), (param_types, r_type.clone()));
+
+        let new_func = self.module.add_function(name, fn_type, None);
+
+        // Set param names
+        for (param, name) in new_func.get_param_iter().zip(param_names.iter()) {
+            if let BasicValueEnum::IntValue(param) = param {
+                param.set_name(name);
             }
+        }
 
-            // Create the function type
-            let fn_type = if let Some(typ) = r_type {
-                match typ {
-                    LiteralType::I32 => {
-                        let i32_type = self.context.i32_type();
-                        i32_type.fn_type(&param_types, false)
-                    }
-                    LiteralType::Bool => {
-                        let bool_type = self.context.bool_type();
-                        bool_type.fn_type(&param_types, false)
+        self.context.append_basic_block(&new_func, "entry");
+    }
+
+    /// Infers the type arguments of a call to a generic function from the types of the
+    /// arguments
+    fn type_args(&self, func: &Node, args: &Vec<Box<Node>>) -> HashMap<String, LiteralType> {
+        let mut type_args = HashMap::new();
+        if let Node::Func { params, .. } = func {
+            for (param, arg) in params.iter().zip(args.iter()) {
+                if let Node::FuncParam(_, typ, _) = &**param {
+                    typ.infer_args(&self.expr_type(arg), &mut type_args);
+                }
+            }
+        }
+        type_args
+    }
+
+    /// Gets the type of an expression, the value of a match is assumed to be an i32 as in
+    /// compile_expr
+    fn expr_type(&self, expr: &Box<Node>) -> LiteralType {
+        match &**expr {
+            Node::Number(_) | Node::Match { .. } => LiteralType::I32,
+            Node::Bool(_) => LiteralType::Bool,
+            Node::_String(_) => LiteralType::_String,
+            Node::UnaryOp(_, expr) => self.expr_type(expr),
+            Node::Expr(_, op, _) => match op {
+                Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div => LiteralType::I32,
+                _ => LiteralType::Bool,
+            },
+            Node::Var(id) if self.is_variable(id) => self.get_variable(id).1,
+            Node::Var(name) => {
+                let (params, r_type) = self.fn_types[name].clone();
+                LiteralType::Fn(params, Box::new(r_type.unwrap_or(LiteralType::Void)))
+            }
+            // The return type of a closure is not needed to infer type arguments from it
+            Node::Closure { params, .. } => {
+                let mut param_types = vec![];
+                for param in params.iter() {
+                    if let Node::FuncParam(_, typ, _) = &**param {
+                        param_types.push(typ.clone());
                     }
+                }
+                LiteralType::Fn(param_types, Box::new(LiteralType::Infer))
+            }
+            Node::Array(elems) => {
+                LiteralType::Array(Box::new(self.expr_type(&elems[0])), elems.len())
+            }
+            Node::ArrayRepeat(expr, len) => {
+                LiteralType::Array(Box::new(self.expr_type(expr)), *len)
+            }
+            Node::VecNew => LiteralType::Vec(Box::new(LiteralType::Infer)),
+            Node::Tuple(elems) => {
+                LiteralType::Tuple(elems.iter().map(|elem| self.expr_type(elem)).collect())
+            }
+            Node::StructLiteral { name, .. } => LiteralType::Named(name.to_string()),
+            Node::Variant { enm, .. } => LiteralType::Named(enm.to_string()),
+            Node::Index(arr, _) => match self.expr_type(arr) {
+                LiteralType::Array(elem_type, _) | LiteralType::Vec(elem_type) => *elem_type,
+                _ => unreachable!(),
+            },
+            Node::Field(strct, field) => match self.expr_type(strct) {
+                LiteralType::Named(name) => self.structs[&name].get_field_type(field).unwrap(),
+                LiteralType::Tuple(mut elems) => elems.swap_remove(field.parse().unwrap()),
+                _ => unreachable!(),
+            },
+            Node::Ref(place, mutable) => {
+                LiteralType::Ref(Box::new(self.expr_type(place)), *mutable)
+            }
+            Node::Deref(expr) => match self.expr_type(expr) {
+                LiteralType::Ref(typ, _) => *typ,
+                _ => unreachable!(),
+            },
+            Node::FuncCall { name, .. } if self.is_variable(name) => {
+                match self.get_variable(name).1 {
+                    LiteralType::Fn(_, r_type) => *r_type,
                     _ => unreachable!(),
                 }
-            } else {
-                let void_type = self.context.void_type();
-                void_type.fn_type(&param_types, false)
-            };
-
-            // Store function bodies and return type for compiling specific functions
-            funcs.insert(name, (&r_type, body));
-
-            let new_func = self.module.add_function(name, fn_type, None);
-
-            // Set param names
-            for (param, name) in new_func.get_param_iter().zip(param_names.iter()) {
-                param.into_int_value().set_name(name);
             }
-
-            self.context.append_basic_block(&new_func, "entry");
+            Node::FuncCall { name, args, .. } => match self.generics.get(name) {
+                Some(func) => match &**func {
+                    Node::Func { r_type, .. } => {
+                        r_type.clone().unwrap().subst(&self.type_args(func, args))
+                    }
+                    _ => unreachable!(),
+                },
+                None => self.fn_types[name].1.clone().unwrap(),
+            },
+            Node::MethodCall { recv, name, .. } => match (name.as_str(), self.expr_type(recv)) {
+                ("pop", LiteralType::Vec(elem_type)) => *elem_type,
+                _ => LiteralType::I32,
+            },
+            _ => unreachable!(),
         }
+    }
 
-        // Compile the functions
-        for (name, (r_type, body)) in funcs.iter() {
-            let func = self.module.get_function(name).unwrap();
-            self.compile_fn(func, r_type, body);
-        }
+    /// Gets the name of the function which is called, a generic function is declared for
+    /// the type arguments of the call if it has not been called with them before
+    fn fn_instance(&mut self, name: &str, args: &Vec<Box<Node>>) -> String {
+        let mut func = match self.generics.get(name) {
+            Some(func) => func.clone(),
+            None => return name.to_string(),
+        };
+        let type_args = self.type_args(&func, args);
+        func.map_types(&|typ| Some(typ.subst(&type_args)));
+
+        let instance = match &mut *func {
+            Node::Func {
+                name,
+                generics,
+                params,
+                r_type,
+                ..
+            } => {
+                let types: Vec<String> = generics
+                    .iter()
+                    .map(|param| type_args[param].to_string())
+                    .collect();
+                *name = format!("{}<{}>", name, types.join(", "));
+                if self.fn_types.contains_key(name) {
+                    return name.to_string();
+                }
+                self.declare_fn(name, params, r_type);
+                name.to_string()
+            }
+            _ => unreachable!(),
+        };
+        self.instances.push(func);
+        instance
     }
 
-    fn compile_fn(&mut self, func: FunctionValue, r_type: &Option<LiteralType>, body: &Box<Node>) {
+    fn compile_fn(
+        &mut self,
+        func: FunctionValue,
+        params: &Vec<Box<Node>>,
+        r_type: &Option<LiteralType>,
+        body: &Box<Node>,
+    ) {
 		self.curr_fn = Some(func);
+		self.curr_r_type = r_type.clone();
+        self.stmnt = None;
+        self.stmnts = 0;
 
 		// New scope for function
 		self.scopes.push(HashMap::new());
 
         let block = &func.get_first_basic_block().unwrap();
         // allocate parameters
-        for param in func.get_param_iter() {
-            let name = param
-                .into_int_value()
-                .get_name()
-                .to_string_lossy()
-                .into_owned();
-            let alloca = self.create_entry_block_alloca(&name, &block);
+        for (param, param_node) in func.get_param_iter().zip(params.iter()) {
+            let (name, typ) = match &**param_node {
+                Node::FuncParam(var, typ, _) => match &**var {
+                    Node::Var(name) => (name.to_string(), typ.clone()),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            };
+            let alloca = self.create_entry_block_alloca(&name, &block, param.get_type());
             self.builder.position_at_end(&block);
             self.builder.build_store(alloca, param);
-            self.scopes.last_mut().unwrap().insert(name, alloca);
+            self.insert_variable(name, alloca, typ);
         }
 
         self.builder.position_at_end(&block);
+        self.build_step();
 
         // compile body
         self.compile_block(body, &block);
 
-        // void functions still needs to return
+        // void functions still needs to return, the parameters are owned by the function
+        // and are freed before returning
         if let None = r_type {
-            self.builder
-                .position_at_end(&func.get_first_basic_block().unwrap());
-            self.builder.build_return(None);
+            if !self.block_terminated() {
+                self.build_free_scope(self.scopes.last().unwrap());
+                self.build_void_return();
+            }
 		}
 		
 		// Done with func so pop scope
 		self.scopes.pop();
     }
 
+    /// Returns from a function without a return type, main returns 0 as the exit code
+    fn build_void_return(&self) -> InstructionValue {
+        let func = self.curr_fn.unwrap();
+        match func.get_name().to_str() {
+            Ok("main") => self
+                .builder
+                .build_return(Some(&self.context.i32_type().const_int(0, false))),
+            _ => self.builder.build_return(None),
+        }
+    }
+
     /// Compiles all of the statements in a block
     fn compile_block(&mut self, statement: &Box<Node>, block: &BasicBlock) {
 		self.scopes.push(HashMap::new());
@@ -285,45 +1279,80 @@ impl Compiler {
             self.compile_stmnt(&next_statement.clone().unwrap(), block);
             next_statement = extract_next!(next_statement);
 		}
-		self.scopes.pop();
+
+        // Free the memory of the variables going out of scope, unless it has been done
+        // before returning
+        let scope = self.scopes.pop().unwrap();
+        if !self.block_terminated() {
+            self.build_free_scope(&scope);
+        }
+    }
+
+    /// Compiles a statement, the statements of a function are numbered in the order they
+    /// appear in like the interpreter does
+    fn compile_stmnt(&mut self, statement: &Box<Node>, block: &BasicBlock) {
+        let outer = self.stmnt.replace(self.stmnts);
+        self.stmnts += 1;
+        self.compile_node(statement, block);
+        self.stmnt = outer;
     }
 
     /// Compiles a statement and returns the instruction value along with a bool which indactes
     /// if the statement was a return statement
-    fn compile_stmnt(&mut self, statement: &Box<Node>, block: &BasicBlock) {
+    fn compile_node(&mut self, statement: &Box<Node>, block: &BasicBlock) {
         match *statement.clone() {
             Node::Let { var, expr, .. } => {
-                // Get variable identifier
-                let id = match *var {
-                    Node::VarBinding(var, _, _) => match *var {
-                        Node::Var(id) => id,
+                // Get variable identifier and type
+                let (id, typ) = match *var {
+                    Node::VarBinding(var, typ, _) => match *var {
+                        Node::Var(id) => (id, typ),
                         _ => unreachable!(),
                     },
+                    Node::TupleBinding(vars, typ) => {
+                        return self.compile_tuple_binding(&vars, &typ, &expr, block)
+                    }
                     _ => unreachable!(),
                 };
-                let expr_val = self.compile_expr(&expr);
 
-                // Allocate local variable on stack
-                let alloca = self.create_entry_block_alloca(&id, block);
-                self.builder.build_store(alloca, expr_val);
+                // Allocate local variable on stack, it is not in scope until it has been
+                // initialized so that it can shadow a variable used in its initializer
+                let alloca = self.create_entry_block_alloca(&id, block, self.llvm_type(&typ));
+                self.compile_store(alloca, &typ, &expr);
+                self.insert_variable(id, alloca, typ);
             }
 
             Node::VarValue { var, expr, .. } => {
-                // update var
-                let id = match *var {
-                    Node::Var(id) => id,
-                    _ => unreachable!(),
-                };
-                let expr_val = self.compile_expr(&expr);
-
-                // Get the variables pointer value and store new value
-				let var = self.get_variable(&id);
-                self.builder.build_store(var, expr_val);
+                // Get the pointer value of the assigned place and store new value
+                let (ptr, typ) = self.compile_place(&var);
+
+                if self.needs_free(&typ) {
+                    // The overwritten value is freed after the new value has been stored
+                    // since the new value might be a copy of it
+                    let old_val = self.builder.build_load(ptr, "old");
+                    self.compile_store(ptr, &typ, &expr);
+                    let old = self.create_entry_block_alloca("old", block, self.llvm_type(&typ));
+                    self.builder.build_store(old, old_val);
+                    self.build_free(old, &typ);
+                } else {
+                    self.compile_store(ptr, &typ, &expr);
+                }
             }
 
             Node::Return { expr, .. } => {
-                let ret_val = self.compile_expr(&expr);
-                self.builder.build_return(Some(&ret_val));
+                // "return;" in a function without a return type
+                let ret_val = match self.curr_r_type.clone() {
+                    Some(r_type) => Some(self.compile_value(&expr, &r_type)),
+                    None => None,
+                };
+
+                // Free the memory of all variables in the function before returning
+                for scope in self.scopes.iter() {
+                    self.build_free_scope(scope);
+                }
+                match ret_val {
+                    Some(ret_val) => self.builder.build_return(Some(&ret_val)),
+                    None => self.build_void_return(),
+                };
             }
 
             Node::If {
@@ -340,26 +1369,407 @@ impl Compiler {
             Node::While {
                 cond, statement, ..
 			} => self.compile_while(&cond, &statement),
+
+            Node::MatchStatement { expr, arms, .. } => self.compile_match(&expr, &arms, None),
 			
-			Node::FuncCall { name, args, .. } => {self.compile_call(&name, &args);},
+			Node::FuncCall { name, args, .. } => {
+                // A returned value which owns memory is freed with the current scope
+                if let Some(val) = self.compile_call(&name, &args) {
+                    let r_type = self.call_r_type(&name, &args).unwrap();
+                    if self.needs_free(&r_type) {
+                        let tmp = self.build_temp(&r_type);
+                        self.builder.build_store(tmp, val);
+                    }
+                }
+            }
+
+            Node::MethodCall {
+                recv, name, args, ..
+            } => match name.as_str() {
+                "push" => {
+                    let (vec, typ) = self.compile_place(&recv);
+                    match typ {
+                        LiteralType::Vec(elem_type) => self.compile_vec_push(vec, &elem_type, &args[0]),
+                        _ => unreachable!(),
+                    }
+                }
+                _ => {
+                    self.compile_expr(statement);
+                }
+            },
 
             _ => unimplemented!("compile_stmnt: Node {:?}", statement),
         }
     }
 
-    fn compile_call(&mut self, name: &str, args: &Vec<Box<Node>>) -> IntValue {
-        let mut compiled_args: Vec<IntValue> = vec![];
+    /// Declares each of the variables of a tuple binding as an element of the tuple, the
+    /// elements are moved out of the tuple which is not freed
+    fn compile_tuple_binding(
+        &mut self,
+        vars: &Vec<(Box<Node>, bool)>,
+        typ: &LiteralType,
+        expr: &Box<Node>,
+        block: &BasicBlock,
+    ) {
+        let tuple = self.create_entry_block_alloca("tuple", block, self.llvm_type(typ));
+        self.compile_store(tuple, typ, expr);
+
+        for (i, ((var, _), elem_type)) in vars.iter().zip(self.field_types(typ)).enumerate() {
+            let id = match &**var {
+                Node::Var(id) => id.to_string(),
+                _ => unreachable!(),
+            };
+            let alloca = self.create_entry_block_alloca(&id, block, self.llvm_type(&elem_type));
+            let elem = self.builder.build_load(self.build_field_ptr(tuple, i as u32), "elem");
+            self.builder.build_store(alloca, elem);
+            self.insert_variable(id, alloca, elem_type);
+        }
+    }
+
+    /// Compiles an expression of type typ and stores its value at ptr, array and struct
+    /// literals are stored element by element and arrays, vectors and structs referred to
+    /// by a place are copied
+    fn compile_store(&mut self, ptr: PointerValue, typ: &LiteralType, expr: &Box<Node>) {
+        match (typ, &**expr) {
+            (LiteralType::Array(elem_type, _), Node::Array(elems)) => {
+                for (i, elem) in elems.iter().enumerate() {
+                    let idx = self.context.i32_type().const_int(i as u64, false);
+                    let elem_ptr = self.build_elem_ptr(ptr, idx);
+                    self.compile_store(elem_ptr, elem_type, elem);
+                }
+            }
+
+            (LiteralType::Array(elem_type, len), Node::ArrayRepeat(elem, _)) => {
+                self.compile_array_repeat(ptr, elem_type, elem, *len)
+            }
+
+            (LiteralType::Vec(elem_type), Node::VecNew) => self.build_vec_new(ptr, elem_type),
+
+            (_, Node::Variant { enm, name, args }) => {
+                let enm_def = self.enums[enm].clone();
+                let tag = enm_def.get_variant_index(name).unwrap();
+                let variant = self.build_variant_ptr(ptr, enm, name);
+
+                let tag = self.context.i32_type().const_int(tag as u64, false);
+                self.builder.build_store(self.build_field_ptr(variant, 0), tag);
+                let field_types = enm_def.get_variant(name).unwrap();
+                for (i, (arg, typ)) in args.iter().zip(field_types.iter()).enumerate() {
+                    let field_ptr = self.build_field_ptr(variant, i as u32 + 1);
+                    self.compile_store(field_ptr, typ, arg);
+                }
+            }
+
+            (_, Node::Match { expr, arms }) => self.compile_match(expr, arms, Some((ptr, typ))),
+
+            (LiteralType::Fn(params, r_type), Node::Closure { .. }) => {
+                self.compile_closure(ptr, params, r_type, expr)
+            }
+
+            // A function used as a value is a closure without captured variables
+            (LiteralType::Fn(_, _), Node::Var(name)) if !self.is_variable(name) => {
+                self.build_fn_value(ptr, name)
+            }
+
+            (LiteralType::Tuple(elem_types), Node::Tuple(elems)) => {
+                for (i, (elem, elem_type)) in elems.iter().zip(elem_types.iter()).enumerate() {
+                    let elem_ptr = self.build_field_ptr(ptr, i as u32);
+                    self.compile_store(elem_ptr, elem_type, elem);
+                }
+            }
+
+            (LiteralType::Named(name), Node::StructLiteral { fields, .. }) => {
+                let strct = self.structs[name].clone();
+                for (field, expr) in fields.iter() {
+                    let idx = strct.get_field_index(field).unwrap();
+                    let field_ptr = self.build_field_ptr(ptr, idx);
+                    self.compile_store(field_ptr, &strct.get_field_type(field).unwrap(), expr);
+                }
+            }
+
+            // The returned value is owned by the caller and does not have to be copied
+            (_, Node::FuncCall { name, args, .. }) => {
+                let val = self.compile_call(name, args).unwrap();
+                self.builder.build_store(ptr, val);
+            }
+
+            (LiteralType::Ref(_, _), _) => {
+                let reference = self.compile_ref(expr);
+                self.builder.build_store(ptr, reference);
+            }
+
+            (LiteralType::Array(_, _), _)
+            | (LiteralType::Vec(_), _)
+            | (LiteralType::Named(_), _)
+            | (LiteralType::Tuple(_), _)
+            | (LiteralType::Fn(_, _), _) => {
+                let (src, _) = self.compile_place(expr);
+                self.build_copy(ptr, src, typ);
+            }
+
+            _ => {
+                let expr_val = self.compile_expr(expr);
+                self.builder.build_store(ptr, self.build_i32(expr_val));
+            }
+        }
+    }
+
+    /// Stores elem in each of the len elements of the array at ptr using a loop
+    fn compile_array_repeat(
+        &mut self,
+        ptr: PointerValue,
+        elem_type: &LiteralType,
+        elem: &Box<Node>,
+        len: usize,
+    ) {
+        let func = self.fn_value();
+        let i32_type = self.context.i32_type();
+
+        let counter = self.create_entry_block_alloca(
+            "i",
+            &func.get_first_basic_block().unwrap(),
+            i32_type.into(),
+        );
+        self.builder.build_store(counter, i32_type.const_int(0, false));
+
+        // build branches
+        let cond_bb = self.context.append_basic_block(&func, "repeatcond");
+        let do_bb = self.context.append_basic_block(&func, "repeat");
+        let cont_bb = self.context.append_basic_block(&func, "repeatcont");
+
+        self.builder.build_unconditional_branch(&cond_bb);
+
+        // build cond block
+        self.builder.position_at_end(&cond_bb);
+        let i = self.builder.build_load(counter, "i").into_int_value();
+        let len = i32_type.const_int(len as u64, false);
+        let cond = self
+            .builder
+            .build_int_compare(IntPredicate::ULT, i, len, "repeatcond");
+        self.builder.build_conditional_branch(cond, &do_bb, &cont_bb);
+
+        // build do block, storing the element and incrementing the counter
+        self.builder.position_at_end(&do_bb);
+        let elem_ptr = self.build_elem_ptr(ptr, i);
+        self.compile_store(elem_ptr, elem_type, elem);
+        let one = i32_type.const_int(1, false);
+        let next_i = self.builder.build_int_add(i, one, "nexti");
+        self.builder.build_store(counter, next_i);
+        self.builder.build_unconditional_branch(&cond_bb);
+
+        self.builder.position_at_end(&cont_bb);
+    }
+
+    /// Appends elem to the vector at vec, the capacity of the vector is doubled if it is full
+    fn compile_vec_push(&mut self, vec: PointerValue, elem_type: &LiteralType, elem: &Box<Node>) {
+        let func = self.fn_value();
+        let i32_type = self.context.i32_type();
+        let str_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+
+        let len = self.build_vec_len(vec);
+        let cap_ptr = self.build_vec_field(vec, 2);
+        let cap = self.builder.build_load(cap_ptr, "cap").into_int_value();
+
+        // build branches
+        let grow_bb = self.context.append_basic_block(&func, "grow");
+        let push_bb = self.context.append_basic_block(&func, "push");
+
+        let full = self
+            .builder
+            .build_int_compare(IntPredicate::EQ, len, cap, "full");
+        self.builder.build_conditional_branch(full, &grow_bb, &push_bb);
+
+        // build grow block, the first allocation has room for 4 elements
+        self.builder.position_at_end(&grow_bb);
+        let zero = i32_type.const_int(0, false);
+        let is_empty = self
+            .builder
+            .build_int_compare(IntPredicate::EQ, cap, zero, "isempty");
+        let double_cap = self
+            .builder
+            .build_int_mul(cap, i32_type.const_int(2, false), "doublecap");
+        let new_cap = self
+            .builder
+            .build_select(is_empty, i32_type.const_int(4, false), double_cap, "newcap")
+            .into_int_value();
+
+        let size = self.build_vec_size(vec, new_cap);
+        let data = self.build_vec_data(vec);
+        let raw = self.builder.build_pointer_cast(data, str_type, "raw");
+        let new_raw = self.build_libc_call("realloc", &[raw.into(), size.into()]);
+        let new_data = self
+            .builder
+            .build_pointer_cast(new_raw, data.get_type(), "data");
+        self.builder.build_store(self.build_vec_field(vec, 0), new_data);
+        self.builder.build_store(cap_ptr, new_cap);
+        self.builder.build_unconditional_branch(&push_bb);
+
+        // build push block, storing the element after the last element
+        self.builder.position_at_end(&push_bb);
+        let data = self.build_vec_data(vec);
+        let elem_ptr = unsafe { self.builder.build_gep(data, &[len], "elemptr") };
+        self.compile_store(elem_ptr, elem_type, elem);
+        let new_len = self
+            .builder
+            .build_int_add(len, i32_type.const_int(1, false), "newlen");
+        self.builder.build_store(self.build_vec_field(vec, 1), new_len);
+    }
+
+    /// Removes and returns the last element of the vector at vec, aborts if it is empty
+    fn compile_vec_pop(&mut self, vec: PointerValue) -> IntValue {
+        let func = self.fn_value();
+        let i32_type = self.context.i32_type();
+
+        let len = self.build_vec_len(vec);
+        let zero = i32_type.const_int(0, false);
+        let is_empty = self
+            .builder
+            .build_int_compare(IntPredicate::EQ, len, zero, "isempty");
+
+        let empty_bb = self.context.append_basic_block(&func, "popempty");
+        let pop_bb = self.context.append_basic_block(&func, "pop");
+        self.builder
+            .build_conditional_branch(is_empty, &empty_bb, &pop_bb);
+
+        self.builder.position_at_end(&empty_bb);
+        self.build_abort("Cannot pop from an empty vector\n", &[]);
+
+        self.builder.position_at_end(&pop_bb);
+        let new_len = self
+            .builder
+            .build_int_sub(len, i32_type.const_int(1, false), "newlen");
+        self.builder.build_store(self.build_vec_field(vec, 1), new_len);
+        let data = self.build_vec_data(vec);
+        let elem_ptr = unsafe { self.builder.build_gep(data, &[new_len], "elemptr") };
+        self.builder.build_load(elem_ptr, "elem").into_int_value()
+    }
 
-        // compile each argument
-        for arg in args.iter() {
-            compiled_args.push(self.compile_expr(arg));
+    /// Gets a pointer to a place, i.e. a variable, an element of an array or vector or a
+    /// field of a struct, along with the type of the place. Indexing is bounds checked
+    fn compile_place(&mut self, place: &Box<Node>) -> (PointerValue, LiteralType) {
+        match &**place {
+            Node::Var(id) => self.get_variable(&id),
+            Node::Index(arr, idx) => {
+                let (arr, typ) = self.compile_place(&arr);
+                let idx = self.compile_expr(&idx);
+                match typ {
+                    LiteralType::Array(elem_type, len) => {
+                        let len = self.context.i32_type().const_int(len as u64, false);
+                        self.build_bounds_check(idx, len);
+                        (self.build_elem_ptr(arr, idx), *elem_type)
+                    }
+                    LiteralType::Vec(elem_type) => {
+                        let len = self.build_vec_len(arr);
+                        self.build_bounds_check(idx, len);
+                        let data = self.build_vec_data(arr);
+                        let elem_ptr = unsafe { self.builder.build_gep(data, &[idx], "elemptr") };
+                        (elem_ptr, *elem_type)
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Node::Field(strct, field) => {
+                let (ptr, typ) = self.compile_place(&strct);
+                match typ {
+                    LiteralType::Named(name) => {
+                        let strct = &self.structs[&name];
+                        let field_type = strct.get_field_type(field).unwrap();
+                        let idx = strct.get_field_index(field).unwrap();
+                        (self.build_field_ptr(ptr, idx), field_type)
+                    }
+                    LiteralType::Tuple(mut elems) => {
+                        let idx: usize = field.parse().unwrap();
+                        (self.build_field_ptr(ptr, idx as u32), elems.swap_remove(idx))
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            // Dereferencing a borrowed place i.e. "*&x" is the place itself
+            Node::Deref(expr) => match &**expr {
+                Node::Ref(place, _) => self.compile_place(place),
+                _ => {
+                    let (ptr, typ) = self.compile_place(&expr);
+                    match typ {
+                        LiteralType::Ref(typ, _) => {
+                            let reference = self.builder.build_load(ptr, "ref");
+                            (reference.into_pointer_value(), *typ)
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+            },
+            // Returned values are stored in a temporary so that their fields can be read
+            Node::FuncCall { name, args, .. } => {
+                let typ = self.call_r_type(name, args).unwrap();
+                let val = self.compile_call(name, args).unwrap();
+                let tmp = self.build_temp(&typ);
+                self.builder.build_store(tmp, val);
+                (tmp, typ)
+            }
+            // The place is replaced by a temporary of its type, so that the error is only
+            // reported once
+            _ => {
+                let node = format!("{:?}", place);
+                let variant = node.split(|c: char| !c.is_alphanumeric() && c != '_').next();
+                let node = variant.unwrap_or_default().to_string();
+                self.errors.push(CompileError::PlaceNotSupported { node: node });
+                let typ = self.expr_type(place);
+                (self.build_temp(&typ), typ)
+            }
         }
+    }
 
-        let args_val: Vec<BasicValueEnum> = compiled_args
-            .iter()
-            .by_ref()
-            .map(|&val| val.into())
-            .collect();
+    /// Compiles an expression of a reference type into a pointer
+    fn compile_ref(&mut self, expr: &Box<Node>) -> PointerValue {
+        match &**expr {
+            Node::Ref(place, _) => self.compile_place(place).0,
+            _ => {
+                let (ptr, _) = self.compile_place(expr);
+                self.builder.build_load(ptr, "ref").into_pointer_value()
+            }
+        }
+    }
+
+    /// Compiles an expression of type typ into a value, arrays, vectors and structs are
+    /// copied into a temporary which is loaded
+    fn compile_value(&mut self, expr: &Box<Node>, typ: &LiteralType) -> BasicValueEnum {
+        match typ {
+            LiteralType::I32 | LiteralType::Bool => self.compile_expr(expr).into(),
+            LiteralType::Ref(_, _) => self.compile_ref(expr).into(),
+            _ => {
+                let block = self.fn_value().get_first_basic_block().unwrap();
+                let tmp = self.create_entry_block_alloca("tmp", &block, self.llvm_type(typ));
+                self.compile_store(tmp, typ, expr);
+                self.builder.build_load(tmp, "tmp")
+            }
+        }
+    }
+
+    /// Gets the return type of a call, None if the function does not return a value
+    fn call_r_type(&mut self, name: &str, args: &Vec<Box<Node>>) -> Option<LiteralType> {
+        if self.is_variable(name) {
+            return match self.get_variable(name).1 {
+                LiteralType::Fn(_, r_type) if *r_type != LiteralType::Void => Some(*r_type),
+                _ => None,
+            };
+        }
+        let name = self.fn_instance(name, args);
+        self.fn_types[&name].1.clone()
+    }
+
+    /// Compiles a function call, returns None if the function does not return a value.
+    /// Variables holding closures shadow the functions with the same name
+    fn compile_call(&mut self, name: &str, args: &Vec<Box<Node>>) -> Option<BasicValueEnum> {
+        if self.is_variable(name) {
+            return self.compile_closure_call(name, args);
+        }
+        let name = &self.fn_instance(name, args);
+        let (param_types, _) = self.fn_types[name].clone();
+
+        // compile each argument, the called function owns the arguments
+        let mut args_val: Vec<BasicValueEnum> = vec![];
+        for (arg, typ) in args.iter().zip(param_types.iter()) {
+            args_val.push(self.compile_value(arg, typ));
+        }
 
         self.builder
             .build_call(
@@ -369,8 +1779,411 @@ impl Compiler {
             )
             .try_as_basic_value()
             .left()
-            .unwrap()
-            .into_int_value()
+    }
+
+    /// Calls the closure held by a variable with the pointer to its captured variables
+    fn compile_closure_call(
+        &mut self,
+        name: &str,
+        args: &Vec<Box<Node>>,
+    ) -> Option<BasicValueEnum> {
+        let (ptr, typ) = self.get_variable(name);
+        let (param_types, r_type) = match typ {
+            LiteralType::Fn(params, r_type) => (params, *r_type),
+            _ => unreachable!(),
+        };
+
+        let mut args_val: Vec<BasicValueEnum> = vec![];
+        for (arg, typ) in args.iter().zip(param_types.iter()) {
+            args_val.push(self.compile_value(arg, typ));
+        }
+
+        let fn_type = self
+            .closure_fn_type(&param_types, &r_type)
+            .ptr_type(AddressSpace::Generic);
+        let func = self.builder.build_load(self.build_field_ptr(ptr, 0), "fn");
+        let func = self
+            .builder
+            .build_pointer_cast(func.into_pointer_value(), fn_type, "fn");
+        let env = self.builder.build_load(self.build_field_ptr(ptr, 1), "env");
+        args_val.insert(0, env);
+
+        self.builder
+            .build_call(func, args_val.as_slice(), "tmp")
+            .try_as_basic_value()
+            .left()
+    }
+
+    /// Compiles a closure into a function which takes a pointer to the captured variables
+    /// before its parameters. The captured variables are copied to the heap and the closure
+    /// is stored at ptr
+    fn compile_closure(
+        &mut self,
+        ptr: PointerValue,
+        param_types: &Vec<LiteralType>,
+        r_type: &LiteralType,
+        closure: &Box<Node>,
+    ) {
+        let (params, body) = match &**closure {
+            Node::Closure { params, body } => (params, body),
+            _ => unreachable!(),
+        };
+        let str_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+
+        // The captured variables are stored in a struct, names which are not variables are
+        // functions
+        let mut captures = vec![];
+        for name in closure.captures().into_iter() {
+            if self.is_variable(&name) {
+                let (var, typ) = self.get_variable(&name);
+                if self.needs_free(&typ) {
+                    self.errors.push(CompileError::CaptureOwnsMemory {
+                        var: name.to_string(),
+                        typ: typ.clone(),
+                    });
+                }
+                captures.push((name, var, typ));
+            }
+        }
+        let field_types: Vec<BasicTypeEnum> = captures
+            .iter()
+            .map(|(_, _, typ)| self.llvm_type(typ))
+            .collect();
+        let env_type = self
+            .context
+            .struct_type(&field_types, false)
+            .ptr_type(AddressSpace::Generic);
+
+        // The address of the second struct of an array starting at address 0 is its size
+        let one = self.context.i32_type().const_int(1, false);
+        let end = unsafe { self.builder.build_gep(env_type.const_null(), &[one], "end") };
+        let size = self
+            .builder
+            .build_ptr_to_int(end, self.context.i64_type(), "size");
+        let raw = self.build_libc_call("malloc", &[size.into()]);
+        let env = self.builder.build_pointer_cast(raw, env_type, "env");
+        for (i, (_, var, typ)) in captures.iter().enumerate() {
+            self.build_copy(self.build_field_ptr(env, i as u32), *var, typ);
+        }
+
+        let fn_type = self.closure_fn_type(param_types, r_type);
+        let func = self.module.add_function("closure", fn_type, None);
+        let func_ptr = func.as_global_value().as_pointer_value();
+        let func_ptr = self.builder.build_pointer_cast(func_ptr, str_type, "fn");
+        self.builder.build_store(self.build_field_ptr(ptr, 0), func_ptr);
+        self.builder.build_store(self.build_field_ptr(ptr, 1), raw);
+        self.builder.build_store(self.build_field_ptr(ptr, 2), size);
+
+        // Compile the function of the closure, the current function is continued after it
+        let block = self.builder.get_insert_block().unwrap();
+        let curr_fn = self.curr_fn;
+        let curr_r_type = self.curr_r_type.clone();
+        let scopes = std::mem::replace(&mut self.scopes, vec![HashMap::new()]);
+        self.curr_fn = Some(func);
+        self.curr_r_type = match r_type {
+            LiteralType::Void => None,
+            typ => Some(typ.clone()),
+        };
+
+        let entry = self.context.append_basic_block(&func, "entry");
+        self.builder.position_at_end(&entry);
+        let env_param = func.get_nth_param(0).unwrap().into_pointer_value();
+        let env = self.builder.build_pointer_cast(env_param, env_type, "env");
+        for (i, (name, _, typ)) in captures.into_iter().enumerate() {
+            let var = self.build_field_ptr(env, i as u32);
+            self.insert_variable(name, var, typ);
+        }
+        for (i, (param, typ)) in params.iter().zip(param_types.iter()).enumerate() {
+            let name = match &**param {
+                Node::FuncParam(var, _, _) => match &**var {
+                    Node::Var(name) => name.to_string(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            };
+            let alloca = self.create_entry_block_alloca(&name, &entry, self.llvm_type(typ));
+            self.builder.position_at_end(&entry);
+            let val = func.get_nth_param(i as u32 + 1).unwrap();
+            self.builder.build_store(alloca, val);
+            self.insert_variable(name, alloca, typ.clone());
+        }
+        self.build_step();
+
+        // The parameters are owned by the closure and are freed before returning
+        match r_type {
+            LiteralType::Void => {
+                if let Node::FuncCall { name, args, .. } = &**body {
+                    self.compile_call(name, args);
+                }
+                self.build_free_scope(self.scopes.last().unwrap());
+                self.builder.build_return(None);
+            }
+            typ => {
+                let ret_val = self.compile_value(body, typ);
+                self.build_free_scope(self.scopes.last().unwrap());
+                self.builder.build_return(Some(&ret_val));
+            }
+        }
+
+        self.scopes = scopes;
+        self.curr_fn = curr_fn;
+        self.curr_r_type = curr_r_type;
+        self.builder.position_at_end(&block);
+    }
+
+    /// Stores a function as a closure without captured variables at ptr, which calls a
+    /// thunk that ignores the pointer to the captured variables
+    fn build_fn_value(&mut self, ptr: PointerValue, name: &str) {
+        let str_type = self.context.i8_type().ptr_type(AddressSpace::Generic);
+        let thunk_name = format!("{}.thunk", name);
+        let thunk = match self.module.get_function(&thunk_name) {
+            Some(thunk) => thunk,
+            None => self.build_thunk(name, &thunk_name),
+        };
+
+        let func_ptr = thunk.as_global_value().as_pointer_value();
+        let func_ptr = self.builder.build_pointer_cast(func_ptr, str_type, "fn");
+        let zero = self.context.i64_type().const_int(0, false);
+        self.builder.build_store(self.build_field_ptr(ptr, 0), func_ptr);
+        self.builder
+            .build_store(self.build_field_ptr(ptr, 1), str_type.const_null());
+        self.builder.build_store(self.build_field_ptr(ptr, 2), zero);
+    }
+
+    /// Builds a function which calls the function name with all but its first parameter
+    fn build_thunk(&mut self, name: &str, thunk_name: &str) -> FunctionValue {
+        let (param_types, r_type) = self.fn_types[name].clone();
+        let r_type = r_type.unwrap_or(LiteralType::Void);
+        let fn_type = self.closure_fn_type(&param_types, &r_type);
+        let thunk = self.module.add_function(thunk_name, fn_type, None);
+
+        let block = self.builder.get_insert_block().unwrap();
+        let entry = self.context.append_basic_block(&thunk, "entry");
+        self.builder.position_at_end(&entry);
+
+        let args: Vec<BasicValueEnum> = thunk.get_params().into_iter().skip(1).collect();
+        let func = self.module.get_function(name).unwrap();
+        match self.builder.build_call(func, &args, "tmp").try_as_basic_value().left() {
+            Some(ret_val) => self.builder.build_return(Some(&ret_val)),
+            None => self.builder.build_return(None),
+        };
+
+        self.builder.position_at_end(&block);
+        thunk
+    }
+
+    /// Compiles a match, the arms are compiled as blocks or, if result is given, as expressions
+    /// whose value is stored at the pointer of the result. Matches where the patterns only
+    /// test the tag of an enum or the value of a scalar are compiled to a switch
+    fn compile_match(
+        &mut self,
+        expr: &Box<Node>,
+        arms: &Vec<(Pattern, Box<Node>)>,
+        result: Option<(PointerValue, &LiteralType)>,
+    ) {
+        let func = self.fn_value();
+        let (ptr, typ) = self.compile_scrutinee(expr);
+
+        let arm_bbs: Vec<BasicBlock> = arms
+            .iter()
+            .map(|_| self.context.append_basic_block(&func, "arm"))
+            .collect();
+        let cont_bb = self.context.append_basic_block(&func, "matchcont");
+
+        let is_switch = arms.iter().all(|(pat, _)| match pat {
+            Pattern::Variant { fields, .. } => fields.iter().all(|field| field.is_irrefutable()),
+            _ => true,
+        });
+        match is_switch {
+            true => self.build_match_switch(ptr, &typ, arms, &arm_bbs),
+            false => self.build_match_tests(ptr, &typ, arms, &arm_bbs),
+        }
+
+        // build the arms, in a scope containing the variables bound by the pattern
+        let mut continues = false;
+        for ((pat, arm), arm_bb) in arms.iter().zip(arm_bbs.iter()) {
+            self.builder.position_at_end(arm_bb);
+            self.scopes.push(HashMap::new());
+            self.build_bindings(ptr, &typ, pat);
+
+            match result {
+                Some((res_ptr, res_type)) => self.compile_store(res_ptr, res_type, arm),
+                None => self.compile_block(arm, arm_bb),
+            }
+
+            let scope = self.scopes.pop().unwrap();
+            if !self.block_terminated() {
+                self.build_free_scope(&scope);
+                self.builder.build_unconditional_branch(&cont_bb);
+                continues = true;
+            }
+        }
+
+        // merge, unless all of the arms have returned
+        self.builder.position_at_end(&cont_bb);
+        if !continues {
+            self.builder.build_unreachable();
+        }
+    }
+
+    /// Gets a pointer to the matched value and its type, values which are not places are
+    /// stored in a temporary
+    fn compile_scrutinee(&mut self, expr: &Box<Node>) -> (PointerValue, LiteralType) {
+        match &**expr {
+            Node::Var(_) | Node::Index(_, _) | Node::Field(_, _) | Node::FuncCall { .. } => {
+                self.compile_place(expr)
+            }
+            Node::Variant { enm, .. } => {
+                let typ = LiteralType::Named(enm.to_string());
+                let tmp = self.build_temp(&typ);
+                self.compile_store(tmp, &typ, expr);
+                (tmp, typ)
+            }
+            _ => {
+                let block = self.fn_value().get_first_basic_block().unwrap();
+                let typ = LiteralType::I32;
+                let tmp = self.create_entry_block_alloca("tmp", &block, self.llvm_type(&typ));
+                self.compile_store(tmp, &typ, expr);
+                (tmp, typ)
+            }
+        }
+    }
+
+    /// Branches to the first arm whose pattern matches the tag of the enum or the scalar at ptr
+    fn build_match_switch(
+        &mut self,
+        ptr: PointerValue,
+        typ: &LiteralType,
+        arms: &Vec<(Pattern, Box<Node>)>,
+        arm_bbs: &Vec<BasicBlock>,
+    ) {
+        let value = match typ {
+            LiteralType::Named(_) => self.builder.build_load(self.build_field_ptr(ptr, 0), "tag"),
+            _ => self.builder.build_load(ptr, "val"),
+        }
+        .into_int_value();
+
+        let mut cases: Vec<(IntValue, &BasicBlock)> = vec![];
+        let mut covered: Vec<u64> = vec![];
+        let mut else_bb: Option<&BasicBlock> = None;
+        for ((pat, _), arm_bb) in arms.iter().zip(arm_bbs.iter()) {
+            let case = match pat {
+                Pattern::Number(num) => *num as u64,
+                Pattern::Bool(b) => *b as u64,
+                Pattern::Variant { enm, name, .. } => {
+                    self.enums[enm].get_variant_index(name).unwrap() as u64
+                }
+                Pattern::Wildcard | Pattern::Binding(_) => {
+                    // Any arm after a wildcard can not be reached
+                    else_bb = Some(arm_bb);
+                    break;
+                }
+            };
+            if !covered.contains(&case) {
+                covered.push(case);
+                cases.push((value.get_type().const_int(case, false), arm_bb));
+            }
+        }
+
+        match else_bb {
+            Some(else_bb) => {
+                self.builder.build_switch(value, else_bb, &cases);
+            }
+            // Every value is covered by the cases since the match is exhaustive
+            None => {
+                let func = self.fn_value();
+                let switch_bb = self.builder.get_insert_block().unwrap();
+                let nomatch_bb = self.context.append_basic_block(&func, "nomatch");
+                self.builder.position_at_end(&nomatch_bb);
+                self.builder.build_unreachable();
+
+                self.builder.position_at_end(&switch_bb);
+                self.builder.build_switch(value, &nomatch_bb, &cases);
+            }
+        }
+    }
+
+    /// Tests the patterns of the arms in order and branches to the first arm which matches
+    fn build_match_tests(
+        &mut self,
+        ptr: PointerValue,
+        typ: &LiteralType,
+        arms: &Vec<(Pattern, Box<Node>)>,
+        arm_bbs: &Vec<BasicBlock>,
+    ) {
+        let func = self.fn_value();
+        for ((pat, _), arm_bb) in arms.iter().zip(arm_bbs.iter()) {
+            let next_bb = self.context.append_basic_block(&func, "nextarm");
+            self.build_pattern_test(ptr, typ, pat, &next_bb);
+            self.builder.build_unconditional_branch(arm_bb);
+            self.builder.position_at_end(&next_bb);
+        }
+
+        // The match is exhaustive so the last test can not fail
+        self.builder.build_unreachable();
+    }
+
+    /// Branches to fail_bb if the value at ptr does not match the pattern, otherwise the
+    /// builder is positioned where the value has matched
+    fn build_pattern_test(
+        &mut self,
+        ptr: PointerValue,
+        typ: &LiteralType,
+        pat: &Pattern,
+        fail_bb: &BasicBlock,
+    ) {
+        let (value, expected) = match pat {
+            Pattern::Wildcard | Pattern::Binding(_) => return,
+            Pattern::Number(num) => (self.builder.build_load(ptr, "val"), *num as u64),
+            Pattern::Bool(b) => (self.builder.build_load(ptr, "val"), *b as u64),
+            Pattern::Variant { enm, name, .. } => {
+                let tag = self.enums[enm].get_variant_index(name).unwrap();
+                let tag_ptr = self.build_field_ptr(ptr, 0);
+                (self.builder.build_load(tag_ptr, "tag"), tag as u64)
+            }
+        };
+        let value = value.into_int_value();
+        let expected = value.get_type().const_int(expected, false);
+
+        let func = self.fn_value();
+        let matched_bb = self.context.append_basic_block(&func, "matched");
+        let matches = self
+            .builder
+            .build_int_compare(IntPredicate::EQ, value, expected, "matches");
+        self.builder
+            .build_conditional_branch(matches, &matched_bb, fail_bb);
+        self.builder.position_at_end(&matched_bb);
+
+        // test the fields of the variant
+        if let (Pattern::Variant { enm, name, fields }, LiteralType::Named(_)) = (pat, typ) {
+            let variant = self.build_variant_ptr(ptr, enm, name);
+            let field_types = self.enums[enm].get_variant(name).unwrap().clone();
+            for (i, (field, field_type)) in fields.iter().zip(field_types.iter()).enumerate() {
+                let field_ptr = self.build_field_ptr(variant, i as u32 + 1);
+                self.build_pattern_test(field_ptr, field_type, field, fail_bb);
+            }
+        }
+    }
+
+    /// Declares the variables bound by a pattern as copies of the matched parts of the value
+    fn build_bindings(&mut self, ptr: PointerValue, typ: &LiteralType, pat: &Pattern) {
+        match pat {
+            Pattern::Binding(name) => {
+                let block = self.fn_value().get_first_basic_block().unwrap();
+                let alloca = self.create_entry_block_alloca(name, &block, self.llvm_type(typ));
+                self.build_copy(alloca, ptr, typ);
+                self.insert_variable(name.to_string(), alloca, typ.clone());
+            }
+            Pattern::Variant { enm, name, fields } => {
+                let variant = self.build_variant_ptr(ptr, enm, name);
+                let field_types = self.enums[enm].get_variant(name).unwrap().clone();
+                for (i, (field, field_type)) in fields.iter().zip(field_types.iter()).enumerate() {
+                    let field_ptr = self.build_field_ptr(variant, i as u32 + 1);
+                    self.build_bindings(field_ptr, field_type, field);
+                }
+            }
+            _ => (),
+        }
     }
 
     fn compile_while(&mut self, cond: &Box<Node>, statement: &Box<Node>) {
@@ -392,6 +2205,9 @@ impl Compiler {
         // build do block
         self.builder.position_at_end(&do_bb);
         self.compile_block(statement, &do_bb);
+        if !self.block_terminated() {
+            self.build_step();
+        }
 
         // continue while loop
         self.builder.build_unconditional_branch(&cond_bb);
@@ -464,6 +2280,37 @@ impl Compiler {
         phi.add_incoming(&[(&some_num, &then_bb), (&some_num, &cont_bb)]);
     }
 
+    /// Compiles a logical operation which only evaluates its right operand if the left one
+    /// does not decide the result, the result is an i1 like the one of a comparison
+    fn compile_log_op(&mut self, left: &Box<Node>, op: Opcode, right: &Box<Node>) -> IntValue {
+        let func = self.fn_value();
+        let l_val = self.compile_expr(left);
+        let l_val = self.build_i1(l_val);
+        let left_bb = self.builder.get_insert_block().unwrap();
+        let right_bb = self.context.append_basic_block(&func, "right");
+        let cont_bb = self.context.append_basic_block(&func, "logcont");
+        match op {
+            Opcode::AND => self
+                .builder
+                .build_conditional_branch(l_val, &right_bb, &cont_bb),
+            _ => self
+                .builder
+                .build_conditional_branch(l_val, &cont_bb, &right_bb),
+        };
+
+        self.builder.position_at_end(&right_bb);
+        let r_val = self.compile_expr(right);
+        let r_val = self.build_i1(r_val);
+        // The right operand may have ended in another block, i.e. after a bounds check
+        let right_bb = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(&cont_bb);
+
+        self.builder.position_at_end(&cont_bb);
+        let phi = self.builder.build_phi(self.context.bool_type(), "logtmp");
+        phi.add_incoming(&[(&l_val, &left_bb), (&r_val, &right_bb)]);
+        phi.as_basic_value().into_int_value()
+    }
+
     fn compile_expr(&mut self, expr: &Box<Node>) -> IntValue {
         match &**expr {
             Node::Number(num) => self.context.i32_type().const_int(*num as u64, false),
@@ -473,31 +2320,73 @@ impl Compiler {
                 false => self.context.i32_type().const_int(0, false),
             },
 
-            Node::UnaryOp(op, expr) => {
-                let value = self.compile_expr(&expr);
-                match op {
-                    Opcode::Sub => self.builder.build_int_neg(value, "neg"),
-                    _ => unreachable!(),
+            Node::UnaryOp(op, expr) => {
+                let value = self.compile_expr(&expr);
+                match op {
+                    Opcode::Sub => self.build_neg(value),
+                    _ => unreachable!(),
+                }
+            }
+
+            Node::Var(id) => {
+				let (var, _) = self.get_variable(&id);
+                self.builder.build_load(var, &id).into_int_value()
+            }
+
+            Node::FuncCall { name, args, .. } => {
+                self.compile_call(&name, &args).unwrap().into_int_value()
+            }
+
+            Node::Index(_, _) | Node::Field(_, _) | Node::Deref(_) => {
+                let (elem_ptr, _) = self.compile_place(expr);
+                self.builder.build_load(elem_ptr, "elem").into_int_value()
+            }
+
+            Node::Match { .. } => {
+                let block = self.fn_value().get_first_basic_block().unwrap();
+                let i32_type = self.context.i32_type();
+                let res = self.create_entry_block_alloca("match", &block, i32_type.into());
+                self.compile_store(res, &LiteralType::I32, expr);
+                self.builder.build_load(res, "match").into_int_value()
+            }
+
+            Node::MethodCall {
+                recv, name, args, ..
+            } if arith_method(name).is_some() => {
+                let (op, overflow) = arith_method(name).unwrap();
+                let l_val = self.compile_expr(recv);
+                let r_val = self.compile_expr(&args[0]);
+                self.build_arith(l_val, op, r_val, overflow)
+            }
+
+            Node::MethodCall { recv, name, .. } => {
+                let (ptr, typ) = self.compile_place(&recv);
+                match (name.as_str(), typ) {
+                    ("len", LiteralType::Array(_, len)) => {
+                        self.context.i32_type().const_int(len as u64, false)
+                    }
+                    ("len", LiteralType::Vec(_)) => self.build_vec_len(ptr),
+                    ("pop", LiteralType::Vec(_)) => self.compile_vec_pop(ptr),
+                    _ => {
+                        let name = name.to_string();
+                        self.errors.push(CompileError::MethodNotSupported { name: name });
+                        self.context.i32_type().const_int(0, false)
+                    }
                 }
             }
 
-            Node::Var(id) => {
-				let var = self.get_variable(&id);
-                self.builder.build_load(var, &id).into_int_value()
+            Node::Expr(left, op, right) if *op == Opcode::AND || *op == Opcode::OR => {
+                self.compile_log_op(left, *op, right)
             }
 
-            Node::FuncCall { name, args, .. } => self.compile_call(&name, &args),
-
             Node::Expr(left, op, right) => {
                 let l_val = self.compile_expr(&left);
                 let r_val = self.compile_expr(&right);
                 match op {
-                    Opcode::Add => self.builder.build_int_add(l_val, r_val, "add"),
-                    Opcode::Sub => self.builder.build_int_sub(l_val, r_val, "sub"),
-                    Opcode::Mul => self.builder.build_int_mul(l_val, r_val, "mul"),
-                    Opcode::Div => self.builder.build_int_signed_div(l_val, r_val, "div"),
-                    Opcode::AND => self.builder.build_and(l_val, r_val, "and"),
-                    Opcode::OR => self.builder.build_or(l_val, r_val, "or"),
+                    Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::Div => {
+                        self.build_arith(l_val, *op, r_val, self.overflow)
+                    }
+                    Opcode::AND | Opcode::OR => unreachable!(),
                     Opcode::EQ => {
                         self.builder
                             .build_int_compare(IntPredicate::EQ, l_val, r_val, "eq")
@@ -533,7 +2422,12 @@ impl Compiler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parse::program_parser::parse;
+    use std::fs;
+
+    use crate::interpreter::interp;
+    use crate::parse::program_parser::{parse, parse_with_lines};
+    use crate::type_checker::infer_types;
+    use crate::value::Value;
 
     #[test]
     fn variable_add() {
@@ -726,6 +2620,28 @@ mod tests {
         assert_eq!(unsafe { res.call() }, 10);
     }
 
+    #[test]
+    fn test_sandboxed() {
+        let input = parse(
+            "fn main() -> i32 {
+				let num: i32 = 0;
+
+				while (num < 10) {
+					num = num + 1;
+				}
+				return num;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        // One step is taken when main is entered and one for each iteration of the loop
+        let mut compiler = Compiler::sandboxed(11);
+        let res = compiler.compile(&input).unwrap();
+        assert_eq!(unsafe { res.call() }, 10);
+        assert!(compiler.module.print_to_string().to_string().contains("@steps"));
+    }
+
     #[test]
     fn test_fib() {
     	let input = parse(
@@ -812,6 +2728,558 @@ mod tests {
     	let res = compiler.compile(&input).unwrap();
     	assert_eq!(unsafe{res.call()}, 1);
 	}
-	
+
+	#[test]
+    fn test_array() {
+    	let input = parse(
+    		"fn main() -> i32 {
+				let mut a: [i32; 5] = [0; 5];
+				let mut i: i32 = 0;
+
+				while (i < a.len()) {
+					a[i] = i * 2;
+					i = i + 1;
+				}
+
+				let b: [i32; 3] = [a[1], a[2], a[4]];
+				return b[0] + b[1] + b[2];
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&input).unwrap();
+    	assert_eq!(unsafe{res.call()}, 14);
+	}
+
+	#[test]
+    fn test_array_nested() {
+    	let input = parse(
+    		"fn main() -> i32 {
+				let mut grid: [[i32; 3]; 2] = [[1; 3]; 2];
+				let row: [i32; 3] = grid[1];
+				grid[1][2] = 7;
+				return grid[1][2] + row[2] + grid.len();
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&input).unwrap();
+    	assert_eq!(unsafe{res.call()}, 10);
+	}
+
+	#[test]
+    fn test_vec() {
+    	let input = parse(
+    		"fn main() -> i32 {
+				let mut v: Vec<i32> = Vec::new();
+				let mut i: i32 = 0;
+
+				while (i < 10) {
+					v.push(i);
+					i = i + 1;
+				}
+
+				let last: i32 = v.pop();
+				v[0] = 100;
+				return v[0] + last + v.len();
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&input).unwrap();
+    	assert_eq!(unsafe{res.call()}, 118);
+	}
+
+	#[test]
+    fn test_vec_copy() {
+    	let input = parse(
+    		"fn main() -> i32 {
+				let mut a: Vec<i32> = Vec::new();
+				a.push(1);
+				let mut b: Vec<i32> = a;
+				b.push(2);
+				a[0] = 10;
+				a = b;
+				return a.len() * 100 + b.len() * 10 + a[0];
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&input).unwrap();
+    	assert_eq!(unsafe{res.call()}, 221);
+	}
+
+	#[test]
+    fn test_struct() {
+    	let input = parse(
+    		"struct Point {
+				x: i32,
+				y: i32,
+			}
+
+			struct Line {
+				start: Point,
+				end: Point,
+			}
+
+			fn main() -> i32 {
+				let mut l: Line = Line {
+					start: Point { x: 0, y: 0 },
+					end: Point { x: 1, y: 1 },
+				};
+				let p: Point = l.end;
+				l.end.x = 5;
+				l.start = Point { x: l.end.x, y: 3 };
+				return l.start.x + l.start.y + l.end.x + p.x;
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&input).unwrap();
+    	assert_eq!(unsafe{res.call()}, 14);
+	}
+
+	#[test]
+    fn test_struct_param() {
+    	let input = parse(
+    		"struct Stack {
+				items: Vec<i32>,
+				pushes: i32,
+			}
+
+			fn push(mut s: Stack, x: i32) -> Stack {
+				s.items.push(x);
+				s.pushes = s.pushes + 1;
+				return s;
+			}
+
+			fn main() -> i32 {
+				let mut s: Stack = Stack { items: Vec::new(), pushes: 0 };
+				s = push(s, 1);
+				s = push(s, 2);
+				let t: Stack = push(s, 3);
+				return s.items.len() * 100 + t.items.len() * 10 + t.items[2] + push(t, 4).pushes;
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&input).unwrap();
+    	assert_eq!(unsafe{res.call()}, 237);
+	}
+
+	#[test]
+    fn test_enum_switch() {
+    	let input = parse(
+    		"enum Shape {
+				Square(i32),
+				Rect(i32, i32),
+				Empty,
+			}
+
+			fn area(s: Shape) -> i32 {
+				return match (s) {
+					Shape::Square(w) => w * w,
+					Shape::Rect(w, h) => w * h,
+					Shape::Empty => 0,
+				};
+			}
+
+			fn main() -> i32 {
+				let mut res: i32 = 0;
+				match (area(Shape::Rect(2, 5))) {
+					10 => {
+						res = 100;
+					}
+					_ => {
+						res = 200;
+					}
+				}
+				return res + area(Shape::Square(3)) + area(Shape::Empty);
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&input).unwrap();
+    	assert_eq!(unsafe{res.call()}, 109);
+	}
+
+	#[test]
+    fn test_enum_nested() {
+    	let input = parse(
+    		"enum Opt {
+				Some(Pair),
+				None,
+			}
+
+			enum Pair {
+				P(i32, bool),
+			}
+
+			fn value(o: Opt) -> i32 {
+				match (o) {
+					Opt::Some(Pair::P(x, true)) => {
+						return x;
+					}
+					Opt::Some(Pair::P(x, false)) => {
+						return 0 - x;
+					}
+					Opt::None => {
+						return 100;
+					}
+				}
+			}
+
+			fn main() -> i32 {
+				let a: Opt = Opt::Some(Pair::P(7, true));
+				let b: Opt = Opt::Some(Pair::P(3, 1 > 2));
+				return value(a) + value(b) + value(Opt::None);
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&input).unwrap();
+    	assert_eq!(unsafe{res.call()}, 104);
+	}
+
+	#[test]
+    fn test_tuple() {
+    	let input = parse(
+    		"fn split(v: Vec<i32>) -> (Vec<i32>, i32) {
+				let mut rest: Vec<i32> = v;
+				let last: i32 = rest.pop();
+				return (rest, last);
+			}
+
+			fn main() -> i32 {
+				let mut v: Vec<i32> = Vec::new();
+				v.push(4);
+				v.push(5);
+				let (rest, last): (Vec<i32>, i32) = split(v);
+				let mut t: (i32, bool) = (rest[0], 1 < 2);
+				t.0 = t.0 * 10;
+				if (t.1) {
+					return t.0 + last + rest.len() * 100;
+				}
+				return 0;
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&input).unwrap();
+    	assert_eq!(unsafe{res.call()}, 145);
+	}
+
+    #[test]
+    fn test_ref() {
+    	let input = parse(
+    		"struct Counter {
+				hits: [i32; 2],
+			}
+
+			fn swap(a: &mut i32, b: &mut i32) {
+				let tmp: i32 = *a;
+				*a = *b;
+				*b = tmp;
+			}
+
+			fn hit(c: &mut Counter, i: i32) {
+				(*c).hits[i] = (*c).hits[i] + 1;
+			}
+
+			fn main() -> i32 {
+				let mut c: Counter = Counter { hits: [0, 5] };
+				hit(&mut c, 0);
+				hit(&mut c, 1);
+				let mut x: i32 = 30;
+				swap(&mut c.hits[0], &mut x);
+				let r: &Counter = &c;
+				return (*r).hits[0] * 10 + (*r).hits[1] + x;
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&input).unwrap();
+    	assert_eq!(unsafe{res.call()}, 307);
+	}
+
+    #[test]
+    fn test_infer() {
+    	let input = parse(
+    		"fn divmod(a: i32, b: i32) -> (i32, i32) {
+				return (a / b, a - a / b * b);
+			}
+
+			fn main() -> i32 {
+				let mut sum = 0;
+				let (q, r) = divmod(17, 5);
+				let arr = [q, r, 7];
+				let mut i = 0;
+				while (i < 3) {
+					let x = &arr[i];
+					sum = sum + *x;
+					i = i + 1;
+				}
+				return sum;
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&infer_types(input).unwrap()).unwrap();
+    	assert_eq!(unsafe{res.call()}, 12);
+	}
+
+    #[test]
+    fn test_generic() {
+    	let input = parse(
+    		"struct Point {
+				x: i32,
+				y: i32,
+			}
+
+			fn max<T>(a: T, b: T) -> T {
+				if (a > b) {
+					return a;
+				}
+				return b;
+			}
+
+			fn swap<A, B>(p: (A, B)) -> (B, A) {
+				let (a, b): (A, B) = p;
+				return (b, a);
+			}
+
+			fn largest<T>(v: &Vec<T>) -> T {
+				let mut best = (*v)[0];
+				let mut i = 1;
+				while (i < (*v).len()) {
+					best = max(best, (*v)[i]);
+					i = i + 1;
+				}
+				return best;
+			}
+
+			fn main() -> i32 {
+				let mut v: Vec<i32> = Vec::new();
+				v.push(3);
+				v.push(9);
+				let (p, flag) = swap((true, Point { x: 1, y: 2 }));
+				if (flag) {
+					return largest(&v) * 100 + max(p.x, p.y) * 10 + swap((1, 5)).0;
+				}
+				return 0;
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&infer_types(input).unwrap()).unwrap();
+    	assert_eq!(unsafe{res.call()}, 925);
+	}
+
+    #[test]
+    fn test_closure() {
+    	let input = parse(
+    		"fn double(x: i32) -> i32 {
+				return x * 2;
+			}
+
+			fn adder(n: i32) -> fn(i32) -> i32 {
+				return |x: i32| x + n;
+			}
+
+			fn fold(a: [i32; 3], init: i32, f: fn(i32, i32) -> i32) -> i32 {
+				let mut acc = init;
+				let mut i = 0;
+				while (i < 3) {
+					acc = f(acc, a[i]);
+					i = i + 1;
+				}
+				return acc;
+			}
+
+			fn main() -> i32 {
+				let n = 10;
+				let f = double;
+				let add = adder(f(2));
+				let sum = fold([1, 2, 3], 0, |acc: i32, x: i32| acc + x * n);
+				return add(sum);
+			}
+    		".to_string()
+    	).unwrap();
+
+    	let mut compiler = Compiler::new();
+    	let res = compiler.compile(&infer_types(input).unwrap()).unwrap();
+    	assert_eq!(unsafe{res.call()}, 64);
+	}
+
+	#[test]
+	fn test_stable_ir() {
+		let input = infer_types(parse(
+			"struct P {
+				x: i32,
+				v: Vec<i32>,
+			}
+
+			fn c(p: P) -> i32 {
+				let a = [1, 2];
+				let v: Vec<i32> = Vec::new();
+				return p.x + a[0];
+			}
+
+			fn b(x: i32) -> i32 {
+				return x * 2;
+			}
+
+			fn a(x: i32) -> i32 {
+				return b(x) + 1;
+			}
+
+			fn main() -> i32 {
+				let v: Vec<i32> = Vec::new();
+				return a(1) + c(P { x: 1, v: v });
+			}
+			".to_string()
+		).unwrap()).unwrap();
+
+		// The functions are compiled in source order and the IR is the same every time
+		let mut ir = vec![];
+		for _ in 0..5 {
+			let mut compiler = Compiler::new();
+			compiler.compile_program(&input);
+			ir.push(compiler.module.print_to_string().to_string());
+		}
+		assert!(ir.iter().all(|module| *module == ir[0]));
+
+		let pos = |name: &str| ir[0].find(&format!("define i32 @{}(", name)).unwrap();
+		assert!(pos("c") < pos("b") && pos("b") < pos("a") && pos("a") < pos("main"));
+	}
+
+	// The programs only divide by zero or index out of bounds if the right operand of a
+	// logical operation is evaluated when the left one decides the result
+	#[test]
+	fn test_short_circuit() {
+		for file in fs::read_dir("tests/short_circuit").unwrap() {
+			let input = fs::read_to_string(file.unwrap().path()).unwrap();
+			let program = parse(input).unwrap();
+			let mut compiler = Compiler::new();
+			let res = compiler.compile(&program).unwrap();
+			let expected = interp(program).unwrap();
+			assert_eq!(Some(Value::Number(unsafe { res.call() })), expected);
+		}
+	}
+
+	// A program which overflows is aborted by default, so only the IR is checked for it
+	#[test]
+	fn test_overflow() {
+		let input = fs::read_to_string("tests/arithmetic/factorial.txt").unwrap();
+		let (program, lines) = parse_with_lines(input).unwrap();
+
+		let mut compiler = Compiler::new().with_lines(lines);
+		compiler.compile_program(&program);
+		let ir = compiler.module.print_to_string().to_string();
+		assert!(ir.contains("@llvm.smul.with.overflow.i32"));
+		assert!(ir.contains("Arithmetic overflow in '%d * %d'\0A    on line 5 in 'fact'"));
+
+		let mut compiler = Compiler::new().with_overflow(Overflow::Wrap);
+		let res = compiler.compile(&program).unwrap();
+		assert_eq!(unsafe { res.call() }, 1932053504);
+
+		let mut compiler = Compiler::new().with_overflow(Overflow::Saturate);
+		let res = compiler.compile(&program).unwrap();
+		assert_eq!(unsafe { res.call() }, i32::MAX);
+
+		// The methods have their own overflow behaviour
+		let input = fs::read_to_string("tests/arithmetic/wrapping.txt").unwrap();
+		let mut compiler = Compiler::new();
+		let res = compiler.compile(&parse(input).unwrap()).unwrap();
+		assert_eq!(unsafe { res.call() }, i32::MAX);
+	}
+
+	#[test]
+	fn test_not_supported() {
+		let program = parse(
+			"fn main() -> i32 {
+				let mut v: Vec<Vec<i32>> = Vec::new();
+				let mut w: Vec<i32> = Vec::new();
+				w.push(1);
+				v.push(w);
+				return v.len();
+			}"
+			.to_string(),
+		)
+		.unwrap();
+		let typ = LiteralType::Vec(Box::new(LiteralType::Vec(Box::new(LiteralType::I32))));
+		let mut compiler = Compiler::new();
+		assert_eq!(
+			compiler.compile(&program).err(),
+			Some(vec![CompileError::VecOwnsMemory { typ: typ }])
+		);
+
+		let program = parse(
+			"enum Shape {
+				Point,
+				Path(Vec<i32>),
+			}
+
+			fn main() -> i32 {
+				let s: Shape = Shape::Point;
+				return 0;
+			}"
+			.to_string(),
+		)
+		.unwrap();
+		let mut compiler = Compiler::new();
+		assert_eq!(
+			compiler.compile(&program).err(),
+			Some(vec![CompileError::EnumOwnsMemory {
+				name: String::from("Shape")
+			}])
+		);
+
+		let program = parse(
+			"fn main() -> i32 {
+				let mut v: Vec<i32> = Vec::new();
+				v.push(1);
+				let f: fn() -> i32 = || v.len();
+				return f();
+			}"
+			.to_string(),
+		)
+		.unwrap();
+		let mut compiler = Compiler::new();
+		assert_eq!(
+			compiler.compile(&program).err(),
+			Some(vec![CompileError::CaptureOwnsMemory {
+				var: String::from("v"),
+				typ: LiteralType::Vec(Box::new(LiteralType::I32)),
+			}])
+		);
+
+		let program = parse(
+			"fn main() -> i32 {
+				return (1, 2).0;
+			}"
+			.to_string(),
+		)
+		.unwrap();
+		let mut compiler = Compiler::new();
+		assert_eq!(
+			compiler.compile(&program).err(),
+			Some(vec![CompileError::PlaceNotSupported {
+				node: String::from("Tuple")
+			}])
+		);
+	}
 
 }
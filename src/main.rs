@@ -1,25 +1,146 @@
 #[macro_use]
 extern crate lalrpop_util;
 
+mod arithmetic;
 mod ast;
+mod borrow_checker;
+mod bytecode;
 mod context;
+mod control_flow;
+mod debugger;
+mod enumeration;
 mod function;
 mod interpreter;
+mod limits;
+mod lint;
 mod llvm;
 mod operators;
 mod parse;
+mod pattern;
 mod program;
+mod reference;
+mod runtime_error;
 mod scope;
+mod structure;
+mod tracer;
 mod type_checker;
 mod type_errors;
 mod types;
 mod value;
 mod variable;
 
-use std::path::Path;
+use std::{
+    env,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use limits::Limits;
+use lint::{Level, LintLevels};
+use program::Backend;
+use tracer::TraceOptions;
 
 #[allow(unused)]
 fn main() {
-    program::run(Path::new("input.rs"), false);	// interp
-	// program::run(Path::new("input.rs"), true); // compile
+    let args: Vec<String> = env::args().collect();
+    let print_types = args.iter().any(|arg| arg == "--print-types");
+
+    // The levels of lints are set with i.e. "-D warnings" or "-A dead_code"
+    let mut levels = LintLevels::new();
+    for flag in args.windows(2) {
+        let level = match flag[0].as_str() {
+            "-A" => Level::Allow,
+            "-W" => Level::Warn,
+            "-D" => Level::Deny,
+            _ => continue,
+        };
+        if !levels.set(&flag[1], level) {
+            eprintln!("Unknown lint '{}'", flag[1]);
+        }
+    }
+
+    // The limits of the program are set with i.e. "--max-depth 5000", "--fuel 100000",
+    // "--timeout 500" in milliseconds and "--max-memory 65536" in bytes. For a program compiled
+    // to bytecode the memory limit bounds the size of every vector and repeated array, and
+    // only the fuel is limited for one compiled with llvm.
+    // A maximum depth above the one the interpreter supports is rejected
+    let mut limits = Limits::new();
+    match flag_value(&args, "--max-depth") {
+        Some(max_depth) if max_depth > interpreter::MAX_DEPTH => eprintln!(
+            "Invalid value '{}' for '--max-depth', the maximum depth is at most {}",
+            max_depth,
+            interpreter::MAX_DEPTH
+        ),
+        Some(max_depth) => limits.max_depth = max_depth,
+        None => {}
+    }
+    limits.fuel = flag_value(&args, "--fuel");
+    limits.timeout = flag_value(&args, "--timeout").map(Duration::from_millis);
+    limits.max_memory = flag_value(&args, "--max-memory");
+    // Arithmetic which overflows stops the program with an error, unless it is set to wrap
+    // or saturate with i.e. "--overflow wrap"
+    if let Some(overflow) = flag_value(&args, "--overflow") {
+        limits.overflow = overflow;
+    }
+
+    // A program that was compiled to a bytecode file with "--emit-bytecode program.d7bc" is
+    // run with "--run-bytecode program.d7bc", without parsing it again
+    if let Some(path) = flag_value::<PathBuf>(&args, "--run-bytecode") {
+        if let Err(e) = program::run_bytecode(&path, &limits) {
+            eprintln!("Could not read '{}': {}", path.display(), e);
+        }
+        return;
+    }
+
+    // The program is interpreted unless it is compiled to bytecode with "--vm", which is
+    // disassembled first with "--disassemble", or with llvm with "--llvm". With "--debug" it
+    // is interpreted by a debugger which is driven from a command prompt, breakpoints can be
+    // added with i.e. "--break 12" or "--break fib".
+    // "--trace" logs the statements it executes and the updates of its variables, "--profile"
+    // reports its calls and loops and "--trace-json trace.json" writes a trace of its calls
+    // which can be loaded into a trace viewer
+    let trace = TraceOptions {
+        log: args.iter().any(|arg| arg == "--trace"),
+        profile: args.iter().any(|arg| arg == "--profile"),
+        json: flag_value(&args, "--trace-json"),
+    };
+    let backend = if let Some(path) = flag_value(&args, "--emit-bytecode") {
+        Backend::EmitBytecode(path)
+    } else if args.iter().any(|arg| arg == "--vm") {
+        Backend::Bytecode(args.iter().any(|arg| arg == "--disassemble"))
+    } else if args.iter().any(|arg| arg == "--llvm") {
+        Backend::Llvm
+    } else if args.iter().any(|arg| arg == "--debug") {
+        let breakpoints = args
+            .windows(2)
+            .filter(|pair| pair[0] == "--break")
+            .filter_map(|pair| match pair[1].parse() {
+                Ok(breakpoint) => Some(breakpoint),
+                Err(_) => {
+                    eprintln!("Invalid value '{}' for '--break'", pair[1]);
+                    None
+                }
+            })
+            .collect();
+        Backend::Debug(breakpoints)
+    } else if trace.log || trace.profile || trace.json.is_some() {
+        Backend::Trace(trace)
+    } else {
+        Backend::Interpreter
+    };
+
+    program::run(Path::new("input.rs"), backend, print_types, &levels, &limits);
+}
+
+/// Gets the value of a flag which is followed by it, an invalid value is reported and ignored
+fn flag_value<T: FromStr>(args: &[String], flag: &str) -> Option<T> {
+    let value = &args.windows(2).find(|pair| pair[0] == flag)?[1];
+    match value.parse() {
+        Ok(val) => Some(val),
+        Err(_) => {
+            eprintln!("Invalid value '{}' for '{}'", value, flag);
+            None
+        }
+    }
 }
@@ -3,7 +3,7 @@ use crate::ast::Node;
 
 #[allow(dead_code)]
 pub fn parse(s: &str) -> Result<Box<Node>, ParseError> {
-    let result = crate::parse::grammar::ExprParser::new().parse(s);
+    let result = crate::parse::grammar::ExprParser::new().parse(&mut vec![], s);
     return match result {
         Ok(s) => Ok(s),
         Err(e) => Err(ParseError {
@@ -95,6 +95,72 @@ mod tests {
         assert!(parse("a < b || a + 6").is_ok());
     }
 
+    #[test]
+    fn test_expr_array() {
+        assert!(parse("[1, 2, 3]").is_ok());
+        assert!(parse("[a + 1, b && c]").is_ok());
+        assert!(parse("[0; 10]").is_ok());
+        assert!(parse("[[0; 2]; 2]").is_ok());
+        assert!(parse("[]").is_err());
+        assert!(parse("[0; n]").is_err());
+    }
+
+    #[test]
+    fn test_expr_index() {
+        assert!(parse("a[0] + a[i + 1]").is_ok());
+        assert!(parse("grid[i][j]").is_ok());
+        assert!(parse("[1, 2, 3][1]").is_ok());
+        assert!(parse("a.len() - 1").is_ok());
+        assert!(parse("a[]").is_err());
+    }
+
+    #[test]
+    fn test_expr_vec() {
+        assert!(parse("Vec::new()").is_ok());
+        assert!(parse("v.pop() + v.len()").is_ok());
+        assert!(parse("Vec::new(1)").is_err());
+    }
+
+    #[test]
+    fn test_expr_struct() {
+        assert!(parse("Point { x: 1, y: 2 }").is_ok());
+        assert!(parse("Line { start: Point { x: 0, y: 0 }, end: p }").is_ok());
+        assert!(parse("p.x + l.start.y * ps[0].x").is_ok());
+        assert!(parse("Point { x: 1 y: 2 }").is_err());
+    }
+
+    #[test]
+    fn test_expr_enum() {
+        assert!(parse("Shape::Empty").is_ok());
+        assert!(parse("Shape::Rect(1, w + 2)").is_ok());
+        assert!(parse("Option::Some(Point { x: 1, y: 2 })").is_ok());
+        assert!(parse("Shape::").is_err());
+    }
+
+    #[test]
+    fn test_expr_tuple() {
+        assert!(parse("(1, true)").is_ok());
+        assert!(parse("(a + 1, (b, c),)").is_ok());
+        assert!(parse("t.0 + t.1.0 + f().1").is_ok());
+        assert!(parse("(1, 2").is_err());
+    }
+
+    #[test]
+    fn test_expr_ref() {
+        assert!(parse("&x").is_ok());
+        assert!(parse("&mut a[i].x").is_ok());
+        assert!(parse("*r + 2 * *s").is_ok());
+        assert!(parse("&(1 + 2)").is_err());
+    }
+
+    #[test]
+    fn test_expr_match() {
+        assert!(parse("match (x) { 0 => 1, -1 => 2, n => n * 2 }").is_ok());
+        assert!(parse("match (b) { true => 1, false => 0, }").is_ok());
+        assert!(parse("match (o) { Opt::Some(Pair::P(a, _)) => a, Opt::None => 0 } + 1").is_ok());
+        assert!(parse("match (x) { 1 + 1 => 2 }").is_err());
+    }
+
     #[test]
     fn test_logexpr_number_paran() {
         assert!(parse("a || (1)").is_ok());
@@ -3,7 +3,7 @@ use crate::ast::Node;
 
 #[allow(dead_code)]
 pub fn parse(s: &str) -> Result<Box<Node>, ParseError> {
-    let result = crate::parse::grammar::KeywordParser::new().parse(s);
+    let result = crate::parse::grammar::KeywordParser::new().parse(&mut vec![], s);
     return match result {
         Ok(s) => Ok(s),
         Err(e) => Err(ParseError {
@@ -1,10 +1,39 @@
+use std::collections::HashMap;
+
 use super::ParseError;
-use crate::ast::Node;
+use crate::{ast::Node, control_flow::block_len};
+
+/// The lines of the statements of each function, the statements of a function are numbered
+/// in the order they appear in. The lines start at 1
+pub type FuncLines = HashMap<String, Vec<usize>>;
 
+#[allow(dead_code)]
 pub fn parse(s: String) -> Result<Vec<Box<Node>>, ParseError> {
-    let result = crate::parse::grammar::ProgramParser::new().parse(&s);
+    parse_with_lines(s).map(|(program, _)| program)
+}
+
+/// Parses a program along with the lines of the statements of its functions
+pub fn parse_with_lines(s: String) -> Result<(Vec<Box<Node>>, FuncLines), ParseError> {
+    let mut stmnts = vec![];
+    let result = crate::parse::grammar::ProgramParser::new().parse(&mut stmnts, &s);
     return match result {
-        Ok(s) => Ok(s),
+        Ok(program) => {
+            // A statement is pushed when it has been parsed, which is after the statements
+            // nested in it
+            stmnts.sort();
+            let mut lines = stmnts
+                .iter()
+                .map(|start| s[..*start].matches('\n').count() + 1);
+
+            let mut func_lines = HashMap::new();
+            for item in program.iter() {
+                if let Node::Func { name, body, .. } = &**item {
+                    let body_lines = lines.by_ref().take(block_len(body)).collect();
+                    func_lines.insert(name.to_string(), body_lines);
+                }
+            }
+            Ok((program, func_lines))
+        }
         Err(e) => Err(ParseError {
             message: e.to_string(),
         }),
@@ -3,7 +3,7 @@ use crate::ast::Node;
 
 #[allow(dead_code)]
 pub fn parse(s: &str) -> Result<Box<Node>, ParseError> {
-    let result = crate::parse::grammar::StatementParser::new().parse(s);
+    let result = crate::parse::grammar::StatementParser::new().parse(&mut vec![], s);
     return match result {
         Ok(s) => Ok(s),
         Err(e) => Err(ParseError {
@@ -15,6 +15,7 @@ pub fn parse(s: &str) -> Result<Box<Node>, ParseError> {
 #[cfg(test)]
 mod tests {
     use super::parse;
+    use crate::ast::Node;
 
     #[test]
     fn test_state_let() {
@@ -196,6 +197,22 @@ mod tests {
         .is_ok());
     }
 
+    #[test]
+    fn test_state_after_loop_mod() {
+        // The statements after a break or continue in a block are dropped
+        let stmnt = parse("while (c) { break; x = 1; } y = 2;").unwrap();
+        match *stmnt {
+            Node::While {
+                statement, next, ..
+            } => {
+                assert_eq!(*statement, Node::Break);
+                assert!(next.is_some());
+            }
+            _ => panic!("Expected a while loop"),
+        }
+        assert!(parse("while (c) { continue; print(1); print(2); }").is_ok());
+    }
+
     #[test]
     fn test_state_return() {
         assert!(parse(
@@ -246,4 +263,70 @@ mod tests {
     fn test_state_def_var_func() {
         assert!(parse("let res: i32 = sum();").is_ok());
     }
+
+    #[test]
+    fn test_state_array() {
+        assert!(parse("let a: [i32; 3] = [1, 2, 3];").is_ok());
+        assert!(parse("let mut a: [[bool; 2]; 4] = [[true; 2]; 4];").is_ok());
+        assert!(parse("a[0] = 5; a[i][j] = a[j][i];").is_ok());
+        assert!(parse("a.len() = 5;").is_err());
+    }
+
+    #[test]
+    fn test_state_vec() {
+        assert!(parse("let mut v: Vec<i32> = Vec::new();").is_ok());
+        assert!(parse("let v: Vec<[bool; 2]> = Vec::new();").is_ok());
+        assert!(parse("v.push(1); v[0] = v.pop();").is_ok());
+        assert!(parse("grid[i].push(x);").is_ok());
+    }
+
+    #[test]
+    fn test_state_struct() {
+        assert!(parse("let p: Point = Point { x: 1, y: 2 };").is_ok());
+        assert!(parse("p.x = 1; l.start.y = p.x;").is_ok());
+        assert!(parse("s.items.push(1);").is_ok());
+        assert!(parse("p.x() = 1;").is_err());
+    }
+
+    #[test]
+    fn test_state_match() {
+        assert!(parse("match (code) { 1 => { x = 1; } _ => { return 0; } }").is_ok());
+        assert!(parse("match (s) { Shape::Circle(r) => { print(r); }, Shape::Empty => {} }").is_err());
+        assert!(parse("match (s) { Shape::Circle(r) => { print(r); }, _ => { x = 0; }, }").is_ok());
+        assert!(parse("let area: i32 = match (s) { Shape::Square(w) => w * w, _ => 0 };").is_ok());
+        assert!(parse("match (s) { Shape::Empty => x = 0; }").is_err());
+    }
+
+    #[test]
+    fn test_state_tuple() {
+        assert!(parse("let t: (i32, bool) = (1, true);").is_ok());
+        assert!(parse("let (q, mut r): (i32, i32) = divmod(17, 5);").is_ok());
+        assert!(parse("t.0 = 1; t.1.0 = t.0;").is_ok());
+        assert!(parse("let (a, b + 1): (i32, i32) = t;").is_err());
+    }
+
+    #[test]
+    fn test_state_ref() {
+        assert!(parse("let r: &mut [i32; 2] = &mut a;").is_ok());
+        assert!(parse("*r = *r + 1; (*p).x = 2; (*v).push(3);").is_ok());
+        assert!(parse("swap(&mut a[0], &mut a[1]);").is_ok());
+        assert!(parse("&r = 1;").is_err());
+    }
+
+    #[test]
+    fn test_state_infer() {
+        assert!(parse("let x = 5;").is_ok());
+        assert!(parse("let mut v = [1, 2]; let (a, mut b) = (v[0], true);").is_ok());
+        assert!(parse("let x: = 5;").is_err());
+        assert!(parse("let x;").is_err());
+    }
+
+    #[test]
+    fn test_state_closure() {
+        assert!(parse("let f: fn(i32) -> i32 = |x: i32| x + n;").is_ok());
+        assert!(parse("let g: fn() = || print_all(v); apply(double, 2);").is_ok());
+        assert!(parse("let s = fold(a, 0, |acc: i32, x: i32| acc + x); return || n;").is_ok());
+        assert!(parse("let f = |x| x + 1;").is_err());
+        assert!(parse("let n = 1 + |x: i32| x;").is_err());
+    }
 }
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use crate::{enumeration::Enum, types::LiteralType};
+
+type Enums = HashMap<String, Enum>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    Number(i32),
+    Bool(bool),
+    Wildcard,
+    Binding(String),
+    Variant {
+        enm: String,
+        name: String,
+        fields: Vec<Pattern>,
+    },
+}
+
+impl Pattern {
+    pub fn to_string(&self) -> String {
+        match self {
+            Pattern::Number(num) => num.to_string(),
+            Pattern::Bool(b) => b.to_string(),
+            Pattern::Wildcard => "_".to_string(),
+            Pattern::Binding(name) => name.to_string(),
+            Pattern::Variant { enm, name, fields } => match fields.len() {
+                0 => format!("{}::{}", enm, name),
+                _ => {
+                    let fields: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+                    format!("{}::{}({})", enm, name, fields.join(", "))
+                }
+            },
+        }
+    }
+
+    /// Gets the variables bound by the pattern
+    pub fn bindings(&self) -> Vec<String> {
+        match self {
+            Pattern::Binding(name) => vec![name.to_string()],
+            Pattern::Variant { fields, .. } => fields.iter().flat_map(Pattern::bindings).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Checks if the pattern matches every value
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Pattern::Wildcard | Pattern::Binding(_))
+    }
+}
+
+/// Checks if the patterns of the arms of a match cover every value of type typ, otherwise a
+/// pattern which is not covered is returned
+///
+/// The patterns are expected to have been type checked against typ
+pub fn missing_pattern(pats: &Vec<Pattern>, typ: &LiteralType, enums: &Enums) -> Option<Pattern> {
+    let matrix: Vec<Vec<Pattern>> = pats.iter().map(|pat| vec![pat.clone()]).collect();
+    missing(&matrix, std::slice::from_ref(typ), enums).and_then(|mut witness| witness.pop())
+}
+
+/// Checks if a pattern matches any value which is not matched by the previous patterns
+pub fn is_reachable(prev: &[Pattern], pat: &Pattern, typ: &LiteralType, enums: &Enums) -> bool {
+    let matrix: Vec<Vec<Pattern>> = prev.iter().map(|pat| vec![pat.clone()]).collect();
+    is_useful(&matrix, &vec![pat.clone()], std::slice::from_ref(typ), enums)
+}
+
+// The checks are based on the usefulness algorithm described in "Warnings for pattern
+// matching" by Luc Maranget, where each row of the matrix are the patterns of an arm and
+// each column matches a value of the corresponding type in types
+
+/// What a pattern tests a value to be constructed with
+#[derive(Clone, Debug, PartialEq)]
+enum Ctor {
+    Number(i32),
+    Bool(bool),
+    Variant(String),
+}
+
+fn head_ctor(pat: &Pattern) -> Option<Ctor> {
+    match pat {
+        Pattern::Number(num) => Some(Ctor::Number(*num)),
+        Pattern::Bool(b) => Some(Ctor::Bool(*b)),
+        Pattern::Variant { name, .. } => Some(Ctor::Variant(name.to_string())),
+        Pattern::Wildcard | Pattern::Binding(_) => None,
+    }
+}
+
+/// Gets all the constructors of a type, if the type has a finite number of them
+fn all_ctors(typ: &LiteralType, enums: &Enums) -> Option<Vec<Ctor>> {
+    match typ {
+        LiteralType::Bool => Some(vec![Ctor::Bool(true), Ctor::Bool(false)]),
+        LiteralType::Named(name) => match enums.get(name) {
+            Some(enm) => Some(
+                enm.variants
+                    .iter()
+                    .map(|(variant, _)| Ctor::Variant(variant.to_string()))
+                    .collect(),
+            ),
+            None => None,
+        },
+        _ => None,
+    }
+}
+
+/// Gets the types of the values that a constructor of type typ contains
+fn ctor_fields(ctor: &Ctor, typ: &LiteralType, enums: &Enums) -> Vec<LiteralType> {
+    match (ctor, typ) {
+        (Ctor::Variant(variant), LiteralType::Named(name)) => enums[name]
+            .get_variant(variant)
+            .unwrap()
+            .clone(),
+        _ => vec![],
+    }
+}
+
+fn ctor_pattern(ctor: Ctor, typ: &LiteralType, fields: Vec<Pattern>) -> Pattern {
+    match (ctor, typ) {
+        (Ctor::Number(num), _) => Pattern::Number(num),
+        (Ctor::Bool(b), _) => Pattern::Bool(b),
+        (Ctor::Variant(variant), LiteralType::Named(name)) => Pattern::Variant {
+            enm: name.to_string(),
+            name: variant,
+            fields: fields,
+        },
+        _ => unreachable!(),
+    }
+}
+
+/// Gets the constructors of the first column of the matrix
+fn used_ctors(matrix: &Vec<Vec<Pattern>>) -> Vec<Ctor> {
+    matrix.iter().filter_map(|row| head_ctor(&row[0])).collect()
+}
+
+/// Gets all constructors of the type of the first column, if all of them are used in it
+fn complete_ctors(
+    matrix: &Vec<Vec<Pattern>>,
+    typ: &LiteralType,
+    enums: &Enums,
+) -> Option<Vec<Ctor>> {
+    let ctors = all_ctors(typ, enums)?;
+    let used = used_ctors(matrix);
+    match ctors.iter().all(|ctor| used.contains(ctor)) {
+        true => Some(ctors),
+        false => None,
+    }
+}
+
+/// Removes the first pattern of a row which matches values constructed with ctor and
+/// replaces it with the patterns of the values the constructor contains
+fn specialize(row: &Vec<Pattern>, ctor: &Ctor, arity: usize) -> Option<Vec<Pattern>> {
+    let mut specialized = match &row[0] {
+        Pattern::Wildcard | Pattern::Binding(_) => vec![Pattern::Wildcard; arity],
+        Pattern::Variant { fields, .. } if head_ctor(&row[0]).as_ref() == Some(ctor) => {
+            fields.clone()
+        }
+        pat if head_ctor(pat).as_ref() == Some(ctor) => vec![],
+        _ => return None,
+    };
+    specialized.extend_from_slice(&row[1..]);
+    Some(specialized)
+}
+
+fn specialize_matrix(matrix: &Vec<Vec<Pattern>>, ctor: &Ctor, arity: usize) -> Vec<Vec<Pattern>> {
+    matrix
+        .iter()
+        .filter_map(|row| specialize(row, ctor, arity))
+        .collect()
+}
+
+/// Keeps the rows which match any value in the first column, without the first column
+fn default_matrix(matrix: &Vec<Vec<Pattern>>) -> Vec<Vec<Pattern>> {
+    matrix
+        .iter()
+        .filter(|row| row[0].is_irrefutable())
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+/// Checks if the row matches any values which are not matched by the rows of the matrix
+fn is_useful(
+    matrix: &Vec<Vec<Pattern>>,
+    row: &Vec<Pattern>,
+    types: &[LiteralType],
+    enums: &Enums,
+) -> bool {
+    if row.is_empty() {
+        return matrix.is_empty();
+    }
+
+    let useful_for = |ctor: &Ctor| {
+        let mut sub_types = ctor_fields(ctor, &types[0], enums);
+        let arity = sub_types.len();
+        sub_types.extend_from_slice(&types[1..]);
+
+        is_useful(
+            &specialize_matrix(matrix, ctor, arity),
+            &specialize(row, ctor, arity).unwrap(),
+            &sub_types,
+            enums,
+        )
+    };
+
+    match head_ctor(&row[0]) {
+        Some(ctor) => useful_for(&ctor),
+        None => match complete_ctors(matrix, &types[0], enums) {
+            Some(ctors) => ctors.iter().any(useful_for),
+            None => is_useful(
+                &default_matrix(matrix),
+                &row[1..].to_vec(),
+                &types[1..],
+                enums,
+            ),
+        },
+    }
+}
+
+/// Finds a row of patterns which matches values that are not matched by the rows of the matrix
+fn missing(
+    matrix: &Vec<Vec<Pattern>>,
+    types: &[LiteralType],
+    enums: &Enums,
+) -> Option<Vec<Pattern>> {
+    if types.is_empty() {
+        return match matrix.is_empty() {
+            true => Some(vec![]),
+            false => None,
+        };
+    }
+
+    match complete_ctors(matrix, &types[0], enums) {
+        // A value is missing if it is missing for any of the constructors
+        Some(ctors) => {
+            for ctor in ctors.into_iter() {
+                let mut sub_types = ctor_fields(&ctor, &types[0], enums);
+                let arity = sub_types.len();
+                sub_types.extend_from_slice(&types[1..]);
+
+                let specialized = specialize_matrix(matrix, &ctor, arity);
+                if let Some(mut fields) = missing(&specialized, &sub_types, enums) {
+                    let rest = fields.split_off(arity);
+                    let mut witness = vec![ctor_pattern(ctor, &types[0], fields)];
+                    witness.extend(rest);
+                    return Some(witness);
+                }
+            }
+            None
+        }
+        // Otherwise a constructor which is not used is missing
+        None => {
+            let mut witness = missing(&default_matrix(matrix), &types[1..], enums)?;
+            let used = used_ctors(matrix);
+
+            let head = match all_ctors(&types[0], enums) {
+                Some(ctors) if !used.is_empty() => {
+                    let ctor = ctors.into_iter().find(|ctor| !used.contains(ctor)).unwrap();
+                    let arity = ctor_fields(&ctor, &types[0], enums).len();
+                    ctor_pattern(ctor, &types[0], vec![Pattern::Wildcard; arity])
+                }
+                _ => Pattern::Wildcard,
+            };
+            witness.insert(0, head);
+            Some(witness)
+        }
+    }
+}
@@ -1,18 +1,64 @@
 use std::{
     error::Error,
-    fs::File,
+    fs::{self, File},
     io::prelude::*,
-    io::{self, Write},
-    path::Path,
+    io::{self, BufReader, Write},
+    path::{Path, PathBuf},
 };
 
 use crate::{
-    interpreter::interp, llvm::Compiler, parse::program_parser::parse, type_checker::type_check,
+    ast::Node,
+    borrow_checker::borrow_check,
+    bytecode::{
+        compiler::compile,
+        disassembler::disassemble,
+        file::{deserialize, serialize},
+        vm,
+    },
+    debugger::{Breakpoint, Debugger, Prompt},
+    interpreter::{debug, interp_with_limits, trace},
+    limits::Limits,
+    lint::{lint, Level, LintLevels},
+    llvm::Compiler,
+    parse::program_parser::parse_with_lines,
+    runtime_error::RuntimeError,
+    tracer::{TraceOptions, Tracer},
+    type_checker::infer_types,
+    value::Value,
 };
 
-/// Runs a program defined in the path, if compile is false the program is interpreted
-/// otherwise it will be compiled with llvm
-pub fn run(path: &Path, compile: bool) -> io::Result<()> {
+/// The ways a program can be run
+#[derive(Clone, Debug, PartialEq)]
+pub enum Backend {
+    // Walks the tree of the program
+    Interpreter,
+    // Interprets the program with a debugger which is driven from a command prompt, it stops
+    // the program before its first statement and at the breakpoints given
+    Debug(Vec<Breakpoint>),
+    // Interprets the program with a tracer, which reports the statements it executes, the
+    // profile of its calls and loops or a trace of its calls
+    Trace(TraceOptions),
+    // Compiles the program to bytecode which is run by a virtual machine, the bytecode is
+    // disassembled before it is run if the flag is set
+    Bytecode(bool),
+    // Compiles the program to bytecode which is written to a file instead of being run
+    EmitBytecode(PathBuf),
+    // Compiles the program with llvm
+    Llvm,
+}
+
+/// Runs a program defined in the path with a backend. If print_types is true the types of
+/// the variables are printed after they have been inferred. The lints of the program are
+/// checked with the levels given, unless the attributes of the program override them. An
+/// interpreted program is stopped with an error if it exceeds one of the limits, a compiled
+/// one is sandboxed with a step counter if its fuel is limited
+pub fn run(
+    path: &Path,
+    backend: Backend,
+    print_types: bool,
+    levels: &LintLevels,
+    limits: &Limits,
+) -> io::Result<()> {
     let display = path.display();
     let mut file = match File::open(&path) {
         Ok(file) => file,
@@ -25,28 +71,127 @@ pub fn run(path: &Path, compile: bool) -> io::Result<()> {
         Err(e) => panic!("Could not read file: {:?}", e),
     }
 
-    match parse(input) {
-        Ok(parsed_prog) => {
+    match parse_with_lines(input) {
+        Ok((parsed_prog, lines)) => {
             println!("parsed_prog = {:#?}", &parsed_prog);
-            match type_check(parsed_prog.clone()) {
-                Ok(_) => {
-                    if !compile {
-                        match interp(parsed_prog) {
-                            Some(res) => {
-                                return io::stdout().write_fmt(format_args!("{:?}\n", res))
+            let typed_prog = infer_types(parsed_prog)
+                .map_err(|e| e.locate(&lines))
+                .and_then(|typed_prog| borrow_check(&typed_prog).map(|_| typed_prog));
+            match typed_prog {
+                Ok(typed_prog) => {
+                    // A program with a denied lint is not run
+                    let mut denied = false;
+                    for (level, warning) in lint(&typed_prog, levels).iter() {
+                        let severity = match level {
+                            Level::Deny => "Error",
+                            _ => "Warning",
+                        };
+                        denied |= *level == Level::Deny;
+                        io::stderr().write_fmt(format_args!(
+                            "{} [{}]: {}\n",
+                            severity,
+                            warning.lint().name(),
+                            warning
+                        ))?
+                    }
+                    if denied {
+                        return io::stderr()
+                            .write_fmt(format_args!("Could not compile '{}'\n", display));
+                    }
+
+                    if print_types {
+                        print_var_types(&typed_prog);
+                    }
+                    match backend {
+                        Backend::Interpreter => {
+                            let res = interp_with_limits(typed_prog, limits.clone());
+                            print_result(res.map_err(|e| e.locate(&lines)))
+                        }
+                        Backend::Debug(breakpoints) => {
+                            let prompt = Prompt::new(BufReader::new(io::stdin()), io::stdout());
+                            let mut debugger = Debugger::new(lines.clone(), Box::new(prompt));
+                            for breakpoint in breakpoints.into_iter() {
+                                debugger.add_breakpoint(breakpoint);
                             }
-                            None => return Ok(()),
+                            debugger.stop_at_start();
+                            let res = debug(typed_prog, limits.clone(), debugger);
+                            print_result(res.map_err(|e| e.locate(&lines)))
                         }
-                    } else {
-                        let mut compiler = Compiler::new();
-                        let main_fn = compiler
-                            .compile(&parsed_prog)
-                            .ok_or_else(|| io::stderr().write(b"Unable to JIT execute function"))
-                            .unwrap();
-                        unsafe {
-                            return io::stdout().write_fmt(
-                                format_args! {"Execution result = {}\n", main_fn.call()},
-                            );
+                        Backend::Trace(options) => {
+                            let mut tracer = Tracer::new(lines.clone());
+                            if options.log {
+                                tracer = tracer.log_to(Box::new(io::stderr()));
+                            }
+                            let (res, tracer) = trace(typed_prog, limits.clone(), tracer);
+                            print_result(res.map_err(|e| e.locate(&lines)))?;
+                            if options.profile {
+                                io::stderr().write_all(tracer.profile().to_string().as_bytes())?;
+                            }
+                            match options.json {
+                                Some(out) => {
+                                    fs::write(&out, tracer.chrome_trace())?;
+                                    io::stdout().write_fmt(format_args!(
+                                        "Wrote the trace of '{}' to '{}'\n",
+                                        display,
+                                        out.display()
+                                    ))
+                                }
+                                None => Ok(()),
+                            }
+                        }
+                        Backend::Bytecode(print_code) => match compile(&typed_prog) {
+                            Ok(bytecode) => {
+                                if print_code {
+                                    io::stderr().write_all(disassemble(&bytecode).as_bytes())?;
+                                }
+                                let res = vm::run(&bytecode, limits.clone());
+                                print_result(res.map_err(|e| e.locate(&lines)))
+                            }
+                            Err(e) => io::stderr().write_fmt(format_args!(
+                                "Could not compile '{}' to bytecode: {}\n",
+                                display, e
+                            )),
+                        },
+                        Backend::EmitBytecode(out) => match compile(&typed_prog) {
+                            Ok(bytecode) => {
+                                let bytecode = bytecode.with_lines(&lines);
+                                fs::write(&out, serialize(&bytecode))?;
+                                io::stdout().write_fmt(format_args!(
+                                    "Wrote the bytecode of '{}' to '{}'\n",
+                                    display,
+                                    out.display()
+                                ))
+                            }
+                            Err(e) => io::stderr().write_fmt(format_args!(
+                                "Could not compile '{}' to bytecode: {}\n",
+                                display, e
+                            )),
+                        },
+                        Backend::Llvm => {
+                            let compiler = match limits.fuel {
+                                Some(fuel) => Compiler::sandboxed(fuel),
+                                None => Compiler::new(),
+                            };
+                            let mut compiler =
+                                compiler.with_overflow(limits.overflow).with_lines(lines);
+                            let main_fn = match compiler.compile(&typed_prog) {
+                                Ok(main_fn) => main_fn,
+                                Err(errors) => {
+                                    for error in errors.iter() {
+                                        io::stderr()
+                                            .write_fmt(format_args!("Error: {}\n", error))?
+                                    }
+                                    return io::stderr().write_fmt(format_args!(
+                                        "Could not compile '{}' with llvm\n",
+                                        display
+                                    ));
+                                }
+                            };
+                            unsafe {
+                                io::stdout().write_fmt(
+                                    format_args! {"Execution result = {}\n", main_fn.call()},
+                                )
+                            }
                         }
                     }
                 }
@@ -61,3 +206,100 @@ pub fn run(path: &Path, compile: bool) -> io::Result<()> {
         Err(e) => panic!("Error while parsing 'input.txt': {:?}", e),
     }
 }
+
+/// Runs a program which was compiled to a bytecode file, without parsing or checking it
+/// again. The file is rejected if it is corrupt or was written by another version
+pub fn run_bytecode(path: &Path, limits: &Limits) -> io::Result<()> {
+    let bytes = fs::read(path)?;
+    match deserialize(&bytes) {
+        Ok(bytecode) => {
+            let res = vm::run(&bytecode, limits.clone());
+            print_result(res.map_err(|e| e.locate(&bytecode.lines())))
+        }
+        Err(e) => io::stderr().write_fmt(format_args!(
+            "Could not load '{}': {}\n",
+            path.display(),
+            e
+        )),
+    }
+}
+
+/// Prints the value returned by the main function of a program or the error that stopped it
+fn print_result(res: Result<Option<Value>, RuntimeError>) -> io::Result<()> {
+    match res {
+        Ok(Some(res)) => io::stdout().write_fmt(format_args!("{:?}\n", res)),
+        Ok(None) => Ok(()),
+        Err(e) => io::stderr().write_fmt(format_args!("Runtime error: {}\n", e)),
+    }
+}
+
+/// Prints the type of every variable declared by a let binding in the functions of a
+/// type checked program
+fn print_var_types(program: &Vec<Box<Node>>) {
+    for item in program.iter() {
+        if let Node::Func { name, body, .. } = &**item {
+            println!("fn {}:", name);
+            print_let_types(body);
+        }
+    }
+}
+
+fn print_let_types(stmnt: &Node) {
+    let next = match stmnt {
+        Node::Let { var, next, .. } => {
+            match &**var {
+                Node::VarBinding(var, typ, _) => {
+                    println!("    let {}: {}", var_name(var), typ.to_string())
+                }
+                Node::TupleBinding(vars, typ) => {
+                    let names: Vec<String> = vars.iter().map(|(var, _)| var_name(var)).collect();
+                    println!("    let ({}): {}", names.join(", "), typ.to_string())
+                }
+                _ => (),
+            }
+            next
+        }
+        Node::If {
+            statement, next, ..
+        }
+        | Node::While {
+            statement, next, ..
+        } => {
+            print_let_types(statement);
+            next
+        }
+        Node::IfElse {
+            if_statement,
+            else_statement,
+            next,
+            ..
+        } => {
+            print_let_types(if_statement);
+            print_let_types(else_statement);
+            next
+        }
+        Node::MatchStatement { arms, next, .. } => {
+            for (_, arm) in arms.iter() {
+                print_let_types(arm);
+            }
+            next
+        }
+        Node::VarValue { next, .. }
+        | Node::Print { next, .. }
+        | Node::FuncCall { next, .. }
+        | Node::MethodCall { next, .. }
+        | Node::Return { next, .. } => next,
+        _ => return,
+    };
+
+    if let Some(next) = next {
+        print_let_types(next);
+    }
+}
+
+fn var_name(var: &Node) -> String {
+    match var {
+        Node::Var(name) => name.to_string(),
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{
+    interpreter::value_type, runtime_error::RuntimeErrorKind, types::LiteralType, value::Value,
+};
+
+/// A part of a value that a reference refers to, an element of an array, vector or tuple
+/// or a field of a struct
+#[derive(Clone, Debug, PartialEq)]
+pub enum Access {
+    Index(usize),
+    Field(String),
+}
+
+/// A reference to a variable, which is identified by the index of the scope it is declared
+/// in, or to the part of it that is accessed through the path
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reference {
+    pub scope: usize,
+    pub var: String,
+    pub path: Vec<Access>,
+    pub mutable: bool,
+}
+
+impl Reference {
+    pub fn new(scope: usize, var: String, mutable: bool) -> Reference {
+        Reference {
+            scope: scope,
+            var: var,
+            path: vec![],
+            mutable: mutable,
+        }
+    }
+
+    /// Gets the part of the value of the variable that is referred to
+    pub fn get(&self, val: Value) -> Result<Value, RuntimeErrorKind> {
+        self.path
+            .iter()
+            .try_fold(val, get_part)
+    }
+
+    /// Replaces the part of the value of the variable that is referred to with new
+    pub fn set(&self, val: Value, new: Value) -> Result<Value, RuntimeErrorKind> {
+        set_part(val, &self.path, new)
+    }
+}
+
+fn get_part(val: Value, access: &Access) -> Result<Value, RuntimeErrorKind> {
+    match (val, access) {
+        (Value::Array(mut elems), Access::Index(i))
+        | (Value::Vec(mut elems), Access::Index(i))
+        | (Value::Tuple(mut elems), Access::Index(i)) => {
+            check_bounds(elems.len(), *i)?;
+            Ok(elems.swap_remove(*i))
+        }
+        (Value::Struct(name, fields), Access::Field(field)) => {
+            match fields.into_iter().find(|(name, _)| name == field) {
+                Some((_, value)) => Ok(value),
+                None => Err(RuntimeErrorKind::FieldNotFound {
+                    field: field.to_string(),
+                    found: LiteralType::Named(name),
+                }),
+            }
+        }
+        (val, access) => Err(access_error(&val, access)),
+    }
+}
+
+fn set_part(val: Value, path: &[Access], new: Value) -> Result<Value, RuntimeErrorKind> {
+    let (access, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Ok(new),
+    };
+
+    match (val, access) {
+        (Value::Array(mut elems), Access::Index(i)) => {
+            check_bounds(elems.len(), *i)?;
+            elems[*i] = set_part(elems[*i].clone(), rest, new)?;
+            Ok(Value::Array(elems))
+        }
+        (Value::Vec(mut elems), Access::Index(i)) => {
+            check_bounds(elems.len(), *i)?;
+            elems[*i] = set_part(elems[*i].clone(), rest, new)?;
+            Ok(Value::Vec(elems))
+        }
+        (Value::Tuple(mut elems), Access::Index(i)) => {
+            check_bounds(elems.len(), *i)?;
+            elems[*i] = set_part(elems[*i].clone(), rest, new)?;
+            Ok(Value::Tuple(elems))
+        }
+        (Value::Struct(name, mut fields), Access::Field(field)) => {
+            match fields.iter_mut().find(|(name, _)| name == field) {
+                Some((_, value)) => *value = set_part(value.clone(), rest, new)?,
+                None => {
+                    return Err(RuntimeErrorKind::FieldNotFound {
+                        field: field.to_string(),
+                        found: LiteralType::Named(name),
+                    })
+                }
+            }
+            Ok(Value::Struct(name, fields))
+        }
+        (val, access) => Err(access_error(&val, access)),
+    }
+}
+
+/// Checks that an element which is referred to is still within a vector, it can have been
+/// popped after the reference was taken
+fn check_bounds(len: usize, i: usize) -> Result<(), RuntimeErrorKind> {
+    if i < len {
+        Ok(())
+    } else {
+        Err(RuntimeErrorKind::IndexOutOfBounds {
+            len: len,
+            index: i as i32,
+        })
+    }
+}
+
+fn access_error(val: &Value, access: &Access) -> RuntimeErrorKind {
+    match access {
+        Access::Index(_) => RuntimeErrorKind::IndexNotArray {
+            found: value_type(val),
+        },
+        Access::Field(field) => RuntimeErrorKind::FieldNotFound {
+            field: field.to_string(),
+            found: value_type(val),
+        },
+    }
+}
@@ -0,0 +1,349 @@
+use std::{fmt, time::Duration};
+
+use crate::{operators::Opcode, parse::program_parser::FuncLines, types::LiteralType};
+
+/// An error which stops the execution of a program. The statement it occurred in is the
+/// innermost statement that was evaluated, and the trace holds the names of the functions
+/// that were called when it occurred, starting with the one it occurred in
+#[derive(Debug, PartialEq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub stmnt: Option<String>,
+    // The index of the statement in its function, where the statements are numbered in the
+    // order they appear in, and its line once the error has been located
+    pub stmnt_idx: Option<usize>,
+    pub line: Option<usize>,
+    pub trace: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RuntimeErrorKind {
+    VarNotDefined {
+        var: String,
+    },
+    VarImmut {
+        var: String,
+    },
+    RefImmut {
+        var: String,
+    },
+    RefDangling {
+        var: String,
+    },
+    NotRef {
+        found: LiteralType,
+    },
+    FnNotDefined {
+        name: String,
+    },
+    FnDefinedMultiple {
+        name: String,
+    },
+    FnArgCount {
+        name: String,
+        takes: usize,
+        supplied: usize,
+    },
+    NotCallable {
+        name: String,
+        found: LiteralType,
+    },
+    MainMissing,
+    IndexNotArray {
+        found: LiteralType,
+    },
+    IndexWrongType {
+        found: LiteralType,
+    },
+    IndexOutOfBounds {
+        len: usize,
+        index: i32,
+    },
+    FieldNotFound {
+        field: String,
+        found: LiteralType,
+    },
+    DestructureNotTuple {
+        found: LiteralType,
+    },
+    PatternMismatch {
+        pattern: String,
+        found: LiteralType,
+    },
+    NoMatchingArm {
+        found: LiteralType,
+    },
+    MethodNotFound {
+        name: String,
+        found: LiteralType,
+    },
+    PopEmpty,
+    UnaryOpWrongType {
+        found: LiteralType,
+    },
+    OpWrongType {
+        op: Opcode,
+        left: LiteralType,
+        right: LiteralType,
+    },
+    Overflow {
+        op: Opcode,
+        left: i32,
+        right: i32,
+    },
+    NegOverflow {
+        num: i32,
+    },
+    DivByZero {
+        left: i32,
+    },
+    Cond {
+        found: LiteralType,
+    },
+    LoopModifierOutsideLoop,
+    StackOverflow {
+        name: String,
+        max_depth: usize,
+    },
+    OutOfFuel {
+        fuel: u64,
+    },
+    Timeout {
+        timeout: Duration,
+    },
+    OutOfMemory {
+        max_memory: usize,
+    },
+    // The program was quit from the debugger while it was stopped
+    Quit,
+    NoScope,
+    NodeNotSupported {
+        node: String,
+    },
+    BytecodeNotSupported {
+        node: String,
+    },
+}
+
+impl RuntimeError {
+    /// Sets the statement the error occurred in and its index in its function, unless it
+    /// occurred in a statement nested in it which has already been set
+    pub fn at(mut self, stmnt: Option<String>, idx: usize) -> RuntimeError {
+        if self.stmnt_idx.is_none() {
+            self.stmnt = stmnt;
+            self.stmnt_idx = Some(idx);
+        }
+        self
+    }
+
+    /// Finds the line of the statement the error occurred in from the lines of the
+    /// statements of each function, as they are returned by parse_with_lines
+    pub fn locate(mut self, lines: &FuncLines) -> RuntimeError {
+        if let (Some(idx), Some(name)) = (self.stmnt_idx, self.trace.first()) {
+            self.line = lines.get(name).and_then(|lines| lines.get(idx).copied());
+        }
+        self
+    }
+
+    /// Adds a function to the trace as the error is passed on to its caller
+    pub fn called_from(mut self, name: &str) -> RuntimeError {
+        self.trace.push(name.to_string());
+        self
+    }
+}
+
+impl From<RuntimeErrorKind> for RuntimeError {
+    fn from(kind: RuntimeErrorKind) -> RuntimeError {
+        RuntimeError {
+            kind: kind,
+            stmnt: None,
+            stmnt_idx: None,
+            line: None,
+            trace: vec![],
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+
+        let mut trace = self.trace.iter();
+        if let Some(name) = trace.next() {
+            let line = match self.line {
+                Some(line) => format!("on line {} ", line),
+                None => String::new(),
+            };
+            match &self.stmnt {
+                Some(stmnt) => {
+                    write!(f, "\n    at the {} statement {}in '{}'", stmnt, line, name)?
+                }
+                None => write!(f, "\n    {}in '{}'", line, name)?,
+            }
+        }
+        // A function which called itself is only shown once along with how many times
+        let mut trace = trace.peekable();
+        while let Some(name) = trace.next() {
+            let mut times = 1;
+            while trace.next_if_eq(&name).is_some() {
+                times += 1;
+            }
+            match times {
+                1 => write!(f, "\n    called from '{}'", name)?,
+                _ => write!(f, "\n    called from '{}' {} times", name, times)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::VarNotDefined { var } => {
+                write!(f, "Variable '{}' is not defined", var)
+            }
+            RuntimeErrorKind::VarImmut { var } => {
+                write!(f, "Cannot assign twice to immutable variable '{}'", var)
+            }
+            RuntimeErrorKind::RefImmut { var } => {
+                write!(f, "Cannot assign through a shared reference to '{}'", var)
+            }
+            RuntimeErrorKind::RefDangling { var } => write!(
+                f,
+                "Reference to '{}' is used after the variable went out of scope",
+                var
+            ),
+            RuntimeErrorKind::NotRef { found } => write!(
+                f,
+                "Cannot dereference a value of type '{}'",
+                found.to_string()
+            ),
+            RuntimeErrorKind::FnNotDefined { name } => {
+                write!(f, "Function '{}' is not defined", name)
+            }
+            RuntimeErrorKind::FnDefinedMultiple { name } => {
+                write!(f, "Function '{}' is defined multiple times", name)
+            }
+            RuntimeErrorKind::FnArgCount {
+                name,
+                takes,
+                supplied,
+            } => write!(
+                f,
+                "Function '{}' takes {} arguments but {} were supplied",
+                name, takes, supplied
+            ),
+            RuntimeErrorKind::NotCallable { name, found } => write!(
+                f,
+                "Cannot call '{}' which is a value of type '{}'",
+                name,
+                found.to_string()
+            ),
+            RuntimeErrorKind::MainMissing => {
+                write!(f, "No 'main' function is defined in the program")
+            }
+            RuntimeErrorKind::IndexNotArray { found } => write!(
+                f,
+                "Cannot index into a value of type '{}'",
+                found.to_string()
+            ),
+            RuntimeErrorKind::IndexWrongType { found } => write!(
+                f,
+                "Cannot index an array with a value of type '{}'",
+                found.to_string()
+            ),
+            RuntimeErrorKind::IndexOutOfBounds { len, index } => write!(
+                f,
+                "Index out of bounds: the len is {} but the index is {}",
+                len, index
+            ),
+            RuntimeErrorKind::FieldNotFound { field, found } => write!(
+                f,
+                "No field '{}' on a value of type '{}'",
+                field,
+                found.to_string()
+            ),
+            RuntimeErrorKind::DestructureNotTuple { found } => write!(
+                f,
+                "Cannot destructure a value of type '{}' which is not a tuple",
+                found.to_string()
+            ),
+            RuntimeErrorKind::PatternMismatch { pattern, found } => write!(
+                f,
+                "Pattern '{}' cannot match a value of type '{}'",
+                pattern,
+                found.to_string()
+            ),
+            RuntimeErrorKind::NoMatchingArm { found } => write!(
+                f,
+                "No arm matches the value of type '{}'",
+                found.to_string()
+            ),
+            RuntimeErrorKind::MethodNotFound { name, found } => write!(
+                f,
+                "No method named '{}' found for type '{}'",
+                name,
+                found.to_string()
+            ),
+            RuntimeErrorKind::PopEmpty => write!(f, "Cannot pop from an empty vector"),
+            RuntimeErrorKind::UnaryOpWrongType { found } => write!(
+                f,
+                "Unary operation '-' cannot be applied to type '{}'",
+                found.to_string()
+            ),
+            RuntimeErrorKind::OpWrongType { op, left, right } => write!(
+                f,
+                "Operation '{}' cannot be applied to types '{}' and '{}'",
+                op.to_string(),
+                left.to_string(),
+                right.to_string()
+            ),
+            RuntimeErrorKind::Overflow { op, left, right } => write!(
+                f,
+                "Arithmetic overflow in '{} {} {}'",
+                left,
+                op.to_string(),
+                right
+            ),
+            RuntimeErrorKind::NegOverflow { num } => {
+                write!(f, "Arithmetic overflow in '-({})'", num)
+            }
+            RuntimeErrorKind::DivByZero { left } => write!(f, "Attempt to divide {} by zero", left),
+            RuntimeErrorKind::Cond { found } => write!(
+                f,
+                "Condition must be of type 'bool', found '{}'",
+                found.to_string()
+            ),
+            RuntimeErrorKind::LoopModifierOutsideLoop => {
+                write!(f, "'break' or 'continue' outside of a loop")
+            }
+            RuntimeErrorKind::StackOverflow { name, max_depth } => write!(
+                f,
+                "Stack overflow in function '{}', calls cannot be nested more than {} deep",
+                name, max_depth
+            ),
+            RuntimeErrorKind::OutOfFuel { fuel } => {
+                write!(f, "The program ran out of fuel after {} steps", fuel)
+            }
+            RuntimeErrorKind::Timeout { timeout } => write!(
+                f,
+                "The program ran for longer than the time limit of {} ms",
+                timeout.as_millis()
+            ),
+            RuntimeErrorKind::OutOfMemory { max_memory } => write!(
+                f,
+                "The program used more than the memory limit of {} bytes",
+                max_memory
+            ),
+            RuntimeErrorKind::Quit => write!(f, "The program was quit from the debugger"),
+            RuntimeErrorKind::NoScope => write!(f, "There is no scope in the current context"),
+            RuntimeErrorKind::NodeNotSupported { node } => {
+                write!(f, "The interpreter does not support the node {}", node)
+            }
+            RuntimeErrorKind::BytecodeNotSupported { node } => {
+                write!(f, "The bytecode compiler does not support the node {}", node)
+            }
+        }
+    }
+}
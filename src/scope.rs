@@ -24,7 +24,7 @@ impl Scope {
                     match &**var {
                         Node::Var(name) => scope
                             .vars
-                            .insert(name.to_string(), Variable::new(arg.clone(), *mutable, *typ)),
+                            .insert(name.to_string(), Variable::new(arg.clone(), *mutable, typ.clone())),
                         _ => unreachable!(),
                     };
                 }
@@ -34,6 +34,11 @@ impl Scope {
         scope
     }
 
+    /// The number of bytes used by the names and values of the variables in the scope
+    pub fn size(&self) -> usize {
+        self.vars.iter().map(|(name, var)| name.len() + var.size()).sum()
+    }
+
     pub fn init_param_types(params: &Vec<Box<Node>>) -> Scope {
         let mut scope = Scope::new();
         for param in params.iter() {
@@ -41,7 +46,7 @@ impl Scope {
                 Node::FuncParam(var, typ, mutable) => match &**var {
                     Node::Var(name) => scope
                         .vars
-                        .insert(name.to_string(), Variable::new(Value::None, *mutable, *typ)),
+                        .insert(name.to_string(), Variable::new(Value::None, *mutable, typ.clone())),
                     _ => unreachable!(),
                 },
                 _ => unreachable!(),
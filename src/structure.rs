@@ -0,0 +1,31 @@
+use crate::types::LiteralType;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Struct {
+    pub name: String,
+    pub fields: Vec<(String, LiteralType)>,
+}
+
+impl Struct {
+    pub fn new(name: String, fields: Vec<(String, LiteralType)>) -> Struct {
+        Struct {
+            name: name,
+            fields: fields,
+        }
+    }
+
+    pub fn get_field_type(&self, field: &str) -> Option<LiteralType> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, typ)| typ.clone())
+    }
+
+    /// Gets the position of a field in the declaration of the struct
+    pub fn get_field_index(&self, field: &str) -> Option<u32> {
+        self.fields
+            .iter()
+            .position(|(name, _)| name == field)
+            .map(|idx| idx as u32)
+    }
+}
@@ -0,0 +1,397 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{parse::program_parser::FuncLines, value::Value};
+
+/// What is reported about a program which is traced
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceOptions {
+    // Logs every statement which is executed and every update of a variable to stderr
+    pub log: bool,
+    // Prints how often each function was called and how long it took, and how many times
+    // each loop was iterated
+    pub profile: bool,
+    // Writes the calls to a file in the trace event format of Chrome, which can be loaded
+    // into a trace viewer i.e. chrome://tracing
+    pub json: Option<PathBuf>,
+}
+
+/// The calls and time spent in a function, the total time includes the functions it calls
+/// while the self time does not. A recursive call is only counted once in the total time
+#[derive(Clone, Debug, PartialEq)]
+pub struct FuncProfile {
+    pub name: String,
+    pub calls: u64,
+    pub total: Duration,
+    pub self_time: Duration,
+}
+
+/// The number of iterations of a while loop in all of the calls to its function
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoopProfile {
+    pub func: String,
+    pub line: Option<usize>,
+    pub iterations: u64,
+}
+
+/// The profile of a program, the functions are sorted by their self time and the loops by
+/// their location
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub funcs: Vec<FuncProfile>,
+    pub loops: Vec<LoopProfile>,
+}
+
+impl Profile {
+    pub fn to_string(&self) -> String {
+        let mut res = format!(
+            "{:<24} {:>10} {:>14} {:>14}\n",
+            "function", "calls", "total ms", "self ms"
+        );
+        for func in self.funcs.iter() {
+            res.push_str(&format!(
+                "{:<24} {:>10} {:>14.3} {:>14.3}\n",
+                func.name,
+                func.calls,
+                millis(func.total),
+                millis(func.self_time)
+            ));
+        }
+        if !self.loops.is_empty() {
+            res.push_str(&format!("\n{:<35} {:>14}\n", "loop", "iterations"));
+        }
+        for lp in self.loops.iter() {
+            let location = match lp.line {
+                Some(line) => format!("while on line {} in '{}'", line, lp.func),
+                None => format!("while in '{}'", lp.func),
+            };
+            res.push_str(&format!("{:<35} {:>14}\n", location, lp.iterations));
+        }
+        res
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// A call which has not returned, the time it started at and the time spent in the calls
+/// it made are relative to the start of the program
+struct Call {
+    func: String,
+    start: Duration,
+    children: Duration,
+}
+
+/// A call which has returned, it is an event of the trace
+struct Span {
+    func: String,
+    start: Duration,
+    duration: Duration,
+}
+
+/// Traces the statements and calls of an interpreted program and profiles its functions
+pub struct Tracer {
+    lines: FuncLines,
+    // Where every statement and every update of a variable is logged to, if anywhere
+    log: Option<Box<dyn Write + Send>>,
+    start: Instant,
+    calls: Vec<Call>,
+    funcs: HashMap<String, FuncProfile>,
+    // The iterations of each loop by its function and the index of its statement
+    loops: HashMap<(String, usize), u64>,
+    spans: Vec<Span>,
+}
+
+impl Tracer {
+    /// Creates a tracer for a program with the lines of its functions, as they are returned
+    /// by parse_with_lines
+    pub fn new(lines: FuncLines) -> Tracer {
+        Tracer {
+            lines: lines,
+            log: None,
+            start: Instant::now(),
+            calls: vec![],
+            funcs: HashMap::new(),
+            loops: HashMap::new(),
+            spans: vec![],
+        }
+    }
+
+    /// Logs every statement which is executed and every update of a variable to an output
+    pub fn log_to(mut self, output: Box<dyn Write + Send>) -> Self {
+        self.log = Some(output);
+        self
+    }
+
+    fn line(&self, func: &str, idx: usize) -> Option<usize> {
+        self.lines
+            .get(func)
+            .and_then(|lines| lines.get(idx).copied())
+    }
+
+    /// Writes a line to the log, indented by the depth of the call it is logged in
+    fn write_log(&mut self, extra: usize, msg: &str) {
+        let indent = 2 * self.calls.len().saturating_sub(1) + extra;
+        if let Some(log) = &mut self.log {
+            // A trace which cannot be written does not stop the program
+            let _ = writeln!(log, "{:indent$}{}", "", msg, indent = indent);
+        }
+    }
+
+    pub fn enter_call(&mut self, name: &str) {
+        self.calls.push(Call {
+            func: name.to_string(),
+            start: self.start.elapsed(),
+            children: Duration::from_secs(0),
+        });
+        self.write_log(0, &format!("call '{}'", name));
+    }
+
+    pub fn exit_call(&mut self) {
+        let call = match self.calls.pop() {
+            Some(call) => call,
+            None => return,
+        };
+        let duration = self.start.elapsed() - call.start;
+        if let Some(caller) = self.calls.last_mut() {
+            caller.children += duration;
+        }
+
+        let recursive = self.calls.iter().any(|caller| caller.func == call.func);
+        let profile = self
+            .funcs
+            .entry(call.func.to_string())
+            .or_insert_with(|| FuncProfile {
+                name: call.func.to_string(),
+                calls: 0,
+                total: Duration::from_secs(0),
+                self_time: Duration::from_secs(0),
+            });
+        profile.calls += 1;
+        if !recursive {
+            profile.total += duration;
+        }
+        profile.self_time += duration.checked_sub(call.children).unwrap_or_default();
+
+        self.spans.push(Span {
+            func: call.func,
+            start: call.start,
+            duration: duration,
+        });
+    }
+
+    /// Is called before a statement is executed, with the index of the statement in its
+    /// function and its kind. It is logged along with its location
+    pub fn stmnt(&mut self, idx: usize, kind: &Option<String>) {
+        let func = match (&self.log, self.calls.last()) {
+            (Some(_), Some(call)) => call.func.to_string(),
+            _ => return,
+        };
+        // Only a continue statement has no kind
+        let kind = kind.clone().unwrap_or_else(|| String::from("continue"));
+        let msg = match self.line(&func, idx) {
+            Some(line) => format!("{}:{} {}", func, line, kind),
+            None => format!("{} {}", func, kind),
+        };
+        self.write_log(0, &msg);
+    }
+
+    /// Is called when a variable is declared or assigned to
+    pub fn var(&mut self, name: &str, value: &Value) {
+        if self.log.is_some() {
+            self.write_log(4, &format!("{} = {:?}", name, value));
+        }
+    }
+
+    /// Is called before each iteration of a loop, with the index of the loop in its function
+    pub fn iteration(&mut self, idx: usize) {
+        if let Some(call) = self.calls.last() {
+            *self.loops.entry((call.func.to_string(), idx)).or_insert(0) += 1;
+        }
+    }
+
+    pub fn profile(&self) -> Profile {
+        let mut funcs: Vec<FuncProfile> = self.funcs.values().cloned().collect();
+        funcs.sort_by(|a, b| b.self_time.cmp(&a.self_time).then(a.name.cmp(&b.name)));
+        let mut loops: Vec<(&(String, usize), &u64)> = self.loops.iter().collect();
+        loops.sort();
+        let loops = loops
+            .into_iter()
+            .map(|((func, idx), iterations)| LoopProfile {
+                func: func.to_string(),
+                line: self.line(func, *idx),
+                iterations: *iterations,
+            })
+            .collect();
+        Profile {
+            funcs: funcs,
+            loops: loops,
+        }
+    }
+
+    /// Gets the calls in the trace event format of Chrome, each call is a complete event
+    /// with its start and duration in microseconds
+    pub fn chrome_trace(&self) -> String {
+        let events: Vec<String> = self
+            .spans
+            .iter()
+            .map(|span| {
+                format!(
+                    concat!(
+                        "{{\"name\":{},\"cat\":\"call\",\"ph\":\"X\",",
+                        "\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":1}}"
+                    ),
+                    json_string(&span.func),
+                    span.start.as_secs_f64() * 1e6,
+                    span.duration.as_secs_f64() * 1e6
+                )
+            })
+            .collect();
+        format!(
+            "{{\"traceEvents\":[\n{}\n],\"displayTimeUnit\":\"ms\"}}\n",
+            events.join(",\n")
+        )
+    }
+}
+
+/// Quotes a string for JSON
+fn json_string(s: &str) -> String {
+    let mut res = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interpreter::trace, limits::Limits, parse::program_parser::parse_with_lines,
+        runtime_error::RuntimeError,
+    };
+    use std::{
+        fs, io,
+        sync::{Arc, Mutex},
+    };
+
+    // A log which can be read after the tracer has been moved to the interpreter
+    #[derive(Clone)]
+    struct Log(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Log {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn trace_file(path: &str, log: Option<Log>) -> (Result<Option<Value>, RuntimeError>, Tracer) {
+        let input = fs::read_to_string(path).unwrap();
+        let (program, lines) = parse_with_lines(input).unwrap();
+        let mut tracer = Tracer::new(lines);
+        if let Some(log) = log {
+            tracer = tracer.log_to(Box::new(log));
+        }
+        trace(program, Limits::new(), tracer)
+    }
+
+    fn func<'a>(profile: &'a Profile, name: &str) -> &'a FuncProfile {
+        profile.funcs.iter().find(|func| func.name == name).unwrap()
+    }
+
+    #[test]
+    fn profile() {
+        let (res, tracer) = trace_file("tests/debugger/calls.txt", None);
+        assert_eq!(res, Ok(Some(Value::Number(5))));
+        let profile = tracer.profile();
+        assert_eq!(func(&profile, "main").calls, 1);
+        assert_eq!(func(&profile, "square").calls, 2);
+        assert!(func(&profile, "square").total <= func(&profile, "main").total);
+        assert_eq!(
+            profile.loops,
+            vec![LoopProfile {
+                func: String::from("main"),
+                line: Some(9),
+                iterations: 3,
+            }]
+        );
+
+        let report = profile.to_string();
+        assert!(report.starts_with("function "));
+        assert!(report.contains("while on line 9 in 'main'"));
+
+        // The time of a recursive call is part of the total time of the outermost call
+        let (_, tracer) = trace_file("tests/arithmetic/factorial.txt", None);
+        let profile = tracer.profile();
+        let (main, fact) = (func(&profile, "main"), func(&profile, "fact"));
+        assert_eq!(fact.calls, 14);
+        assert!(fact.total <= main.total);
+        assert!(fact.self_time <= fact.total);
+        assert!(profile.loops.is_empty());
+    }
+
+    #[test]
+    fn log() {
+        let log = Log(Arc::new(Mutex::new(vec![])));
+        trace_file("tests/debugger/calls.txt", Some(log.clone()))
+            .0
+            .unwrap();
+        let log = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(
+            lines[..17].to_vec(),
+            vec![
+                "call 'main'",
+                "main:7 let",
+                "    sum = Number(0)",
+                "main:8 let",
+                "    i = Number(1)",
+                "main:9 while",
+                "main:10 if",
+                "main:12 if",
+                "main:15 assignment",
+                "  call 'square'",
+                "  square:2 let",
+                "      y = Number(1)",
+                "  square:3 return",
+                "    sum = Number(1)",
+                "main:16 assignment",
+                "    i = Number(2)",
+                "main:10 if",
+            ]
+        );
+        assert_eq!(lines.last(), Some(&"main:18 return"));
+    }
+
+    #[test]
+    fn chrome_trace() {
+        let (_, tracer) = trace_file("tests/debugger/calls.txt", None);
+        let json = tracer.chrome_trace();
+        assert!(json.starts_with("{\"traceEvents\":[\n"));
+        assert!(json.ends_with("],\"displayTimeUnit\":\"ms\"}\n"));
+        assert_eq!(json.matches("\"ph\":\"X\"").count(), 3);
+        assert_eq!(
+            json.matches("{\"name\":\"square\",\"cat\":\"call\"")
+                .count(),
+            2
+        );
+
+        assert_eq!(json_string("a \"b\"\\\n"), "\"a \\\"b\\\"\\\\\\u000a\"");
+    }
+}
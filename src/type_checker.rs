@@ -1,25 +1,22 @@
 use std::collections::HashMap;
 
 use crate::{
-    ast::Node,
+    arithmetic::arith_method,
+    ast::{param_name, Node},
     context::Context,
+    control_flow::falls_through,
+    enumeration::Enum,
     function::Func,
     operators::Opcode,
+    pattern::{is_reachable, missing_pattern, Pattern},
     scope::Scope,
+    structure::Struct,
     type_errors::{ErrorKind, TypeErrors},
     types::LiteralType,
     value::Value,
+    variable::Variable,
 };
 
-macro_rules! check_next {
-    ($next:tt) => {
-        match $next {
-            Some(next) => does_return(next),
-            None => false,
-        }
-    };
-}
-
 macro_rules! get_type {
     ($res:tt) => {
         match $res {
@@ -34,21 +31,89 @@ macro_rules! get_type {
 
 type Funcs = HashMap<String, Func>;
 
+#[allow(dead_code)]
+pub fn type_check(funcs_ast: Vec<Box<Node>>) -> Result<(), TypeErrors> {
+    infer_types(funcs_ast).map(|_| ())
+}
+
+/// Type checks a program and returns it with the types of the let bindings without a type
+/// annotation filled in with the types inferred from their values
 #[allow(unused_must_use)]
-pub fn type_check(mut funcs_ast: Vec<Box<Node>>) -> Result<(), TypeErrors> {
+pub fn infer_types(mut funcs_ast: Vec<Box<Node>>) -> Result<Vec<Box<Node>>, TypeErrors> {
     let mut funcs: Funcs = HashMap::new();
     let mut context: Context = Context::new();
     let mut type_errors: TypeErrors = TypeErrors::new();
 
-    // Declare all of the functions
-    for func in funcs_ast.drain(..) {
-        func_dec(func, &mut funcs, &mut type_errors);
+    // The index of the item that each error belongs to and of the statement in it, the
+    // errors are sorted by them so that they are reported in source order
+    let mut locations: Vec<(usize, usize)> = vec![];
+
+    // Declare all of the structs, enums and functions, the functions are checked in the
+    // order they are defined in
+    let mut types = vec![];
+    let mut order: Vec<(usize, String)> = vec![];
+    for (i, item) in funcs_ast.iter().enumerate() {
+        match *item.clone() {
+            // A struct or enum with the name of a type which is already declared is reported,
+            // and the first declaration is kept
+            Node::Struct { name, .. } | Node::Enum { name, .. }
+                if context.get_struct(&name).is_some() || context.get_enum(&name).is_some() =>
+            {
+                type_errors.insert_err(ErrorKind::TypeDefinedMultiple { name: name });
+            }
+            Node::Struct { name, fields } => {
+                types.push((i, name.clone()));
+                context.insert_struct(Struct::new(name, fields));
+            }
+            Node::Enum { name, variants } => {
+                types.push((i, name.clone()));
+                context.insert_enum(Enum::new(name, variants));
+            }
+            Node::Attribute { .. } => (),
+            Node::Func { name, .. } => {
+                if !funcs.contains_key(&name) {
+                    order.push((i, name));
+                }
+                func_dec(item.clone(), &mut funcs, &mut type_errors);
+            }
+            _ => unreachable!(),
+        }
+        locations.resize(type_errors.len(), (i, 0));
+    }
+
+    check_main(&funcs, &mut type_errors);
+    let main = order.iter().find(|(_, name)| name == "main");
+    locations.resize(type_errors.len(), (main.map_or(funcs_ast.len(), |(i, _)| *i), 0));
+
+    // Check that the fields of the structs and enums have known types
+    for (i, name) in types.iter() {
+        type_dec(name, &context, &mut type_errors);
+        locations.resize(type_errors.len(), (*i, 0));
+    }
+
+    // Check that the types of the signatures are defined
+    for (i, name) in order.iter() {
+        let func = funcs.get_mut(name).unwrap();
+        for param in func.params.iter_mut() {
+            if let Node::FuncParam(_, typ, _) = &mut **param {
+                *typ = defined_type(typ.clone(), &context, &mut type_errors);
+            }
+        }
+        if let Some(typ) = func.r_type.take() {
+            func.r_type = Some(defined_type(typ, &context, &mut type_errors));
+        }
+        locations.resize(type_errors.len(), (*i, 0));
     }
 
     // Type check the function bodies
-    for (_, func) in funcs.clone().iter() {
+    let mut inferred: HashMap<String, Vec<LiteralType>> = HashMap::new();
+    let mut param_ops: HashMap<String, Vec<(String, Opcode, usize)>> = HashMap::new();
+    let mut type_args: HashMap<String, Vec<(String, HashMap<String, LiteralType>, usize)>> =
+        HashMap::new();
+    for (i, name) in order.iter() {
+        let func = funcs[name].clone();
         context.push(Scope::init_param_types(&func.params)); // Push scope containing params and their types
-        visit(
+        visit_stmnt(
             func.body.clone(),
             &mut context,
             &funcs,
@@ -56,30 +121,131 @@ pub fn type_check(mut funcs_ast: Vec<Box<Node>>) -> Result<(), TypeErrors> {
             &mut type_errors,
         );
         context.pop();
+        // An error belongs to the statement which was type checked when it was found
+        for error in locations.len()..type_errors.len() {
+            let stmnt = context.stmnt_errors.partition_point(|errors| *errors <= error);
+            locations.push((*i, stmnt.saturating_sub(1)));
+        }
+        context.stmnt_errors.clear();
+        inferred.insert(func.name.clone(), context.inferred.drain(..).collect());
+        param_ops.insert(func.name.clone(), context.param_ops.drain(..).collect());
+        type_args.insert(func.name.clone(), context.type_args.drain(..).collect());
+    }
+
+    // An unsupported operation on a type argument belongs to the generic function using it
+    let callers: Vec<&String> = order.iter().map(|(_, name)| name).collect();
+    let stmnts = check_type_args(&callers, &mut param_ops, &type_args, &mut type_errors);
+    for (error, stmnt) in type_errors.errors[locations.len()..].iter().zip(stmnts) {
+        let item = match error {
+            ErrorKind::TypeArgOp { name, .. } => match order.iter().find(|(_, f)| f == name) {
+                Some((i, _)) => *i,
+                None => funcs_ast.len(),
+            },
+            _ => funcs_ast.len(),
+        };
+        locations.push((item, stmnt));
     }
 
     if type_errors.len() > 0 {
+        type_errors.sort_by_location(locations);
         return Err(type_errors);
     }
-    Ok(())
+
+    for item in funcs_ast.iter_mut() {
+        if let Node::Func { name, body, .. } = &mut **item {
+            if let Some(types) = inferred.remove(name) {
+                fill_inferred(body, &mut types.into_iter());
+            }
+        }
+    }
+    Ok(funcs_ast)
+}
+
+/// Fills in the inferred types of the let bindings of a block, in the same order as they
+/// were type checked
+fn fill_inferred(stmnt: &mut Box<Node>, types: &mut impl Iterator<Item = LiteralType>) {
+    let next = match &mut **stmnt {
+        Node::Let { var, next, .. } => {
+            match &mut **var {
+                Node::VarBinding(_, typ, _) | Node::TupleBinding(_, typ) => {
+                    if *typ == LiteralType::Infer {
+                        *typ = types.next().unwrap();
+                    }
+                }
+                _ => unreachable!(),
+            }
+            next
+        }
+        Node::If {
+            statement, next, ..
+        }
+        | Node::While {
+            statement, next, ..
+        } => {
+            fill_inferred(statement, types);
+            next
+        }
+        Node::IfElse {
+            if_statement,
+            else_statement,
+            next,
+            ..
+        } => {
+            fill_inferred(if_statement, types);
+            fill_inferred(else_statement, types);
+            next
+        }
+        Node::MatchStatement { arms, next, .. } => {
+            for (_, arm) in arms.iter_mut() {
+                fill_inferred(arm, types);
+            }
+            next
+        }
+        Node::VarValue { next, .. }
+        | Node::Print { next, .. }
+        | Node::FuncCall { next, .. }
+        | Node::MethodCall { next, .. }
+        | Node::Return { next, .. } => next,
+        _ => return,
+    };
+
+    if let Some(next) = next {
+        fill_inferred(next, types);
+    }
 }
 
 fn func_dec(func: Box<Node>, funcs: &mut Funcs, err: &mut TypeErrors) {
     match *func {
         Node::Func {
             name,
+            generics,
             params,
             r_type,
             body,
         } => {
-            let func = Func::new(name.to_string(), params.clone(), r_type, body.clone());
+            if funcs.contains_key(&name) {
+                err.insert_err(ErrorKind::FnDefinedMultiple { name });
+                return;
+            }
+            check_params(&name, &params, err);
+
+            let func = Func::new(
+                name.to_string(),
+                generics,
+                params.clone(),
+                r_type.clone(),
+                body.clone(),
+            );
             funcs.insert(name.clone(), func);
 
             if let Some(typ) = r_type {
-                if !does_return(body) {
+                if let Some((stmnt_idx, stmnt)) = falls_through(&body) {
                     err.insert_err(ErrorKind::FnMissingReturn {
                         name: name.to_string(),
                         r_type: typ,
+                        stmnt: stmnt,
+                        stmnt_idx: stmnt_idx,
+                        line: None,
                     })
                 }
             }
@@ -88,45 +254,162 @@ fn func_dec(func: Box<Node>, funcs: &mut Funcs, err: &mut TypeErrors) {
     }
 }
 
-/// Checks if the body of a function that returns has a tail expression
-fn does_return(body: Box<Node>) -> bool {
-    match *body {
-        Node::Return { .. } => true,
-        Node::VarValue {
-            var: _,
-            expr: _,
-            next,
-        } => check_next!(next),
-        Node::Let {
-            var: _,
-            expr: _,
-            next,
-        } => check_next!(next),
-        Node::Print { expr: _, next } => check_next!(next),
-        Node::If {
-            cond: _,
-            statement: _,
-            next,
-        } => check_next!(next),
-        Node::IfElse {
-            cond: _,
-            if_statement: _,
-            else_statement,
-            next,
-        } => match does_return(else_statement) {
-            true => true,
-            false => check_next!(next),
-        },
-        Node::While {
-            cond: _,
-            statement: _,
-            next,
-        } => check_next!(next),
-        Node::FuncCall {
-            name: _,
-            args: _,
-            next,
-        } => check_next!(next),
+/// Checks that a parameter name is only used once by a function or closure
+fn check_params(name: &str, params: &Vec<Box<Node>>, err: &mut TypeErrors) {
+    let mut names: Vec<String> = vec![];
+    for param in params.iter() {
+        let param = param_name(param);
+        if names.contains(&param) {
+            err.insert_err(ErrorKind::ParamDuplicate {
+                name: name.to_string(),
+                param: param.clone(),
+            });
+        }
+        names.push(param);
+    }
+}
+
+/// Checks that the program defines a main function which can be called without arguments
+/// and whose return value can be used as the exit code of the program
+fn check_main(funcs: &Funcs, err: &mut TypeErrors) {
+    let main = match funcs.get("main") {
+        Some(main) => main,
+        None => return err.insert_err(ErrorKind::MainMissing),
+    };
+
+    if !main.generics.is_empty() {
+        err.insert_err(ErrorKind::MainGeneric);
+    }
+    if !main.params.is_empty() {
+        err.insert_err(ErrorKind::MainParams {
+            found: main.params.len(),
+        });
+    }
+    match &main.r_type {
+        None | Some(LiteralType::I32) => (),
+        Some(typ) => err.insert_err(ErrorKind::MainReturnType { found: typ.clone() }),
+    }
+}
+
+fn type_dec(name: &str, context: &Context, err: &mut TypeErrors) {
+    let fields = field_types(name, context);
+
+    let mut defined = true;
+    for typ in fields.iter() {
+        defined &= check_elem_type(typ, &LiteralType::Named(name.to_string()), context, err);
+    }
+
+    let mut visited = vec![name.to_string()];
+    if defined
+        && fields
+            .iter()
+            .any(|typ| contains_type(typ, name, context, &mut visited))
+    {
+        err.insert_err(ErrorKind::TypeRecursive {
+            name: name.to_string(),
+        });
+    }
+}
+
+/// Gets the types of the fields of a struct or of all the variants of an enum
+fn field_types(name: &str, context: &Context) -> Vec<LiteralType> {
+    if let Some(strct) = context.get_struct(name) {
+        return strct.fields.iter().map(|(_, typ)| typ.clone()).collect();
+    }
+    match context.get_enum(name) {
+        Some(enm) => enm
+            .variants
+            .iter()
+            .flat_map(|(_, fields)| fields.clone())
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Checks that all structs and enums used in a type are defined
+fn check_type(typ: &LiteralType, context: &Context, err: &mut TypeErrors) -> bool {
+    match typ {
+        LiteralType::Array(elem_type, _)
+        | LiteralType::Vec(elem_type)
+        | LiteralType::Ref(elem_type, _) => check_elem_type(elem_type, typ, context, err),
+        LiteralType::Tuple(elems) => {
+            let mut defined = true;
+            for elem in elems.iter() {
+                defined &= check_elem_type(elem, typ, context, err);
+            }
+            defined
+        }
+        LiteralType::Fn(params, r_type) => {
+            let mut defined = true;
+            for param in params.iter() {
+                defined &= check_elem_type(param, typ, context, err);
+            }
+            defined &= check_elem_type(r_type, typ, context, err);
+            defined
+        }
+        LiteralType::Named(name) => {
+            if context.get_struct(name).is_some() || context.get_enum(name).is_some() {
+                return true;
+            }
+            err.insert_err(ErrorKind::TypeNotDefined {
+                name: name.to_string(),
+            });
+            false
+        }
+        _ => true,
+    }
+}
+
+/// Replaces a type which is not defined by the error type, so that it is only reported once
+fn defined_type(typ: LiteralType, context: &Context, err: &mut TypeErrors) -> LiteralType {
+    match check_type(&typ, context, err) {
+        true => typ,
+        false => LiteralType::Error,
+    }
+}
+
+/// Checks a type which is contained in the type typ, it can not be a reference since the
+/// lifetimes of references stored in other values are not checked
+fn check_elem_type(
+    elem_type: &LiteralType,
+    typ: &LiteralType,
+    context: &Context,
+    err: &mut TypeErrors,
+) -> bool {
+    if let LiteralType::Ref(_, _) = elem_type {
+        err.insert_err(ErrorKind::RefNotAllowed { typ: typ.clone() });
+        return false;
+    }
+    check_type(elem_type, context, err)
+}
+
+/// Checks if a type contains the named type by value, i.e. the named type would have an
+/// infinite size if it is one of its fields. Vectors are stored on the heap and are
+/// therefore not followed
+fn contains_type(
+    typ: &LiteralType,
+    named: &str,
+    context: &Context,
+    visited: &mut Vec<String>,
+) -> bool {
+    match typ {
+        LiteralType::Array(elem_type, _) => contains_type(elem_type, named, context, visited),
+        LiteralType::Tuple(elems) => elems
+            .iter()
+            .any(|typ| contains_type(typ, named, context, visited)),
+        LiteralType::Named(name) => {
+            if name == named {
+                return true;
+            }
+            if visited.contains(name) {
+                return false;
+            }
+            visited.push(name.to_string());
+
+            field_types(name, context)
+                .iter()
+                .any(|typ| contains_type(typ, named, context, visited))
+        }
         _ => false,
     }
 }
@@ -136,21 +419,109 @@ fn does_return(body: Box<Node>) -> bool {
 /// Returns a result of either
 /// Ok(LiteralType): The type determined
 /// Err(Option<LiteralType>): If possible, the type that would have resulted if the
-/// 	sub expression was correctly typed, otherwise None
-#[allow(unused_must_use)]
+///     sub expression was correctly typed, otherwise None. An expression of the error type
+///     has an error which has already been reported and is None so it is not reported again
 fn visit(
     node: Box<Node>,
     context: &mut Context,
     funcs: &Funcs,
     curr_func: &str,
     err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    match visit_node(node, context, funcs, curr_func, err) {
+        Ok(LiteralType::Error) | Err(Some(LiteralType::Error)) => Err(None),
+        res => res,
+    }
+}
+
+/// Type checks a statement of a function, the errors found until the next statement is
+/// type checked belong to it
+fn visit_stmnt(
+    node: Box<Node>,
+    context: &mut Context,
+    funcs: &Funcs,
+    curr_func: &str,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    context.stmnt_errors.push(err.len());
+    visit(node, context, funcs, curr_func, err)
+}
+
+#[allow(unused_must_use)]
+fn visit_node(
+    node: Box<Node>,
+    context: &mut Context,
+    funcs: &Funcs,
+    curr_func: &str,
+    err: &mut TypeErrors,
 ) -> Result<LiteralType, Option<LiteralType>> {
     match *node {
         Node::Number(_) => Ok(LiteralType::I32),
         Node::Bool(_) => Ok(LiteralType::Bool),
         Node::_String(_) => Ok(LiteralType::_String),
+        Node::Unit => Ok(LiteralType::Void),
         Node::UnaryOp(_, expr) => unary_op(visit(expr, context, funcs, curr_func, err), err),
-        Node::Var(name) => var(&name, context, err),
+        Node::Var(name) => var(&name, context, funcs, err),
+        Node::Array(elems) => {
+            let mut elem_types = vec![];
+            for elem in elems.iter() {
+                elem_types.push(visit(elem.clone(), context, funcs, curr_func, err));
+            }
+            array(elem_types, err)
+        }
+        Node::Tuple(elems) => {
+            let mut elem_types = vec![];
+            for elem in elems.iter() {
+                elem_types.push(visit(elem.clone(), context, funcs, curr_func, err));
+            }
+            tuple(elem_types)
+        }
+        Node::Ref(expr, mutable) => reference(expr, mutable, context, funcs, curr_func, err),
+        Node::Deref(expr) => deref(visit(expr, context, funcs, curr_func, err), err),
+        Node::ArrayRepeat(expr, len) => match visit(expr, context, funcs, curr_func, err) {
+            Ok(typ) => Ok(LiteralType::Array(Box::new(typ), len)),
+            Err(Some(typ)) => Err(Some(LiteralType::Array(Box::new(typ), len))),
+            Err(None) => Err(None),
+        },
+        Node::Index(arr, idx) => index(
+            visit(arr, context, funcs, curr_func, err),
+            visit(idx.clone(), context, funcs, curr_func, err),
+            &idx,
+            err,
+        ),
+        Node::VecNew => Ok(LiteralType::Vec(Box::new(LiteralType::Infer))),
+        Node::StructLiteral { name, fields } => {
+            let mut field_types = vec![];
+            for (field, expr) in fields.iter() {
+                let typ = visit(expr.clone(), context, funcs, curr_func, err);
+                field_types.push((field.to_string(), typ));
+            }
+            struct_literal(&name, field_types, context, err)
+        }
+        Node::Field(expr, name) => field(
+            visit(expr, context, funcs, curr_func, err),
+            &name,
+            context,
+            err,
+        ),
+        Node::Variant { enm, name, args } => {
+            let mut arg_types = vec![];
+            for arg in args.iter() {
+                arg_types.push(visit(arg.clone(), context, funcs, curr_func, err));
+            }
+            variant(&enm, &name, arg_types, context, err)
+        }
+        Node::Match { expr, arms } => match_expr(expr, arms, context, funcs, curr_func, err),
+        Node::Closure { .. } => closure(node, context, funcs, curr_func, err),
+        Node::MatchStatement { expr, arms, next } => {
+            match_statement(expr, arms, context, funcs, curr_func, err, next)
+        }
+        Node::MethodCall {
+            recv,
+            name,
+            args,
+            next,
+        } => method_call(recv, &name, args, context, funcs, curr_func, err, next),
         Node::VarValue { var, expr, next } => var_update(
             var,
             visit(expr, context, funcs, curr_func, err),
@@ -164,6 +535,7 @@ fn visit(
             visit(left, context, funcs, curr_func, err),
             op,
             visit(right, context, funcs, curr_func, err),
+            context,
             err,
         ),
         Node::Let { var, expr, next } => var_dec(
@@ -191,35 +563,31 @@ fn visit(
             statement,
             next,
         } => {
-            // Type check the statement and check that the condition is a boolean
+            // Check that the condition is a boolean and type check the statement
+            check_cond(visit(cond, context, funcs, curr_func, err), err);
             context.push(Scope::new());
-            visit(statement, context, funcs, curr_func, err);
+            visit_stmnt(statement, context, funcs, curr_func, err);
             context.pop();
-            check_cond(
-                visit(cond, context, funcs, curr_func, err),
-                context,
-                funcs,
-                curr_func,
-                err,
-                next,
-            )
+            match next {
+                Some(next) => visit_stmnt(next, context, funcs, curr_func, err),
+                None => Err(None),
+            }
         }
         Node::While {
             cond,
             statement,
             next,
         } => {
+            check_cond(visit(cond, context, funcs, curr_func, err), err);
             context.push(Scope::new());
-            visit(statement, context, funcs, curr_func, err);
+            context.loops += 1;
+            visit_stmnt(statement, context, funcs, curr_func, err);
+            context.loops -= 1;
             context.pop();
-            check_cond(
-                visit(cond, context, funcs, curr_func, err),
-                context,
-                funcs,
-                curr_func,
-                err,
-                next,
-            )
+            match next {
+                Some(next) => visit_stmnt(next, context, funcs, curr_func, err),
+                None => Err(None),
+            }
         }
         Node::IfElse {
             cond,
@@ -227,38 +595,47 @@ fn visit(
             else_statement,
             next,
         } => {
+            check_cond(visit(cond, context, funcs, curr_func, err), err);
             context.push(Scope::new());
-            visit(if_statement, context, funcs, curr_func, err);
+            visit_stmnt(if_statement, context, funcs, curr_func, err);
             context.pop();
             context.push(Scope::new());
-            visit(else_statement, context, funcs, curr_func, err);
+            visit_stmnt(else_statement, context, funcs, curr_func, err);
             context.pop();
-            check_cond(
-                visit(cond, context, funcs, curr_func, err),
-                context,
-                funcs,
-                curr_func,
-                err,
-                next,
-            )
+            match next {
+                Some(next) => visit_stmnt(next, context, funcs, curr_func, err),
+                None => Err(None),
+            }
         }
         // Print node used for debugging
         Node::Print { expr: _, next } => match next {
-            Some(next) => visit(next, context, funcs, curr_func, err),
+            Some(next) => visit_stmnt(next, context, funcs, curr_func, err),
             None => Err(None),
         },
+        // Loop modifiers are always the last statement of a block
+        Node::Break => loop_modifier("break", context, curr_func, err),
+        Node::Continue => loop_modifier("continue", context, curr_func, err),
         _ => unimplemented!(),
     }
 }
 
-fn check_cond(
-    cond: Result<LiteralType, Option<LiteralType>>,
-    context: &mut Context,
-    funcs: &Funcs,
+/// Checks that a break or continue is in a loop
+fn loop_modifier(
+    modifier: &str,
+    context: &Context,
     curr_func: &str,
     err: &mut TypeErrors,
-    next: Option<Box<Node>>,
 ) -> Result<LiteralType, Option<LiteralType>> {
+    if context.loops == 0 {
+        err.insert_err(ErrorKind::LoopModifierOutsideLoop {
+            name: curr_func.to_string(),
+            modifier: modifier.to_string(),
+        });
+    }
+    Err(None)
+}
+
+fn check_cond(cond: Result<LiteralType, Option<LiteralType>>, err: &mut TypeErrors) {
     let cond = get_type!(cond);
 
     if let Some(cond_typ) = cond {
@@ -266,11 +643,6 @@ fn check_cond(
             err.insert_err(ErrorKind::Cond { found: cond_typ });
         }
     }
-
-    match next {
-        Some(next) => visit(next, context, funcs, curr_func, err),
-        None => Err(None),
-    }
 }
 
 fn check_return(
@@ -286,14 +658,14 @@ fn check_return(
         _ => unreachable!(),
     };
 
-    let mut ret = Ok(fn_r_type);
+    let mut ret = Ok(fn_r_type.clone());
 
     let val = get_type!(val);
     if let Some(val_type) = val {
-        if fn_r_type != val_type {
+        if !fn_r_type.is_compatible(&val_type) {
             err.insert_err(ErrorKind::FnReturnMismatch {
                 name: curr_func.to_string(),
-                expected: fn_r_type,
+                expected: fn_r_type.clone(),
                 found: val_type,
             });
             ret = Err(Some(fn_r_type));
@@ -301,7 +673,7 @@ fn check_return(
     }
 
     match next {
-        Some(next) => visit(next, context, funcs, curr_func, err),
+        Some(next) => visit_stmnt(next, context, funcs, curr_func, err),
         None => ret,
     }
 }
@@ -315,6 +687,12 @@ fn func_call(
     err: &mut TypeErrors,
     next: Option<Box<Node>>,
 ) -> Result<LiteralType, Option<LiteralType>> {
+    // Variables shadow the functions with the same name
+    if let Some(var) = context.get_var(name) {
+        let typ = var.get_type();
+        return value_call(name, typ, args, context, funcs, curr_func, err, next);
+    }
+
     let func = match funcs.get(name) {
         Some(func) => func,
         None => {
@@ -322,19 +700,59 @@ fn func_call(
                 name: name.to_string(),
             });
             match next {
-                Some(next) => return visit(next, context, funcs, curr_func, err),
+                Some(next) => return visit_stmnt(next, context, funcs, curr_func, err),
                 None => return Err(None),
             }
         }
     };
 
-    let params = func.get_param_types();
+    let mut params = func.get_param_types();
     let mut arg_types = vec![];
 
     for arg in args.iter() {
         arg_types.push(visit(arg.clone(), context, funcs, curr_func, err));
     }
 
+    // The type arguments of a generic function are inferred from the types of the arguments
+    let mut r_type = Ok(func.get_r_type());
+    if !func.generics.is_empty() && params.len() != arg_types.len() {
+        r_type = Err(None);
+    } else if !func.generics.is_empty() {
+        let mut args = HashMap::new();
+        for ((_, param_type), arg_type) in params.iter().zip(arg_types.iter()) {
+            if let Ok(arg_type) = arg_type {
+                param_type.infer_args(arg_type, &mut args);
+            }
+        }
+
+        let missing: Vec<&String> = func
+            .generics
+            .iter()
+            .filter(|param| !args.contains_key(*param))
+            .collect();
+        if missing.is_empty() {
+            for (_, param_type) in params.iter_mut() {
+                *param_type = param_type.subst(&args);
+            }
+            r_type = Ok(func.get_r_type().subst(&args));
+            let stmnt = context.stmnt_errors.len().saturating_sub(1);
+            context.type_args.push((name.to_string(), args, stmnt));
+        } else {
+            // Arguments without a type have already been reported
+            if arg_types.iter().all(|arg_type| arg_type.is_ok()) {
+                for param in missing.into_iter() {
+                    err.insert_err(ErrorKind::TypeArgNotInferred {
+                        name: name.to_string(),
+                        param: param.to_string(),
+                    });
+                }
+            }
+            params.clear();
+            arg_types.clear();
+            r_type = Err(None);
+        }
+    }
+
     if params.len() != arg_types.len() {
         err.insert_err(ErrorKind::FnNumParamMismatch {
             name: name.to_string(),
@@ -344,12 +762,12 @@ fn func_call(
     } else {
         for pair in params.iter().zip(arg_types.iter()) {
             if let ((param_name, param_type), Ok(arg_type)) = pair {
-                if param_type != arg_type {
+                if !param_type.is_compatible(arg_type) {
                     err.insert_err(ErrorKind::FnParamTypeMismatch {
                         name: name.to_string(),
                         param: param_name.to_string(),
-                        found: *arg_type,
-                        expected: *param_type,
+                        found: arg_type.clone(),
+                        expected: param_type.clone(),
                     });
                 }
             }
@@ -357,111 +775,365 @@ fn func_call(
     }
 
     match next {
-        Some(next) => visit(next, context, funcs, curr_func, err),
-        None => Ok(func.get_r_type()),
+        Some(next) => visit_stmnt(next, context, funcs, curr_func, err),
+        None => r_type,
     }
 }
 
-fn var_dec(
-    var: Box<Node>,
-    val: Result<LiteralType, Option<LiteralType>>,
+/// Type checks calling a variable which holds a function or a closure
+fn value_call(
+    name: &str,
+    typ: LiteralType,
+    args: Vec<Box<Node>>,
     context: &mut Context,
     funcs: &Funcs,
     curr_func: &str,
     err: &mut TypeErrors,
     next: Option<Box<Node>>,
 ) -> Result<LiteralType, Option<LiteralType>> {
-    let val = get_type!(val);
+    let mut arg_types = vec![];
+    for arg in args.iter() {
+        arg_types.push(visit(arg.clone(), context, funcs, curr_func, err));
+    }
 
-    let (name, var_type, mutable) = match *var {
-        Node::VarBinding(var, var_type, mutable) => match *var {
-            Node::Var(name) => (name, var_type, mutable),
-            _ => unreachable!(),
-        },
+    let r_type = match typ {
+        LiteralType::Fn(params, r_type) => {
+            if params.len() != arg_types.len() {
+                err.insert_err(ErrorKind::FnNumParamMismatch {
+                    name: name.to_string(),
+                    takes: params.len(),
+                    supplied: args.len(),
+                });
+            } else {
+                // The parameters of a function type are named by their position
+                for (i, pair) in params.iter().zip(arg_types.iter()).enumerate() {
+                    if let (param_type, Ok(arg_type)) = pair {
+                        if !param_type.is_compatible(arg_type) {
+                            err.insert_err(ErrorKind::FnParamTypeMismatch {
+                                name: name.to_string(),
+                                param: i.to_string(),
+                                found: arg_type.clone(),
+                                expected: param_type.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+            Ok(*r_type)
+        }
+        // The type of the variable has an error, which has already been reported
+        LiteralType::Error => Err(None),
+        found => {
+            err.insert_err(ErrorKind::NotCallable {
+                name: name.to_string(),
+                found,
+            });
+            Err(None)
+        }
+    };
+
+    match next {
+        Some(next) => visit_stmnt(next, context, funcs, curr_func, err),
+        None => r_type,
+    }
+}
+
+/// Type checks a closure, its body can only use its parameters and the variables it
+/// captures. The captured variables are copied into the closure, they can therefore not be
+/// references and are immutable in the closure
+fn closure(
+    node: Box<Node>,
+    context: &mut Context,
+    funcs: &Funcs,
+    curr_func: &str,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    let captures = node.captures();
+    let (params, body) = match *node {
+        Node::Closure { params, body } => (params, body),
         _ => unreachable!(),
     };
 
-    // Push to context so it can be used to type check if used in other expr
-    context.insert_var(name.clone(), mutable, var_type, Value::None);
+    check_params("closure", &params, err);
 
-    // Handle mismatched types
-    let mut ret = Ok(var_type);
-    if let Some(val_type) = val {
-        if val_type != var_type {
-            err.insert_err(ErrorKind::MismatchedTypesVar {
-                var: name,
-                expected: var_type,
-                found: val_type,
+    let mut param_types = vec![];
+    for param in params.iter() {
+        if let Node::FuncParam(_, typ, _) = &**param {
+            param_types.push(typ.clone());
+        }
+    }
+    let mut scope = Scope::init_param_types(&params);
+
+    // Names which are not variables are functions or are reported when the body is checked
+    for name in captures.into_iter() {
+        let typ = match context.get_var(&name) {
+            Some(var) => var.get_type(),
+            None => continue,
+        };
+        if let LiteralType::Ref(_, _) = typ {
+            err.insert_err(ErrorKind::CaptureRef {
+                var: name.clone(),
+                typ: typ.clone(),
             });
-            ret = Err(Some(var_type));
         }
-    } else {
-        ret = Err(None);
+        scope.vars.insert(name, Variable::new(Value::None, false, typ));
     }
 
-    match next {
-        Some(next) => return visit(next, context, funcs, curr_func, err),
-        None => ret,
+    let scopes = std::mem::replace(&mut context.scopes, vec![scope]);
+    let body_type = visit(body, context, funcs, curr_func, err);
+    context.scopes = scopes;
+
+    let typ = |r_type| LiteralType::Fn(param_types.clone(), Box::new(r_type));
+    let defined = check_type(&typ(LiteralType::Void), context, err);
+    match body_type {
+        Ok(r_type) if defined => Ok(typ(r_type)),
+        Ok(r_type) | Err(Some(r_type)) => Err(Some(typ(r_type))),
+        Err(None) => Err(None),
     }
 }
 
-fn var(
-    name: &str,
-    context: &mut Context,
+/// Checks that the type arguments of the calls to generic functions support the operators
+/// used on their type parameters. The operators used on a type parameter which is given as
+/// a type argument by a generic function are required of its own type parameter, they are
+/// used in the statement of the call. Returns the statement of each error that is reported
+fn check_type_args(
+    callers: &Vec<&String>,
+    param_ops: &mut HashMap<String, Vec<(String, Opcode, usize)>>,
+    type_args: &HashMap<String, Vec<(String, HashMap<String, LiteralType>, usize)>>,
     err: &mut TypeErrors,
-) -> Result<LiteralType, Option<LiteralType>> {
-    match context.get_var(name) {
-        Some(var) => Ok(var.get_type()),
-        None => {
-            err.insert_err(ErrorKind::VarNotInScope {
-                var: name.to_string(),
-            });
-            Err(None)
+) -> Vec<usize> {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for caller in callers.iter() {
+            for (name, args, stmnt) in type_args[*caller].iter() {
+                for (param, op, _) in param_ops[name].clone().into_iter() {
+                    if let Some(LiteralType::Param(arg)) = args.get(&param) {
+                        let ops = param_ops.get_mut(*caller).unwrap();
+                        if !ops.iter().any(|(used, used_op, _)| used == arg && *used_op == op) {
+                            ops.push((arg.to_string(), op, *stmnt));
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut reported = vec![];
+    let mut stmnts = vec![];
+    for caller in callers.iter() {
+        for (name, args, _) in type_args[*caller].iter() {
+            for (param, op, stmnt) in param_ops[name].iter() {
+                let typ = &args[param];
+                let supported = matches!(
+                    (typ, op),
+                    (LiteralType::Param(_), _)
+                        | (LiteralType::I32, _)
+                        | (LiteralType::Bool, Opcode::EQ)
+                        | (LiteralType::Bool, Opcode::NEQ)
+                );
+                if !supported && !reported.contains(&(name, param, op, typ)) {
+                    reported.push((name, param, op, typ));
+                    err.insert_err(ErrorKind::TypeArgOp {
+                        name: name.to_string(),
+                        param: param.to_string(),
+                        typ: typ.clone(),
+                        op: *op,
+                    });
+                    stmnts.push(*stmnt);
+                }
+            }
         }
     }
+    stmnts
 }
 
-fn var_update(
+fn var_dec(
     var: Box<Node>,
     val: Result<LiteralType, Option<LiteralType>>,
     context: &mut Context,
     funcs: &Funcs,
     curr_func: &str,
-    next: Option<Box<Node>>,
     err: &mut TypeErrors,
+    next: Option<Box<Node>>,
 ) -> Result<LiteralType, Option<LiteralType>> {
     let val = get_type!(val);
 
-    // Get variable name and type, if not defined generate error and go to next
-    let (var_name, var_type, var_mut) = match *var {
-        Node::Var(name) => match context.get_var(name.as_str()) {
-            Some(var) => (name, var.get_type(), var.is_mut()),
-            None => {
-                err.insert_err(ErrorKind::VarNotInScope { var: name });
-                match next {
-                    Some(next) => return visit(next, context, funcs, curr_func, err),
-                    None => return Err(None),
+    let (name, var_type) = match *var {
+        Node::VarBinding(var, var_type, mutable) => {
+            let name = var_name(&var);
+            let var_type = match var_type {
+                LiteralType::Infer => infer_type(&name, &val, context, err),
+                _ => defined_type(var_type, context, err),
+            };
+
+            // Push to context so it can be used to type check if used in other expr
+            context.insert_var(name.clone(), mutable, var_type.clone(), Value::None);
+            (name, var_type)
+        }
+        Node::TupleBinding(vars, var_type) => {
+            let names: Vec<String> = vars.iter().map(|(var, _)| var_name(var)).collect();
+            let name = format!("({})", names.join(", "));
+            let var_type = match var_type {
+                LiteralType::Infer => infer_type(&name, &val, context, err),
+                _ => defined_type(var_type, context, err),
+            };
+
+            // Each variable gets the type of the corresponding element of the tuple, or the
+            // error type if the type is not a tuple with one element for each variable
+            match &var_type {
+                LiteralType::Tuple(elems) if elems.len() == vars.len() => {
+                    for ((name, (_, mutable)), typ) in names.iter().zip(vars.iter()).zip(elems) {
+                        context.insert_var(name.clone(), *mutable, typ.clone(), Value::None);
+                    }
+                }
+                _ => {
+                    if var_type != LiteralType::Error {
+                        err.insert_err(ErrorKind::MismatchedTupleBinding {
+                            expected: var_type.clone(),
+                            bindings: vars.len(),
+                        });
+                    }
+                    for (name, (_, mutable)) in names.iter().zip(vars.iter()) {
+                        context.insert_var(name.clone(), *mutable, LiteralType::Error, Value::None);
+                    }
                 }
             }
-        },
+            (name, var_type)
+        }
+        _ => unreachable!(),
+    };
+
+    // Handle mismatched types, a type that could not be inferred has already been reported
+    let mut ret = Ok(var_type.clone());
+    if let Some(val_type) = val {
+        if !var_type.is_compatible(&val_type) {
+            err.insert_err(ErrorKind::MismatchedTypesVar {
+                var: name,
+                expected: var_type.clone(),
+                found: val_type,
+            });
+            ret = Err(Some(var_type));
+        }
+    } else {
+        ret = Err(None);
+    }
+
+    match next {
+        Some(next) => return visit_stmnt(next, context, funcs, curr_func, err),
+        None => ret,
+    }
+}
+
+/// Gets the type of a let binding without a type annotation from the type of its value,
+/// which has to be fully known
+fn infer_type(
+    name: &str,
+    val: &Option<LiteralType>,
+    context: &mut Context,
+    err: &mut TypeErrors,
+) -> LiteralType {
+    // A variable whose type can not be inferred gets the error type
+    let typ = match val {
+        Some(found) if *found == LiteralType::Void || !found.is_known() => {
+            err.insert_err(ErrorKind::CannotInfer {
+                var: name.to_string(),
+                found: found.clone(),
+            });
+            LiteralType::Error
+        }
+        Some(typ) => typ.clone(),
+        None => LiteralType::Error,
+    };
+    context.inferred.push(typ.clone());
+    typ
+}
+
+fn var_name(var: &Node) -> String {
+    match var {
+        Node::Var(name) => name.to_string(),
         _ => unreachable!(),
+    }
+}
+
+/// Gets the type of a variable, or of a function which is used as a value
+fn var(
+    name: &str,
+    context: &mut Context,
+    funcs: &Funcs,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    match context.get_var(name) {
+        // The error of a variable of the error type has already been reported
+        Some(var) if var.get_type() == LiteralType::Error => Err(None),
+        Some(var) => Ok(var.get_type()),
+        None if funcs.contains_key(name) => {
+            let func = &funcs[name];
+            let params = func
+                .get_param_types()
+                .into_iter()
+                .map(|(_, typ)| typ)
+                .collect();
+            let typ = LiteralType::Fn(params, Box::new(func.get_r_type()));
+
+            // The type arguments of a generic function can only be inferred from a call
+            for param in func.generics.iter() {
+                err.insert_err(ErrorKind::TypeArgNotInferred {
+                    name: name.to_string(),
+                    param: param.to_string(),
+                });
+            }
+            match func.generics.is_empty() {
+                true => Ok(typ),
+                false => Err(None),
+            }
+        }
+        None => {
+            err.insert_err(ErrorKind::VarNotInScope {
+                var: name.to_string(),
+            });
+            Err(None)
+        }
+    }
+}
+
+fn var_update(
+    var: Box<Node>,
+    val: Result<LiteralType, Option<LiteralType>>,
+    context: &mut Context,
+    funcs: &Funcs,
+    curr_func: &str,
+    next: Option<Box<Node>>,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    let val = get_type!(val);
+    let shared_ref = is_deref(&var);
+
+    // Get variable name and type of the assigned place, if not defined generate error and go to next
+    let (var_name, var_type, var_mut) = match place(var, context, funcs, curr_func, err) {
+        Some(place) => place,
+        None => match next {
+            Some(next) => return visit_stmnt(next, context, funcs, curr_func, err),
+            None => return Err(None),
+        },
     };
 
-    let mut ret = Ok(var_type);
+    let mut ret = Ok(var_type.clone());
 
     // If the new value of the variable has a type (passed type check),
     // check if the variable has the same type as the new value
     if let Some(val_type) = val {
-        if var_type != val_type {
+        if !var_type.is_compatible(&val_type) {
             err.insert_err(ErrorKind::MismatchedTypesVar {
                 var: var_name,
-                expected: var_type,
+                expected: var_type.clone(),
                 found: val_type,
             });
             ret = Err(Some(var_type));
         } else {
             if !var_mut {
-                err.insert_err(ErrorKind::VarImmut { var: var_name });
+                err.insert_err(immut_err(var_name, shared_ref));
             }
         }
     } else {
@@ -470,282 +1142,2256 @@ fn var_update(
     }
 
     match next {
-        Some(next) => visit(next, context, funcs, curr_func, err),
+        Some(next) => visit_stmnt(next, context, funcs, curr_func, err),
         None => ret,
     }
 }
 
-fn expr(
-    left: Result<LiteralType, Option<LiteralType>>,
-    op: Opcode,
-    right: Result<LiteralType, Option<LiteralType>>,
+/// Gets the variable name, type and mutability of a place that is assigned to,
+/// i.e. "a", "a[i]" or "a.x", where the name and mutability is that of the indexed variable.
+/// Places behind a reference i.e. "*r" have the mutability of the reference
+fn place(
+    node: Box<Node>,
+    context: &mut Context,
+    funcs: &Funcs,
+    curr_func: &str,
+    err: &mut TypeErrors,
+) -> Option<(String, LiteralType, bool)> {
+    match *node {
+        Node::Var(name) => match context.get_var(name.as_str()) {
+            // The error of a variable of the error type has already been reported
+            Some(var) if var.get_type() == LiteralType::Error => None,
+            Some(var) => Some((name, var.get_type(), var.is_mut())),
+            None => {
+                err.insert_err(ErrorKind::VarNotInScope { var: name });
+                None
+            }
+        },
+        Node::Index(arr, idx) => {
+            let idx_type = visit(idx.clone(), context, funcs, curr_func, err);
+            let (name, arr_type, mutable) = place(arr, context, funcs, curr_func, err)?;
+            match index(Ok(arr_type), idx_type, &idx, err) {
+                Ok(typ) => Some((name, typ, mutable)),
+                Err(_) => None,
+            }
+        }
+        Node::Field(strct, field_name) => {
+            let (name, strct_type, mutable) = place(strct, context, funcs, curr_func, err)?;
+            match field(Ok(strct_type), &field_name, context, err) {
+                Ok(typ) => Some((name, typ, mutable)),
+                Err(_) => None,
+            }
+        }
+        Node::Deref(expr) => {
+            let (name, ref_type, _) = place(expr, context, funcs, curr_func, err)?;
+            match deref(Ok(ref_type.clone()), err) {
+                Ok(typ) => match ref_type {
+                    LiteralType::Ref(_, mutable) => Some((name, typ, mutable)),
+                    _ => unreachable!(),
+                },
+                Err(_) => None,
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Gets the error of assigning to a place which is not mutable
+fn immut_err(var: String, shared_ref: bool) -> ErrorKind {
+    match shared_ref {
+        true => ErrorKind::AssignSharedRef { var: var },
+        false => ErrorKind::VarImmut { var: var },
+    }
+}
+
+/// Type checks borrowing a place, a place can only be borrowed as mutable if it is mutable
+fn reference(
+    expr: Box<Node>,
+    mutable: bool,
+    context: &mut Context,
+    funcs: &Funcs,
+    curr_func: &str,
     err: &mut TypeErrors,
 ) -> Result<LiteralType, Option<LiteralType>> {
-    // Determine if the left and right sub expressions evaluated to a type
-    let (l, r) = match (left, right) {
-        (Ok(l_type), Ok(r_type)) => (Some(l_type), Some(r_type)),
-        _ => (None, None),
+    let shared_ref = is_deref(&expr);
+    match place(expr, context, funcs, curr_func, err) {
+        Some((name, typ, var_mut)) => {
+            let typ = LiteralType::Ref(Box::new(typ), mutable);
+            if mutable && !var_mut {
+                match shared_ref {
+                    true => err.insert_err(ErrorKind::AssignSharedRef { var: name }),
+                    false => err.insert_err(ErrorKind::RefMutImmut { var: name }),
+                }
+                return Err(Some(typ));
+            }
+            Ok(typ)
+        }
+        None => Err(None),
+    }
+}
+
+fn deref(
+    expr: Result<LiteralType, Option<LiteralType>>,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    match get_type!(expr) {
+        Some(LiteralType::Ref(typ, _)) => Ok(*typ),
+        Some(typ) => {
+            err.insert_err(ErrorKind::DerefNotRef { found: typ });
+            Err(None)
+        }
+        None => Err(None),
+    }
+}
+
+/// Type checks an array literal, all elements are expected to have the type of the first element
+fn array(
+    elems: Vec<Result<LiteralType, Option<LiteralType>>>,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    let len = elems.len();
+    let mut elems = elems.into_iter().map(|elem| get_type!(elem));
+
+    let elem_type = match elems.next() {
+        Some(Some(typ)) => typ,
+        _ => return Err(None),
     };
 
-    // If left and right has a type, calculate new type
-    if let (Some(left), Some(right)) = (l, r) {
-        match op {
-            Opcode::Add | Opcode::Sub | Opcode::Div | Opcode::Mul => num_expr(left, op, right, err),
-            Opcode::AND | Opcode::OR => log_op(left, op, right, err),
-            Opcode::EQ | Opcode::NEQ | Opcode::GT | Opcode::LT | Opcode::LEQ | Opcode::GEQ => {
-                rel_op(left, op, right, err)
+    let mut well_typed = true;
+    for elem in elems {
+        match elem {
+            Some(typ) => {
+                if !elem_type.is_compatible(&typ) {
+                    err.insert_err(ErrorKind::MismatchedTypesArray {
+                        expected: elem_type.clone(),
+                        found: typ,
+                    });
+                    well_typed = false;
+                }
             }
+            None => well_typed = false,
         }
-    } else {
-        match op {
-            Opcode::Add | Opcode::Sub | Opcode::Div | Opcode::Mul => Err(Some(LiteralType::I32)),
-            _ => Err(Some(LiteralType::Bool)),
+    }
+
+    let typ = LiteralType::Array(Box::new(elem_type), len);
+    match well_typed {
+        true => Ok(typ),
+        false => Err(Some(typ)),
+    }
+}
+
+/// Type checks a tuple expression, the type of each element is kept
+fn tuple(
+    elems: Vec<Result<LiteralType, Option<LiteralType>>>,
+) -> Result<LiteralType, Option<LiteralType>> {
+    let mut elem_types = vec![];
+    let mut well_typed = true;
+    for elem in elems.into_iter() {
+        well_typed &= elem.is_ok();
+        match get_type!(elem) {
+            Some(typ) => elem_types.push(typ),
+            None => return Err(None),
         }
     }
+
+    let typ = LiteralType::Tuple(elem_types);
+    match well_typed {
+        true => Ok(typ),
+        false => Err(Some(typ)),
+    }
 }
 
-fn num_expr(
-    left: LiteralType,
-    op: Opcode,
-    right: LiteralType,
+/// Type checks indexing into an array or vector, if the index is a constant it is also
+/// bounds checked against the length of the array
+fn index(
+    arr: Result<LiteralType, Option<LiteralType>>,
+    idx: Result<LiteralType, Option<LiteralType>>,
+    idx_node: &Node,
     err: &mut TypeErrors,
 ) -> Result<LiteralType, Option<LiteralType>> {
-    if let (LiteralType::I32, LiteralType::I32) = (left, right) {
-        return Ok(LiteralType::I32);
-    } else {
-        err.insert_err(ErrorKind::OpWrongType {
-            op: op,
-            typ: LiteralType::Bool,
+    let arr = get_type!(arr);
+    let idx = get_type!(idx);
+
+    let mut well_typed = true;
+    if let Some(idx_type) = idx {
+        if idx_type != LiteralType::I32 {
+            err.insert_err(ErrorKind::IndexWrongType { found: idx_type });
+            well_typed = false;
+        }
+    }
+
+    match arr {
+        Some(LiteralType::Array(elem_type, len)) => {
+            if let Some(idx) = const_index(idx_node) {
+                if idx < 0 || idx as usize >= len {
+                    err.insert_err(ErrorKind::IndexOutOfBounds {
+                        len: len,
+                        index: idx,
+                    });
+                    well_typed = false;
+                }
+            }
+
+            match well_typed {
+                true => Ok(*elem_type),
+                false => Err(Some(*elem_type)),
+            }
+        }
+        Some(LiteralType::Vec(elem_type)) => match well_typed {
+            true => Ok(*elem_type),
+            false => Err(Some(*elem_type)),
+        },
+        Some(typ) => {
+            err.insert_err(ErrorKind::IndexNotArray { found: typ });
+            Err(None)
+        }
+        None => Err(None),
+    }
+}
+
+/// Type checks a struct literal, every field of the struct has to be initialized exactly once
+fn struct_literal(
+    name: &str,
+    fields: Vec<(String, Result<LiteralType, Option<LiteralType>>)>,
+    context: &Context,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    let strct = match context.get_struct(name) {
+        Some(strct) => strct,
+        None => {
+            err.insert_err(ErrorKind::TypeNotDefined {
+                name: name.to_string(),
+            });
+            return Err(None);
+        }
+    };
+
+    let mut well_typed = true;
+    let mut initialized: Vec<String> = vec![];
+    for (field, typ) in fields.into_iter() {
+        if initialized.contains(&field) {
+            err.insert_err(ErrorKind::StructFieldDuplicate {
+                strct: name.to_string(),
+                field: field,
+            });
+            well_typed = false;
+            continue;
+        }
+        initialized.push(field.clone());
+
+        match (strct.get_field_type(&field), get_type!(typ)) {
+            (Some(expected), Some(found)) => {
+                if !expected.is_compatible(&found) {
+                    err.insert_err(ErrorKind::MismatchedTypesField {
+                        strct: name.to_string(),
+                        field: field,
+                        expected: expected,
+                        found: found,
+                    });
+                    well_typed = false;
+                }
+            }
+            (Some(_), None) => well_typed = false,
+            (None, _) => {
+                err.insert_err(ErrorKind::StructFieldNotFound {
+                    strct: name.to_string(),
+                    field: field,
+                });
+                well_typed = false;
+            }
+        }
+    }
+
+    for (field, _) in strct.fields.iter() {
+        if !initialized.contains(field) {
+            err.insert_err(ErrorKind::StructFieldMissing {
+                strct: name.to_string(),
+                field: field.to_string(),
+            });
+            well_typed = false;
+        }
+    }
+
+    let typ = LiteralType::Named(name.to_string());
+    match well_typed {
+        true => Ok(typ),
+        false => Err(Some(typ)),
+    }
+}
+
+/// Type checks reading a field of a struct or an element of a tuple i.e. "p.x" or "t.0"
+fn field(
+    strct: Result<LiteralType, Option<LiteralType>>,
+    name: &str,
+    context: &Context,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    match get_type!(strct) {
+        Some(LiteralType::Named(ref strct)) if context.get_struct(strct).is_some() => {
+            match context.get_struct(strct).unwrap().get_field_type(name) {
+                Some(typ) => Ok(typ),
+                None => {
+                    err.insert_err(ErrorKind::StructFieldNotFound {
+                        strct: strct.to_string(),
+                        field: name.to_string(),
+                    });
+                    Err(None)
+                }
+            }
+        }
+        Some(LiteralType::Tuple(ref elems)) if tuple_index(name, elems.len()).is_some() => {
+            Ok(elems[tuple_index(name, elems.len()).unwrap()].clone())
+        }
+        Some(typ) => {
+            err.insert_err(ErrorKind::FieldNotStruct {
+                field: name.to_string(),
+                found: typ,
+            });
+            Err(None)
+        }
+        None => Err(None),
+    }
+}
+
+/// Gets the index of the element of a tuple of length len that a field refers to, if any
+fn tuple_index(field: &str, len: usize) -> Option<usize> {
+    match field.parse::<usize>() {
+        Ok(idx) if idx < len => Some(idx),
+        _ => None,
+    }
+}
+
+/// Type checks constructing a variant of an enum i.e. "Shape::Circle(1)"
+fn variant(
+    enm: &str,
+    name: &str,
+    args: Vec<Result<LiteralType, Option<LiteralType>>>,
+    context: &Context,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    let fields = match context.get_enum(enm) {
+        Some(def) => match def.get_variant(name) {
+            Some(fields) => fields.clone(),
+            None => {
+                err.insert_err(ErrorKind::VariantNotFound {
+                    enm: enm.to_string(),
+                    name: name.to_string(),
+                });
+                return Err(None);
+            }
+        },
+        None => {
+            err.insert_err(ErrorKind::TypeNotDefined {
+                name: enm.to_string(),
+            });
+            return Err(None);
+        }
+    };
+
+    let mut well_typed = true;
+    if args.len() != fields.len() {
+        err.insert_err(ErrorKind::VariantNumFieldsMismatch {
+            name: format!("{}::{}", enm, name),
+            takes: fields.len(),
+            supplied: args.len(),
         });
+        well_typed = false;
+    }
+
+    for (i, (arg, expected)) in args.into_iter().zip(fields).enumerate() {
+        match get_type!(arg) {
+            Some(found) => {
+                if !expected.is_compatible(&found) {
+                    err.insert_err(ErrorKind::MismatchedTypesVariant {
+                        name: format!("{}::{}", enm, name),
+                        field: i,
+                        expected: expected,
+                        found: found,
+                    });
+                    well_typed = false;
+                }
+            }
+            None => well_typed = false,
+        }
+    }
+
+    let typ = LiteralType::Named(enm.to_string());
+    match well_typed {
+        true => Ok(typ),
+        false => Err(Some(typ)),
     }
-    Err(Some(LiteralType::I32))
 }
 
-fn log_op(
-    left: LiteralType,
-    op: Opcode,
-    right: LiteralType,
+/// Type checks a match expression, all arms are expected to have the type of the first arm
+fn match_expr(
+    expr: Box<Node>,
+    arms: Vec<(Pattern, Box<Node>)>,
+    context: &mut Context,
+    funcs: &Funcs,
+    curr_func: &str,
     err: &mut TypeErrors,
 ) -> Result<LiteralType, Option<LiteralType>> {
-    if let (LiteralType::Bool, LiteralType::Bool) = (left, right) {
-        return Ok(LiteralType::Bool);
-    } else {
-        err.insert_err(ErrorKind::OpWrongType {
-            op: op,
-            typ: LiteralType::I32,
+    let typ = visit(expr, context, funcs, curr_func, err);
+    let typ = get_type!(typ);
+    let pats: Vec<Pattern> = arms.iter().map(|(pat, _)| pat.clone()).collect();
+    let bindings = check_arms(&pats, typ, context, err);
+
+    let mut arm_type: Option<LiteralType> = None;
+    let mut well_typed = true;
+    for ((_, arm), bindings) in arms.into_iter().zip(bindings) {
+        context.push(Scope::new());
+        for (name, typ) in bindings.into_iter() {
+            context.insert_var(name, false, typ, Value::None);
+        }
+        let found = visit(arm, context, funcs, curr_func, err);
+        let found = get_type!(found);
+        context.pop();
+
+        match (&arm_type, found) {
+            (None, Some(found)) => arm_type = Some(found),
+            (Some(expected), Some(found)) => {
+                if !expected.is_compatible(&found) {
+                    err.insert_err(ErrorKind::MismatchedTypesMatchArm {
+                        expected: expected.clone(),
+                        found: found,
+                    });
+                    well_typed = false;
+                }
+            }
+            (_, None) => well_typed = false,
+        }
+    }
+
+    match (arm_type, well_typed) {
+        (Some(typ), true) => Ok(typ),
+        (typ, _) => Err(typ),
+    }
+}
+
+/// Type checks a match with blocks as arms
+#[allow(unused_must_use)]
+fn match_statement(
+    expr: Box<Node>,
+    arms: Vec<(Pattern, Box<Node>)>,
+    context: &mut Context,
+    funcs: &Funcs,
+    curr_func: &str,
+    err: &mut TypeErrors,
+    next: Option<Box<Node>>,
+) -> Result<LiteralType, Option<LiteralType>> {
+    let typ = visit(expr, context, funcs, curr_func, err);
+    let typ = get_type!(typ);
+    let pats: Vec<Pattern> = arms.iter().map(|(pat, _)| pat.clone()).collect();
+    let bindings = check_arms(&pats, typ, context, err);
+
+    for ((_, arm), bindings) in arms.into_iter().zip(bindings) {
+        context.push(Scope::new());
+        for (name, typ) in bindings.into_iter() {
+            context.insert_var(name, false, typ, Value::None);
+        }
+        visit_stmnt(arm, context, funcs, curr_func, err);
+        context.pop();
+    }
+
+    match next {
+        Some(next) => visit_stmnt(next, context, funcs, curr_func, err),
+        None => Err(None),
+    }
+}
+
+/// Type checks the patterns of the arms of a match against the type of the matched value,
+/// the patterns have to cover every value and each arm has to be reachable.
+/// Returns the variables bound by the pattern of each arm
+fn check_arms(
+    pats: &Vec<Pattern>,
+    typ: Option<LiteralType>,
+    context: &Context,
+    err: &mut TypeErrors,
+) -> Vec<Vec<(String, LiteralType)>> {
+    // The bindings of the patterns get the error type if the type of the value has an error
+    let typ = typ.unwrap_or(LiteralType::Error);
+
+    let mut bindings = vec![];
+    let mut well_typed = true;
+    for pat in pats.iter() {
+        let mut arm_bindings = vec![];
+        well_typed &= check_pattern(pat, &typ, context, &mut arm_bindings, err);
+        bindings.push(arm_bindings);
+    }
+
+    // The patterns can only be compared when they match values of the same type
+    if well_typed {
+        for (i, pat) in pats.iter().enumerate() {
+            if !is_reachable(&pats[..i], pat, &typ, &context.enums) {
+                err.insert_err(ErrorKind::MatchArmUnreachable {
+                    pattern: pat.to_string(),
+                });
+            }
+        }
+
+        if let Some(missing) = missing_pattern(pats, &typ, &context.enums) {
+            err.insert_err(ErrorKind::MatchNotExhaustive {
+                missing: missing.to_string(),
+            });
+        }
+    }
+    bindings
+}
+
+/// Type checks a pattern against the type of the value it matches and collects the
+/// variables it binds
+fn check_pattern(
+    pat: &Pattern,
+    typ: &LiteralType,
+    context: &Context,
+    bindings: &mut Vec<(String, LiteralType)>,
+    err: &mut TypeErrors,
+) -> bool {
+    match (pat, typ) {
+        (_, LiteralType::Error) => {
+            for name in pat.bindings().into_iter() {
+                bindings.push((name, LiteralType::Error));
+            }
+            false
+        }
+        (Pattern::Wildcard, _) => true,
+        (Pattern::Binding(name), _) => {
+            bindings.push((name.to_string(), typ.clone()));
+            true
+        }
+        (Pattern::Number(_), LiteralType::I32) | (Pattern::Bool(_), LiteralType::Bool) => true,
+        (Pattern::Variant { enm, name, fields }, _) => {
+            let variant_fields = match context.get_enum(enm) {
+                Some(def) => def.get_variant(name),
+                None => {
+                    err.insert_err(ErrorKind::TypeNotDefined {
+                        name: enm.to_string(),
+                    });
+                    return false;
+                }
+            };
+
+            if *typ != LiteralType::Named(enm.to_string()) {
+                err.insert_err(ErrorKind::MismatchedTypesPattern {
+                    pattern: pat.to_string(),
+                    expected: typ.clone(),
+                });
+                return false;
+            }
+
+            let variant_fields = match variant_fields {
+                Some(variant_fields) => variant_fields,
+                None => {
+                    err.insert_err(ErrorKind::VariantNotFound {
+                        enm: enm.to_string(),
+                        name: name.to_string(),
+                    });
+                    return false;
+                }
+            };
+
+            if fields.len() != variant_fields.len() {
+                err.insert_err(ErrorKind::VariantNumFieldsMismatch {
+                    name: format!("{}::{}", enm, name),
+                    takes: variant_fields.len(),
+                    supplied: fields.len(),
+                });
+                return false;
+            }
+
+            let mut well_typed = true;
+            for (field, field_type) in fields.iter().zip(variant_fields.iter()) {
+                well_typed &= check_pattern(field, field_type, context, bindings, err);
+            }
+            well_typed
+        }
+        _ => {
+            err.insert_err(ErrorKind::MismatchedTypesPattern {
+                pattern: pat.to_string(),
+                expected: typ.clone(),
+            });
+            false
+        }
+    }
+}
+
+/// Gets the value of an index if it is known at compile time
+fn const_index(idx: &Node) -> Option<i32> {
+    match idx {
+        Node::Number(num) => Some(*num),
+        Node::UnaryOp(Opcode::Sub, expr) => match **expr {
+            Node::Number(num) => Some(-num),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Checks if a node is a place which can be assigned to, i.e. "a", "a[i]" or "a.x"
+fn is_place(node: &Node) -> bool {
+    match node {
+        Node::Var(_) => true,
+        Node::Index(arr, _) => is_place(arr),
+        Node::Field(strct, _) => is_place(strct),
+        Node::Deref(expr) => is_place(expr),
+        _ => false,
+    }
+}
+
+/// Checks if a place is behind a reference i.e. "*r" or "(*r).x"
+fn is_deref(node: &Node) -> bool {
+    match node {
+        Node::Deref(_) => true,
+        Node::Index(arr, _) => is_deref(arr),
+        Node::Field(strct, _) => is_deref(strct),
+        _ => false,
+    }
+}
+
+#[allow(unused_must_use)]
+fn method_call(
+    recv: Box<Node>,
+    name: &str,
+    args: Vec<Box<Node>>,
+    context: &mut Context,
+    funcs: &Funcs,
+    curr_func: &str,
+    err: &mut TypeErrors,
+    next: Option<Box<Node>>,
+) -> Result<LiteralType, Option<LiteralType>> {
+    // Methods modifying a variable requires it to be mutable
+    let recv_type = match name {
+        "push" | "pop" if is_place(&recv) => {
+            let shared_ref = is_deref(&recv);
+            match place(recv, context, funcs, curr_func, err) {
+                Some((var_name, typ, mutable)) => {
+                    if !mutable {
+                        err.insert_err(immut_err(var_name, shared_ref));
+                    }
+                    Some(typ)
+                }
+                None => None,
+            }
+        }
+        _ => {
+            let recv = visit(recv, context, funcs, curr_func, err);
+            get_type!(recv)
+        }
+    };
+
+    let mut arg_types = vec![];
+    for arg in args.iter() {
+        let arg_type = visit(arg.clone(), context, funcs, curr_func, err);
+        arg_types.push(get_type!(arg_type));
+    }
+
+    let ret = match recv_type {
+        Some(typ) => method_type(typ, name, arg_types, err),
+        None => Err(None),
+    };
+
+    match next {
+        Some(next) => visit_stmnt(next, context, funcs, curr_func, err),
+        None => ret,
+    }
+}
+
+/// Gets the return type of calling the built-in method name on a value of type typ
+fn method_type(
+    typ: LiteralType,
+    name: &str,
+    args: Vec<Option<LiteralType>>,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    let (params, r_type) = match (&typ, name) {
+        (LiteralType::Array(_, _), "len") | (LiteralType::Vec(_), "len") => {
+            (vec![], LiteralType::I32)
+        }
+        (LiteralType::Vec(elem_type), "push") => {
+            (vec![("value", *elem_type.clone())], LiteralType::Void)
+        }
+        (LiteralType::Vec(elem_type), "pop") => (vec![], *elem_type.clone()),
+        (LiteralType::I32, name) if arith_method(name).is_some() => {
+            (vec![("rhs", LiteralType::I32)], LiteralType::I32)
+        }
+        _ => {
+            err.insert_err(ErrorKind::MethodNotFound {
+                name: name.to_string(),
+                typ: typ,
+            });
+            return Err(None);
+        }
+    };
+
+    if params.len() != args.len() {
+        err.insert_err(ErrorKind::FnNumParamMismatch {
+            name: name.to_string(),
+            takes: params.len(),
+            supplied: args.len(),
+        });
+        return Err(Some(r_type));
+    }
+
+    let mut ret = Ok(r_type.clone());
+    for ((param_name, param_type), arg) in params.into_iter().zip(args) {
+        if let Some(arg_type) = arg {
+            if !param_type.is_compatible(&arg_type) {
+                err.insert_err(ErrorKind::FnParamTypeMismatch {
+                    name: name.to_string(),
+                    param: param_name.to_string(),
+                    expected: param_type,
+                    found: arg_type,
+                });
+                ret = Err(Some(r_type.clone()));
+            }
+        }
+    }
+    ret
+}
+
+fn expr(
+    left: Result<LiteralType, Option<LiteralType>>,
+    op: Opcode,
+    right: Result<LiteralType, Option<LiteralType>>,
+    context: &mut Context,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    // Determine if the left and right sub expressions evaluated to a type
+    let (l, r) = match (left, right) {
+        (Ok(l_type), Ok(r_type)) => (Some(l_type), Some(r_type)),
+        _ => (None, None),
+    };
+
+    // If left and right has a type, calculate new type
+    if let (Some(left), Some(right)) = (l, r) {
+        match op {
+            Opcode::Add | Opcode::Sub | Opcode::Div | Opcode::Mul => num_expr(left, op, right, err),
+            Opcode::AND | Opcode::OR => log_op(left, op, right, err),
+            Opcode::EQ | Opcode::NEQ | Opcode::GT | Opcode::LT | Opcode::LEQ | Opcode::GEQ => {
+                rel_op(left, op, right, context, err)
+            }
+        }
+    } else {
+        Err(None)
+    }
+}
+
+fn num_expr(
+    left: LiteralType,
+    op: Opcode,
+    right: LiteralType,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    if let Some(param) = operand_param(&left, &right) {
+        err.insert_err(ErrorKind::OpOnParam { op: op, param: param });
+        return Err(None);
+    }
+    if let (LiteralType::I32, LiteralType::I32) = (&left, &right) {
+        return Ok(LiteralType::I32);
+    }
+    // The operand which is not an i32 is reported, and the result is the error type so that
+    // the expression it is used in is not reported as well
+    let typ = if left == LiteralType::I32 { right } else { left };
+    err.insert_err(ErrorKind::OpWrongType { op: op, typ: typ });
+    Err(None)
+}
+
+fn log_op(
+    left: LiteralType,
+    op: Opcode,
+    right: LiteralType,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    if let Some(param) = operand_param(&left, &right) {
+        err.insert_err(ErrorKind::OpOnParam { op: op, param: param });
+        return Err(None);
+    }
+    if let (LiteralType::Bool, LiteralType::Bool) = (&left, &right) {
+        return Ok(LiteralType::Bool);
+    }
+    let typ = if left == LiteralType::Bool { right } else { left };
+    err.insert_err(ErrorKind::OpWrongType { op: op, typ: typ });
+    Err(None)
+}
+
+fn rel_op(
+    left: LiteralType,
+    op: Opcode,
+    right: LiteralType,
+    context: &mut Context,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    // Type parameters can be compared if their type arguments can be compared, which is
+    // checked after all of the calls have been type checked
+    if let (LiteralType::Param(left), LiteralType::Param(right)) = (&left, &right) {
+        if left == right {
+            let stmnt = context.stmnt_errors.len().saturating_sub(1);
+            context.param_ops.push((left.to_string(), op, stmnt));
+            return Ok(LiteralType::Bool);
+        }
+    }
+    if let Some(param) = operand_param(&left, &right) {
+        err.insert_err(ErrorKind::OpOnParam { op: op, param: param });
+        return Err(None);
+    }
+
+    match op {
+        Opcode::EQ | Opcode::NEQ => {
+            // '==' and '!=' can compare bools or i32s, otherwise type error
+            if let (LiteralType::Bool, LiteralType::Bool) | (LiteralType::I32, LiteralType::I32) =
+                (&left, &right)
+            {
+                return Ok(LiteralType::Bool);
+            } else {
+                // Relational operations expects right to be same type as left
+                err.insert_err(ErrorKind::MismatchedTypesOp {
+                    op: op,
+                    found: right,
+                    expected: left,
+                });
+            }
+            Err(None)
+        }
+        Opcode::GT | Opcode::LT | Opcode::LEQ | Opcode::GEQ => {
+            // '>', '<', '<=' and '>=' can compare i32s, otherwise type error
+            if let (LiteralType::I32, LiteralType::I32) = (&left, &right) {
+                return Ok(LiteralType::Bool);
+            }
+            let typ = if left == LiteralType::I32 { right } else { left };
+            err.insert_err(ErrorKind::OpWrongType { op: op, typ: typ });
+            Err(None)
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Gets the type parameter of an operand, the operators can only be applied to a type
+/// parameter by comparing it to a value of the same type parameter
+fn operand_param(left: &LiteralType, right: &LiteralType) -> Option<String> {
+    match (left, right) {
+        (LiteralType::Param(param), _) | (_, LiteralType::Param(param)) => Some(param.to_string()),
+        _ => None,
+    }
+}
+
+fn unary_op(
+    expr: Result<LiteralType, Option<LiteralType>>,
+    err: &mut TypeErrors,
+) -> Result<LiteralType, Option<LiteralType>> {
+    let expr = get_type!(expr);
+
+    if let Some(expr_type) = expr {
+        if expr_type != LiteralType::I32 {
+            err.insert_err(ErrorKind::UnaryOpWrongType { typ: expr_type });
+        } else {
+            return Ok(LiteralType::I32);
+        }
+    }
+    Err(None)
+}
+
+// --------------------------- TESTS ---------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::program_parser::{parse, parse_with_lines};
+
+    #[test]
+    fn op_type_num_expr_bool() {
+        let input = parse(
+            "fn main() {
+				let a: i32 = 1 + true;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::OpWrongType {
+            op: Opcode::Add,
+            typ: LiteralType::Bool,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn op_type_num_expr_num() {
+        let input = parse(
+            "fn main() {
+				let a: i32 = 1 + 2;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(type_check(input).is_ok());
+    }
+
+    #[test]
+    fn op_type_rel_expr_num() {
+        let input = parse(
+            "fn main() {
+				let a: bool = 1 > 2;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(type_check(input).is_ok());
+    }
+
+    #[test]
+    fn op_type_rel_expr_bool() {
+        let input = parse(
+            "fn main() {
+				let a: bool = 1 > false;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::OpWrongType {
+            op: Opcode::GT,
+            typ: LiteralType::Bool,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn op_type_log_expr_mix() {
+        let input = parse(
+            "fn main() {
+				let a: bool = 1 == false;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MismatchedTypesOp {
+            op: Opcode::EQ,
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+
+        let input = parse(
+            "fn main() {
+				let a: bool = false != 1;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MismatchedTypesOp {
+            op: Opcode::NEQ,
+            expected: LiteralType::Bool,
+            found: LiteralType::I32,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn let_i32() {
+        let input = parse(
+            "fn main() {
+				let a: i32 = 1 + 2;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(type_check(input).is_ok());
+    }
+
+    #[test]
+    fn let_bool() {
+        let input = parse(
+            "fn main() {
+				let a: bool = true || false;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(type_check(input).is_ok());
+    }
+
+    #[test]
+    fn mm_let_i32() {
+        let input = parse(
+            "fn main() {
+				let a: i32 = 1 > 5;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("a"),
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn mm_let_bool() {
+        let input = parse(
+            "fn main() {
+				let a: bool = 1 + 5;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("a"),
+            expected: LiteralType::Bool,
+            found: LiteralType::I32,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn var_not_in_scope() {
+        let input = parse(
+            "fn main() {
+				let mut a: bool = true;
+				let b: bool = a && c;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::VarNotInScope {
+            var: String::from("c"),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn var_in_scope() {
+        let input = parse(
+            "fn main() {
+				let mut a: bool = true;
+				let mut c: bool = false;
+				let b: bool = a && c;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(type_check(input).is_ok());
+    }
+
+    #[test]
+    fn var_let_mm() {
+        let input = parse(
+            "fn main() {
+				let mut a: bool = true;
+				let mut b: bool = false;
+				let c: i32 = a && b;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("c"),
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn var_update() {
+        let input = parse(
+            "fn main() {
+				let mut a: bool = true;
+				let b: i32 = 0;
+				let mut c: i32 = false;
+				b = a && c;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("c"),
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        errors.insert_err(ErrorKind::OpWrongType {
+            op: Opcode::AND,
+            typ: LiteralType::I32,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+
+        let input = parse(
+            "fn main() {
+				let mut a: bool = true;
+				let b: i32 = 0;
+				let mut c: i32 = false;
+				let b: i32 = a && c;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("c"),
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        errors.insert_err(ErrorKind::OpWrongType {
+            op: Opcode::AND,
+            typ: LiteralType::I32,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+
+        let input = parse(
+            "fn main() {
+				let mut a: bool = true;
+				let b: bool = false;
+				let mut c: bool = false;
+				b = a && c;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::VarImmut {
+            var: String::from("b"),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn fn_params() {
+        let input = parse(
+            "fn main() {
+				let a: i32 = 5;
+				let b: bool = false;
+				test(a);
+				let c: i32 = 4;
+			}
+			
+			fn test(a: i32, b: i32) {
+				let c: i32 = 5;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::FnNumParamMismatch {
+            name: String::from("test"),
+            takes: 2,
+            supplied: 1,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+
+        let input = parse(
+            "fn main() {
+				let a: i32 = 5;
+				let b: bool = false;
+				test(a, b);
+				let c: i32 = 4;
+			}
+			
+			fn test(a: i32, b: i32) {
+				let c: i32 = 5;	
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::FnParamTypeMismatch {
+            name: String::from("test"),
+            param: String::from("b"),
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn fn_return() {
+        let input = parse(
+            "fn main() {
+				test();
+			}
+			
+			fn test() -> i32 {
+                return true;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::FnReturnMismatch {
+            name: String::from("test"),
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+
+        let input = parse(
+            "fn main() {
+				test();
+			}
+			
+			fn test() -> i32 {
+                if (true) {
+                    return 1;
+                }
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::FnMissingReturn {
+            name: String::from("test"),
+            r_type: LiteralType::I32,
+            stmnt: String::from("if"),
+            stmnt_idx: 0,
+            line: None,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+
+        let input = parse(
+            "fn main() {
+				test();
+			}
+			
+			fn test() -> i32 {
+                if (true) {
+                    print(0);
+                } else {
+                    return 1;
+                }
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::FnMissingReturn {
+            name: String::from("test"),
+            r_type: LiteralType::I32,
+            stmnt: String::from("print"),
+            stmnt_idx: 1,
+            line: None,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+
+        let input = parse(
+            "fn main() {
+				test();
+			}
+			
+			fn test() -> i32 {
+                let mut x: i32 = 0;
+                while (true) {
+                    x = x + 1;
+                    if (x > 10) {
+                        return x;
+                    }
+                }
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(type_check(input).is_ok());
+
+        let input = parse(
+            "fn main() {
+				test();
+			}
+			
+			fn test() -> i32 {
+                if (true) {
+                    return 0;
+                }
+                return 1;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(type_check(input).is_ok());
+
+        // The error is located at the line of the statement after which the end is reached
+        let (input, lines) = parse_with_lines(
+            "fn test(x: i32) -> i32 {
+                if (x > 0) {
+                    return 0;
+                } else {
+                    print(x);
+                }
+            }"
+            .to_string(),
+        )
+        .unwrap();
+        let errors = infer_types(input).unwrap_err().locate(&lines);
+        assert_eq!(
+            errors.errors[0].to_string(),
+            "Function 'test' implicitly returns '()' when its end is reached after the print \
+             statement on line 5
+                Note: expected type 'i32' but found '()'"
+        );
+    }
+
+    #[test]
+    fn cond_type() {
+        let input = parse(
+            "fn main() {
+				test();
+			}
+			
+			fn test() -> i32 {
+                if(123) {
+                    return 0;
+                } else {
+                    return 1;
+                }
+                return 1;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::Cond {
+            found: LiteralType::I32,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+
+        let input = parse(
+            "fn main() {
+				test();
+			}
+			
+			fn test() -> i32 {
+                while(123) {
+                    return 0;
+                }
+                return 1;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::Cond {
+            found: LiteralType::I32,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+
+        let input = parse(
+            "fn main() {
+				test();
+			}
+			
+			fn test() -> i32 {
+                if(true) {
+                    return 0;
+                } else if (123) {
+                    return 1;
+                }
+                return 1;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::Cond {
+            found: LiteralType::I32,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn loop_modifier_outside_loop() {
+        let input = parse(
+            "fn main() {
+                let mut i: i32 = 0;
+                while (i < 3) {
+                    i = i + 1;
+                    if (i == 1) {
+                        continue;
+                    }
+                }
+                if (i == 3) {
+                    break;
+                }
+            }"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::LoopModifierOutsideLoop {
+            name: String::from("main"),
+            modifier: String::from("break"),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn array() {
+        let input = parse(
+            "fn main() {
+				let mut a: [i32; 3] = [1, 2, 3];
+				let b: [bool; 2] = [true; 2];
+				a[0] = a[a.len() - 1];
+				let c: bool = b[1];
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(type_check(input).is_ok());
+
+        let input = parse(
+            "fn main() {
+				let a: [i32; 3] = [1, true, 3];
+				let b: [i32; 2] = [1, 2, 3];
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MismatchedTypesArray {
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("b"),
+            expected: LiteralType::Array(Box::new(LiteralType::I32), 2),
+            found: LiteralType::Array(Box::new(LiteralType::I32), 3),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn array_index() {
+        let input = parse(
+            "fn main() {
+				let a: [i32; 3] = [1, 2, 3];
+				let b: i32 = a[3];
+				let c: i32 = a[-1];
+				let d: i32 = a[true];
+				let e: i32 = d[0];
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::IndexOutOfBounds { len: 3, index: 3 });
+        errors.insert_err(ErrorKind::IndexOutOfBounds { len: 3, index: -1 });
+        errors.insert_err(ErrorKind::IndexWrongType {
+            found: LiteralType::Bool,
+        });
+        errors.insert_err(ErrorKind::IndexNotArray {
+            found: LiteralType::I32,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+
+        let input = parse(
+            "fn main() {
+				let a: [i32; 3] = [1, 2, 3];
+				a[0] = 5;
+				let mut b: [i32; 3] = a;
+				b[1] = false;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::VarImmut {
+            var: String::from("a"),
+        });
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("b"),
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn vec() {
+        let input = parse(
+            "fn main() {
+				let mut v: Vec<i32> = Vec::new();
+				v.push(1);
+				let a: i32 = v.pop() + v[0] + v.len();
+				let w: Vec<Vec<bool>> = Vec::new();
+				let b: Vec<i32> = v;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(type_check(input).is_ok());
+
+        let input = parse(
+            "fn main() {
+				let v: Vec<i32> = Vec::new();
+				v.push(true);
+				let a: bool = v.pop();
+				let b: Vec<bool> = v;
+				let c: i32 = Vec::new();
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::VarImmut {
+            var: String::from("v"),
+        });
+        errors.insert_err(ErrorKind::FnParamTypeMismatch {
+            name: String::from("push"),
+            param: String::from("value"),
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        errors.insert_err(ErrorKind::VarImmut {
+            var: String::from("v"),
+        });
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("a"),
+            expected: LiteralType::Bool,
+            found: LiteralType::I32,
+        });
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("b"),
+            expected: LiteralType::Vec(Box::new(LiteralType::Bool)),
+            found: LiteralType::Vec(Box::new(LiteralType::I32)),
+        });
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("c"),
+            expected: LiteralType::I32,
+            found: LiteralType::Vec(Box::new(LiteralType::Infer)),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn method_not_found() {
+        let input = parse(
+            "fn main() {
+				let a: i32 = 5;
+				let b: i32 = a.len();
+				let mut c: [i32; 2] = [1, 2];
+				c.push(3);
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MethodNotFound {
+            name: String::from("len"),
+            typ: LiteralType::I32,
+        });
+        errors.insert_err(ErrorKind::MethodNotFound {
+            name: String::from("push"),
+            typ: LiteralType::Array(Box::new(LiteralType::I32), 2),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn arith_methods() {
+        let input = parse(
+            "fn main() -> i32 {
+				let a: i32 = 5;
+				let b: bool = true;
+				let c: i32 = b.wrapping_add(1);
+				return a.saturating_mul(2).wrapping_sub(b);
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MethodNotFound {
+            name: String::from("wrapping_add"),
+            typ: LiteralType::Bool,
+        });
+        errors.insert_err(ErrorKind::FnParamTypeMismatch {
+            name: String::from("wrapping_sub"),
+            param: String::from("rhs"),
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn struct_fields() {
+        let input = parse(
+            "struct Point {
+				x: i32,
+				y: i32,
+			}
+
+			fn main() {
+				let mut p: Point = Point { x: 1, y: 2 };
+				p.x = p.y;
+				let b: bool = p.x;
+				p.z = 3;
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("b"),
+            expected: LiteralType::Bool,
+            found: LiteralType::I32,
         });
+        errors.insert_err(ErrorKind::StructFieldNotFound {
+            strct: String::from("Point"),
+            field: String::from("z"),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
     }
-    Err(Some(LiteralType::Bool))
-}
 
-fn rel_op(
-    left: LiteralType,
-    op: Opcode,
-    right: LiteralType,
-    err: &mut TypeErrors,
-) -> Result<LiteralType, Option<LiteralType>> {
-    match op {
-        Opcode::EQ | Opcode::NEQ => {
-            // '==' and '!=' can compare bools or i32s, otherwise type error
-            if let (LiteralType::Bool, LiteralType::Bool) | (LiteralType::I32, LiteralType::I32) =
-                (left, right)
-            {
-                return Ok(LiteralType::Bool);
-            } else {
-                // Relational operations expects right to be same type as left
-                err.insert_err(ErrorKind::MismatchedTypesOp {
-                    op: op,
-                    found: right,
-                    expected: left,
-                });
-            }
-            return Err(Some(LiteralType::Bool));
-        }
-        Opcode::GT | Opcode::LT | Opcode::LEQ | Opcode::GEQ => {
-            // '>', '<', '<=' and '>=' can compare i32s, otherwise type error
-            if let (LiteralType::I32, LiteralType::I32) = (left, right) {
-                return Ok(LiteralType::Bool);
-            } else {
-                err.insert_err(ErrorKind::OpWrongType {
-                    op: op,
-                    typ: LiteralType::Bool,
-                });
-            }
-            return Err(Some(LiteralType::Bool));
-        }
-        _ => unreachable!(),
-    }
-}
+    #[test]
+    fn struct_literal() {
+        let input = parse(
+            "struct Point {
+				x: i32,
+				y: i32,
+			}
 
-fn unary_op(
-    expr: Result<LiteralType, Option<LiteralType>>,
-    err: &mut TypeErrors,
-) -> Result<LiteralType, Option<LiteralType>> {
-    let expr = get_type!(expr);
+			fn main() {
+				let a: Point = Point { x: 1, y: 2 };
+				let b: Point = Point { x: true, y: 2 };
+				let c: Point = Point { x: 1 };
+				let d: Point = Point { x: 1, y: 2, x: 3 };
+				let e: Point = Points { x: 1, y: 2 };
+			}"
+            .to_string(),
+        )
+        .unwrap();
 
-    if let Some(expr_type) = expr {
-        if expr_type != LiteralType::I32 {
-            err.insert_err(ErrorKind::UnaryOpWrongType { typ: expr_type });
-        } else {
-            return Ok(LiteralType::I32);
-        }
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MismatchedTypesField {
+            strct: String::from("Point"),
+            field: String::from("x"),
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        errors.insert_err(ErrorKind::StructFieldMissing {
+            strct: String::from("Point"),
+            field: String::from("y"),
+        });
+        errors.insert_err(ErrorKind::StructFieldDuplicate {
+            strct: String::from("Point"),
+            field: String::from("x"),
+        });
+        errors.insert_err(ErrorKind::TypeNotDefined {
+            name: String::from("Points"),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
     }
-    return Err(Some(LiteralType::I32));
-}
-
-// --------------------------- TESTS ---------------------------
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::parse::program_parser::parse;
 
     #[test]
-    fn op_type_num_expr_bool() {
+    fn struct_immut() {
         let input = parse(
-            "fn main() {
-				let a: i32 = 1 + true;	
+            "struct Point {
+				x: i32,
+				y: i32,
+			}
+
+			fn main() {
+				let p: Point = Point { x: 1, y: 2 };
+				p.x = 3;
+				let a: i32 = 1;
+				let b: i32 = a.x;
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::OpWrongType {
-            op: Opcode::Add,
-            typ: LiteralType::Bool,
+        errors.insert_err(ErrorKind::VarImmut {
+            var: String::from("p"),
+        });
+        errors.insert_err(ErrorKind::FieldNotStruct {
+            field: String::from("x"),
+            found: LiteralType::I32,
         });
         assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn op_type_num_expr_num() {
+    fn struct_decl() {
         let input = parse(
-            "fn main() {
-				let a: i32 = 1 + 2;	
+            "struct Node {
+				value: i32,
+				next: Node,
+			}
+
+			struct Tree {
+				children: Vec<Tree>,
+				data: Data,
+			}
+
+			fn main() {
+				let a: i32 = 0;
 			}"
             .to_string(),
         )
         .unwrap();
 
-        assert!(type_check(input).is_ok());
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::TypeRecursive {
+            name: String::from("Node"),
+        });
+        errors.insert_err(ErrorKind::TypeNotDefined {
+            name: String::from("Data"),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn op_type_rel_expr_num() {
+    fn struct_fn() {
         let input = parse(
-            "fn main() {
-				let a: bool = 1 > 2;	
+            "struct Point {
+				x: i32,
+				y: i32,
+			}
+
+			fn main() {
+				let p: Point = origin();
+				let x: i32 = norm(p);
+			}
+
+			fn origin() -> Point {
+				return Point { x: 0, y: 0 };
+			}
+
+			fn norm(p: Point) -> i32 {
+				return p.x * p.x + p.y * p.y;
 			}"
             .to_string(),
         )
         .unwrap();
 
-        assert!(type_check(input).is_ok());
+        assert_eq!(type_check(input), Ok(()));
     }
 
     #[test]
-    fn op_type_rel_expr_bool() {
+    fn enum_variant() {
         let input = parse(
-            "fn main() {
-				let a: bool = 1 > false;	
+            "enum Shape {
+				Square(i32),
+				Rect(i32, i32),
+				Empty,
+			}
+
+			fn main() {
+				let a: Shape = Shape::Square(1);
+				let b: Shape = Shape::Circle(1);
+				let c: Shape = Shape::Rect(1);
+				let d: Shape = Shape::Square(true);
+				let e: i32 = Shape::Empty;
+				let f: Shape = Shapes::Empty;
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::OpWrongType {
-            op: Opcode::GT,
-            typ: LiteralType::Bool,
+        errors.insert_err(ErrorKind::VariantNotFound {
+            enm: String::from("Shape"),
+            name: String::from("Circle"),
+        });
+        errors.insert_err(ErrorKind::VariantNumFieldsMismatch {
+            name: String::from("Shape::Rect"),
+            takes: 2,
+            supplied: 1,
+        });
+        errors.insert_err(ErrorKind::MismatchedTypesVariant {
+            name: String::from("Shape::Square"),
+            field: 0,
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("e"),
+            expected: LiteralType::I32,
+            found: LiteralType::Named(String::from("Shape")),
+        });
+        errors.insert_err(ErrorKind::TypeNotDefined {
+            name: String::from("Shapes"),
         });
         assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn op_type_log_expr_mix() {
+    fn match_patterns() {
         let input = parse(
-            "fn main() {
-				let a: bool = 1 == false;	
+            "enum Shape {
+				Square(i32),
+				Empty,
+			}
+
+			fn main() {
+				let s: Shape = Shape::Square(2);
+				let a: i32 = match (s) {
+					Shape::Square(true) => 1,
+					Shape::Empty => 0,
+				};
+				let b: i32 = match (1) {
+					Shape::Empty => 0,
+				};
+				let c: i32 = match (s) {
+					Shape::Square(w) => w,
+					Shape::Empty => false,
+				};
+				let d: i32 = match (s) {
+					Shape::Square(w) => w,
+					Shape::Empty => w,
+				};
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::MismatchedTypesOp {
-            op: Opcode::EQ,
+        errors.insert_err(ErrorKind::MismatchedTypesPattern {
+            pattern: String::from("true"),
+            expected: LiteralType::I32,
+        });
+        errors.insert_err(ErrorKind::MismatchedTypesPattern {
+            pattern: String::from("Shape::Empty"),
+            expected: LiteralType::I32,
+        });
+        errors.insert_err(ErrorKind::MismatchedTypesMatchArm {
             expected: LiteralType::I32,
             found: LiteralType::Bool,
         });
+        errors.insert_err(ErrorKind::VarNotInScope {
+            var: String::from("w"),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn match_exhaustive() {
+        let input = parse(
+            "enum Opt {
+				Some(bool),
+				None,
+			}
+
+			fn main() {
+				let o: Opt = Opt::None;
+				let a: i32 = match (o) {
+					Opt::Some(true) => 1,
+					Opt::None => 0,
+				};
+				let b: i32 = match (5) {
+					0 => 1,
+					1 => 2,
+				};
+				let c: i32 = match (o) {
+					_ => 1,
+					Opt::None => 0,
+				};
+				let d: i32 = match (o) {
+					Opt::Some(x) => 1,
+					Opt::Some(false) => 2,
+					Opt::None => 0,
+				};
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MatchNotExhaustive {
+            missing: String::from("Opt::Some(false)"),
+        });
+        errors.insert_err(ErrorKind::MatchNotExhaustive {
+            missing: String::from("_"),
+        });
+        errors.insert_err(ErrorKind::MatchArmUnreachable {
+            pattern: String::from("Opt::None"),
+        });
+        errors.insert_err(ErrorKind::MatchArmUnreachable {
+            pattern: String::from("Opt::Some(false)"),
+        });
         assert_eq!(type_check(input).unwrap_err(), errors);
+    }
+
+    #[test]
+    fn match_return() {
+        let input = parse(
+            "enum Opt {
+				Some(i32),
+				None,
+			}
 
+			fn unwrap_or(o: Opt, default: i32) -> i32 {
+				match (o) {
+					Opt::Some(x) => {
+						return x;
+					}
+					Opt::None => {
+						return default;
+					}
+				}
+			}
+
+			fn main() {
+				let a: i32 = unwrap_or(Opt::Some(1), 0);
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(type_check(input), Ok(()));
+    }
+
+    #[test]
+    fn tuple_fields() {
         let input = parse(
             "fn main() {
-				let a: bool = false != 1;	
+				let mut t: (i32, bool) = (1, true);
+				t.0 = t.1;
+				let b: bool = t.0;
+				let c: i32 = t.2;
+				let d: (i32, bool) = (true, 1);
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::MismatchedTypesOp {
-            op: Opcode::NEQ,
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("t"),
+            expected: LiteralType::I32,
+            found: LiteralType::Bool,
+        });
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("b"),
             expected: LiteralType::Bool,
             found: LiteralType::I32,
         });
+        errors.insert_err(ErrorKind::FieldNotStruct {
+            field: String::from("2"),
+            found: LiteralType::Tuple(vec![LiteralType::I32, LiteralType::Bool]),
+        });
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("d"),
+            expected: LiteralType::Tuple(vec![LiteralType::I32, LiteralType::Bool]),
+            found: LiteralType::Tuple(vec![LiteralType::Bool, LiteralType::I32]),
+        });
         assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn let_i32() {
+    fn poison_type() {
+        let programs = vec![
+            (
+                "fn main() {
+                    let (a, b) = 5;
+                    let c: bool = a;
+                    let d: i32 = b + 1;
+                }",
+                ErrorKind::MismatchedTupleBinding {
+                    expected: LiteralType::I32,
+                    bindings: 2,
+                },
+            ),
+            (
+                "fn main() {
+                    let m: T = 5;
+                    let n: i32 = m.x + 1;
+                }",
+                ErrorKind::TypeNotDefined {
+                    name: String::from("T"),
+                },
+            ),
+            (
+                "fn main() {
+                    let n: i32 = test(1) + 1;
+                }
+
+                fn test(x: U) -> i32 {
+                    return x.y;
+                }",
+                ErrorKind::TypeNotDefined {
+                    name: String::from("U"),
+                },
+            ),
+            (
+                "enum E { A(i32) }
+
+                fn main() {
+                    let o = E::B(1);
+                    let p: i32 = match (o) { E::A(x) => x + 1, _ => 0 };
+                    match (o) {
+                        E::A(y) => {
+                            print(y);
+                        }
+                    }
+                }",
+                ErrorKind::VariantNotFound {
+                    enm: String::from("E"),
+                    name: String::from("B"),
+                },
+            ),
+            (
+                "fn main() {
+                    let mut v = Vec::new();
+                    let w: bool = v.len();
+                    v.push(true);
+                }",
+                ErrorKind::CannotInfer {
+                    var: String::from("v"),
+                    found: LiteralType::Vec(Box::new(LiteralType::Infer)),
+                },
+            ),
+            (
+                "enum E { A(bool) }
+
+                fn main() {
+                    let y = z;
+                    let a: i32 = [y, 1][0] + y.len();
+                    let b: bool = match (y) { E::A(u) => u, _ => y };
+                    let c = y(1, 2);
+                }",
+                ErrorKind::VarNotInScope {
+                    var: String::from("z"),
+                },
+            ),
+            (
+                "fn main() {
+                    let x: bool = 1 + true;
+                }",
+                ErrorKind::OpWrongType {
+                    op: Opcode::Add,
+                    typ: LiteralType::Bool,
+                },
+            ),
+        ];
+
+        for (input, expected) in programs.into_iter() {
+            let mut errors = TypeErrors::new();
+            errors.insert_err(expected);
+            assert_eq!(type_check(parse(input.to_string()).unwrap()).unwrap_err(), errors);
+        }
+    }
+
+    #[test]
+    fn tuple_binding() {
         let input = parse(
-            "fn main() {
-				let a: i32 = 1 + 2;	
+            "fn divmod(a: i32, b: i32) -> (i32, i32) {
+				return (a / b, a - a / b * b);
+			}
+
+			fn main() {
+				let (q, r): (i32, i32) = divmod(7, 2);
+				let (x, y): (i32, bool) = divmod(7, 2);
+				let (u, v, w): (i32, i32) = divmod(7, 2);
+				let z: i32 = q + r + w;
+				q = 1;
 			}"
             .to_string(),
         )
         .unwrap();
 
-        assert!(type_check(input).is_ok());
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::MismatchedTypesVar {
+            var: String::from("(x, y)"),
+            expected: LiteralType::Tuple(vec![LiteralType::I32, LiteralType::Bool]),
+            found: LiteralType::Tuple(vec![LiteralType::I32, LiteralType::I32]),
+        });
+        errors.insert_err(ErrorKind::MismatchedTupleBinding {
+            expected: LiteralType::Tuple(vec![LiteralType::I32, LiteralType::I32]),
+            bindings: 3,
+        });
+        errors.insert_err(ErrorKind::VarImmut {
+            var: String::from("q"),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn let_bool() {
+    fn ref_types() {
         let input = parse(
-            "fn main() {
-				let a: bool = true || false;	
+            "struct Holder {
+				r: &i32,
+			}
+
+			fn inc(a: &mut i32) {
+				*a = *a + 1;
+			}
+
+			fn main() {
+				let x: i32 = 1;
+				let mut y: i32 = 2;
+				inc(&mut x);
+				inc(&y);
+				let r: &i32 = &y;
+				*r = 3;
+				let z: i32 = *y;
+				let v: Vec<&i32> = Vec::new();
+				let s: &mut i32 = &mut y;
+				let t: &i32 = s;
 			}"
             .to_string(),
         )
         .unwrap();
 
-        assert!(type_check(input).is_ok());
+        let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::RefNotAllowed {
+            typ: LiteralType::Named(String::from("Holder")),
+        });
+        errors.insert_err(ErrorKind::RefMutImmut {
+            var: String::from("x"),
+        });
+        errors.insert_err(ErrorKind::FnParamTypeMismatch {
+            name: String::from("inc"),
+            param: String::from("a"),
+            expected: LiteralType::Ref(Box::new(LiteralType::I32), true),
+            found: LiteralType::Ref(Box::new(LiteralType::I32), false),
+        });
+        errors.insert_err(ErrorKind::AssignSharedRef {
+            var: String::from("r"),
+        });
+        errors.insert_err(ErrorKind::DerefNotRef {
+            found: LiteralType::I32,
+        });
+        errors.insert_err(ErrorKind::RefNotAllowed {
+            typ: LiteralType::Vec(Box::new(LiteralType::Ref(Box::new(LiteralType::I32), false))),
+        });
+        assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn mm_let_i32() {
+    fn infer_let() {
         let input = parse(
+            "fn pair() -> (i32, bool) {
+				return (1, true);
+			}
+
+			fn main() {
+				let mut x = 1;
+				if (x > 0) {
+					let (a, b) = pair();
+					let r = &mut x;
+				}
+				let v = [x > 1; 2];
+			}"
+            .to_string(),
+        )
+        .unwrap();
+
+        let typed = infer_types(input).unwrap();
+        let typed_main = typed
+            .iter()
+            .find(|item| match &***item {
+                Node::Func { name, .. } => name == "main",
+                _ => false,
+            })
+            .unwrap();
+        let expected = parse(
             "fn main() {
-				let a: i32 = 1 > 5;	
+				let mut x: i32 = 1;
+				if (x > 0) {
+					let (a, b): (i32, bool) = pair();
+					let r: &mut i32 = &mut x;
+				}
+				let v: [bool; 2] = [x > 1; 2];
+			}"
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(typed_main, &expected[0]);
+    }
+
+    #[test]
+    fn infer_let_unknown() {
+        let input = parse(
+            "fn nothing() {
+				let a: i32 = 1;
+			}
+
+			fn main() {
+				let v = Vec::new();
+				let n = nothing();
+				let m = n + 1;
+				let w: Vec<i32> = v;
+				let (a, b) = 1;
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::MismatchedTypesVar {
-            var: String::from("a"),
+        errors.insert_err(ErrorKind::CannotInfer {
+            var: String::from("v"),
+            found: LiteralType::Vec(Box::new(LiteralType::Infer)),
+        });
+        errors.insert_err(ErrorKind::CannotInfer {
+            var: String::from("n"),
+            found: LiteralType::Void,
+        });
+        errors.insert_err(ErrorKind::MismatchedTupleBinding {
             expected: LiteralType::I32,
-            found: LiteralType::Bool,
+            bindings: 2,
         });
         assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn mm_let_bool() {
+    fn generic_fn() {
         let input = parse(
-            "fn main() {
-				let a: bool = 1 + 5;	
+            "fn id<T>(x: T) -> T {
+				return x;
+			}
+
+			fn first<T>(v: Vec<T>) -> T {
+				let mut v = v;
+				return v.pop();
+			}
+
+			fn main() {
+				let a: i32 = id(1);
+				let b: bool = id(a);
+				let c = id((a, true)).1 && b;
+				let n: i32 = first(Vec::new());
+				let m = id(1, 2);
 			}"
             .to_string(),
         )
@@ -753,103 +3399,211 @@ mod tests {
 
         let mut errors = TypeErrors::new();
         errors.insert_err(ErrorKind::MismatchedTypesVar {
-            var: String::from("a"),
+            var: String::from("b"),
             expected: LiteralType::Bool,
             found: LiteralType::I32,
         });
+        errors.insert_err(ErrorKind::TypeArgNotInferred {
+            name: String::from("first"),
+            param: String::from("T"),
+        });
+        errors.insert_err(ErrorKind::FnNumParamMismatch {
+            name: String::from("id"),
+            takes: 1,
+            supplied: 2,
+        });
         assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn var_not_in_scope() {
+    fn generic_ops() {
         let input = parse(
-            "fn main() {
-				let mut a: bool = true;
-				let b: bool = a && c;
+            "struct P {
+				x: i32,
+			}
+
+			fn less<T>(a: T, b: T) -> bool {
+				return a < b;
+			}
+
+			fn eq<T>(a: T, b: T) -> bool {
+				return a == b;
+			}
+
+			fn sum<T>(a: T, b: T) -> T {
+				return a + b;
+			}
+
+			fn both<T>(a: T, b: bool) -> bool {
+				return a && b;
+			}
+
+			fn sorted<U>(a: U, b: U, c: U) -> bool {
+				return less(a, b) && less(b, c);
+			}
+
+			fn main() {
+				let a: bool = less(1, 2) && eq(true, false);
+				let b: bool = eq(P { x: 1 }, P { x: 2 });
+				let c: bool = sorted(true, false, true);
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::VarNotInScope {
-            var: String::from("c"),
+        errors.insert_err(ErrorKind::TypeArgOp {
+            name: String::from("eq"),
+            param: String::from("T"),
+            typ: LiteralType::Named(String::from("P")),
+            op: Opcode::EQ,
+        });
+        errors.insert_err(ErrorKind::OpOnParam {
+            op: Opcode::Add,
+            param: String::from("T"),
+        });
+        errors.insert_err(ErrorKind::OpOnParam {
+            op: Opcode::AND,
+            param: String::from("T"),
+        });
+        errors.insert_err(ErrorKind::TypeArgOp {
+            name: String::from("sorted"),
+            param: String::from("U"),
+            typ: LiteralType::Bool,
+            op: Opcode::LT,
         });
         assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn var_in_scope() {
+    fn closure_types() {
         let input = parse(
-            "fn main() {
-				let mut a: bool = true;
-				let mut c: bool = false;
-				let b: bool = a && c;
-			}"
-            .to_string(),
-        )
-        .unwrap();
+            "fn double(x: i32) -> i32 {
+				return x * 2;
+			}
 
-        assert!(type_check(input).is_ok());
-    }
+			fn id<T>(x: T) -> T {
+				return x;
+			}
 
-    #[test]
-    fn var_let_mm() {
-        let input = parse(
-            "fn main() {
-				let mut a: bool = true;
-				let mut b: bool = false;
-				let c: i32 = a && b;
+			fn main() {
+				let n = 1;
+				let r = &n;
+				let f = |x: i32| x + *r;
+				let g: fn(i32) -> bool = double;
+				let h = id;
+				let a: i32 = n(1);
+				let b: i32 = f(true);
+				let c = f(1, 2);
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
+        errors.insert_err(ErrorKind::CaptureRef {
+            var: String::from("r"),
+            typ: LiteralType::Ref(Box::new(LiteralType::I32), false),
+        });
         errors.insert_err(ErrorKind::MismatchedTypesVar {
-            var: String::from("c"),
+            var: String::from("g"),
+            expected: LiteralType::Fn(vec![LiteralType::I32], Box::new(LiteralType::Bool)),
+            found: LiteralType::Fn(vec![LiteralType::I32], Box::new(LiteralType::I32)),
+        });
+        errors.insert_err(ErrorKind::TypeArgNotInferred {
+            name: String::from("id"),
+            param: String::from("T"),
+        });
+        errors.insert_err(ErrorKind::NotCallable {
+            name: String::from("n"),
+            found: LiteralType::I32,
+        });
+        errors.insert_err(ErrorKind::FnParamTypeMismatch {
+            name: String::from("f"),
+            param: String::from("0"),
             expected: LiteralType::I32,
             found: LiteralType::Bool,
         });
+        errors.insert_err(ErrorKind::FnNumParamMismatch {
+            name: String::from("f"),
+            takes: 1,
+            supplied: 2,
+        });
         assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn var_update() {
+    fn source_order() {
         let input = parse(
-            "fn main() {
-				let mut a: bool = true;
-				let b: i32 = 0;
-				let mut c: i32 = false;
-				b = a && c;
+            "struct S {
+				x: T,
+			}
+
+			fn b() -> i32 {
+				return true;
+			}
+
+			fn main() {
+				let x: i32 = y;
+				b();
+				a(1);
+			}
+
+			fn a(x: bool) {
+				let z: i32 = x + 1;
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::MismatchedTypesVar {
-            var: String::from("c"),
+        errors.insert_err(ErrorKind::TypeNotDefined {
+            name: String::from("T"),
+        });
+        errors.insert_err(ErrorKind::FnReturnMismatch {
+            name: String::from("b"),
             expected: LiteralType::I32,
             found: LiteralType::Bool,
         });
-        errors.insert_err(ErrorKind::OpWrongType {
-            op: Opcode::AND,
-            typ: LiteralType::I32,
+        errors.insert_err(ErrorKind::VarNotInScope {
+            var: String::from("y"),
         });
-        errors.insert_err(ErrorKind::MismatchedTypesVar {
-            var: String::from("b"),
-            expected: LiteralType::I32,
-            found: LiteralType::Bool,
+        errors.insert_err(ErrorKind::FnParamTypeMismatch {
+            name: String::from("a"),
+            param: String::from("x"),
+            expected: LiteralType::Bool,
+            found: LiteralType::I32,
         });
-        assert_eq!(type_check(input).unwrap_err(), errors);
+        errors.insert_err(ErrorKind::OpWrongType {
+            op: Opcode::Add,
+            typ: LiteralType::Bool,
+        });
+
+        // The order must not depend on the order of the functions in a HashMap
+        for _ in 0..10 {
+            assert_eq!(type_check(input.clone()).unwrap_err(), errors);
+        }
 
+        // The errors of a function are in the order of its statements
         let input = parse(
-            "fn main() {
-				let mut a: bool = true;
-				let b: i32 = 0;
-				let mut c: i32 = false;
-				let b: i32 = a && c;
+            "struct P {
+				x: i32,
+			}
+
+			fn eq<T>(a: T, b: T) -> bool {
+				let c: i32 = true;
+				let d: bool = a == b;
+				return c;
+			}
+
+			fn main() {
+				while (1) {
+					let e: bool = eq(P { x: 1 }, P { x: 2 });
+				}
+				if (true) {
+					break;
+				}
+				let f: i32 = false;
 			}"
             .to_string(),
         )
@@ -861,138 +3615,135 @@ mod tests {
             expected: LiteralType::I32,
             found: LiteralType::Bool,
         });
-        errors.insert_err(ErrorKind::OpWrongType {
-            op: Opcode::AND,
-            typ: LiteralType::I32,
+        errors.insert_err(ErrorKind::TypeArgOp {
+            name: String::from("eq"),
+            param: String::from("T"),
+            typ: LiteralType::Named(String::from("P")),
+            op: Opcode::EQ,
+        });
+        errors.insert_err(ErrorKind::FnReturnMismatch {
+            name: String::from("eq"),
+            expected: LiteralType::Bool,
+            found: LiteralType::I32,
+        });
+        errors.insert_err(ErrorKind::Cond {
+            found: LiteralType::I32,
+        });
+        errors.insert_err(ErrorKind::LoopModifierOutsideLoop {
+            name: String::from("main"),
+            modifier: String::from("break"),
         });
         errors.insert_err(ErrorKind::MismatchedTypesVar {
-            var: String::from("b"),
+            var: String::from("f"),
             expected: LiteralType::I32,
             found: LiteralType::Bool,
         });
         assert_eq!(type_check(input).unwrap_err(), errors);
-
-        let input = parse(
-            "fn main() {
-				let mut a: bool = true;
-				let b: bool = false;
-				let mut c: bool = false;
-				b = a && c;
-			}"
-            .to_string(),
-        )
-        .unwrap();
-
-        let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::VarImmut {
-            var: String::from("b"),
-        });
-        assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn fn_params() {
+    fn fn_duplicates() {
         let input = parse(
             "fn main() {
-				let a: i32 = 5;
-				let b: bool = false;
-				test(a);
-				let c: i32 = 4;
+				let f = |x: i32, x: i32| x;
+				test(1, true);
 			}
-			
+
+			fn test(a: i32, a: bool) {
+				print(1);
+			}
+
 			fn test(a: i32, b: i32) {
-				let c: i32 = 5;	
+				print(a + b);
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::FnNumParamMismatch {
+        errors.insert_err(ErrorKind::ParamDuplicate {
+            name: String::from("closure"),
+            param: String::from("x"),
+        });
+        errors.insert_err(ErrorKind::ParamDuplicate {
+            name: String::from("test"),
+            param: String::from("a"),
+        });
+        errors.insert_err(ErrorKind::FnDefinedMultiple {
             name: String::from("test"),
-            takes: 2,
-            supplied: 1,
         });
         assert_eq!(type_check(input).unwrap_err(), errors);
+    }
 
+    #[test]
+    fn type_duplicates() {
         let input = parse(
-            "fn main() {
-				let a: i32 = 5;
-				let b: bool = false;
-				test(a, b);
-				let c: i32 = 4;
+            "struct P {
+				x: i32,
 			}
-			
-			fn test(a: i32, b: i32) {
-				let c: i32 = 5;	
+
+			enum E {
+				A,
+			}
+
+			struct P {
+				y: bool,
+			}
+
+			struct E {
+				z: i32,
+			}
+
+			fn main() {
+				let p: P = P { x: 1 };
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::FnParamTypeMismatch {
-            name: String::from("test"),
-            param: String::from("b"),
-            expected: LiteralType::I32,
-            found: LiteralType::Bool,
+        errors.insert_err(ErrorKind::TypeDefinedMultiple {
+            name: String::from("P"),
+        });
+        errors.insert_err(ErrorKind::TypeDefinedMultiple {
+            name: String::from("E"),
         });
         assert_eq!(type_check(input).unwrap_err(), errors);
     }
 
     #[test]
-    fn fn_return() {
+    fn main_signature() {
         let input = parse(
-            "fn main() {
-				test();
-			}
-			
-			fn test() -> i32 {
-                return true;
+            "fn test() -> i32 {
+				return 1;
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::FnReturnMismatch {
-            name: String::from("test"),
-            expected: LiteralType::I32,
-            found: LiteralType::Bool,
-        });
+        errors.insert_err(ErrorKind::MainMissing);
         assert_eq!(type_check(input).unwrap_err(), errors);
 
         let input = parse(
-            "fn main() {
-				test();
-			}
-			
-			fn test() -> i32 {
-                if (true) {
-                    return 1;
-                }
+            "fn main<T>(x: i32) -> bool {
+				return x > 0;
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::FnMissingReturn {
-            name: String::from("test"),
-            r_type: LiteralType::I32,
+        errors.insert_err(ErrorKind::MainGeneric);
+        errors.insert_err(ErrorKind::MainParams { found: 1 });
+        errors.insert_err(ErrorKind::MainReturnType {
+            found: LiteralType::Bool,
         });
         assert_eq!(type_check(input).unwrap_err(), errors);
 
         let input = parse(
-            "fn main() {
-				test();
-			}
-			
-			fn test() -> i32 {
-                if (true) {
-                    return 0;
-                }
-                return 1;
+            "fn main() -> i32 {
+				return 0;
 			}"
             .to_string(),
         )
@@ -1002,71 +3753,27 @@ mod tests {
     }
 
     #[test]
-    fn cond_type() {
+    fn fn_caller_var() {
         let input = parse(
-            "fn main() {
-				test();
+            "fn peek() -> i32 {
+				secret = 5;
+				return secret;
 			}
-			
-			fn test() -> i32 {
-                if(123) {
-                    return 0;
-                } else {
-                    return 1;
-                }
-                return 1;
-			}"
-            .to_string(),
-        )
-        .unwrap();
-
-        let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::Cond {
-            found: LiteralType::I32,
-        });
-        assert_eq!(type_check(input).unwrap_err(), errors);
 
-        let input = parse(
-            "fn main() {
-				test();
-			}
-			
-			fn test() -> i32 {
-                while(123) {
-                    return 0;
-                }
-                return 1;
+			fn main() -> i32 {
+				let mut secret = 1;
+				return peek();
 			}"
             .to_string(),
         )
         .unwrap();
 
         let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::Cond {
-            found: LiteralType::I32,
+        errors.insert_err(ErrorKind::VarNotInScope {
+            var: String::from("secret"),
         });
-        assert_eq!(type_check(input).unwrap_err(), errors);
-
-        let input = parse(
-            "fn main() {
-				test();
-			}
-			
-			fn test() -> i32 {
-                if(true) {
-                    return 0;
-                } else if (123) {
-                    return 1;
-                }
-                return 1;
-			}"
-            .to_string(),
-        )
-        .unwrap();
-
-        let mut errors = TypeErrors::new();
-        errors.insert_err(ErrorKind::Cond {
-            found: LiteralType::I32,
+        errors.insert_err(ErrorKind::VarNotInScope {
+            var: String::from("secret"),
         });
         assert_eq!(type_check(input).unwrap_err(), errors);
     }
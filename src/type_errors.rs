@@ -1,6 +1,8 @@
 use std::fmt;
 
-use crate::{operators::Opcode, types::LiteralType};
+use crate::{
+    lint::Lint, operators::Opcode, parse::program_parser::FuncLines, types::LiteralType,
+};
 
 #[derive(Debug, PartialEq)]
 pub struct TypeErrors {
@@ -51,13 +53,178 @@ pub enum ErrorKind {
         expected: LiteralType,
         found: LiteralType,
     },
+    // The end of the function is reached after a statement, which is given by its kind, its
+    // index in the function and its line once it is known
     FnMissingReturn {
         name: String,
         r_type: LiteralType,
+        stmnt: String,
+        stmnt_idx: usize,
+        line: Option<usize>,
+    },
+    FnDefinedMultiple {
+        name: String,
+    },
+    ParamDuplicate {
+        name: String,
+        param: String,
+    },
+    MainMissing,
+    MainGeneric,
+    MainParams {
+        found: usize,
+    },
+    MainReturnType {
+        found: LiteralType,
     },
     Cond {
         found: LiteralType,
     },
+    LoopModifierOutsideLoop {
+        name: String,
+        modifier: String,
+    },
+    MismatchedTypesArray {
+        expected: LiteralType,
+        found: LiteralType,
+    },
+    IndexWrongType {
+        found: LiteralType,
+    },
+    IndexNotArray {
+        found: LiteralType,
+    },
+    IndexOutOfBounds {
+        len: usize,
+        index: i32,
+    },
+    MethodNotFound {
+        name: String,
+        typ: LiteralType,
+    },
+    TypeNotDefined {
+        name: String,
+    },
+    TypeRecursive {
+        name: String,
+    },
+    TypeDefinedMultiple {
+        name: String,
+    },
+    StructFieldNotFound {
+        strct: String,
+        field: String,
+    },
+    StructFieldMissing {
+        strct: String,
+        field: String,
+    },
+    StructFieldDuplicate {
+        strct: String,
+        field: String,
+    },
+    MismatchedTypesField {
+        strct: String,
+        field: String,
+        expected: LiteralType,
+        found: LiteralType,
+    },
+    FieldNotStruct {
+        field: String,
+        found: LiteralType,
+    },
+    VariantNotFound {
+        enm: String,
+        name: String,
+    },
+    VariantNumFieldsMismatch {
+        name: String,
+        takes: usize,
+        supplied: usize,
+    },
+    MismatchedTypesVariant {
+        name: String,
+        field: usize,
+        expected: LiteralType,
+        found: LiteralType,
+    },
+    MismatchedTypesPattern {
+        pattern: String,
+        expected: LiteralType,
+    },
+    MismatchedTypesMatchArm {
+        expected: LiteralType,
+        found: LiteralType,
+    },
+    MatchNotExhaustive {
+        missing: String,
+    },
+    MatchArmUnreachable {
+        pattern: String,
+    },
+    MismatchedTupleBinding {
+        expected: LiteralType,
+        bindings: usize,
+    },
+    DerefNotRef {
+        found: LiteralType,
+    },
+    RefMutImmut {
+        var: String,
+    },
+    AssignSharedRef {
+        var: String,
+    },
+    RefNotAllowed {
+        typ: LiteralType,
+    },
+    CannotInfer {
+        var: String,
+        found: LiteralType,
+    },
+    TypeArgNotInferred {
+        name: String,
+        param: String,
+    },
+    TypeArgOp {
+        name: String,
+        param: String,
+        typ: LiteralType,
+        op: Opcode,
+    },
+    OpOnParam {
+        op: Opcode,
+        param: String,
+    },
+    CaptureRef {
+        var: String,
+        typ: LiteralType,
+    },
+    NotCallable {
+        name: String,
+        found: LiteralType,
+    },
+
+    // Errors found by the borrow checker
+    BorrowConflict {
+        var: String,
+        mutable: bool,
+    },
+    AssignBorrowed {
+        var: String,
+    },
+    UseMutBorrowed {
+        var: String,
+    },
+    BorrowOutlives {
+        var: String,
+    },
+    ReturnLocalRef {
+        var: String,
+    },
+    ShadowBorrowed {
+        var: String,
+    },
 }
 
 impl fmt::Display for ErrorKind {
@@ -158,19 +325,279 @@ impl fmt::Display for ErrorKind {
                 expected.to_string(),
                 found.to_string(),
             ),
-            ErrorKind::FnMissingReturn { name, r_type } => write!(
+            ErrorKind::FnMissingReturn {
+                name,
+                r_type,
+                stmnt,
+                line,
+                ..
+            } => write!(
 				f,
-				"Function '{}' implicitly returns '()' as its body has no tail or 'return' expression
+				"Function '{}' implicitly returns '()' when its end is reached after the {} statement{}
                 Note: expected type '{}' but found '()'",
 				name,
+				stmnt,
+				line.map_or(String::new(), |line| format!(" on line {}", line)),
 				r_type.to_string(),
 			),
+            ErrorKind::FnDefinedMultiple { name } => {
+                write!(f, "Function '{}' is defined multiple times", name)
+            }
+            ErrorKind::ParamDuplicate { name, param } => write!(
+                f,
+                "Identifier '{}' is bound more than once in the parameters of '{}'",
+                param, name
+            ),
+            ErrorKind::MainMissing => write!(f, "No 'main' function is defined in the program"),
+            ErrorKind::MainGeneric => write!(f, "Function 'main' cannot have type parameters"),
+            ErrorKind::MainParams { found } => write!(
+                f,
+                "Function 'main' cannot take parameters
+                Note: found {} parameters",
+                found
+            ),
+            ErrorKind::MainReturnType { found } => write!(
+                f,
+                "Function 'main' cannot return '{}'
+                Note: 'main' can only return 'i32', which is used as the exit code",
+                found.to_string()
+            ),
             ErrorKind::Cond { found } => write!(
                 f,
                 "Mismatched type in condition
                 Note: expected type 'bool' but found type '{}'",
                 found.to_string(),
             ),
+            ErrorKind::LoopModifierOutsideLoop { name, modifier } => write!(
+                f,
+                "'{}' outside of a loop in function '{}'",
+                modifier, name
+            ),
+            ErrorKind::MismatchedTypesArray { expected, found } => write!(
+                f,
+                "Mismatched type of array element
+                Note: expected type '{}' but found type '{}'",
+                expected.to_string(),
+                found.to_string(),
+            ),
+            ErrorKind::IndexWrongType { found } => write!(
+                f,
+                "Mismatched type of array index
+                Note: expected type 'i32' but found type '{}'",
+                found.to_string(),
+            ),
+            ErrorKind::IndexNotArray { found } => write!(
+                f,
+                "Cannot index into a value of type '{}'",
+                found.to_string()
+            ),
+            ErrorKind::IndexOutOfBounds { len, index } => write!(
+                f,
+                "Index out of bounds: the len is {} but the index is {}",
+                len, index
+            ),
+            ErrorKind::MethodNotFound { name, typ } => write!(
+                f,
+                "No method named '{}' found for type '{}'",
+                name,
+                typ.to_string()
+            ),
+            ErrorKind::TypeNotDefined { name } => {
+                write!(f, "Cannot find type '{}' in this scope", name)
+            }
+            ErrorKind::TypeRecursive { name } => write!(
+                f,
+                "Recursive type '{}' has infinite size
+                Note: use a 'Vec' to store values of the same type",
+                name
+            ),
+            ErrorKind::TypeDefinedMultiple { name } => {
+                write!(f, "Type '{}' is defined multiple times", name)
+            }
+            ErrorKind::StructFieldNotFound { strct, field } => {
+                write!(f, "Struct '{}' has no field named '{}'", strct, field)
+            }
+            ErrorKind::StructFieldMissing { strct, field } => write!(
+                f,
+                "Missing field '{}' in initializer of struct '{}'",
+                field, strct
+            ),
+            ErrorKind::StructFieldDuplicate { strct, field } => write!(
+                f,
+                "Field '{}' specified more than once in initializer of struct '{}'",
+                field, strct
+            ),
+            ErrorKind::MismatchedTypesField {
+                strct,
+                field,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Mismatched type of field '{}' in struct '{}'
+                Note: expected type '{}' but found type '{}'",
+                field,
+                strct,
+                expected.to_string(),
+                found.to_string(),
+            ),
+            ErrorKind::FieldNotStruct { field, found } => write!(
+                f,
+                "No field '{}' on type '{}'",
+                field,
+                found.to_string()
+            ),
+            ErrorKind::VariantNotFound { enm, name } => {
+                write!(f, "No variant named '{}' found for enum '{}'", name, enm)
+            }
+            ErrorKind::VariantNumFieldsMismatch {
+                name,
+                takes,
+                supplied,
+            } => write!(
+                f,
+                "Variant '{}' has '{}' fields but '{}' was supplied",
+                name, takes, supplied
+            ),
+            ErrorKind::MismatchedTypesVariant {
+                name,
+                field,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Mismatched type of field '{}' of variant '{}'
+                Note: expected type '{}' but found type '{}'",
+                field,
+                name,
+                expected.to_string(),
+                found.to_string(),
+            ),
+            ErrorKind::MismatchedTypesPattern { pattern, expected } => write!(
+                f,
+                "Mismatched type of pattern '{}'
+                Note: expected a pattern of type '{}'",
+                pattern,
+                expected.to_string(),
+            ),
+            ErrorKind::MismatchedTypesMatchArm { expected, found } => write!(
+                f,
+                "Mismatched types of match arms
+                Note: expected type '{}' but found type '{}'",
+                expected.to_string(),
+                found.to_string(),
+            ),
+            ErrorKind::MatchNotExhaustive { missing } => {
+                write!(f, "Non-exhaustive patterns: '{}' not covered", missing)
+            }
+            ErrorKind::MatchArmUnreachable { pattern } => {
+                write!(f, "Unreachable pattern '{}'", pattern)
+            }
+            ErrorKind::MismatchedTupleBinding { expected, bindings } => write!(
+                f,
+                "Mismatched number of variables in tuple binding
+                Note: expected a tuple of type '{}' but found {} variables",
+                expected.to_string(),
+                bindings
+            ),
+            ErrorKind::DerefNotRef { found } => {
+                write!(f, "Type '{}' cannot be dereferenced", found.to_string())
+            }
+            ErrorKind::RefMutImmut { var } => write!(
+                f,
+                "Cannot borrow immutable variable '{}' as mutable",
+                var
+            ),
+            ErrorKind::AssignSharedRef { var } => write!(
+                f,
+                "Cannot assign through '{}' which is a shared reference",
+                var
+            ),
+            ErrorKind::RefNotAllowed { typ } => write!(
+                f,
+                "References cannot be stored in the type '{}'
+                Note: references can only be the type of variables, parameters and return values",
+                typ.to_string()
+            ),
+            ErrorKind::CannotInfer { var, found } => write!(
+                f,
+                "Cannot infer the type of variable \"{}\"
+                Note: the value has type '{}', add a type annotation i.e. \"let {}: <type> = ...\"",
+                var,
+                found.to_string(),
+                var
+            ),
+            ErrorKind::TypeArgNotInferred { name, param } => write!(
+                f,
+                "Cannot infer the type parameter {} of function \"{}\"
+                Note: the type parameter has to be used in the type of a parameter",
+                param, name
+            ),
+            ErrorKind::TypeArgOp {
+                name,
+                param,
+                typ,
+                op,
+            } => write!(
+                f,
+                "Binary operation '{}' cannot be applied to type '{}'
+                Note: the operation is used on the type parameter {} of function \"{}\"",
+                op.to_string(),
+                typ.to_string(),
+                param,
+                name
+            ),
+            ErrorKind::OpOnParam { op, param } => write!(
+                f,
+                "Operation '{}' cannot be applied to the type parameter '{}'",
+                op.to_string(),
+                param
+            ),
+            ErrorKind::CaptureRef { var, typ } => write!(
+                f,
+                "Closure cannot capture variable \"{}\" of type '{}'
+                Note: closures capture variables by value and references can not be stored",
+                var,
+                typ.to_string()
+            ),
+            ErrorKind::NotCallable { name, found } => write!(
+                f,
+                "Cannot call \"{}\" of type '{}', expected a function",
+                name,
+                found.to_string()
+            ),
+            ErrorKind::BorrowConflict { var, mutable } => match mutable {
+                true => write!(
+                    f,
+                    "Cannot borrow '{}' as mutable because it is also borrowed",
+                    var
+                ),
+                false => write!(
+                    f,
+                    "Cannot borrow '{}' because it is also borrowed as mutable",
+                    var
+                ),
+            },
+            ErrorKind::AssignBorrowed { var } => {
+                write!(f, "Cannot assign to '{}' because it is borrowed", var)
+            }
+            ErrorKind::UseMutBorrowed { var } => {
+                write!(f, "Cannot use '{}' because it is borrowed as mutable", var)
+            }
+            ErrorKind::BorrowOutlives { var } => write!(
+                f,
+                "'{}' does not live long enough
+                Note: it is dropped while it is still borrowed",
+                var
+            ),
+            ErrorKind::ReturnLocalRef { var } => write!(
+                f,
+                "Cannot return a reference to the local variable '{}'",
+                var
+            ),
+            ErrorKind::ShadowBorrowed { var } => {
+                write!(f, "Cannot shadow '{}' because it is borrowed", var)
+            }
         }
     }
 }
@@ -187,4 +614,116 @@ impl TypeErrors {
     pub fn insert_err(&mut self, err: ErrorKind) {
         self.errors.push(err);
     }
+
+    /// Sorts the errors by the location given for each of them, which is the index of an
+    /// item and of a statement in it. Errors with the same location keep the order they were
+    /// found in
+    pub fn sort_by_location(&mut self, locations: Vec<(usize, usize)>) {
+        let mut errors: Vec<((usize, usize), ErrorKind)> =
+            locations.into_iter().zip(self.errors.drain(..)).collect();
+        errors.sort_by_key(|(location, _)| *location);
+        self.errors = errors.into_iter().map(|(_, err)| err).collect();
+    }
+
+    /// Finds the lines of the statements that the errors refer to by their index
+    pub fn locate(mut self, lines: &FuncLines) -> TypeErrors {
+        for error in self.errors.iter_mut() {
+            if let ErrorKind::FnMissingReturn {
+                name,
+                stmnt_idx,
+                line,
+                ..
+            } = error
+            {
+                *line = lines.get(name).and_then(|lines| lines.get(*stmnt_idx).copied());
+            }
+        }
+        self
+    }
+}
+
+/// Diagnostics reported by the lints, they stop a program from being run only if their lint
+/// is denied
+#[derive(Debug, PartialEq)]
+pub enum Warning {
+    // The kinds of the statement which is unreachable and of the one before it which never
+    // completes
+    UnreachableCode {
+        name: String,
+        stmnt: String,
+        after: String,
+    },
+    UnusedVar { name: String, var: String },
+    UnusedParam { name: String, param: String },
+    UnusedMut { name: String, var: String },
+    UnusedAssign { name: String, var: String },
+    UnusedFn { name: String },
+    Shadowed { name: String, var: String },
+    UnknownLint { lint: String },
+}
+
+impl Warning {
+    /// Gets the lint which reports the warning
+    pub fn lint(&self) -> Lint {
+        match self {
+            Warning::UnreachableCode { .. } => Lint::UnreachableCode,
+            Warning::UnusedVar { .. } | Warning::UnusedParam { .. } => Lint::UnusedVariables,
+            Warning::UnusedMut { .. } => Lint::UnusedMut,
+            Warning::UnusedAssign { .. } => Lint::UnusedAssignments,
+            Warning::UnusedFn { .. } => Lint::DeadCode,
+            Warning::Shadowed { .. } => Lint::Shadowing,
+            Warning::UnknownLint { .. } => Lint::UnknownLints,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::UnreachableCode { name, stmnt, after } => match after.as_str() {
+                "return" => write!(
+                    f,
+                    "Unreachable code in function '{}'
+                Note: the {} statement follows a return statement",
+                    name, stmnt
+                ),
+                "while" => write!(
+                    f,
+                    "Unreachable code in function '{}'
+                Note: the {} statement follows a loop which never exits",
+                    name, stmnt
+                ),
+                _ => write!(
+                    f,
+                    "Unreachable code in function '{}'
+                Note: the {} statement follows the {} statement before it, which never completes",
+                    name, stmnt, after
+                ),
+            },
+            Warning::UnusedVar { name, var } => {
+                write!(f, "Unused variable '{}' in function '{}'", var, name)
+            }
+            Warning::UnusedParam { name, param } => {
+                write!(f, "Unused parameter '{}' of function '{}'", param, name)
+            }
+            Warning::UnusedMut { name, var } => write!(
+                f,
+                "Variable '{}' in function '{}' does not need to be mutable",
+                var, name
+            ),
+            Warning::UnusedAssign { name, var } => write!(
+                f,
+                "Value assigned to '{}' in function '{}' is never read
+                Note: it is overwritten or goes out of scope before it is read",
+                var, name
+            ),
+            Warning::UnusedFn { name } => write!(f, "Function '{}' is never used", name),
+            Warning::Shadowed { name, var } => write!(
+                f,
+                "Variable '{}' in function '{}' shadows an earlier binding",
+                var, name
+            ),
+            Warning::UnknownLint { lint } => write!(f, "Unknown lint '{}'", lint),
+        }
+    }
 }
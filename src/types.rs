@@ -1,9 +1,29 @@
-#[derive(Clone, Copy, Debug, PartialEq)]
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum LiteralType {
     Bool,
     I32,
     _String,
     Void,
+    Array(Box<LiteralType>, usize),
+    Vec(Box<LiteralType>),
+    // A struct or enum declared by the program
+    Named(String),
+    Tuple(Vec<LiteralType>),
+    // A reference i.e. "&i32" or, if it is mutable, "&mut i32"
+    Ref(Box<LiteralType>, bool),
+    // A type parameter of a generic function i.e. "T" in "fn id<T>(x: T) -> T"
+    Param(String),
+    // A function or closure i.e. "fn(i32, bool) -> i32", the return type is Void if the
+    // function does not return a value
+    Fn(Vec<LiteralType>, Box<LiteralType>),
+    // Element type of an empty vector that is not yet known, or the type of a let binding
+    // without a type annotation before it has been inferred
+    Infer,
+    // The type of a variable or parameter whose type has an error that has already been
+    // reported, it is compatible with every type so the error is only reported once
+    Error,
 }
 
 impl LiteralType {
@@ -13,6 +33,129 @@ impl LiteralType {
             LiteralType::I32 => "i32".to_string(),
             LiteralType::_String => "string".to_string(),
             LiteralType::Void => "()".to_string(),
+            LiteralType::Array(typ, len) => format!("[{}; {}]", typ.to_string(), len),
+            LiteralType::Vec(typ) => format!("Vec<{}>", typ.to_string()),
+            LiteralType::Named(name) => name.to_string(),
+            LiteralType::Tuple(elems) => match elems.len() {
+                1 => format!("({},)", elems[0].to_string()),
+                _ => {
+                    let elems: Vec<String> = elems.iter().map(|typ| typ.to_string()).collect();
+                    format!("({})", elems.join(", "))
+                }
+            },
+            LiteralType::Ref(typ, mutable) => match mutable {
+                true => format!("&mut {}", typ.to_string()),
+                false => format!("&{}", typ.to_string()),
+            },
+            LiteralType::Param(name) => name.to_string(),
+            LiteralType::Fn(params, r_type) => {
+                let params: Vec<String> = params.iter().map(|typ| typ.to_string()).collect();
+                match **r_type {
+                    LiteralType::Void => format!("fn({})", params.join(", ")),
+                    _ => format!("fn({}) -> {}", params.join(", "), r_type.to_string()),
+                }
+            }
+            LiteralType::Infer => "_".to_string(),
+            LiteralType::Error => "{error}".to_string(),
+        }
+    }
+
+    /// Replaces the parts of the type for which f returns a type, starting with the type itself
+    pub fn map(&self, f: &dyn Fn(&LiteralType) -> Option<LiteralType>) -> LiteralType {
+        if let Some(typ) = f(self) {
+            return typ;
+        }
+        match self {
+            LiteralType::Array(typ, len) => LiteralType::Array(Box::new(typ.map(f)), *len),
+            LiteralType::Vec(typ) => LiteralType::Vec(Box::new(typ.map(f))),
+            LiteralType::Ref(typ, mutable) => LiteralType::Ref(Box::new(typ.map(f)), *mutable),
+            LiteralType::Tuple(elems) => {
+                LiteralType::Tuple(elems.iter().map(|typ| typ.map(f)).collect())
+            }
+            LiteralType::Fn(params, r_type) => LiteralType::Fn(
+                params.iter().map(|typ| typ.map(f)).collect(),
+                Box::new(r_type.map(f)),
+            ),
+            _ => self.clone(),
+        }
+    }
+
+    /// Replaces the type parameters of the type with the type arguments given for them
+    pub fn subst(&self, args: &HashMap<String, LiteralType>) -> LiteralType {
+        self.map(&|typ| match typ {
+            LiteralType::Param(name) => args.get(name).cloned(),
+            _ => None,
+        })
+    }
+
+    /// Infers the type arguments for the type parameters of this type from the type found
+    /// in its place, the first type found for a type parameter is used
+    pub fn infer_args(&self, found: &LiteralType, args: &mut HashMap<String, LiteralType>) {
+        match (self, found) {
+            (LiteralType::Param(name), _) => {
+                if !args.contains_key(name) && found.is_known() {
+                    args.insert(name.to_string(), found.clone());
+                }
+            }
+            (LiteralType::Array(param, _), LiteralType::Array(found, _))
+            | (LiteralType::Vec(param), LiteralType::Vec(found))
+            | (LiteralType::Ref(param, _), LiteralType::Ref(found, _)) => {
+                param.infer_args(found, args)
+            }
+            (LiteralType::Tuple(params), LiteralType::Tuple(found)) => {
+                for (param, found) in params.iter().zip(found.iter()) {
+                    param.infer_args(found, args);
+                }
+            }
+            (LiteralType::Fn(params, r_type), LiteralType::Fn(found, found_r_type)) => {
+                for (param, found) in params.iter().zip(found.iter()) {
+                    param.infer_args(found, args);
+                }
+                r_type.infer_args(found_r_type, args);
+            }
+            _ => (),
+        }
+    }
+
+    /// Checks if the type is fully known, i.e. it does not contain an unknown element type
+    pub fn is_known(&self) -> bool {
+        match self {
+            LiteralType::Infer => false,
+            LiteralType::Array(typ, _) | LiteralType::Vec(typ) | LiteralType::Ref(typ, _) => {
+                typ.is_known()
+            }
+            LiteralType::Tuple(elems) => elems.iter().all(|typ| typ.is_known()),
+            LiteralType::Fn(params, r_type) => {
+                params.iter().all(|typ| typ.is_known()) && r_type.is_known()
+            }
+            _ => true,
+        }
+    }
+
+    /// Checks if a value of type found can be used where this type is expected,
+    /// unknown element types (i.e. of "Vec::new()") and the error type are compatible with
+    /// any type and mutable references can be used as shared references
+    pub fn is_compatible(&self, found: &LiteralType) -> bool {
+        match (self, found) {
+            (_, LiteralType::Infer) | (_, LiteralType::Error) | (LiteralType::Error, _) => true,
+            (LiteralType::Array(expected, e_len), LiteralType::Array(found, f_len)) => {
+                e_len == f_len && expected.is_compatible(found)
+            }
+            (LiteralType::Vec(expected), LiteralType::Vec(found)) => expected.is_compatible(found),
+            (LiteralType::Ref(expected, e_mut), LiteralType::Ref(found, f_mut)) => {
+                (*f_mut || !e_mut)
+                    && (**expected == **found
+                        || **expected == LiteralType::Error
+                        || **found == LiteralType::Error)
+            }
+            (LiteralType::Tuple(expected), LiteralType::Tuple(found)) => {
+                expected.len() == found.len()
+                    && expected
+                        .iter()
+                        .zip(found.iter())
+                        .all(|(expected, found)| expected.is_compatible(found))
+            }
+            _ => self == found,
         }
     }
 }
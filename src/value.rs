@@ -1,7 +1,45 @@
+use std::mem;
+
+use crate::{ast::Node, reference::Reference};
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     Number(i32),
     Bool(bool),
     String(String),
+    Array(Vec<Value>),
+    Vec(Vec<Value>),
+    Struct(String, Vec<(String, Value)>),
+    Enum(String, String, Vec<Value>),
+    Tuple(Vec<Value>),
+    Ref(Reference),
+    // A closure and the values of the variables it captured, a function that is used as a
+    // value is a closure which calls it
+    Closure(Vec<Box<Node>>, Box<Node>, Vec<(String, Value)>),
+    // The value of a function which does not return anything
+    Unit,
     None,
 }
+
+impl Value {
+    /// An estimate of the number of bytes used by a value, including the strings and
+    /// elements it owns
+    pub fn size(&self) -> usize {
+        let owned = match self {
+            Value::String(text) => text.len(),
+            Value::Array(vals) | Value::Vec(vals) | Value::Tuple(vals) => {
+                vals.iter().map(Value::size).sum()
+            }
+            Value::Struct(name, fields) => {
+                let fields = fields.iter().map(|(field, val)| field.len() + val.size());
+                name.len() + fields.sum::<usize>()
+            }
+            Value::Enum(name, variant, vals) => {
+                name.len() + variant.len() + vals.iter().map(Value::size).sum::<usize>()
+            }
+            Value::Closure(_, _, env) => env.iter().map(|(var, val)| var.len() + val.size()).sum(),
+            _ => 0,
+        };
+        mem::size_of::<Value>() + owned
+    }
+}
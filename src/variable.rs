@@ -21,7 +21,7 @@ impl Variable {
     }
 
     pub fn get_type(&self) -> LiteralType {
-        self.typ
+        self.typ.clone()
     }
 
     pub fn update_value(&mut self, val: Value) -> Value {
@@ -32,4 +32,9 @@ impl Variable {
     pub fn is_mut(&self) -> bool {
         self.mutable
     }
+
+    /// The number of bytes used by the value of the variable
+    pub fn size(&self) -> usize {
+        self.value.size()
+    }
 }